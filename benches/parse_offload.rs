@@ -0,0 +1,122 @@
+//! Page parsing on tokio's async workers vs. on its blocking pool, the
+//! way `scrape::off_runtime` runs it. Two numbers per mode, for a batch of
+//! `PAGES` large pages parsed concurrently on a two-worker runtime:
+//!
+//! - `parse`: wall time of the whole batch;
+//! - `stall`: how late a 1 ms timer on the same runtime fires at worst,
+//!   standing in for the socket I/O of the fetches sharing it.
+//!
+//! `cargo bench --bench parse_offload`
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use scraper::Html;
+use tokio::runtime::Runtime;
+
+/// Pages parsed at once, a busy `--concurrency`.
+const PAGES: usize = 16;
+
+/// A long article page: nested sections of headings, paragraphs with
+/// inline markup, lists and a table.
+fn page() -> String {
+    let mut html = String::from("<html><head><title>Bench</title></head><body><main>");
+    for section in 0..300 {
+        html.push_str(&format!("<section><h2>Section {section}</h2>"));
+        for p in 0..5 {
+            html.push_str(&format!(
+                "<p>Paragraph {p} with <a href=\"/s/{section}/{p}\">a link</a>, \
+                 <strong>bold</strong> and <em>emphasised</em> words in it.</p>"
+            ));
+        }
+        html.push_str("<ul><li>One</li><li>Two</li><li>Three</li></ul>");
+        html.push_str("<table><tr><td>a</td><td>b</td></tr></table></section>");
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+/// Parse and walk the DOM, like extraction does.
+fn extract(html: &str) -> usize {
+    let doc = Html::parse_document(html);
+    doc.root_element()
+        .descendants()
+        .filter_map(|node| node.value().as_text())
+        .map(|text| text.split_whitespace().count())
+        .sum()
+}
+
+async fn parse_all(html: Arc<str>, offload: bool) -> usize {
+    let tasks: Vec<_> = (0..PAGES)
+        .map(|_| {
+            let html = Arc::clone(&html);
+            tokio::spawn(async move {
+                if offload {
+                    tokio::task::spawn_blocking(move || extract(&html))
+                        .await
+                        .unwrap()
+                } else {
+                    extract(&html)
+                }
+            })
+        })
+        .collect();
+    let mut words = 0;
+    for task in tasks {
+        words += task.await.unwrap();
+    }
+    words
+}
+
+/// Worst lateness of a 1 ms timer while a batch is parsed.
+async fn worst_stall(html: Arc<str>, offload: bool) -> Duration {
+    let done = Arc::new(AtomicBool::new(false));
+    let ticker = tokio::spawn({
+        let done = Arc::clone(&done);
+        async move {
+            let mut worst = Duration::ZERO;
+            while !done.load(Ordering::Relaxed) {
+                let started = Instant::now();
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                worst = worst.max(started.elapsed().saturating_sub(Duration::from_millis(1)));
+            }
+            worst
+        }
+    });
+    parse_all(html, offload).await;
+    done.store(true, Ordering::Relaxed);
+    ticker.await.unwrap()
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn parse_offload(c: &mut Criterion) {
+    let rt = runtime();
+    let html: Arc<str> = page().into();
+    let mut group = c.benchmark_group("parse_offload");
+    group.sample_size(20);
+    for (name, offload) in [("on_runtime", false), ("blocking_pool", true)] {
+        group.bench_function(format!("parse/{name}"), |b| {
+            b.iter(|| rt.block_on(parse_all(Arc::clone(&html), offload)))
+        });
+        group.bench_function(format!("stall/{name}"), |b| {
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| rt.block_on(worst_stall(Arc::clone(&html), offload)))
+                    .sum()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_offload);
+criterion_main!(benches);
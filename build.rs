@@ -0,0 +1,12 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Only the `grpc` feature has protos to compile.
+    #[cfg(feature = "grpc")]
+    {
+        // Vendored protoc, so building doesn't need one installed.
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/dump_it.proto"], &["proto"])?;
+    }
+    Ok(())
+}
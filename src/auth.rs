@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Request, Response, StatusCode};
+use serde::Deserialize;
+use url::Url;
+
+use crate::cli::RunArgs;
+
+/// `--header` / `--site-header` values per origin (scheme + host without
+/// `www.`), for every site of the process: under `batch` each job adds
+/// its own, and a request only ever gets those of the origin it goes to,
+/// never another host's or the plain-`http` twin of an `https` site's.
+static SITE_HEADERS: Mutex<Option<HashMap<String, Headers>>> = Mutex::new(None);
+
+type Headers = Vec<(HeaderName, HeaderValue)>;
+
+/// `--oauth-token-url` settings per origin, keyed and shared like
+/// [`SITE_HEADERS`]: a token for an `https` site never goes out over
+/// plain `http`.
+static OAUTH: Mutex<Option<HashMap<String, Arc<OAuth>>>> = Mutex::new(None);
+
+/// `--client-cert`: the origins the certificate is for and the dedicated
+/// client that presents it. Only requests to those origins go through
+/// it, so asset CDNs, oEmbed providers and the hosts a redirect leads to
+/// never see the certificate.
+static IDENTITY: Mutex<ClientIdentity> = Mutex::new(ClientIdentity {
+    origins: Vec::new(),
+    client: None,
+});
+
+struct ClientIdentity {
+    origins: Vec<String>,
+    client: Option<Client>,
+}
+
+/// An OAuth2 token endpoint and the token it last gave out.
+struct OAuth {
+    token_url: Url,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    scope: Option<String>,
+    /// Async so one task refreshes while the others wait for its token
+    /// instead of each asking for their own.
+    token: tokio::sync::Mutex<Token>,
+}
+
+#[derive(Default)]
+struct Token {
+    /// `Bearer …`, `None` until the first fetch.
+    bearer: Option<HeaderValue>,
+    /// When to fetch a new one; `None` = when the site answers 401.
+    renew_at: Option<Instant>,
+    /// Refresh token, replaced when the endpoint rotates it.
+    refresh: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    /// As the `Bearer …` header; a token that can't be one fails the
+    /// token request.
+    #[serde(rename = "access_token", deserialize_with = "bearer_header")]
+    bearer: HeaderValue,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+fn host_key(host: &str) -> String {
+    let host = host.to_ascii_lowercase();
+    host.strip_prefix("www.").unwrap_or(&host).to_string()
+}
+
+/// [`SITE_HEADERS`] key of `url`: `https://example.com` for
+/// `https://www.example.com/a`.
+fn origin_key(url: &Url) -> Option<String> {
+    Some(format!("{}://{}", url.scheme(), host_key(url.host_str()?)))
+}
+
+fn bearer_header<'de, D: serde::Deserializer<'de>>(d: D) -> Result<HeaderValue, D::Error> {
+    use serde::de::Error;
+    let token = String::deserialize(d)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(D::Error::custom("empty access_token"));
+    }
+    let mut bearer = HeaderValue::try_from(format!("Bearer {token}"))
+        .map_err(|_| D::Error::custom("access_token is not a valid header value"))?;
+    bearer.set_sensitive(true);
+    Ok(bearer)
+}
+
+/// Origin key of a `--site-header` / `--oauth-host` HOST, optionally with
+/// an `http://` or `https://` scheme (`https` when left out).
+fn host_origin(host: &str) -> Option<String> {
+    let origin = if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("https://{host}")
+    };
+    Url::parse(&origin)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https") && u.path() == "/")
+        .filter(|u| u.port().is_none() && u.query().is_none())
+        .and_then(|u| origin_key(&u))
+}
+
+/// `Name: value` as a header, `None` (with a warning) when malformed.
+fn parse_header(header: &str) -> Option<(HeaderName, HeaderValue)> {
+    let parsed = header.split_once(':').and_then(|(name, value)| {
+        let name = HeaderName::try_from(name.trim()).ok()?;
+        let value = HeaderValue::try_from(value.trim()).ok()?;
+        Some((name, value))
+    });
+    if parsed.is_none() {
+        tracing::warn!("ignored malformed header value: {header}");
+    }
+    parsed
+}
+
+/// `--site-header` parser: `HOST=Name: value`, HOST optionally with an
+/// `http://` or `https://` scheme (`https` when left out). The host
+/// comes back as the origin its headers go to.
+pub(crate) fn parse_site_header(s: &str) -> Result<(String, String), String> {
+    let invalid = || format!("`{s}` is not like \"shop.example.com=Cookie: sid=…\"");
+    let (host, header) = s
+        .split_once('=')
+        .filter(|(_, header)| header.contains(':'))
+        .ok_or_else(invalid)?;
+    let origin = host_origin(host.trim()).ok_or_else(invalid)?;
+    Ok((origin, header.trim().to_string()))
+}
+
+/// Send `headers` with every request to `origin` (an [`origin_key`]) from
+/// now on. A header given again for the origin replaces the old value.
+fn scope(origin: &str, headers: &[String]) {
+    let parsed: Vec<_> = headers.iter().filter_map(|h| parse_header(h)).collect();
+    if parsed.is_empty() {
+        return;
+    }
+    let mut sites = SITE_HEADERS.lock().unwrap();
+    let scoped = sites
+        .get_or_insert_with(HashMap::new)
+        .entry(origin.to_string())
+        .or_default();
+    for (name, value) in parsed {
+        scoped.retain(|(n, _)| *n != name);
+        scoped.push((name, value));
+    }
+}
+
+/// Scope `--header` (plus the `DUMPIT_*` variables) and the
+/// `--client-cert` identity for a run of `site_url` to the site's origin,
+/// and every `--site-header` to its own. None of them is a client-wide
+/// default: asset CDNs, oEmbed providers and the other sites of a crawl
+/// never see them.
+pub(crate) fn scope_headers(site_url: &str, args: &RunArgs) {
+    let headers = crate::config::request_headers(&args.headers);
+    match Url::parse(site_url).ok().as_ref().and_then(origin_key) {
+        Some(origin) => {
+            if args.client_cert.is_some() {
+                IDENTITY.lock().unwrap().origins.push(origin.clone());
+            }
+            scope(&origin, &headers)
+        }
+        None if !headers.is_empty() => {
+            tracing::warn!("--header ignored: {site_url} has no host to send it to")
+        }
+        None => {}
+    }
+    for (origin, header) in &args.site_headers {
+        scope(origin, std::slice::from_ref(header));
+    }
+}
+
+/// Forget every site's credentials and OAuth2 settings, for a process
+/// that runs one crawl after another (`dump-it grpc`).
+#[cfg(feature = "grpc")]
+pub(crate) fn reset() {
+    *SITE_HEADERS.lock().unwrap() = None;
+    *OAUTH.lock().unwrap() = None;
+    let mut identity = IDENTITY.lock().unwrap();
+    identity.origins.clear();
+    identity.client = None;
+}
+
+/// The client presenting the `--client-cert` identity to the origins
+/// [`scope_headers`] gave it. A scraper built again replaces it.
+pub(crate) fn present_identity(client: Client) {
+    IDENTITY.lock().unwrap().client = Some(client);
+}
+
+/// The `--client-cert` client, if `url` is on an origin it's for.
+fn identity_client(url: &Url) -> Option<Client> {
+    let origin = origin_key(url)?;
+    let identity = IDENTITY.lock().unwrap();
+    identity
+        .origins
+        .contains(&origin)
+        .then(|| identity.client.clone())
+        .flatten()
+}
+
+/// Add the headers scoped to `request`'s origin. Runs below the HAR
+/// recorder, so they stay out of `--har` logs; headers already on the
+/// request win, and these win over the client's defaults.
+fn apply(request: &mut Request) {
+    let Some(origin) = origin_key(request.url()) else {
+        return;
+    };
+    let sites = SITE_HEADERS.lock().unwrap();
+    let Some(scoped) = sites.as_ref().and_then(|sites| sites.get(&origin)) else {
+        return;
+    };
+    for (name, value) in scoped {
+        if !request.headers().contains_key(name) {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// `--oauth-token-url`: fetch OAuth2 tokens for `site_url`'s origin (and
+/// every `--oauth-host`, over `https` unless it says `http://`) from the
+/// token endpoint, with the refresh-token grant
+/// when `DUMPIT_OAUTH_REFRESH_TOKEN` is set and client credentials
+/// (`--oauth-client-id` + `DUMPIT_OAUTH_CLIENT_SECRET`) otherwise. The
+/// first token is fetched with the first request.
+pub(crate) fn start_oauth(site_url: &str, args: &RunArgs) -> anyhow::Result<()> {
+    let Some(token_url) = &args.oauth_token_url else {
+        return Ok(());
+    };
+    let token_url =
+        Url::parse(token_url).with_context(|| format!("invalid --oauth-token-url {token_url}"))?;
+    let env = |var: &str| {
+        std::env::var(var)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let client_secret = env("DUMPIT_OAUTH_CLIENT_SECRET");
+    let refresh = env("DUMPIT_OAUTH_REFRESH_TOKEN");
+    let grant = if refresh.is_some() {
+        "refresh-token"
+    } else if args.oauth_client_id.is_some() && client_secret.is_some() {
+        "client-credentials"
+    } else {
+        anyhow::bail!(
+            "--oauth-token-url needs DUMPIT_OAUTH_REFRESH_TOKEN, or --oauth-client-id \
+             and DUMPIT_OAUTH_CLIENT_SECRET"
+        );
+    };
+    let site_origin = Url::parse(site_url)
+        .ok()
+        .as_ref()
+        .and_then(origin_key)
+        .ok_or_else(|| anyhow::anyhow!("--oauth-token-url: {site_url} has no host"))?;
+    let mut origins = vec![site_origin];
+    for host in &args.oauth_hosts {
+        let origin = host_origin(host.trim())
+            .ok_or_else(|| anyhow::anyhow!("invalid --oauth-host {host}"))?;
+        origins.push(origin);
+    }
+    let oauth = Arc::new(OAuth {
+        token_url: token_url.clone(),
+        client_id: args.oauth_client_id.clone(),
+        client_secret,
+        scope: args.oauth_scope.clone(),
+        token: tokio::sync::Mutex::new(Token {
+            refresh,
+            ..Token::default()
+        }),
+    });
+    let mut sites = OAUTH.lock().unwrap();
+    let sites = sites.get_or_insert_with(HashMap::new);
+    for origin in &origins {
+        sites.insert(origin.clone(), oauth.clone());
+    }
+    progress!(
+        "🔑 OAuth2 ({grant}) tokens from {token_url} for {}",
+        origins.join(", ")
+    );
+    Ok(())
+}
+
+impl OAuth {
+    /// The current `Bearer …` header, fetching a new token first when
+    /// there is none, it's about to expire, or it is `rejected` (a 401).
+    async fn bearer(
+        &self,
+        client: &Client,
+        rejected: Option<&HeaderValue>,
+    ) -> reqwest::Result<HeaderValue> {
+        let mut token = self.token.lock().await;
+        let usable = token.bearer.as_ref().filter(|b| {
+            Some(*b) != rejected && token.renew_at.is_none_or(|at| Instant::now() < at)
+        });
+        if let Some(bearer) = usable {
+            return Ok(bearer.clone());
+        }
+        let fetched = self.fetch(client, token.refresh.clone()).await;
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                tracing::warn!("OAuth2 token request to {} failed: {e}", self.token_url);
+                return Err(e);
+            }
+        };
+        // Renew a little early, so a token doesn't run out mid-request.
+        token.renew_at = fetched
+            .expires_in
+            .map(|s| Instant::now() + Duration::from_secs(s - (s / 2).min(60)));
+        if fetched.refresh_token.is_some() {
+            token.refresh = fetched.refresh_token;
+        }
+        let bearer = fetched.bearer;
+        tracing::debug!(
+            "OAuth2 token from {} (expires in {:?}s)",
+            self.token_url,
+            fetched.expires_in
+        );
+        token.bearer = Some(bearer.clone());
+        Ok(bearer)
+    }
+
+    /// One token request, straight to the endpoint: not recorded,
+    /// cached or logged to `--har`.
+    async fn fetch(
+        &self,
+        client: &Client,
+        refresh: Option<String>,
+    ) -> reqwest::Result<TokenResponse> {
+        let mut form = match &refresh {
+            Some(refresh) => vec![
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh.as_str()),
+            ],
+            None => vec![("grant_type", "client_credentials")],
+        };
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.as_str()));
+        }
+        let mut request = client.post(self.token_url.clone());
+        match (&self.client_id, &self.client_secret) {
+            (Some(id), Some(secret)) => request = request.basic_auth(id, Some(secret)),
+            (Some(id), None) => form.push(("client_id", id.as_str())),
+            _ => {}
+        }
+        let request = request
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&form)
+            .build()?;
+        client
+            .execute(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+}
+
+fn oauth_for(url: &Url) -> Option<Arc<OAuth>> {
+    let origin = origin_key(url)?;
+    OAUTH.lock().unwrap().as_ref()?.get(&origin).cloned()
+}
+
+/// Redirects followed before a 3xx is handed back as it is.
+const MAX_REDIRECTS: usize = 10;
+
+/// Send `request`, following redirects hop by hop (the clients of
+/// `HttpOptions` don't follow them themselves) so that every hop carries
+/// the credentials of its own origin and none of the previous one's.
+pub(crate) async fn execute(client: &Client, mut request: Request) -> reqwest::Result<Response> {
+    for _ in 0..MAX_REDIRECTS {
+        let next = request.try_clone();
+        let response = execute_hop(client, request).await?;
+        match next.and_then(|next| redirect(next, &response)) {
+            Some(next) => request = next,
+            None => return Ok(response),
+        }
+    }
+    execute_hop(client, request).await
+}
+
+/// The request `response` redirects `previous` (as the caller built it,
+/// without credentials) to; `None` when it isn't a redirect to a web
+/// URL. As in reqwest's own policy, a 303 — or a 301 / 302 after a
+/// POST — becomes a GET without body, and Authorization / Cookie headers
+/// the caller set don't follow it to another origin.
+fn redirect(mut previous: Request, response: &Response) -> Option<Request> {
+    use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION};
+    use reqwest::Method;
+
+    let status = response.status().as_u16();
+    if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    let url = response.url().join(location).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let method = previous.method().clone();
+    let to_get = (status == 303 && method != Method::HEAD)
+        || (matches!(status, 301 | 302) && method == Method::POST);
+    if to_get {
+        *previous.method_mut() = Method::GET;
+        *previous.body_mut() = None;
+        previous.headers_mut().remove(CONTENT_TYPE);
+        previous.headers_mut().remove(CONTENT_LENGTH);
+    }
+    if origin_key(&url) != origin_key(previous.url()) {
+        for name in [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE] {
+            previous.headers_mut().remove(name);
+        }
+    }
+    *previous.url_mut() = url;
+    Some(previous)
+}
+
+/// Send one request with the credentials of its host: an OAuth2 token
+/// where one is set up, then the scoped headers, over the `--client-cert`
+/// client where that is scoped to it. A 401 to a token-bearing request
+/// gets one retry with a fresh token, for tokens revoked or expired
+/// before their `expires_in`.
+async fn execute_hop(client: &Client, mut request: Request) -> reqwest::Result<Response> {
+    let identified = identity_client(request.url());
+    let sender = identified.as_ref().unwrap_or(client);
+    let oauth = oauth_for(request.url()).filter(|_| !request.headers().contains_key(AUTHORIZATION));
+    let mut retry = None;
+    if let Some(oauth) = &oauth {
+        let bearer = oauth.bearer(client, None).await?;
+        retry = request.try_clone().map(|again| (again, bearer.clone()));
+        request.headers_mut().insert(AUTHORIZATION, bearer);
+    }
+    apply(&mut request);
+    let response = crate::cassette::execute(sender, request).await?;
+    let (Some(oauth), Some((mut again, used))) = (oauth, retry) else {
+        return Ok(response);
+    };
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+    let Ok(bearer) = oauth.bearer(client, Some(&used)).await else {
+        return Ok(response);
+    };
+    again.headers_mut().insert(AUTHORIZATION, bearer);
+    apply(&mut again);
+    crate::cassette::execute(sender, again).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn credentials_only_go_to_their_site() {
+        let args = crate::cli::Args::parse_from([
+            "dump-it",
+            "--url",
+            "https://a-site.test",
+            "--header",
+            "Cookie: sid=a",
+            "--header",
+            "Accept-Language: de",
+            "--site-header",
+            "cdn.b-site.test=Authorization: Bearer b",
+        ]);
+        scope_headers("https://a-site.test/", &args.run);
+
+        let sent = |url: &str| {
+            let mut request = Request::new(reqwest::Method::GET, Url::parse(url).unwrap());
+            apply(&mut request);
+            let mut names: Vec<String> = request
+                .headers()
+                .iter()
+                .map(|(n, v)| format!("{n}: {}", v.to_str().unwrap()))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            sent("https://www.a-site.test/x"),
+            ["accept-language: de", "cookie: sid=a"]
+        );
+        assert_eq!(
+            sent("https://cdn.b-site.test/i.png"),
+            ["authorization: Bearer b"]
+        );
+        // Not over plain http, nor to other hosts.
+        assert!(sent("http://www.a-site.test/x").is_empty());
+        assert!(sent("http://cdn.b-site.test/i.png").is_empty());
+        assert!(sent("https://b-site.test/").is_empty());
+        assert!(sent("https://tracker.a-site.test/").is_empty());
+
+        assert!(parse_site_header("Cookie: sid=1").is_err());
+        assert!(parse_site_header("ftp://shop.test=Cookie: a=b").is_err());
+        assert_eq!(
+            parse_site_header("www.shop.test=Cookie: a=b").unwrap(),
+            ("https://shop.test".to_string(), "Cookie: a=b".to_string())
+        );
+        assert_eq!(
+            parse_site_header("http://shop.test=Cookie: a=b").unwrap(),
+            ("http://shop.test".to_string(), "Cookie: a=b".to_string())
+        );
+    }
+
+    #[test]
+    fn the_client_certificate_only_goes_to_its_site() {
+        let args = crate::cli::Args::parse_from([
+            "dump-it",
+            "--url",
+            "https://mtls-site.test",
+            "--client-cert",
+            "cert.pem",
+            "--client-key",
+            "key.pem",
+        ]);
+        scope_headers("https://mtls-site.test/", &args.run);
+        present_identity(Client::new());
+
+        let identified = |url: &str| identity_client(&Url::parse(url).unwrap()).is_some();
+        assert!(identified("https://mtls-site.test/x"));
+        assert!(identified("https://www.mtls-site.test/x"));
+        assert!(!identified("http://mtls-site.test/x"));
+        assert!(!identified("https://cdn.mtls-site.test/i.png"));
+        assert!(!identified("https://other-site.test/"));
+    }
+
+    #[tokio::test]
+    async fn scoped_headers_stay_behind_on_a_cross_origin_redirect() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // One-request servers answering `response`, handing back the
+        // request head they got.
+        async fn serve(response: String) -> (u16, tokio::task::JoinHandle<String>) {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let task = tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0; 4096];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&buf[..read]);
+                }
+                socket.write_all(response.as_bytes()).await.unwrap();
+                String::from_utf8_lossy(&head).to_ascii_lowercase()
+            });
+            (port, task)
+        }
+        let ok = "HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok";
+        let (target, landing) = serve(ok.to_string()).await;
+        // 127.0.0.1 and localhost: the same machine, different origins.
+        let (start, redirecting) = serve(format!(
+            "HTTP/1.1 302 Found\r\nlocation: http://localhost:{target}/landing\r\n\
+             content-length: 0\r\nconnection: close\r\n\r\n"
+        ))
+        .await;
+        scope("http://127.0.0.1", &["X-Api-Key: k-secret".to_string()]);
+
+        let client = Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let url = Url::parse(&format!("http://127.0.0.1:{start}/")).unwrap();
+        let response = execute(&client, Request::new(reqwest::Method::GET, url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.url().path(), "/landing");
+        assert!(redirecting.await.unwrap().contains("x-api-key: k-secret"));
+        let landed = landing.await.unwrap();
+        assert!(landed.starts_with("get /landing"));
+        assert!(!landed.contains("x-api-key"));
+    }
+
+    #[test]
+    fn redirects_are_followed_like_reqwest_does() {
+        use reqwest::ResponseBuilderExt;
+        let response = |status: u16, location: &str| {
+            let built = http::Response::builder()
+                .status(status)
+                .url(Url::parse("https://shop.test/cart").unwrap())
+                .header(reqwest::header::LOCATION, location)
+                .body(Vec::new())
+                .unwrap();
+            Response::from(built)
+        };
+        let post = || {
+            let mut request = Client::new()
+                .post("https://shop.test/cart")
+                .header(AUTHORIZATION, "Basic x")
+                .body("a=1")
+                .build()
+                .unwrap();
+            request.headers_mut().insert(
+                reqwest::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+            request
+        };
+        let next = redirect(post(), &response(303, "/done")).unwrap();
+        assert_eq!(next.url().as_str(), "https://shop.test/done");
+        assert_eq!(next.method(), reqwest::Method::GET);
+        assert!(next.body().is_none());
+        assert!(next.headers().contains_key(AUTHORIZATION));
+
+        let next = redirect(post(), &response(307, "https://pay.test/")).unwrap();
+        assert_eq!(next.method(), reqwest::Method::POST);
+        assert!(!next.headers().contains_key(AUTHORIZATION));
+
+        assert!(redirect(post(), &response(200, "/x")).is_none());
+        assert!(redirect(post(), &response(302, "file:///etc/passwd")).is_none());
+    }
+
+    #[test]
+    fn oauth_tokens_go_to_their_origins_only() {
+        let token = |json: &str| serde_json::from_str::<TokenResponse>(json).map(|t| t.bearer);
+        assert_eq!(token(r#"{"access_token":"abc"}"#).unwrap(), "Bearer abc");
+        assert!(token(r#"{"access_token":"a\nb"}"#).is_err());
+        assert!(token(r#"{"access_token":" "}"#).is_err());
+
+        let args = crate::cli::Args::parse_from([
+            "dump-it",
+            "--url",
+            "https://oauth-site.test",
+            "--oauth-token-url",
+            "https://auth.oauth-site.test/token",
+            "--oauth-host",
+            "api.oauth-site.test",
+        ]);
+        std::env::set_var("DUMPIT_OAUTH_REFRESH_TOKEN", "r0");
+        start_oauth("https://www.oauth-site.test/", &args.run).unwrap();
+        let has_token = |url: &str| oauth_for(&Url::parse(url).unwrap()).is_some();
+        assert!(has_token("https://oauth-site.test/a"));
+        assert!(has_token("https://api.oauth-site.test/v1"));
+        assert!(!has_token("http://oauth-site.test/a"));
+        assert!(!has_token("http://api.oauth-site.test/v1"));
+        assert!(!has_token("https://other.test/"));
+    }
+
+    #[tokio::test]
+    async fn oauth_tokens_are_reused_and_renewed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A token endpoint that hands out t1, t2, … and rotates the
+        // refresh token with each, keeping the request bodies.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let bodies = Arc::new(Mutex::new(Vec::<String>::new()));
+        let seen = bodies.clone();
+        tokio::spawn(async move {
+            for n in 1.. {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 4096];
+                let body = loop {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .filter_map(|l| l.split_once(':'))
+                            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                            .and_then(|(_, v)| v.trim().parse().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length || read == 0 {
+                            break body.to_string();
+                        }
+                    }
+                };
+                seen.lock().unwrap().push(body);
+                let json = format!(
+                    r#"{{"access_token":"t{n}","token_type":"bearer","expires_in":3600,"refresh_token":"r{n}"}}"#
+                );
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{json}",
+                    json.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let oauth = OAuth {
+            token_url: Url::parse(&format!("http://{addr}/token")).unwrap(),
+            client_id: Some("dump-it".into()),
+            client_secret: Some("s3cret".into()),
+            scope: Some("read".into()),
+            token: tokio::sync::Mutex::new(Token::default()),
+        };
+        let client = Client::new();
+        let first = oauth.bearer(&client, None).await.unwrap();
+        assert_eq!(first, "Bearer t1");
+        assert_eq!(oauth.bearer(&client, None).await.unwrap(), "Bearer t1");
+        // Rejected by the site: the next token comes from the refresh token.
+        assert_eq!(
+            oauth.bearer(&client, Some(&first)).await.unwrap(),
+            "Bearer t2"
+        );
+        // Expired: renewed with the rotated refresh token.
+        oauth.token.lock().await.renew_at = Some(Instant::now());
+        assert_eq!(oauth.bearer(&client, None).await.unwrap(), "Bearer t3");
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(
+            *bodies,
+            [
+                "grant_type=client_credentials&scope=read",
+                "grant_type=refresh_token&refresh_token=r1&scope=read",
+                "grant_type=refresh_token&refresh_token=r2&scope=read",
+            ]
+        );
+    }
+}
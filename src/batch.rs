@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use futures::stream::{self, StreamExt};
+use tokio::sync::Semaphore;
+
+use crate::cli::RunArgs;
+use crate::util::url_to_host_slug;
+
+/// One line of a jobs file, parsed with the CLI's own flags.
+#[derive(Parser)]
+#[command(name = "dump-it", no_binary_name = true)]
+struct JobArgs {
+    /// Target website URL, sitemap URL, or local folder
+    #[arg(short, long, required_unless_present = "url_list")]
+    url: Option<String>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+/// A site of the batch.
+pub(crate) struct Job {
+    /// Line in the jobs file, for messages.
+    line: usize,
+    url: Option<String>,
+    run: RunArgs,
+}
+
+/// Parse a jobs file: one site per line, written as the flags of a
+/// `dump-it crawl` (shell quoting applies, `--profile` too); blank lines
+/// and `#` comments are skipped. A job without `--output` writes to
+/// `output/<host>/scraped.json` so sites don't overwrite each other.
+pub(crate) fn parse_jobs(text: &str) -> anyhow::Result<Vec<Job>> {
+    let mut jobs = Vec::new();
+    let mut outputs: HashMap<String, usize> = HashMap::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        let words = shlex::split(raw).with_context(|| format!("line {line}: unbalanced quotes"))?;
+        let words = crate::config::expand_profile(words, |_| 0)
+            .map_err(|e| anyhow::anyhow!("line {line}: {e:#}"))?;
+        let JobArgs { url, mut run } = JobArgs::command()
+            .args_override_self(true)
+            .try_get_matches_from(&words)
+            .and_then(|matches| JobArgs::from_arg_matches(&matches))
+            .map_err(|e| anyhow::anyhow!("line {line}: {e}"))?;
+        if let Some(flag) = run.process_wide_flag() {
+            anyhow::bail!("line {line}: {flag} is not supported in a batch job");
+        }
+        if run.output == "output/scraped.json" && !run.test_run {
+            let site = url.as_deref().unwrap_or_default();
+            run.output = format!("output/{}/scraped.json", url_to_host_slug(site));
+        }
+        if let Some(other) = outputs.insert(run.output.clone(), line) {
+            anyhow::bail!(
+                "line {line}: writes to {} like line {other}; give one of them --output",
+                run.output
+            );
+        }
+        jobs.push(Job { line, url, run });
+    }
+    Ok(jobs)
+}
+
+/// `dump-it batch`: run every job of `path`, `sites` of them at a time,
+/// with at most `budget` page fetches in flight across all of them. A
+/// failed site doesn't stop the others; the batch fails at the end if any
+/// did.
+pub(crate) async fn run_batch(path: &Path, sites: usize, budget: usize) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let jobs = parse_jobs(&text).with_context(|| format!("in {}", path.display()))?;
+    if jobs.is_empty() {
+        anyhow::bail!("{} lists no jobs", path.display());
+    }
+    progress!(
+        "🗂️  {} jobs from {}: {} sites at a time, {} fetches in flight",
+        jobs.len(),
+        path.display(),
+        sites.max(1),
+        budget.max(1)
+    );
+    crate::scrape::set_fetch_budget(Some(Arc::new(Semaphore::new(budget.max(1)))));
+
+    let results: Vec<(usize, String, anyhow::Result<()>)> = stream::iter(jobs)
+        .map(|Job { line, url, run }| async move {
+            let label = url.clone().unwrap_or_else(|| run.output.clone());
+            let result = async {
+                let (url, source) = crate::url_source(url, Vec::new(), &run)?;
+                crate::run_scrape(url, run, source).await
+            }
+            .await;
+            match &result {
+                Ok(()) => progress!("✅ Job {line} done: {label}"),
+                Err(e) => tracing::error!("Job {line} ({label}) failed: {e:#}"),
+            }
+            (line, label, result)
+        })
+        .buffer_unordered(sites.max(1))
+        .collect()
+        .await;
+    crate::scrape::set_fetch_budget(None);
+
+    let failed: Vec<_> = results.iter().filter(|(.., r)| r.is_err()).collect();
+    progress!(
+        "🗂️  Batch finished: {} of {} jobs succeeded",
+        results.len() - failed.len(),
+        results.len()
+    );
+    if !failed.is_empty() {
+        let lines: Vec<String> = failed
+            .iter()
+            .map(|(line, label, _)| format!("{line} ({label})"))
+            .collect();
+        anyhow::bail!("jobs failed on line {}", lines.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_get_their_own_flags_and_outputs() {
+        let jobs = parse_jobs(
+            "# weekly clients\n\
+             --url https://www.acme.com --max-depth 2 --exclude /blog/\n\
+             \n\
+             --url https://shop.example.org --no-js -c 16 -o 'out/shop/scraped.json'\n",
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].line, 2);
+        assert_eq!(jobs[0].run.output, "output/acme_com/scraped.json");
+        assert_eq!(jobs[1].run.output, "out/shop/scraped.json");
+        assert!(jobs[1].run.no_js);
+        assert_eq!(jobs[1].run.concurrency, 16);
+
+        let err = parse_jobs("--url https://a.com\n--url https://a.com/x\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("line 2"), "{err}");
+        let err = parse_jobs("--url https://a.com --tui\n").err().unwrap();
+        assert!(err.to_string().contains("--tui"), "{err}");
+        for flag in [
+            "--max-memory-mb 512",
+            "--client-cert c.pem --client-key k.pem",
+            "--oauth-token-url https://a.com/t",
+        ] {
+            let err = parse_jobs(&format!("--url https://a.com {flag}\n"))
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains("not supported"), "{err}");
+        }
+        assert!(parse_jobs("--max-depth 2\n").is_err());
+    }
+}
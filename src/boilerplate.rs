@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{BoilerplateBlock, ContentBlock, PageData};
+use crate::util::{blocks_to_plain_text, build_outline, content_metrics, count_words, hash_blocks};
+
+/// Fewest pages a run needs before a block can count as boilerplate —
+/// on two or three pages "on most pages" says nothing.
+const MIN_PAGES: usize = 4;
+
+/// Characters of block text kept in `BoilerplateBlock.sample`.
+const SAMPLE_CHARS: usize = 120;
+
+/// Normalized hash of one block: same rules as `content_hash`
+/// (whitespace collapsed, `local_path`s dropped), shortened.
+fn fingerprint(block: &ContentBlock) -> String {
+    hash_blocks(std::slice::from_ref(block))[..16].to_string()
+}
+
+/// Serde tag (`paragraph`, `image`, …) and the start of every string in
+/// the block, so image / form / embed blocks get a sample too.
+fn describe(block: &ContentBlock) -> (String, String) {
+    fn strings(v: &serde_json::Value, out: &mut Vec<String>) {
+        match v {
+            serde_json::Value::String(s) if !s.trim().is_empty() => out.push(s.trim().to_string()),
+            serde_json::Value::Array(items) => items.iter().for_each(|i| strings(i, out)),
+            serde_json::Value::Object(map) => map
+                .iter()
+                .filter(|(k, _)| *k != "type" && !k.ends_with("local_path"))
+                .for_each(|(_, v)| strings(v, out)),
+            _ => {}
+        }
+    }
+    let value = serde_json::to_value(block).unwrap_or_default();
+    let block_type = value
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut parts = Vec::new();
+    strings(&value, &mut parts);
+    let joined = parts.join(" ");
+    let sample = joined.split_whitespace().collect::<Vec<_>>().join(" ");
+    (block_type, sample.chars().take(SAMPLE_CHARS).collect())
+}
+
+/// Fingerprint every content block across the run and find the ones on
+/// more than `threshold_pct` % of pages — cookie banners, newsletter CTAs,
+/// footers rendered inside `<main>`. Each page records their indices in
+/// `boilerplate_blocks`; with `strip` they are removed from
+/// `content_blocks` instead, and the text, word count, outline, metrics
+/// and `content_hash` are recomputed. Returns the repeated blocks, most
+/// widespread first. Nothing is flagged with `threshold_pct` 0 or fewer
+/// than `MIN_PAGES` pages.
+pub(crate) fn detect_boilerplate(
+    pages: &mut [PageData],
+    threshold_pct: u8,
+    strip: bool,
+) -> Vec<BoilerplateBlock> {
+    if threshold_pct == 0 || pages.len() < MIN_PAGES {
+        return Vec::new();
+    }
+    let fingerprints: Vec<Vec<String>> = pages
+        .iter()
+        .map(|p| p.content_blocks.iter().map(fingerprint).collect())
+        .collect();
+    // fingerprint → pages carrying it, and the first block seen with it.
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut first: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (p, prints) in fingerprints.iter().enumerate() {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (b, print) in prints.iter().enumerate() {
+            if seen.insert(print) {
+                *counts.entry(print).or_default() += 1;
+                first.entry(print).or_insert((p, b));
+            }
+        }
+    }
+    let total = pages.len();
+    let repeated: HashSet<&str> = counts
+        .iter()
+        .filter(|(_, &n)| n >= 2 && n * 100 > threshold_pct as usize * total)
+        .map(|(&print, _)| print)
+        .collect();
+    if repeated.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found: Vec<BoilerplateBlock> = repeated
+        .iter()
+        .map(|&print| {
+            let (p, b) = first[print];
+            let (block_type, sample) = describe(&pages[p].content_blocks[b]);
+            let page_count = counts[print];
+            BoilerplateBlock {
+                fingerprint: print.to_string(),
+                block_type,
+                sample,
+                page_count,
+                page_share: (page_count as f64 / total as f64 * 1000.0).round() / 1000.0,
+            }
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        b.page_count
+            .cmp(&a.page_count)
+            .then(a.fingerprint.cmp(&b.fingerprint))
+    });
+
+    for (page, prints) in pages.iter_mut().zip(&fingerprints) {
+        let flagged: Vec<usize> = prints
+            .iter()
+            .enumerate()
+            .filter(|(_, print)| repeated.contains(print.as_str()))
+            .map(|(i, _)| i)
+            .collect();
+        if flagged.is_empty() {
+            continue;
+        }
+        if !strip {
+            page.boilerplate_blocks = flagged;
+            continue;
+        }
+        page.boilerplate_blocks.clear();
+        let mut index = 0;
+        page.content_blocks.retain(|_| {
+            index += 1;
+            !repeated.contains(prints[index - 1].as_str())
+        });
+        page.total_words = count_words(&page.content_blocks);
+        page.plain_text = blocks_to_plain_text(&page.content_blocks);
+        page.outline = build_outline(&page.content_blocks);
+        page.content_hash = hash_blocks(&page.content_blocks);
+        // The text-to-HTML ratio describes the document, not the blocks.
+        let ratio = page.content_metrics.text_to_html_ratio;
+        page.content_metrics = content_metrics(&page.content_blocks, page.total_words, 0, 0);
+        page.content_metrics.text_to_html_ratio = ratio;
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, paragraphs: &[&str]) -> PageData {
+        let mut page = crate::model::test_page(url);
+        page.content_blocks = paragraphs
+            .iter()
+            .map(|t| ContentBlock::Paragraph {
+                text: t.to_string(),
+                links: Vec::new(),
+                markdown: None,
+            })
+            .collect();
+        page
+    }
+
+    #[test]
+    fn blocks_on_most_pages_are_flagged_or_stripped() {
+        let cookie = "We use cookies.  Accept?";
+        let mut pages = vec![
+            page("https://x.com/", &["Welcome home", cookie]),
+            page("https://x.com/a", &["About us", "We use cookies. Accept?"]),
+            page("https://x.com/b", &["Blog", cookie, "Subscribe"]),
+            page("https://x.com/c", &["Contact", "Subscribe"]),
+        ];
+        let found = detect_boilerplate(&mut pages, 60, false);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].block_type, "paragraph");
+        assert_eq!(found[0].sample, "We use cookies. Accept?");
+        assert_eq!(found[0].page_count, 3);
+        assert_eq!(pages[2].boilerplate_blocks, vec![1]);
+        assert!(pages[3].boilerplate_blocks.is_empty());
+
+        let found = detect_boilerplate(&mut pages, 40, true);
+        assert_eq!(found.len(), 2);
+        assert_eq!(pages[2].content_blocks.len(), 1);
+        assert_eq!(pages[2].plain_text, "Blog");
+        assert_eq!(pages[2].total_words, 1);
+    }
+}
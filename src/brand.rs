@@ -605,7 +605,7 @@ pub(crate) fn dominant_colors_from_image(path: &Path, top_n: usize) -> Vec<Color
     }
 
     let mut entries: Vec<((u8, u8, u8), usize)> = counts.into_iter().collect();
-    entries.sort_by(|a, b| b.1.cmp(&a.1));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.1));
     entries.truncate(top_n);
 
     entries
@@ -823,7 +823,7 @@ mod tests {
 
     #[test]
     fn parse_google_fonts_url_handles_plus_encoded() {
-        let urls = vec![
+        let urls = [
             "https://fonts.googleapis.com/css?family=Inter:wght@400;700&family=Roboto&display=swap"
                 .to_string(),
         ];
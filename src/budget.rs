@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use url::Url;
+
+use crate::model::SkippedPage;
+
+/// `--budget "PATTERN=N"`: at most N of the pages whose path matches
+/// PATTERN.
+#[derive(Clone, Debug)]
+pub(crate) struct SectionBudget {
+    pattern: String,
+    limit: usize,
+}
+
+/// `--budget` parser: `/blog/*=200`.
+pub(crate) fn parse_section_budget(s: &str) -> Result<SectionBudget, String> {
+    let (pattern, limit) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("`{s}` is not a budget like \"/blog/*=200\""))?;
+    let pattern = pattern.trim();
+    if !pattern.starts_with('/') {
+        return Err(format!(
+            "`{pattern}`: a budget pattern is a path, starting with /"
+        ));
+    }
+    let limit = limit
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{limit}` is not a page count"))?;
+    Ok(SectionBudget {
+        pattern: pattern.to_string(),
+        limit,
+    })
+}
+
+/// Whether `path` matches a glob `pattern` in full; `*` matches any run of
+/// characters, `/` included, so `/blog/*` covers the whole section.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Pages taken per section as URLs come in, in order.
+pub(crate) struct SectionBudgets<'a> {
+    budgets: &'a [SectionBudget],
+    taken: Vec<usize>,
+    dropped: Vec<usize>,
+}
+
+impl<'a> SectionBudgets<'a> {
+    pub fn new(budgets: &'a [SectionBudget]) -> Self {
+        Self {
+            budgets,
+            taken: vec![0; budgets.len()],
+            dropped: vec![0; budgets.len()],
+        }
+    }
+
+    /// Count `url` against the first budget its path matches; false once
+    /// that section is full. URLs outside every section always pass.
+    pub fn admit(&mut self, url: &str) -> bool {
+        let Ok(parsed) = Url::parse(url) else {
+            return true;
+        };
+        let Some(i) = self
+            .budgets
+            .iter()
+            .position(|b| path_matches(&b.pattern, parsed.path()))
+        else {
+            return true;
+        };
+        if self.taken[i] < self.budgets[i].limit {
+            self.taken[i] += 1;
+            true
+        } else {
+            self.dropped[i] += 1;
+            false
+        }
+    }
+
+    /// One progress line per section that ran over its budget.
+    pub fn report(&self) {
+        for (i, budget) in self.budgets.iter().enumerate() {
+            if self.dropped[i] > 0 {
+                progress!(
+                    "📐 Budget {}={} reached: left out {} more URLs",
+                    budget.pattern,
+                    budget.limit,
+                    self.dropped[i]
+                );
+            }
+        }
+    }
+}
+
+/// `--max-query-variants`: distinct query strings kept per path, so
+/// faceted navigation (`?color=…&size=…&sort=…`) can't flood the queue
+/// with one listing page.
+pub(crate) struct QueryVariants {
+    /// 0 = unlimited.
+    limit: usize,
+    /// Query strings seen per `scheme://host/path`, parameters sorted so
+    /// their order doesn't make a new variant.
+    seen: HashMap<String, HashSet<String>>,
+    left_out: HashSet<String>,
+    skipped: Vec<SkippedPage>,
+}
+
+impl QueryVariants {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            seen: HashMap::new(),
+            left_out: HashSet::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Whether `url` may be enqueued: always without a query string,
+    /// otherwise while its path has fewer than `limit` other variants.
+    /// URLs left out are kept for `skipped_pages` as `query_variants`.
+    pub fn admit(&mut self, url: &str) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        let Ok(mut parsed) = Url::parse(url) else {
+            return true;
+        };
+        let mut pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+        if pairs.is_empty() {
+            return true;
+        }
+        pairs.sort();
+        let query = pairs
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        parsed.set_query(None);
+        parsed.set_fragment(None);
+        let variants = self.seen.entry(parsed.to_string()).or_default();
+        if variants.contains(&query) || variants.len() < self.limit {
+            variants.insert(query);
+            return true;
+        }
+        if self.left_out.insert(url.to_string()) {
+            self.skipped.push(SkippedPage {
+                url: url.to_string(),
+                reason: "query_variants".to_string(),
+                challenge: None,
+            });
+        }
+        false
+    }
+
+    /// A progress line when variants were left out, and the skipped pages.
+    pub fn finish(self) -> Vec<SkippedPage> {
+        if !self.skipped.is_empty() {
+            progress!(
+                "🔀 Left out {} query-string variants (--max-query-variants {} per path)",
+                self.skipped.len(),
+                self.limit
+            );
+        }
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_stop_at_their_budget() {
+        assert!(path_matches("/blog/*", "/blog/2019/05/post"));
+        assert!(!path_matches("/blog/*", "/blogroll"));
+        assert!(path_matches("/*/reviews", "/p/42/reviews"));
+        assert!(!path_matches("/*/reviews", "/p/42/reviews/2"));
+        assert!(path_matches("/about", "/about"));
+        assert!(parse_section_budget("blog=5").is_err());
+        assert!(parse_section_budget("/blog/*=many").is_err());
+
+        let budgets = [
+            parse_section_budget("/blog/*=2").unwrap(),
+            parse_section_budget("/*=100").unwrap(),
+        ];
+        let mut sections = SectionBudgets::new(&budgets[..1]);
+        let kept: Vec<bool> = ["/blog/a", "/blog/b", "/products/x", "/blog/c?page=2"]
+            .iter()
+            .map(|p| sections.admit(&format!("https://x.com{p}")))
+            .collect();
+        assert_eq!(kept, [true, true, true, false]);
+        assert_eq!(sections.dropped, [1]);
+
+        // The first matching budget is the one that counts.
+        let mut sections = SectionBudgets::new(&budgets);
+        for _ in 0..3 {
+            sections.admit("https://x.com/blog/a");
+        }
+        assert_eq!(sections.taken, [2, 0]);
+    }
+
+    #[test]
+    fn query_variants_are_capped_per_path() {
+        let mut variants = QueryVariants::new(2);
+        let kept: Vec<bool> = [
+            "https://x.com/shoes",
+            "https://x.com/shoes?color=red&size=9",
+            "https://x.com/shoes?size=9&color=red",
+            "https://x.com/shoes?color=blue",
+            "https://x.com/shoes?color=blue&sort=price",
+            "https://x.com/shoes?color=blue&sort=price",
+            "https://x.com/bags?color=blue&sort=price",
+            "https://x.com/shoes?color=red&size=9#reviews",
+        ]
+        .iter()
+        .map(|u| variants.admit(u))
+        .collect();
+        assert_eq!(kept, [true, true, true, true, false, false, true, true]);
+        let skipped = variants.finish();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].url, "https://x.com/shoes?color=blue&sort=price");
+        assert_eq!(skipped[0].reason, "query_variants");
+        assert!(QueryVariants::new(0).admit("https://x.com/shoes?page=9"));
+    }
+}
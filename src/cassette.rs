@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use reqwest::{Client, Request, Response, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// `--record` / `--replay`: the cassette directory and what to do with it.
+/// `None` while neither is on, which makes [`execute`] a plain `execute`.
+static CASSETTE: Mutex<Option<Cassette>> = Mutex::new(None);
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Mode {
+    /// Save every response (and every Chrome-rendered page) to the cassette.
+    Record,
+    /// Answer every request from the cassette; nothing goes to the network.
+    Replay,
+}
+
+#[derive(Clone)]
+struct Cassette {
+    mode: Mode,
+    dir: PathBuf,
+}
+
+/// One recorded response, stored as `<key>.json` next to its body in
+/// `<key>.body`.
+#[derive(Serialize, Deserialize)]
+struct Tape {
+    method: String,
+    url: String,
+    /// URL the response came from after redirects.
+    final_url: String,
+    status: u16,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    /// The document Chrome rendered rather than an HTTP response; replays
+    /// serve it as a plain HTML response.
+    #[serde(default)]
+    rendered: bool,
+}
+
+/// File stem of a request in the cassette: a hash of method, URL and
+/// request body (so POSTs with different payloads don't collide).
+pub(crate) fn key(method: &str, url: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(url.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher
+        .finalize()
+        .iter()
+        .take(16)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn parse_version(version: &str) -> http::Version {
+    match version {
+        "HTTP/0.9" => http::Version::HTTP_09,
+        "HTTP/1.0" => http::Version::HTTP_10,
+        "HTTP/2.0" => http::Version::HTTP_2,
+        "HTTP/3.0" => http::Version::HTTP_3,
+        _ => http::Version::HTTP_11,
+    }
+}
+
+fn write_tape(dir: &Path, key: &str, tape: &Tape, body: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(dir.join(format!("{key}.body")), body)?;
+    std::fs::write(
+        dir.join(format!("{key}.json")),
+        serde_json::to_string_pretty(tape)?,
+    )?;
+    Ok(())
+}
+
+/// Replayed response of a request, `None` when it isn't in the cassette.
+fn read_tape(dir: &Path, key: &str) -> Option<Response> {
+    let tape: Tape =
+        serde_json::from_slice(&std::fs::read(dir.join(format!("{key}.json"))).ok()?).ok()?;
+    let body = std::fs::read(dir.join(format!("{key}.body"))).ok()?;
+    response(&tape, body)
+}
+
+fn response(tape: &Tape, body: Vec<u8>) -> Option<Response> {
+    let mut builder = http::Response::builder()
+        .status(tape.status)
+        .version(parse_version(&tape.http_version))
+        .url(Url::parse(&tape.final_url).ok()?);
+    for (name, value) in &tape.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder.body(body).ok().map(Response::from)
+}
+
+/// Turn the cassette in `dir` on for the rest of the run. A replay needs
+/// the directory to exist; a recording creates it and replaces the tapes
+/// of the requests it makes again.
+pub(crate) fn start(mode: Mode, dir: &Path) -> anyhow::Result<()> {
+    match mode {
+        Mode::Record => std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cassette {}", dir.display()))?,
+        Mode::Replay if !dir.is_dir() => {
+            anyhow::bail!("cassette {} does not exist", dir.display())
+        }
+        Mode::Replay => {}
+    }
+    *CASSETTE.lock().unwrap() = Some(Cassette {
+        mode,
+        dir: dir.to_path_buf(),
+    });
+    Ok(())
+}
+
+fn cassette() -> Option<Cassette> {
+    CASSETTE.lock().unwrap().clone()
+}
+
+/// The `404` a request gets when it isn't on disk to answer from.
+pub(crate) fn not_recorded(url: &Url) -> Response {
+    let missing = http::Response::builder()
+        .status(404)
+        .url(url.clone())
+        .body(Vec::new())
+        .expect("static response parts");
+    Response::from(missing)
+}
+
+/// Execute `request`, through the cassette when one is on. Recording reads
+/// the whole body up front to save it and hands back an identical response
+/// over the saved bytes. A replayed request missing from the cassette
+/// answers `404` (it never reached a server). `file://` URLs are read
+/// from disk and never recorded.
+pub(crate) async fn execute(client: &Client, request: Request) -> reqwest::Result<Response> {
+    if request.url().scheme() == "file" {
+        return Ok(crate::local::file_response(request.url()).await);
+    }
+    let Some(cassette) = cassette() else {
+        return crate::httpcache::execute(client, request).await;
+    };
+    let method = request.method().to_string();
+    let url = request.url().to_string();
+    let key = key(
+        &method,
+        &url,
+        request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .unwrap_or_default(),
+    );
+    if cassette.mode == Mode::Replay {
+        if let Some(resp) = read_tape(&cassette.dir, &key) {
+            return Ok(resp);
+        }
+        tracing::warn!("Not in the cassette: {method} {url}");
+        return Ok(not_recorded(request.url()));
+    }
+
+    let resp = crate::httpcache::execute(client, request).await?;
+    let tape = Tape {
+        method,
+        url,
+        final_url: resp.url().to_string(),
+        status: resp.status().as_u16(),
+        http_version: format!("{:?}", resp.version()),
+        headers: resp
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.to_string(), value)
+            })
+            .collect(),
+        rendered: false,
+    };
+    let body = resp.bytes().await?.to_vec();
+    if let Err(e) = write_tape(&cassette.dir, &key, &tape, &body) {
+        tracing::warn!("Failed to record {}: {e}", tape.url);
+    }
+    Ok(response(&tape, body).expect("parts of a live response"))
+}
+
+/// Record the document Chrome rendered for `url` as its GET response, so
+/// a replay (which runs without Chrome) extracts the same DOM.
+pub(crate) fn record_rendered(url: &str, final_url: &str, html: &str) {
+    let Some(cassette) = cassette().filter(|c| c.mode == Mode::Record) else {
+        return;
+    };
+    let tape = Tape {
+        method: "GET".to_string(),
+        url: url.to_string(),
+        final_url: final_url.to_string(),
+        status: 200,
+        http_version: "HTTP/1.1".to_string(),
+        headers: vec![(
+            "content-type".to_string(),
+            "text/html; charset=utf-8".to_string(),
+        )],
+        rendered: true,
+    };
+    if let Err(e) = write_tape(&cassette.dir, &key("GET", url, &[]), &tape, html.as_bytes()) {
+        tracing::warn!("Failed to record {url}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_tapes_replay_as_responses() {
+        let dir = std::env::temp_dir().join(format!("dump-it-cassette-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tape = Tape {
+            method: "GET".into(),
+            url: "https://x.com/old".into(),
+            final_url: "https://x.com/new".into(),
+            status: 200,
+            http_version: "HTTP/2.0".into(),
+            headers: vec![("content-type".into(), "text/html".into())],
+            rendered: false,
+        };
+        let k = key("GET", "https://x.com/old", &[]);
+        write_tape(&dir, &k, &tape, b"<h1>Hi</h1>").unwrap();
+
+        let resp = read_tape(&dir, &k).unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.url().as_str(), "https://x.com/new");
+        assert_eq!(resp.version(), http::Version::HTTP_2);
+        assert_eq!(resp.content_length(), Some(11));
+        assert!(read_tape(&dir, &key("HEAD", "https://x.com/old", &[])).is_none());
+        assert_ne!(k, key("POST", "https://x.com/old", b"{}"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
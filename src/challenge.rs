@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use reqwest::header::HeaderMap;
+use url::Url;
+
+use crate::model::{ChallengeHost, PageData, SkippedPage};
+
+/// HTTP statuses bot protection answers with when it blocks a request.
+/// Their bodies are checked for a challenge before the page counts as a
+/// plain `http_error`.
+pub(crate) const CHALLENGE_STATUSES: &[u16] = &[403, 429, 503];
+
+/// How long `--solve-challenges` lets a challenge run in Chrome.
+pub(crate) const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `CHALLENGE_TIMEOUT`, cut to what's left of the page budget that ends
+/// at `deadline`. `None` once the budget is spent: not worth opening a tab.
+pub(crate) fn challenge_timeout(deadline: Option<Instant>) -> Option<Duration> {
+    let Some(deadline) = deadline else {
+        return Some(CHALLENGE_TIMEOUT);
+    };
+    let left = deadline.saturating_duration_since(Instant::now());
+    (!left.is_zero()).then(|| left.min(CHALLENGE_TIMEOUT))
+}
+
+/// Challenge documents are small; a page bigger than this that mentions a
+/// vendor marker is real content with the vendor's script on it.
+const CHALLENGE_MAX_BYTES: usize = 256 * 1024;
+
+/// Lowercased markers of each vendor's challenge / block page: the
+/// challenge's own scripts and elements first, then phrases of its text.
+/// Cloudflare's bare `challenge-platform` path is left out on purpose: its
+/// bot-detection script (`/cdn-cgi/challenge-platform/scripts/jsd/`) sits
+/// on normal pages too; only the challenge orchestrator
+/// (`…/challenge-platform/h/`) counts. The same goes for PerimeterX's
+/// sensor script: only its block page does.
+const SIGNATURES: &[(&str, &[&str], &[&str])] = &[
+    (
+        "cloudflare",
+        &[
+            "cf-browser-verification",
+            "cf-challenge-running",
+            "/cdn-cgi/challenge-platform/h/",
+            "window._cf_chl_opt",
+        ],
+        &[
+            "<title>just a moment...</title>",
+            "attention required! | cloudflare",
+            "ddos protection by cloudflare",
+            "checking your browser before accessing",
+            "verifying you are human",
+        ],
+    ),
+    (
+        "perimeterx",
+        &["px-captcha"],
+        &["access to this page has been denied"],
+    ),
+    (
+        "akamai",
+        &["/_sec/cp_challenge", "sec-if-cpt-container"],
+        &["errors.edgesuite.net"],
+    ),
+    (
+        "datadome",
+        &["captcha-delivery.com", "geo.captcha-delivery"],
+        &[],
+    ),
+];
+
+/// Vendor whose challenge or block page `html` is — `cloudflare`,
+/// `perimeterx`, `akamai` or `datadome` — if it is one. Phrases like
+/// "Verifying you are human" turn up in articles too, so they only count
+/// when the response already looks blocked (`suspected`, see
+/// [`challenge_suspected`]); the challenges' own markers always do.
+pub(crate) fn challenge_vendor(html: &str, suspected: bool) -> Option<&'static str> {
+    if html.len() > CHALLENGE_MAX_BYTES {
+        return None;
+    }
+    let lc = html.to_lowercase();
+    SIGNATURES
+        .iter()
+        .find(|(_, markers, phrases)| {
+            markers.iter().any(|m| lc.contains(m))
+                || (suspected && phrases.iter().any(|p| lc.contains(p)))
+        })
+        .map(|(vendor, _, _)| *vendor)
+}
+
+/// Whether a response looks like bot protection stepped in: a
+/// [`CHALLENGE_STATUSES`] status, a [`header_vendor`] header or
+/// `Server: cloudflare`.
+pub(crate) fn challenge_suspected(status: u16, headers: &HeaderMap) -> bool {
+    CHALLENGE_STATUSES.contains(&status)
+        || header_vendor(headers).is_some()
+        || headers
+            .get(reqwest::header::SERVER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("cloudflare"))
+}
+
+/// Vendor named by the response headers: Cloudflare's
+/// `cf-mitigated: challenge`, DataDome's `x-datadome: protected`.
+pub(crate) fn header_vendor(headers: &HeaderMap) -> Option<&'static str> {
+    let is = |name: &str, value: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(value))
+    };
+    if is("cf-mitigated", "challenge") {
+        Some("cloudflare")
+    } else if is("x-datadome", "protected") {
+        Some("datadome")
+    } else {
+        None
+    }
+}
+
+fn host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Challenges rolled up by host and vendor: `bot_protected` skips, and
+/// pages `--solve-challenges` got through. Most pages first.
+pub(crate) fn challenge_hosts(skipped: &[SkippedPage], pages: &[PageData]) -> Vec<ChallengeHost> {
+    // (host, vendor) → (blocked, passed)
+    let mut counts: BTreeMap<(String, String), (usize, usize)> = BTreeMap::new();
+    for page in skipped.iter().filter(|s| s.reason == "bot_protected") {
+        let vendor = page.challenge.clone().unwrap_or_else(|| "unknown".into());
+        counts.entry((host(&page.url), vendor)).or_default().0 += 1;
+    }
+    for page in pages {
+        if let Some(vendor) = &page.challenge_passed {
+            counts
+                .entry((host(&page.url), vendor.clone()))
+                .or_default()
+                .1 += 1;
+        }
+    }
+    let mut hosts: Vec<ChallengeHost> = counts
+        .into_iter()
+        .map(|((host, vendor), (pages, passed))| ChallengeHost {
+            host,
+            vendor,
+            pages,
+            passed,
+        })
+        .collect();
+    hosts.sort_by_key(|h| std::cmp::Reverse(h.pages + h.passed));
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_pages_are_attributed_to_vendors() {
+        let cf = "<html><head><title>Just a moment...</title></head>\
+                  <body><script>window._cf_chl_opt={}</script></body></html>";
+        assert_eq!(challenge_vendor(cf, false), Some("cloudflare"));
+        let px = "<div id='px-captcha'></div><p>Press and hold to confirm</p>";
+        assert_eq!(challenge_vendor(px, false), Some("perimeterx"));
+        let normal = "<title>Shop</title>\
+                      <script src='/cdn-cgi/challenge-platform/scripts/jsd/main.js'></script>";
+        assert_eq!(challenge_vendor(normal, true), None);
+        let article = "<title>Just a moment...</title><p>Verifying you are human, explained</p>";
+        assert_eq!(challenge_vendor(article, false), None);
+        assert_eq!(challenge_vendor(article, true), Some("cloudflare"));
+
+        let mut headers = HeaderMap::new();
+        assert!(!challenge_suspected(200, &headers));
+        assert!(challenge_suspected(503, &headers));
+        headers.insert("server", "cloudflare".parse().unwrap());
+        assert!(challenge_suspected(200, &headers));
+
+        let skip = |url: &str, vendor: &str| SkippedPage {
+            url: url.into(),
+            reason: "bot_protected".into(),
+            challenge: Some(vendor.into()),
+        };
+        let hosts = challenge_hosts(
+            &[
+                skip("https://a.com/1", "cloudflare"),
+                skip("https://shop.b.com/1", "akamai"),
+                skip("https://shop.b.com/2", "akamai"),
+                SkippedPage {
+                    url: "https://a.com/2".into(),
+                    reason: "http_error".into(),
+                    challenge: None,
+                },
+            ],
+            &[],
+        );
+        let rows: Vec<(&str, &str, usize)> = hosts
+            .iter()
+            .map(|h| (h.host.as_str(), h.vendor.as_str(), h.pages))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("shop.b.com", "akamai", 2), ("a.com", "cloudflare", 1)]
+        );
+    }
+
+    #[test]
+    fn challenge_timeout_stays_within_the_page_budget() {
+        assert_eq!(challenge_timeout(None), Some(CHALLENGE_TIMEOUT));
+        let later = Instant::now() + Duration::from_secs(3600);
+        assert_eq!(challenge_timeout(Some(later)), Some(CHALLENGE_TIMEOUT));
+        let soon = challenge_timeout(Some(Instant::now() + Duration::from_secs(5))).unwrap();
+        assert!(soon <= Duration::from_secs(5) && !soon.is_zero());
+        assert_eq!(challenge_timeout(Some(Instant::now())), None);
+    }
+}
@@ -4,36 +4,34 @@ use std::time::Duration;
 
 use crate::util::normalize_path;
 
-/// Returns `true` if the HTML body looks like a bot-protection / challenge
-/// interstitial (Cloudflare "Just a moment...", PerimeterX, Akamai, etc.)
-/// rather than the real page. We don't pretend to bypass these — we just
-/// flag them so the caller can drop the page and the user knows why.
-fn looks_like_challenge_page(title_or_html: &str) -> bool {
-    let lc = title_or_html.to_lowercase();
-    lc.contains("just a moment...")
-        || lc.contains("verifying you are human")
-        || lc.contains("cf-browser-verification")
-        || lc.contains("cf-challenge-running")
-        || lc.contains("challenge-platform")
-        || lc.contains("checking your browser before accessing")
-        || lc.contains("attention required! | cloudflare")
-        || lc.contains("ddos protection by cloudflare")
-        || lc.contains("/_px/")
-        || lc.contains("perimeterx")
+/// Whether Chrome may be pointed at `url`, see [`crate::local::navigable`].
+fn may_open(url: &str) -> bool {
+    let ok = crate::local::navigable(url);
+    if !ok {
+        tracing::warn!("Not opening {url} in Chrome: not a web URL or a local target's file");
+    }
+    ok
 }
 
-/// Render a single page in headless Chrome and return its HTML.
+/// Render a single page in headless Chrome and return its HTML and the
+/// URL it ended up on (after redirects).
 ///
 /// Always closes the tab before returning so the browser doesn't leak tabs
 /// across a large crawl. If `wait_selector` is provided, waits for that
 /// element to appear (with a short post-buffer); otherwise falls back to a
-/// fixed `js_wait_ms` sleep.
+/// fixed `js_wait_ms` sleep. `mobile` renders with the
+/// `--compare-mobile` user agent and viewport. Challenge interstitials come
+/// back like any page; `challenge::challenge_vendor` tells them apart.
 pub(crate) fn render_in_chrome(
     browser: &Arc<Browser>,
     url: &str,
     js_wait_ms: u64,
     wait_selector: Option<&str>,
-) -> Option<String> {
+    mobile: bool,
+) -> Option<(String, String)> {
+    if !may_open(url) {
+        return None;
+    }
     let tab = match browser.new_tab() {
         Ok(t) => t,
         Err(e) => {
@@ -42,7 +40,35 @@ pub(crate) fn render_in_chrome(
         }
     };
 
-    let html = (|| -> Option<String> {
+    let html = (|| -> Option<(String, String)> {
+        if mobile {
+            use headless_chrome::protocol::cdp::Emulation;
+            let (width, height) = crate::mobile::MOBILE_VIEWPORT;
+            let emulated = tab
+                .set_user_agent(crate::mobile::MOBILE_USER_AGENT, None, None)
+                .and_then(|_| {
+                    tab.call_method(Emulation::SetDeviceMetricsOverride {
+                        width,
+                        height,
+                        device_scale_factor: 1.0,
+                        mobile: true,
+                        scale: None,
+                        screen_width: None,
+                        screen_height: None,
+                        position_x: None,
+                        position_y: None,
+                        dont_set_visible_size: None,
+                        screen_orientation: None,
+                        viewport: None,
+                        display_feature: None,
+                        device_posture: None,
+                    })
+                });
+            if let Err(e) = emulated {
+                tracing::warn!("Failed to emulate mobile for {url}: {e}");
+                return None;
+            }
+        }
         if let Err(e) = tab.navigate_to(url) {
             tracing::warn!("Failed to navigate {url}: {e}");
             return None;
@@ -63,15 +89,7 @@ pub(crate) fn render_in_chrome(
             std::thread::sleep(Duration::from_millis(js_wait_ms));
         }
         match tab.get_content() {
-            Ok(content) => {
-                if looks_like_challenge_page(&content) {
-                    tracing::warn!(
-                        "Bot-protection / challenge interstitial detected on {url} — skipping"
-                    );
-                    return None;
-                }
-                Some(content)
-            }
+            Ok(content) => Some((content, tab.get_url())),
             Err(e) => {
                 tracing::warn!("Failed to read content from {url}: {e}");
                 None
@@ -83,6 +101,59 @@ pub(crate) fn render_in_chrome(
     html
 }
 
+/// `--solve-challenges`: load `url` in a fresh tab and give its JS
+/// challenge up to `timeout` to clear, polling the DOM every 500 ms. Once
+/// no challenge marker is left, waits `js_wait_ms` for the real page and
+/// returns it like `render_in_chrome`; `None` if the challenge never
+/// clears. Clearance cookies stay in the browser, so later pages of the
+/// host usually get through at once.
+pub(crate) fn pass_challenge(
+    browser: &Arc<Browser>,
+    url: &str,
+    js_wait_ms: u64,
+    timeout: Duration,
+) -> Option<(String, String)> {
+    if !may_open(url) {
+        return None;
+    }
+    let tab = match browser.new_tab() {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("Failed to open Chrome tab for {url}: {e}");
+            return None;
+        }
+    };
+
+    let html = (|| -> Option<(String, String)> {
+        if let Err(e) = tab.navigate_to(url) {
+            tracing::warn!("Failed to navigate {url}: {e}");
+            return None;
+        }
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            std::thread::sleep(Duration::from_millis(500));
+            let cleared = tab
+                .get_content()
+                .is_ok_and(|c| crate::challenge::challenge_vendor(&c, true).is_none());
+            if cleared {
+                break;
+            }
+            if std::time::Instant::now() >= deadline {
+                return None;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(js_wait_ms));
+        let content = tab.get_content().ok()?;
+        if crate::challenge::challenge_vendor(&content, true).is_some() {
+            return None;
+        }
+        Some((content, tab.get_url()))
+    })();
+
+    let _ = tab.close(true);
+    html
+}
+
 /// Render at the requested viewport and capture a full-page PNG screenshot.
 /// Returns the relative `output/...` path on success.
 pub(crate) fn capture_screenshot(
@@ -97,6 +168,10 @@ pub(crate) fn capture_screenshot(
     use headless_chrome::protocol::cdp::Emulation;
     use headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption;
 
+    if !may_open(url) {
+        return None;
+    }
+
     let tab = match browser.new_tab() {
         Ok(t) => t,
         Err(e) => {
@@ -0,0 +1,251 @@
+use crate::model::{Chunk, ContentBlock, PageData};
+
+/// Same ~4 chars/token heuristic as `PageData.token_estimate`.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Readable text of a block as it should appear inside a chunk. `None` for
+/// headings (they become the heading path) and for blocks with nothing to
+/// embed (images, forms, embeds, media, CTA buttons).
+pub(crate) fn block_text(block: &ContentBlock) -> Option<String> {
+    match block {
+        ContentBlock::Paragraph { text, .. } | ContentBlock::Code { text, .. } => {
+            Some(text.clone())
+        }
+        ContentBlock::Quote { text, cite } => Some(match cite {
+            Some(c) => format!("{text} — {c}"),
+            None => text.clone(),
+        }),
+        ContentBlock::List { items, .. } => Some(
+            items
+                .iter()
+                .map(|i| format!("- {i}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        ContentBlock::Table {
+            caption,
+            headers,
+            rows,
+        } => {
+            let mut lines: Vec<String> = caption.iter().cloned().collect();
+            if !headers.is_empty() {
+                lines.push(headers.join(" | "));
+            }
+            lines.extend(rows.iter().map(|r| r.join(" | ")));
+            Some(lines.join("\n"))
+        }
+        ContentBlock::DefinitionList { items } => Some(
+            items
+                .iter()
+                .map(|d| format!("{}: {}", d.term, d.description))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        ContentBlock::Heading { .. }
+        | ContentBlock::Image { .. }
+        | ContentBlock::Form { .. }
+        | ContentBlock::Embed { .. }
+        | ContentBlock::Media { .. }
+        | ContentBlock::Video { .. }
+        | ContentBlock::Cta { .. } => None,
+    }
+}
+
+/// Text between two headings, with the path of headings enclosing it.
+struct Section {
+    heading_path: Vec<String>,
+    text: String,
+}
+
+fn sections(blocks: &[ContentBlock]) -> Vec<Section> {
+    let mut out = Vec::new();
+    let mut path: Vec<(u8, String)> = Vec::new();
+    let mut current = Section {
+        heading_path: Vec::new(),
+        text: String::new(),
+    };
+    for block in blocks {
+        if let ContentBlock::Heading { level, text, .. } = block {
+            if !current.text.is_empty() {
+                out.push(current);
+            }
+            path.retain(|(l, _)| l < level);
+            path.push((*level, text.clone()));
+            current = Section {
+                heading_path: path.iter().map(|(_, t)| t.clone()).collect(),
+                text: String::new(),
+            };
+            continue;
+        }
+        let Some(text) = block_text(block) else {
+            continue;
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        if !current.text.is_empty() {
+            current.text.push_str("\n\n");
+        }
+        current.text.push_str(&text);
+    }
+    if !current.text.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// Byte ranges of `text` in windows of at most `max_chars` characters,
+/// consecutive windows sharing about `overlap_chars`. Windows end at the
+/// last whitespace in their second half when there is one, and the next
+/// window starts on a word boundary, so words aren't cut in two.
+pub(crate) fn split_with_overlap(
+    text: &str,
+    max_chars: usize,
+    overlap_chars: usize,
+) -> Vec<(usize, usize)> {
+    let max_chars = max_chars.max(1);
+    let overlap = overlap_chars.min(max_chars / 2);
+    // bounds[k] = byte offset of char k; bounds[n] = text.len().
+    let bounds: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let n = bounds.len() - 1;
+    let is_space_at = |k: usize| text[bounds[k]..].starts_with(char::is_whitespace);
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start < n {
+        let mut end = (start + max_chars).min(n);
+        if end < n {
+            if let Some(ws) = (start + max_chars / 2..end).rev().find(|&k| is_space_at(k)) {
+                end = ws;
+            }
+        }
+        out.push((bounds[start], bounds[end]));
+        if end >= n {
+            break;
+        }
+        let mut next = end.saturating_sub(overlap).max(start + 1);
+        while next < end && !is_space_at(next - 1) {
+            next += 1;
+        }
+        start = next;
+    }
+    out
+}
+
+/// Split a page into retrieval chunks. Chunks never cross a heading, so
+/// every chunk has an exact `heading_path`; long sections are windowed
+/// with overlap. Sizes are in tokens (~4 chars each).
+pub(crate) fn chunk_blocks(
+    url: &str,
+    title: &str,
+    blocks: &[ContentBlock],
+    chunk_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for (section_index, section) in sections(blocks).into_iter().enumerate() {
+        let ranges = split_with_overlap(
+            &section.text,
+            chunk_tokens * CHARS_PER_TOKEN,
+            overlap_tokens * CHARS_PER_TOKEN,
+        );
+        for (start, end) in ranges {
+            let raw = &section.text[start..end];
+            let text = raw.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let lead = raw.len() - raw.trim_start().len();
+            let char_start = section.text[..start + lead].chars().count();
+            let char_len = text.chars().count();
+            let chunk_index = chunks.len();
+            chunks.push(Chunk {
+                id: format!("{url}#chunk-{chunk_index}"),
+                url: url.to_string(),
+                title: title.to_string(),
+                heading_path: section.heading_path.clone(),
+                chunk_index,
+                section_index,
+                char_start,
+                char_end: char_start + char_len,
+                token_estimate: char_len / CHARS_PER_TOKEN,
+                text: text.to_string(),
+                embedding: None,
+            });
+        }
+    }
+    chunks
+}
+
+pub(crate) fn chunk_page(
+    page: &PageData,
+    chunk_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<Chunk> {
+    chunk_blocks(
+        &page.url,
+        &page.title,
+        &page.content_blocks,
+        chunk_tokens,
+        overlap_tokens,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_with_overlap_breaks_on_words_and_overlaps() {
+        let text = "alpha beta gamma delta epsilon zeta eta theta";
+        let ranges = split_with_overlap(text, 16, 6);
+        let parts: Vec<&str> = ranges.iter().map(|&(s, e)| text[s..e].trim()).collect();
+        assert_eq!(parts[0], "alpha beta");
+        assert!(parts.len() > 2);
+        // Every part is whole words, and consecutive parts share one.
+        for p in &parts {
+            assert!(p.split_whitespace().all(|w| text.contains(w)));
+        }
+        assert!(parts[1].starts_with("beta"));
+        assert_eq!(ranges.last().unwrap().1, text.len());
+        assert!(split_with_overlap("", 10, 2).is_empty());
+    }
+
+    #[test]
+    fn chunk_blocks_tracks_heading_path() {
+        let h = |level: u8, text: &str| ContentBlock::Heading {
+            level,
+            text: text.into(),
+            markdown: None,
+        };
+        let p = |text: &str| ContentBlock::Paragraph {
+            text: text.into(),
+            links: Vec::new(),
+            markdown: None,
+        };
+        let blocks = vec![
+            p("Intro before any heading."),
+            h(1, "Guide"),
+            h(2, "Install"),
+            p("Run the installer."),
+            h(2, "Usage"),
+            p("Call it."),
+        ];
+        let chunks = chunk_blocks("https://x.com/g", "Guide", &blocks, 512, 64);
+        let paths: Vec<Vec<String>> = chunks.iter().map(|c| c.heading_path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                vec![],
+                vec!["Guide".to_string(), "Install".to_string()],
+                vec!["Guide".to_string(), "Usage".to_string()],
+            ]
+        );
+        assert_eq!(chunks[1].text, "Run the installer.");
+        assert_eq!(chunks[2].id, "https://x.com/g#chunk-2");
+        assert_eq!(chunks[2].char_end, "Call it.".len());
+    }
+}
@@ -0,0 +1,251 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+/// Where `--profile` looks for profiles without `--config`, in order.
+fn default_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from("dump-it.toml")];
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    if let Some(dir) = config_home {
+        paths.push(dir.join("dump-it").join("config.toml"));
+    }
+    paths
+}
+
+/// Request headers that can come from the environment instead of
+/// `--header`, which would put the secret in shell history, a profile or a
+/// jobs file: variable, header name, value prefix.
+const ENV_HEADERS: &[(&str, &str, &str)] = &[
+    ("DUMPIT_BEARER_TOKEN", "Authorization", "Bearer "),
+    ("DUMPIT_COOKIE", "Cookie", ""),
+];
+
+/// `--header` values plus those from `DUMPIT_BEARER_TOKEN`,
+/// `DUMPIT_COOKIE` and `DUMPIT_HEADERS` (one `Name: value` per line), in
+/// that order. A header already given with `--header` (on the command
+/// line or in a profile) keeps that value. All of them go to the target
+/// site's origin only ([`crate::auth::scope_headers`]), never as client
+/// defaults.
+pub(crate) fn request_headers(headers: &[String]) -> Vec<String> {
+    merge_env_headers(headers, |var| std::env::var(var).ok())
+}
+
+fn merge_env_headers(headers: &[String], env: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let name_of = |header: &str| {
+        header
+            .split_once(':')
+            .map(|(name, _)| name.trim().to_ascii_lowercase())
+    };
+    let mut from_env = Vec::new();
+    for (var, name, prefix) in ENV_HEADERS {
+        if let Some(value) = env(var).filter(|v| !v.trim().is_empty()) {
+            from_env.push(format!("{name}: {prefix}{}", value.trim()));
+        }
+    }
+    let listed = env("DUMPIT_HEADERS").unwrap_or_default();
+    from_env.extend(
+        listed
+            .lines()
+            .map(str::trim)
+            .filter(|l| l.contains(':'))
+            .map(str::to_string),
+    );
+    let mut all = headers.to_vec();
+    for header in from_env {
+        let name = name_of(&header);
+        if !all.iter().any(|h| name_of(h) == name) {
+            all.push(header);
+        }
+    }
+    all
+}
+
+/// Remove `--<name> VALUE` / `--<name>=VALUE` from `args` and return the
+/// value. Stops at `--`.
+fn take_option(args: &mut Vec<String>, name: &str) -> anyhow::Result<Option<String>> {
+    let flag = format!("--{name}");
+    let prefix = format!("--{name}=");
+    let mut value = None;
+    let mut i = 0;
+    while i < args.len() && args[i] != "--" {
+        if args[i] == flag {
+            let Some(next) = args.get(i + 1).cloned() else {
+                anyhow::bail!("{flag} needs a value");
+            };
+            args.drain(i..i + 2);
+            value = Some(next);
+        } else if let Some(rest) = args[i].strip_prefix(&prefix) {
+            value = Some(rest.to_string());
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    Ok(value)
+}
+
+/// A scalar setting as a command-line value.
+fn flag_value(key: &str, value: &toml::Value) -> anyhow::Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(n) => Ok(n.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        _ => anyhow::bail!("{key}: expected a string or number"),
+    }
+}
+
+/// The flags a profile stands for. Keys are long flag names (`no-js` or
+/// `no_js`): `true` sets a switch, `false` leaves it out, arrays repeat
+/// the flag.
+fn profile_flags(config: &toml::Table, name: &str) -> anyhow::Result<Vec<String>> {
+    let profiles = config.get("profiles").and_then(toml::Value::as_table);
+    let Some(profile) = profiles
+        .and_then(|p| p.get(name))
+        .and_then(toml::Value::as_table)
+    else {
+        let known: Vec<&str> = profiles
+            .map(|p| p.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+        anyhow::bail!(
+            "no profile {name} (profiles: {})",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        );
+    };
+    let mut flags = Vec::new();
+    for (key, value) in profile {
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value {
+            toml::Value::Boolean(true) => flags.push(flag),
+            toml::Value::Boolean(false) => {}
+            toml::Value::Array(items) => {
+                for item in items {
+                    flags.push(flag.clone());
+                    flags.push(flag_value(key, item)?);
+                }
+            }
+            other => {
+                flags.push(flag);
+                flags.push(flag_value(key, other)?);
+            }
+        }
+    }
+    Ok(flags)
+}
+
+/// `--profile NAME [--config PATH]`: replace them in `args` by the
+/// profile's flags, inserted at `at` (right after the subcommand) so
+/// flags given on the command line win over the profile's.
+pub(crate) fn expand_profile(
+    mut args: Vec<String>,
+    at: impl Fn(&[String]) -> usize,
+) -> anyhow::Result<Vec<String>> {
+    let config_path = take_option(&mut args, "config")?;
+    let Some(profile) = take_option(&mut args, "profile")? else {
+        return Ok(args);
+    };
+    let path = match config_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let candidates = default_paths();
+            let found = candidates.iter().find(|p| p.is_file()).cloned();
+            found.with_context(|| {
+                let tried: Vec<String> =
+                    candidates.iter().map(|p| p.display().to_string()).collect();
+                format!("--profile {profile}: no config file ({})", tried.join(", "))
+            })?
+        }
+    };
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let config: toml::Table = text
+        .parse()
+        .with_context(|| format!("invalid TOML in {}", path.display()))?;
+    let flags = profile_flags(&config, &profile).with_context(|| path.display().to_string())?;
+    let at = at(&args).min(args.len());
+    args.splice(at..at, flags);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_headers_come_from_the_environment() {
+        let env = |var: &str| match var {
+            "DUMPIT_BEARER_TOKEN" => Some("s3cret\n".to_string()),
+            "DUMPIT_COOKIE" => Some(" ".to_string()),
+            "DUMPIT_HEADERS" => Some("X-Api-Key: k1\nnonsense\nAccept-Language: de".to_string()),
+            _ => None,
+        };
+        let headers = merge_env_headers(&["accept-language: cs".to_string()], env);
+        assert_eq!(
+            headers,
+            [
+                "accept-language: cs",
+                "Authorization: Bearer s3cret",
+                "X-Api-Key: k1"
+            ]
+        );
+        let headers = merge_env_headers(&["Authorization: Basic eA==".to_string()], env);
+        assert_eq!(headers[0], "Authorization: Basic eA==");
+        assert!(!headers.iter().any(|h| h.contains("s3cret")));
+    }
+
+    #[test]
+    fn profiles_become_flags_ahead_of_the_command_line() {
+        let config: toml::Table = r#"
+            [profiles.docs-site]
+            content-selector = "main article"
+            exclude = ["/tag/", "/author/"]
+            no_js = true
+            tui = false
+            concurrency = 12
+        "#
+        .parse()
+        .unwrap();
+        let flags = profile_flags(&config, "docs-site").unwrap();
+        let joined = flags.join(" ");
+        assert!(
+            joined.contains("--content-selector main article"),
+            "{joined}"
+        );
+        assert!(
+            joined.contains("--exclude /tag/ --exclude /author/"),
+            "{joined}"
+        );
+        assert!(joined.contains("--no-js"), "{joined}");
+        assert!(joined.contains("--concurrency 12"), "{joined}");
+        assert!(!joined.contains("--tui"), "{joined}");
+        let err = profile_flags(&config, "shop").unwrap_err();
+        assert!(err.to_string().contains("docs-site"), "{err}");
+
+        let dir = std::env::temp_dir().join(format!("dump-it-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dump-it.toml");
+        std::fs::write(&path, "[profiles.quick]\nmax-pages = 5\n").unwrap();
+        let args: Vec<String> = [
+            "dump-it",
+            "crawl",
+            "--profile=quick",
+            "--config",
+            path.to_str().unwrap(),
+            "--max-pages",
+            "50",
+        ]
+        .map(String::from)
+        .to_vec();
+        let expanded = expand_profile(args, |_| 2).unwrap();
+        assert_eq!(
+            expanded,
+            ["dump-it", "crawl", "--max-pages", "5", "--max-pages", "50"]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::cli::IpVersion;
+
+/// How long a resolved host stays in the in-process cache. Long enough to
+/// cover the burst of page + image + CSS requests a crawl makes against one
+/// host, short enough that a DNS cutover mid-run is eventually picked up.
+const DNS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Host → when it was resolved and its addresses.
+type Cache = HashMap<String, (Instant, Vec<SocketAddr>)>;
+
+/// reqwest resolver that memoises lookups per host. The default resolver
+/// hits `getaddrinfo` on every new connection, which on a 5 000-page crawl
+/// means thousands of identical lookups against the same two hosts.
+/// Results are filtered to the `--ip-version` family before caching.
+#[derive(Default)]
+pub(crate) struct CachingResolver {
+    cache: Arc<Mutex<Cache>>,
+    ip_version: IpVersion,
+}
+
+impl CachingResolver {
+    pub fn new(ip_version: IpVersion) -> Self {
+        Self {
+            cache: Arc::default(),
+            ip_version,
+        }
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = Arc::clone(&self.cache);
+        let ip_version = self.ip_version;
+        Box::pin(async move {
+            let host = name.as_str().to_ascii_lowercase();
+            let cached = cache
+                .lock()
+                .ok()
+                .and_then(|c| c.get(&host).cloned())
+                .filter(|(at, _)| at.elapsed() < DNS_CACHE_TTL);
+            if let Some((_, addrs)) = cached {
+                return Ok(Box::new(addrs.into_iter()) as Addrs);
+            }
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|a| ip_version.allows(&a.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host}: no address matches the requested IP version").into());
+            }
+            if let Ok(mut c) = cache.lock() {
+                c.insert(host, (Instant::now(), addrs.clone()));
+            }
+            Ok::<Addrs, BoxError>(Box::new(addrs.into_iter()))
+        })
+    }
+}
+
+/// Parse a `--resolve` value. Accepts `host:ip` and curl's `host:port:ip`
+/// (the port is ignored — reqwest routes to the URL's port regardless).
+/// IPv6 addresses may be bare or bracketed: `example.com:[::1]`.
+pub(crate) fn parse_resolve_override(spec: &str) -> anyhow::Result<(String, IpAddr)> {
+    let (host, rest) = spec
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("--resolve expects `host:ip`, got `{spec}`"))?;
+    let host = host.trim();
+    if host.is_empty() {
+        anyhow::bail!("--resolve expects `host:ip`, got `{spec}`");
+    }
+    let parse_ip = |s: &str| s.trim().trim_matches(['[', ']']).parse::<IpAddr>().ok();
+    let ip = parse_ip(rest)
+        .or_else(|| {
+            let (port, ip) = rest.split_once(':')?;
+            port.parse::<u16>().ok()?;
+            parse_ip(ip)
+        })
+        .ok_or_else(|| anyhow::anyhow!("--resolve: `{rest}` is not an IP address"))?;
+    Ok((host.to_ascii_lowercase(), ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_resolve_override_accepts_curl_forms() {
+        let (h, ip) = parse_resolve_override("Example.com:10.0.0.5").unwrap();
+        assert_eq!(h, "example.com");
+        assert_eq!(ip.to_string(), "10.0.0.5");
+        let (_, ip) = parse_resolve_override("example.com:443:10.0.0.6").unwrap();
+        assert_eq!(ip.to_string(), "10.0.0.6");
+        let (_, ip) = parse_resolve_override("example.com:[2001:db8::1]").unwrap();
+        assert_eq!(ip.to_string(), "2001:db8::1");
+        let (_, ip) = parse_resolve_override("example.com:2001:db8::2").unwrap();
+        assert_eq!(ip.to_string(), "2001:db8::2");
+        assert!(parse_resolve_override("example.com").is_err());
+        assert!(parse_resolve_override("example.com:not-an-ip").is_err());
+    }
+
+    #[tokio::test]
+    async fn lookups_keep_the_ip_version_family() {
+        let resolve = |ip_version| async move {
+            let name = "127.0.0.1".parse().unwrap();
+            CachingResolver::new(ip_version)
+                .resolve(name)
+                .await
+                .map(|addrs| addrs.map(|a| a.ip().to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(resolve(IpVersion::V4).await.unwrap(), ["127.0.0.1"]);
+        assert_eq!(resolve(IpVersion::Auto).await.unwrap(), ["127.0.0.1"]);
+        let err = resolve(IpVersion::V6).await.unwrap_err();
+        assert!(err.to_string().contains("no address matches"));
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::model::{Generator, PageData};
+
+/// Pages of a previous run.
+pub(crate) struct LoadedDump {
+    pub pages: Vec<PageData>,
+    /// `generator.generated_at` of the run; empty when unknown (JSONL
+    /// input, or a bundle from before versioned output).
+    pub generated_at: String,
+}
+
+/// The part of scraped.json a reader needs. Lenient on `generator` so
+/// bundles from before versioned output still load.
+#[derive(Deserialize)]
+struct ScrapedFile {
+    #[serde(default)]
+    generator: Option<Generator>,
+    pages: Vec<PageData>,
+}
+
+/// Load `<input>/scraped.json`, falling back to `<input>/scraped.jsonl`.
+pub(crate) fn load_dump(input: &Path) -> anyhow::Result<LoadedDump> {
+    let json = input.join("scraped.json");
+    if json.is_file() {
+        let text = std::fs::read_to_string(&json)?;
+        let scraped: ScrapedFile = serde_json::from_str(&text)
+            .with_context(|| format!("failed to parse {}", json.display()))?;
+        return Ok(LoadedDump {
+            pages: scraped.pages,
+            generated_at: scraped
+                .generator
+                .map(|g| g.generated_at)
+                .unwrap_or_default(),
+        });
+    }
+    let jsonl = input.join("scraped.jsonl");
+    let text = std::fs::read_to_string(&jsonl)
+        .with_context(|| format!("no scraped.json or scraped.jsonl in {}", input.display()))?;
+    let pages = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(anyhow::Error::from))
+        .collect::<anyhow::Result<Vec<PageData>>>()
+        .with_context(|| format!("failed to parse {}", jsonl.display()))?;
+    Ok(LoadedDump {
+        pages,
+        generated_at: String::new(),
+    })
+}
+
+/// Example URLs listed per quality flag in the audit.
+const AUDIT_EXAMPLES: usize = 5;
+
+fn json_strings(value: &JsonValue) -> Vec<String> {
+    value
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `dump-it audit`: bundle warnings and skipped / orphan pages from
+/// site.json, plus a tally of per-page quality flags from scraped.json.
+pub(crate) fn run_audit(input: &Path, strict: bool) -> anyhow::Result<()> {
+    let dump = load_dump(input)?;
+    // site.json isn't deserialisable into SiteData (serialize-only), and
+    // the audit only needs a few arrays of it.
+    let site: JsonValue = std::fs::read_to_string(input.join("site.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or(JsonValue::Null);
+
+    println!(
+        "🩺 Audit of {} ({} pages)",
+        input.display(),
+        dump.pages.len()
+    );
+
+    let warnings = json_strings(&site["quality_warnings"]);
+    if warnings.is_empty() {
+        println!("✓ No bundle-level quality warnings");
+    }
+    for w in &warnings {
+        println!("⚠️  {w}");
+    }
+
+    // Flags carry a detail suffix (`low_text_to_html:3%`); tally by name.
+    let mut flags: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for page in &dump.pages {
+        for flag in &page.quality_flags {
+            let name = flag.split(':').next().unwrap_or(flag);
+            flags.entry(name).or_default().push(&page.url);
+        }
+    }
+    if !flags.is_empty() {
+        println!("\nPage quality flags:");
+        for (flag, urls) in &flags {
+            println!("  {flag}: {} page(s)", urls.len());
+            for url in urls.iter().take(AUDIT_EXAMPLES) {
+                println!("    - {url}");
+            }
+        }
+    }
+
+    let mut skipped: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in site["skipped_pages"].as_array().into_iter().flatten() {
+        let reason = entry["reason"].as_str().unwrap_or("unknown");
+        *skipped.entry(reason.to_string()).or_default() += 1;
+    }
+    if !skipped.is_empty() {
+        println!("\nSkipped pages:");
+        for (reason, count) in &skipped {
+            println!("  {reason}: {count}");
+        }
+    }
+
+    let failed_images = site["failed_images"].as_array().map_or(0, Vec::len);
+    if failed_images > 0 {
+        println!("\nImages that failed to download: {failed_images} (site.json:failed_images)");
+    }
+
+    let orphans = json_strings(&site["link_report"]["orphan_pages"]);
+    if !orphans.is_empty() {
+        println!("\nOrphan pages (in the sitemap, linked from nowhere):");
+        for url in orphans.iter().take(AUDIT_EXAMPLES) {
+            println!("  - {url}");
+        }
+        if orphans.len() > AUDIT_EXAMPLES {
+            println!("  … and {} more", orphans.len() - AUDIT_EXAMPLES);
+        }
+    }
+
+    if strict && !warnings.is_empty() {
+        anyhow::bail!("{} bundle-level quality warning(s)", warnings.len());
+    }
+    Ok(())
+}
+
+/// The fields `diff` compares, per URL.
+struct Fingerprint {
+    content_hash: String,
+    title: String,
+    meta_description: String,
+    canonical_url: Option<String>,
+}
+
+/// Pages keyed by URL; aliases point at their page's fingerprint too, so
+/// a URL collapsed in one run and kept in the other isn't reported as
+/// added / removed.
+fn fingerprints(pages: &[PageData]) -> BTreeMap<String, Fingerprint> {
+    let mut out = BTreeMap::new();
+    for page in pages {
+        for url in std::iter::once(&page.url).chain(&page.aliases) {
+            out.insert(
+                url.clone(),
+                Fingerprint {
+                    content_hash: page.content_hash.clone(),
+                    title: page.title.clone(),
+                    meta_description: page.meta_description.clone(),
+                    canonical_url: page.canonical_url.clone(),
+                },
+            );
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+struct PageDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    /// URL → names of the fields that differ.
+    changed: Vec<(String, Vec<&'static str>)>,
+    unchanged: usize,
+}
+
+fn diff_fingerprints(
+    old: &BTreeMap<String, Fingerprint>,
+    new: &BTreeMap<String, Fingerprint>,
+) -> PageDiff {
+    let mut diff = PageDiff::default();
+    for (url, after) in new {
+        let Some(before) = old.get(url) else {
+            diff.added.push(url.clone());
+            continue;
+        };
+        let mut fields = Vec::new();
+        if before.content_hash != after.content_hash {
+            fields.push("content");
+        }
+        if before.title != after.title {
+            fields.push("title");
+        }
+        if before.meta_description != after.meta_description {
+            fields.push("meta_description");
+        }
+        if before.canonical_url != after.canonical_url {
+            fields.push("canonical_url");
+        }
+        if fields.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push((url.clone(), fields));
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|url| !new.contains_key(*url))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// `dump-it diff`: what changed on the site between two runs.
+pub(crate) fn run_diff(old_dir: &Path, new_dir: &Path) -> anyhow::Result<()> {
+    let old = load_dump(old_dir)?;
+    let new = load_dump(new_dir)?;
+    let diff = diff_fingerprints(&fingerprints(&old.pages), &fingerprints(&new.pages));
+    println!(
+        "🔀 {} → {}: {} added, {} removed, {} changed, {} unchanged",
+        old_dir.display(),
+        new_dir.display(),
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.unchanged
+    );
+    for url in &diff.added {
+        println!("+ {url}");
+    }
+    for url in &diff.removed {
+        println!("- {url}");
+    }
+    for (url, fields) in &diff.changed {
+        println!("~ {url} ({})", fields.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fp(hash: &str, title: &str) -> Fingerprint {
+        Fingerprint {
+            content_hash: hash.into(),
+            title: title.into(),
+            meta_description: String::new(),
+            canonical_url: None,
+        }
+    }
+
+    #[test]
+    fn diff_fingerprints_reports_added_removed_and_changed_fields() {
+        let old = BTreeMap::from([
+            ("https://x.com/".to_string(), fp("a", "Home")),
+            ("https://x.com/about".to_string(), fp("b", "About")),
+            ("https://x.com/old".to_string(), fp("c", "Old")),
+        ]);
+        let new = BTreeMap::from([
+            ("https://x.com/".to_string(), fp("a", "Home")),
+            ("https://x.com/about".to_string(), fp("b2", "About us")),
+            ("https://x.com/new".to_string(), fp("d", "New")),
+        ]);
+        let diff = diff_fingerprints(&old, &new);
+        assert_eq!(diff.added, vec!["https://x.com/new"]);
+        assert_eq!(diff.removed, vec!["https://x.com/old"]);
+        assert_eq!(
+            diff.changed,
+            vec![("https://x.com/about".to_string(), vec!["content", "title"])]
+        );
+        assert_eq!(diff.unchanged, 1);
+    }
+}
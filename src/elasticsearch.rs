@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::model::{ContentBlock, PageData};
+
+/// Bulk requests of 100 full pages can be tens of MB.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `--export elasticsearch` settings (OpenSearch speaks the same API).
+pub(crate) struct Elasticsearch {
+    client: Client,
+    /// Cluster root, e.g. `http://localhost:9200`. Basic-auth credentials
+    /// can ride in the URL (`https://user:pass@…`).
+    base_url: String,
+    pub index: String,
+    /// Sent as `Authorization: ApiKey …`.
+    api_key: Option<String>,
+}
+
+/// Fields the index searches and filters on. Everything else in the
+/// document is kept in `_source` but not indexed (`dynamic: false`), so
+/// free-form JSON-LD can't blow up or conflict with the mapping.
+fn mapping() -> Value {
+    json!({
+        "mappings": {
+            "dynamic": false,
+            "properties": {
+                "url": {"type": "keyword"},
+                "canonical_url": {"type": "keyword"},
+                "title": {"type": "text"},
+                "meta_description": {"type": "text"},
+                "headings": {"type": "text"},
+                "plain_text": {"type": "text"},
+                "language": {"type": "keyword"},
+                "content_hash": {"type": "keyword"},
+                "access_wall": {"type": "keyword"},
+                "total_words": {"type": "integer"},
+                "internal_links_out": {"type": "keyword"},
+            }
+        }
+    })
+}
+
+/// The page as indexed: its `PageData` plus `headings`, the text of every
+/// heading block, for a boosted field without nested queries.
+pub(crate) fn document(page: &PageData) -> Value {
+    let mut doc = serde_json::to_value(page).unwrap_or_default();
+    let headings: Vec<&str> = page
+        .content_blocks
+        .iter()
+        .filter_map(|b| match b {
+            ContentBlock::Heading { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    doc["headings"] = json!(headings);
+    doc
+}
+
+/// `_id` of a page: SHA-256 of its URL, so re-runs update documents in
+/// place and long URLs stay under the 512-byte id limit.
+fn doc_id(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url.as_bytes()))
+}
+
+/// NDJSON body of a `_bulk` request indexing `docs`.
+fn bulk_body(docs: &[Value]) -> String {
+    let mut body = String::new();
+    for doc in docs {
+        let id = doc_id(doc["url"].as_str().unwrap_or_default());
+        body.push_str(&json!({"index": {"_id": id}}).to_string());
+        body.push('\n');
+        body.push_str(&doc.to_string());
+        body.push('\n');
+    }
+    body
+}
+
+impl Elasticsearch {
+    pub fn from_args(args: &crate::cli::ExportArgs) -> anyhow::Result<Self> {
+        url::Url::parse(&args.es_url)
+            .with_context(|| format!("invalid --es-url {}", args.es_url))?;
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("failed to build Elasticsearch HTTP client")?;
+        Ok(Self {
+            client,
+            base_url: args.es_url.trim_end_matches('/').to_string(),
+            index: args.es_index.clone(),
+            api_key: std::env::var(&args.es_api_key_env)
+                .ok()
+                .filter(|k| !k.trim().is_empty()),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}{path}", self.base_url, self.index);
+        let request = self.client.request(method, url);
+        match &self.api_key {
+            Some(key) => request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {key}")),
+            None => request,
+        }
+    }
+
+    /// Create the index with [`mapping`]. `Ok(false)` when it already
+    /// exists; its mapping is left alone.
+    pub async fn create_index(&self) -> anyhow::Result<bool> {
+        let response = self
+            .request(reqwest::Method::PUT, "")
+            .json(&mapping())
+            .send()
+            .await
+            .with_context(|| format!("could not reach Elasticsearch at {}", self.base_url))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(true);
+        }
+        let body: Value = response.json().await.unwrap_or_default();
+        if body["error"]["type"] == "resource_already_exists_exception" {
+            return Ok(false);
+        }
+        anyhow::bail!(
+            "creating index {} returned {status}: {}",
+            self.index,
+            body["error"]["reason"].as_str().unwrap_or_default()
+        );
+    }
+
+    /// Index `docs` through `_bulk`; returns how many the cluster rejected.
+    pub async fn bulk(&self, docs: &[Value]) -> anyhow::Result<usize> {
+        let request = self
+            .request(reqwest::Method::POST, "/_bulk")
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(bulk_body(docs));
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(300)
+                .collect();
+            anyhow::bail!("_bulk returned {status}: {detail}");
+        }
+        let body: Value = response.json().await.context("unexpected _bulk response")?;
+        let errors: Vec<&Value> = body["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|item| &item["index"]["error"])
+            .filter(|e| !e.is_null())
+            .collect();
+        if let Some(first) = errors.first() {
+            tracing::warn!(
+                "Elasticsearch rejected {} documents, first: {}",
+                errors.len(),
+                first["reason"].as_str().unwrap_or_default()
+            );
+        }
+        Ok(errors.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bulk_body_pairs_an_action_with_each_document() {
+        let docs = vec![
+            json!({"url": "https://x.com/", "title": "Home"}),
+            json!({"url": "https://x.com/a", "title": "A"}),
+        ];
+        let body = bulk_body(&docs);
+        let lines: Vec<Value> = body
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["index"]["_id"], doc_id("https://x.com/"));
+        assert_eq!(lines[1]["title"], "Home");
+        assert_eq!(lines[2]["index"]["_id"].as_str().unwrap().len(), 64);
+        assert_ne!(lines[0]["index"]["_id"], lines[2]["index"]["_id"]);
+        assert!(body.ends_with('\n'));
+        assert_eq!(mapping()["mappings"]["dynamic"], false);
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::model::Chunk;
+
+pub(crate) const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Inputs per `/embeddings` request. Well under every provider's limit
+/// (OpenAI takes 2048) while keeping a failed request cheap to retry.
+const BATCH_SIZE: usize = 64;
+
+/// Embedding models are slow on big batches behind local servers.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `--embed` settings.
+pub(crate) struct EmbedOptions {
+    /// API root, e.g. `https://api.openai.com/v1` or
+    /// `http://localhost:11434/v1`; `/embeddings` is appended.
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a Bearer token. Optional: local servers usually need none.
+    pub api_key: Option<String>,
+}
+
+impl EmbedOptions {
+    /// Resolve the key from the environment and refuse the one setup that
+    /// can't work — the hosted OpenAI API without a key — before the crawl.
+    pub fn from_args(args: &crate::cli::ExportArgs) -> anyhow::Result<Self> {
+        let api_key = std::env::var(&args.embed_api_key_env)
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+        let base_url = args.embed_base_url.trim_end_matches('/').to_string();
+        if api_key.is_none() && base_url == OPENAI_BASE_URL {
+            anyhow::bail!(
+                "--embed against {OPENAI_BASE_URL} needs an API key in ${}",
+                args.embed_api_key_env
+            );
+        }
+        Ok(Self {
+            base_url,
+            model: args.embed_model.clone(),
+            api_key,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingItem>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingItem {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Fill `Chunk.embedding` for every chunk via an OpenAI-compatible
+/// `POST {base_url}/embeddings`. Uses its own client: the scraper's one
+/// carries the target site's `--header` cookies / auth.
+pub(crate) async fn embed_chunks(chunks: &mut [Chunk], opts: &EmbedOptions) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("failed to build embeddings HTTP client")?;
+    let endpoint = format!("{}/embeddings", opts.base_url);
+    for batch in chunks.chunks_mut(BATCH_SIZE) {
+        let body = EmbeddingRequest {
+            model: &opts.model,
+            input: batch.iter().map(|c| c.text.as_str()).collect(),
+        };
+        let mut request = client.post(&endpoint).json(&body);
+        if let Some(key) = &opts.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = crate::har::send(request)
+            .await
+            .with_context(|| format!("embedding request to {endpoint} failed"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(300)
+                .collect();
+            anyhow::bail!("{endpoint} returned {status}: {detail}");
+        }
+        let parsed: EmbeddingResponse = response
+            .json()
+            .await
+            .with_context(|| format!("{endpoint} returned an unexpected body"))?;
+        if parsed.data.len() != batch.len() {
+            anyhow::bail!(
+                "{endpoint} returned {} embeddings for {} inputs",
+                parsed.data.len(),
+                batch.len()
+            );
+        }
+        for item in parsed.data {
+            let chunk = batch.get_mut(item.index).with_context(|| {
+                format!("{endpoint} returned out-of-range index {}", item.index)
+            })?;
+            chunk.embedding = Some(item.embedding);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn embeddings_land_on_their_chunks_by_index() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Answers one request with the embeddings in reverse order, and
+        // hands back the request it got.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length: "))
+                        .map_or(0, |l| l.parse().unwrap());
+                    if n == 0 || body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            let body = r#"{"data": [{"index": 1, "embedding": [0.5]}, {"index": 0, "embedding": [0.25, 1.0]}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let chunk = |text: &str| Chunk {
+            id: String::new(),
+            url: "https://x.com/".to_string(),
+            title: String::new(),
+            heading_path: Vec::new(),
+            chunk_index: 0,
+            section_index: 0,
+            char_start: 0,
+            char_end: 0,
+            token_estimate: 0,
+            text: text.to_string(),
+            embedding: None,
+        };
+        let mut chunks = [chunk("first"), chunk("second")];
+        let opts = EmbedOptions {
+            base_url: format!("http://127.0.0.1:{port}/v1"),
+            model: "nomic-embed-text".to_string(),
+            api_key: Some("sk-test".to_string()),
+        };
+        embed_chunks(&mut chunks, &opts).await.unwrap();
+        assert_eq!(chunks[0].embedding, Some(vec![0.25, 1.0]));
+        assert_eq!(chunks[1].embedding, Some(vec![0.5]));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/embeddings "));
+        assert!(request.contains("authorization: Bearer sk-test"));
+        assert!(request.ends_with(r#"{"model":"nomic-embed-text","input":["first","second"]}"#));
+    }
+}
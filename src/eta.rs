@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Throughput is measured over the last minute, so a slow start (robots,
+/// sitemap, Chrome launch) or a slow host finishing late doesn't skew the
+/// ETA for long.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Gap between two progress lines.
+const REPORT_EVERY: Duration = Duration::from_secs(15);
+
+/// Rolling rate and ETA for a known amount of work: the pages of a scrape
+/// or the image queue.
+pub(crate) struct Throughput {
+    started: Instant,
+    total: usize,
+    finished: usize,
+    /// Completion times within [`WINDOW`].
+    recent: VecDeque<Instant>,
+    last_report: Instant,
+}
+
+/// `2h 05m`, `6m 10s`, `42s`.
+pub(crate) fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
+
+impl Throughput {
+    pub fn new(total: usize, now: Instant) -> Self {
+        Self {
+            started: now,
+            total,
+            finished: 0,
+            recent: VecDeque::new(),
+            last_report: now,
+        }
+    }
+
+    /// Count one finished item. Returns true when a progress line is due.
+    pub fn record(&mut self, now: Instant) -> bool {
+        self.finished += 1;
+        self.recent.push_back(now);
+        while self
+            .recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        if self.finished < self.total && now.duration_since(self.last_report) >= REPORT_EVERY {
+            self.last_report = now;
+            return true;
+        }
+        false
+    }
+
+    /// Items per minute over the window; `None` until there is a rate.
+    pub fn per_minute(&self, now: Instant) -> Option<f64> {
+        let since = now
+            .checked_sub(WINDOW)
+            .map_or(self.started, |t| t.max(self.started));
+        let count = self.recent.iter().filter(|t| **t >= since).count();
+        let span = now.duration_since(since).as_secs_f64();
+        (count > 0 && span >= 1.0).then(|| count as f64 * 60.0 / span)
+    }
+
+    /// Time left for `remaining` items at the current rate.
+    pub fn eta_for(&self, remaining: usize, now: Instant) -> Option<Duration> {
+        let rate = self.per_minute(now)?;
+        Some(Duration::from_secs_f64(remaining as f64 * 60.0 / rate))
+    }
+
+    /// `120/380 pages · 42.0/min · ETA 6m 11s`
+    pub fn line(&self, noun: &str, now: Instant) -> String {
+        let remaining = self.total.saturating_sub(self.finished);
+        match self.eta_for(remaining, now).zip(self.per_minute(now)) {
+            Some((eta, rate)) => format!(
+                "{}/{} {noun} · {rate:.1}/min · ETA {}",
+                self.finished,
+                self.total,
+                format_duration(eta)
+            ),
+            None => format!("{}/{} {noun}", self.finished, self.total),
+        }
+    }
+}
+
+/// Count one finished item of a queue shared by its workers and print the
+/// `⏱️` line when one is due. It's a `progress!` line, so it goes to the
+/// `--tui` log pane while the dashboard is open.
+pub(crate) fn record_and_report(throughput: &Mutex<Throughput>, noun: &str) {
+    let report = {
+        let mut throughput = throughput.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        throughput.record(now).then(|| throughput.line(noun, now))
+    };
+    if let Some(report) = report {
+        progress!("⏱️  {report}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_follows_the_last_minute() {
+        let t0 = Instant::now();
+        let at = |s: u64| t0 + Duration::from_secs(s);
+        let mut rate = Throughput::new(100, t0);
+        assert_eq!(rate.per_minute(t0), None);
+        // A slow first minute: 2 items…
+        rate.record(at(30));
+        rate.record(at(60));
+        // …then one every second.
+        let mut due = 0;
+        for s in 61..=120 {
+            due += usize::from(rate.record(at(s)));
+        }
+        assert_eq!(due, 4);
+        let per_minute = rate.per_minute(at(120)).unwrap();
+        assert!((per_minute - 60.0).abs() < 2.0, "{per_minute}");
+        let eta = rate.eta_for(38, at(120)).unwrap();
+        assert!((37..=39).contains(&eta.as_secs()), "{eta:?}");
+        assert_eq!(
+            rate.line("pages", at(120)),
+            "62/100 pages · 61.0/min · ETA 37s"
+        );
+
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(370)), "6m 10s");
+        assert_eq!(format_duration(Duration::from_secs(7500)), "2h 05m");
+    }
+}
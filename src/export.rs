@@ -0,0 +1,384 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::cli::{ExportArgs, ExportTarget, OutputFormat};
+use crate::embed::EmbedOptions;
+use crate::model::PageData;
+use crate::output::{build_locale_dumps, page_to_markdown};
+use crate::util::{parse_front_matter_field, url_to_slug};
+
+/// `ExportArgs` checked up front, so a typo in `--front-matter` or a
+/// missing API key fails before the crawl rather than after it.
+pub(crate) struct ExportSettings {
+    args: ExportArgs,
+    front_matter: Vec<(String, String)>,
+    embed: Option<EmbedOptions>,
+    template: Option<PageTemplate>,
+    /// `--export` targets, until [`ExportSettings::start_sinks`].
+    sinks: Vec<crate::sink::Sink>,
+}
+
+/// `--template`, parsed: a syntax error fails before the crawl.
+struct PageTemplate {
+    tera: tera::Tera,
+    /// Extension of the rendered files, without the dot.
+    extension: String,
+}
+
+impl PageTemplate {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read template {}", path.display()))?;
+        Self::parse(&source, path)
+    }
+
+    fn parse(source: &str, path: &Path) -> anyhow::Result<Self> {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let stem = name.strip_suffix(".tera").unwrap_or(name);
+        let extension = Path::new(stem)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("txt")
+            .to_string();
+        // Registered as `page.<ext>` so Tera autoescapes `.html` / `.xml`
+        // output as it would for its own template files.
+        let mut tera = tera::Tera::default();
+        tera.add_raw_template(&format!("page.{extension}"), source)
+            .with_context(|| format!("invalid template {}", path.display()))?;
+        Ok(Self { tera, extension })
+    }
+
+    fn render(&self, page: &PageData, generated_at: &str) -> anyhow::Result<String> {
+        let mut context = tera::Context::new();
+        context.insert("page", page);
+        context.insert("scraped_at", generated_at);
+        Ok(self
+            .tera
+            .render(&format!("page.{}", self.extension), &context)?)
+    }
+}
+
+/// What `ExportSettings::write` produced, for site.json.
+#[derive(Default)]
+pub(crate) struct ExportedFiles {
+    /// `site.json:output_files` entries, in write order.
+    pub output_files: Vec<String>,
+    /// `pages/<slug>.json` per page, in page order. Empty without
+    /// --split-pages.
+    pub page_files: Vec<String>,
+    /// `markdown/<slug>.md` per page, in page order. Empty without
+    /// --markdown.
+    pub markdown_files: Vec<String>,
+}
+
+impl ExportSettings {
+    pub fn new(args: ExportArgs) -> anyhow::Result<Self> {
+        let front_matter = args
+            .front_matter
+            .iter()
+            .map(|kv| parse_front_matter_field(kv.as_str()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let embed = if args.embed {
+            Some(EmbedOptions::from_args(&args)?)
+        } else {
+            None
+        };
+        let template = args
+            .template
+            .as_deref()
+            .map(PageTemplate::load)
+            .transpose()?;
+        let mut sinks = Vec::new();
+        for (i, target) in args.sinks.iter().enumerate() {
+            if args.sinks[..i].contains(target) {
+                continue;
+            }
+            sinks.push(match target {
+                ExportTarget::Elasticsearch => crate::sink::Sink::Elasticsearch(
+                    crate::elasticsearch::Elasticsearch::from_args(&args)?,
+                ),
+                ExportTarget::Meilisearch => crate::sink::Sink::Meilisearch(
+                    crate::meilisearch::Meilisearch::from_args(&args)?,
+                ),
+                #[cfg(feature = "kafka")]
+                ExportTarget::Kafka => {
+                    crate::sink::Sink::Kafka(crate::queue::Kafka::from_args(&args)?)
+                }
+                #[cfg(not(feature = "kafka"))]
+                ExportTarget::Kafka => {
+                    anyhow::bail!("--export kafka needs dump-it built with `--features kafka`")
+                }
+                #[cfg(feature = "nats")]
+                ExportTarget::Nats => crate::sink::Sink::Nats(crate::queue::Nats::from_args(&args)),
+                #[cfg(not(feature = "nats"))]
+                ExportTarget::Nats => {
+                    anyhow::bail!("--export nats needs dump-it built with `--features nats`")
+                }
+            });
+        }
+        Ok(Self {
+            args,
+            front_matter,
+            embed,
+            template,
+            sinks,
+        })
+    }
+
+    /// Set up the `--export` sinks before the crawl, so one that can't be
+    /// reached fails the run early. [`ExportSettings::write`] sends them
+    /// the pages.
+    pub async fn start_sinks(&mut self) -> anyhow::Result<()> {
+        crate::sink::start(std::mem::take(&mut self.sinks)).await
+    }
+
+    fn writes_chunks(&self) -> bool {
+        self.args.embed || self.args.formats.contains(&OutputFormat::Chunks)
+    }
+
+    /// Write every requested export of `pages` into `output_dir`.
+    /// `generated_at` is the scrape time (Markdown `scraped_at`).
+    pub async fn write(
+        &self,
+        pages: &[PageData],
+        generated_at: &str,
+        output_dir: &Path,
+    ) -> anyhow::Result<ExportedFiles> {
+        let args = &self.args;
+        let mut exported = ExportedFiles::default();
+
+        // --- --export sinks: the final pages, once every pass is done ---
+        for page in pages {
+            crate::sink::publish(page).await;
+        }
+        crate::sink::finish().await;
+
+        // --- Optional: streaming JSONL ----------------------------------
+        if args.jsonl {
+            let jsonl_path = output_dir.join("scraped.jsonl");
+            let mut buf = String::with_capacity(pages.len() * 1024);
+            for page in pages {
+                buf.push_str(&serde_json::to_string(&page)?);
+                buf.push('\n');
+            }
+            std::fs::write(&jsonl_path, buf)?;
+            exported.output_files.push("scraped.jsonl".to_string());
+        }
+
+        // --- Optional: XML / YAML / SQL renderings ----------------------
+        if args.formats.contains(&OutputFormat::Xml) {
+            let xml = crate::formats::pages_to_xml(pages)?;
+            std::fs::write(output_dir.join("scraped.xml"), xml)?;
+            exported.output_files.push("scraped.xml".to_string());
+        }
+        if args.formats.contains(&OutputFormat::Yaml) {
+            let yaml = crate::formats::pages_to_yaml(pages)?;
+            std::fs::write(output_dir.join("scraped.yaml"), yaml)?;
+            exported.output_files.push("scraped.yaml".to_string());
+        }
+        if args.formats.contains(&OutputFormat::Sql) {
+            let sql = crate::formats::pages_to_sql(pages)?;
+            std::fs::write(output_dir.join("scraped.sql"), sql)?;
+            exported.output_files.push("scraped.sql".to_string());
+        }
+
+        // --- Optional: RAG chunks ---------------------------------------
+        if self.writes_chunks() {
+            let chunks_path = output_dir.join("chunks.jsonl");
+            let mut all_chunks: Vec<_> = pages
+                .iter()
+                .flat_map(|page| {
+                    crate::chunks::chunk_page(page, args.chunk_size, args.chunk_overlap)
+                })
+                .collect();
+            if let Some(opts) = &self.embed {
+                progress!(
+                    "🧠 Embedding {} chunks with {}",
+                    all_chunks.len(),
+                    opts.model
+                );
+                // The scrape itself succeeded; a provider outage shouldn't
+                // cost the chunks, so fall back to writing them without vectors.
+                if let Err(e) = crate::embed::embed_chunks(&mut all_chunks, opts).await {
+                    tracing::warn!("embedding failed, chunks.jsonl written without vectors: {e:#}");
+                    for chunk in &mut all_chunks {
+                        chunk.embedding = None;
+                    }
+                }
+            }
+            let mut buf = String::new();
+            for chunk in &all_chunks {
+                buf.push_str(&serde_json::to_string(chunk)?);
+                buf.push('\n');
+            }
+            std::fs::write(&chunks_path, buf)?;
+            exported.output_files.push("chunks.jsonl".to_string());
+        }
+
+        // --- Optional: full-text index ---------------------------------
+        if let Some(index_dir) = &args.index {
+            let indexed = crate::index::build_index(index_dir, pages)?;
+            progress!("🔎 Indexed {indexed} pages");
+        }
+
+        // --- Optional: split per-page JSON ------------------------------
+        if args.split_pages {
+            let pages_dir = output_dir.join("pages");
+            std::fs::create_dir_all(&pages_dir)?;
+            for page in pages {
+                let filename = format!("{}.json", url_to_slug(&page.url));
+                let page_json = serde_json::to_string_pretty(&page)?;
+                std::fs::write(pages_dir.join(&filename), page_json)?;
+                exported.page_files.push(format!("pages/{filename}"));
+            }
+            exported.output_files.push("pages/".to_string());
+        }
+
+        // --- Optional: per-locale JSON ---------------------------------
+        if args.split_locales {
+            let locales_dir = output_dir.join("locales");
+            std::fs::create_dir_all(&locales_dir)?;
+            for dump in build_locale_dumps(pages) {
+                let path = locales_dir.join(format!("{}.json", dump.locale));
+                std::fs::write(&path, serde_json::to_string_pretty(&dump)?)?;
+            }
+            exported.output_files.push("locales/".to_string());
+        }
+
+        // --- Optional: Markdown export per page -------------------------
+        if args.markdown {
+            let md_dir = output_dir.join("markdown");
+            std::fs::create_dir_all(&md_dir)?;
+            for page in pages {
+                let slug = url_to_slug(&page.url);
+                std::fs::write(
+                    md_dir.join(format!("{slug}.md")),
+                    page_to_markdown(page, generated_at, &self.front_matter),
+                )?;
+                exported.markdown_files.push(format!("markdown/{slug}.md"));
+            }
+            exported.output_files.push("markdown/".to_string());
+        }
+
+        // --- Optional: user template per page --------------------------
+        if let Some(template) = &self.template {
+            let rendered_dir = output_dir.join("rendered");
+            std::fs::create_dir_all(&rendered_dir)?;
+            let mut failed = 0;
+            for page in pages {
+                // One page tripping the template (a field that's null on
+                // it, say) shouldn't cost the rest.
+                match template.render(page, generated_at) {
+                    Ok(text) => {
+                        let filename = format!("{}.{}", url_to_slug(&page.url), template.extension);
+                        std::fs::write(rendered_dir.join(filename), text)?;
+                    }
+                    Err(e) => {
+                        tracing::warn!("template failed on {}: {e:#}", page.url);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                progress!("⚠️  Template failed on {failed} of {} pages", pages.len());
+            }
+            exported.output_files.push("rendered/".to_string());
+        }
+
+        Ok(exported)
+    }
+
+    /// The "where did it go" lines for the end-of-run summary.
+    pub fn print_summary(&self, output_dir: &Path) {
+        let args = &self.args;
+        if args.split_pages {
+            progress!("📂 Per-page files: {}", output_dir.join("pages").display());
+        }
+        if args.split_locales {
+            progress!(
+                "🌍 Per-locale files: {}",
+                output_dir.join("locales").display()
+            );
+        }
+        if args.markdown {
+            progress!("📝 Markdown: {}", output_dir.join("markdown").display());
+        }
+        if self.template.is_some() {
+            progress!("🧾 Rendered: {}", output_dir.join("rendered").display());
+        }
+        if let Some(index_dir) = &args.index {
+            progress!("🔎 Search index: {}", index_dir.display());
+        }
+        if self.writes_chunks() {
+            progress!("🧩 Chunks: {}", output_dir.join("chunks.jsonl").display());
+        }
+        if args.formats.contains(&OutputFormat::Xml) {
+            progress!("🏷️  XML: {}", output_dir.join("scraped.xml").display());
+        }
+        if args.formats.contains(&OutputFormat::Yaml) {
+            progress!("📄 YAML: {}", output_dir.join("scraped.yaml").display());
+        }
+        if args.formats.contains(&OutputFormat::Sql) {
+            progress!("🗄️  SQL: {}", output_dir.join("scraped.sql").display());
+        }
+    }
+}
+
+/// `dump-it export`: re-run the exports over a previous dump, writing next
+/// to its scraped.json.
+pub(crate) async fn run_export(input: &Path, args: ExportArgs) -> anyhow::Result<()> {
+    let mut settings = ExportSettings::new(args)?;
+    let dump = crate::dump::load_dump(input)?;
+    settings.start_sinks().await?;
+    let exported = settings
+        .write(&dump.pages, &dump.generated_at, input)
+        .await?;
+    if exported.output_files.is_empty()
+        && settings.args.index.is_none()
+        && settings.args.sinks.is_empty()
+    {
+        progress!(
+            "Nothing to export — pass --markdown, --jsonl, --format, --embed, \
+             --index, --template, --export, --split-pages or --split-locales"
+        );
+        return Ok(());
+    }
+    progress!(
+        "✅ Exported {} pages from {}",
+        dump.pages.len(),
+        input.display()
+    );
+    settings.print_summary(input);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_render_pages_to_the_named_extension() {
+        let mut page = crate::model::test_page("https://x.com/pricing");
+        page.title = "Pricing".to_string();
+        page.meta_description = "Plans & prices".to_string();
+        let template = PageTemplate::parse(
+            "<h1>{{ page.title }}</h1><p>{{ page.meta_description }}</p>{{ scraped_at }}",
+            Path::new("tpl/page.html.tera"),
+        )
+        .unwrap();
+        assert_eq!(template.extension, "html");
+        assert_eq!(
+            template.render(&page, "2024-03-01").unwrap(),
+            "<h1>Pricing</h1><p>Plans &amp; prices</p>2024-03-01"
+        );
+
+        let plain = PageTemplate::parse("{{ page.url }}", Path::new("page.tera")).unwrap();
+        assert_eq!(plain.extension, "txt");
+        assert_eq!(plain.render(&page, "").unwrap(), "https://x.com/pricing");
+        let missing = PageTemplate::parse("{{ page.nope }}", Path::new("p.md.tera")).unwrap();
+        assert_eq!(missing.extension, "md");
+        assert!(missing.render(&page, "").is_err());
+        assert!(PageTemplate::parse("{% if %}", Path::new("bad.tera")).is_err());
+    }
+}
@@ -1,23 +1,28 @@
-use futures::future;
-use reqwest::Client;
 use scraper::{ElementRef, Html, Selector};
 use serde_json::Value as JsonValue;
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
-use tokio::fs;
 use url::Url;
 
-use crate::model::{ContentBlock, DefinitionItem, FormField, HreflangAlternate, NavLink};
+use crate::microdata::extract_microdata;
+use crate::model::{
+    ContentBlock, DefinitionItem, FormField, FormSection, HreflangAlternate, InlineLink,
+    LinkRelation, NavLink, PageLink, VideoCaption, VideoSource,
+};
+use crate::scrape::PageOptions;
 use crate::selectors::{
-    SEL_CANONICAL, SEL_CAPTION, SEL_CODE_INSIDE_PRE, SEL_DD, SEL_DT, SEL_FAVICON, SEL_FIGCAPTION,
-    SEL_FOOTER, SEL_HEADER_IMG, SEL_HREFLANG, SEL_HTML, SEL_INPUT, SEL_JSONLD, SEL_LI, SEL_LINK,
-    SEL_MAIN, SEL_META, SEL_NAV, SEL_OPTION, SEL_STYLESHEET, SEL_STYLE_BLOCK, SEL_SUBMIT, SEL_TD,
-    SEL_TH, SEL_TITLE, SEL_TR, SEL_VIDEO_SOURCE,
+    SEL_BODY, SEL_CANONICAL, SEL_CAPTION, SEL_CODE_INSIDE_PRE, SEL_DD, SEL_DT, SEL_FAVICON,
+    SEL_FIGCAPTION, SEL_FOOTER, SEL_FORM_BUTTON, SEL_FORM_SECTION_TITLE, SEL_HEADER_IMG,
+    SEL_HREFLANG, SEL_HTML, SEL_IMG, SEL_IMG_ANY, SEL_INPUT, SEL_JSONLD, SEL_LI, SEL_LINK,
+    SEL_LINK_REL, SEL_MAIN, SEL_META, SEL_NAV, SEL_OPTION, SEL_SKIP, SEL_STYLESHEET,
+    SEL_STYLE_BLOCK, SEL_SUBMIT, SEL_TD, SEL_TH, SEL_TITLE, SEL_TR, SEL_VIDEO_SOURCE,
+    SEL_VIDEO_TRACK,
 };
 use crate::util::{
-    classify_form_purpose, element_in_skip_zone, element_text, embed_provider_from_src,
-    fetch_with_retry, heading_level_from_tag, image_extension_from_url, normalize_path,
+    classify_form_purpose, element_in_skip_zone, element_text, element_text_skipping,
+    embed_id_and_url, embed_provider_from_src, heading_level_from_tag, inline_markdown,
+    media_matches, normalize_path, order_srcset_for_viewport, parse_srcset, same_site,
 };
 
 #[allow(clippy::type_complexity)]
@@ -113,6 +118,13 @@ pub(crate) fn extract_canonical(doc: &Html, base: &Url) -> Option<String> {
         .map(|u| u.to_string())
 }
 
+/// AMP documents mark their root element `<html amp>` or `<html ⚡>`.
+pub(crate) fn is_amp_document(doc: &Html) -> bool {
+    doc.select(&SEL_HTML)
+        .next()
+        .is_some_and(|el| el.value().attr("amp").is_some() || el.value().attr("⚡").is_some())
+}
+
 pub(crate) fn extract_language(doc: &Html) -> Option<String> {
     doc.select(&SEL_HTML)
         .next()
@@ -147,6 +159,45 @@ pub(crate) fn extract_favicon(doc: &Html, base: &Url) -> Option<String> {
     best.map(|(_, u)| u)
 }
 
+/// `PageData.link_relations`: one entry per rel token of every
+/// `<link rel href>`, hrefs resolved against `base`. Exact duplicates
+/// are dropped.
+pub(crate) fn extract_link_relations(doc: &Html, base: &Url) -> Vec<LinkRelation> {
+    let mut out: Vec<LinkRelation> = Vec::new();
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    for el in doc.select(&SEL_LINK_REL) {
+        let v = el.value();
+        let (Some(rel), Some(href)) = (v.attr("rel"), v.attr("href")) else {
+            continue;
+        };
+        let Ok(abs) = base.join(href.trim()) else {
+            continue;
+        };
+        let attr = |a: &str| {
+            v.attr(a)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        for token in rel.split_whitespace() {
+            let rel = token.to_ascii_lowercase();
+            if !seen.insert((rel.clone(), abs.to_string())) {
+                continue;
+            }
+            out.push(LinkRelation {
+                rel,
+                href: abs.to_string(),
+                media_type: attr("type"),
+                hreflang: attr("hreflang"),
+                media: attr("media"),
+                as_type: attr("as"),
+                title: attr("title"),
+            });
+        }
+    }
+    out
+}
+
 pub(crate) fn extract_hreflang(doc: &Html, base: &Url) -> Vec<HreflangAlternate> {
     let mut out = Vec::new();
     let mut seen: HashSet<(String, String)> = HashSet::new();
@@ -171,13 +222,47 @@ pub(crate) fn extract_hreflang(doc: &Html, base: &Url) -> Vec<HreflangAlternate>
     out
 }
 
-pub(crate) fn extract_structured_data(doc: &Html) -> Vec<JsonValue> {
-    doc.select(&SEL_JSONLD)
+/// `PageData.meta`: every `<meta>` keyed by `name` / `property` /
+/// `http-equiv` / `charset`. Microdata `<meta itemprop>` tags carry no
+/// such key and are left to `extract_microdata`.
+pub(crate) fn extract_meta_map(doc: &Html) -> BTreeMap<String, Vec<String>> {
+    let mut out: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for el in doc.select(&SEL_META) {
+        let v = el.value();
+        let (key, content) = if let Some(charset) = v.attr("charset") {
+            ("charset".to_string(), charset)
+        } else if let Some(name) = v.attr("name") {
+            (name.trim().to_lowercase(), v.attr("content").unwrap_or(""))
+        } else if let Some(property) = v.attr("property") {
+            (property.trim().to_string(), v.attr("content").unwrap_or(""))
+        } else if let Some(equiv) = v.attr("http-equiv") {
+            (
+                format!("http-equiv:{}", equiv.trim().to_lowercase()),
+                v.attr("content").unwrap_or(""),
+            )
+        } else {
+            continue;
+        };
+        if key.is_empty() || key == "http-equiv:" {
+            continue;
+        }
+        out.entry(key).or_default().push(content.trim().to_string());
+    }
+    out
+}
+
+/// JSON-LD blobs, followed by the page's microdata and RDFa items in the
+/// same shape (see `microdata::extract_microdata`).
+pub(crate) fn extract_structured_data(doc: &Html, base: &Url) -> Vec<JsonValue> {
+    let mut out: Vec<JsonValue> = doc
+        .select(&SEL_JSONLD)
         .filter_map(|el| {
             let text = el.text().collect::<String>();
             serde_json::from_str::<JsonValue>(&text).ok()
         })
-        .collect()
+        .collect();
+    out.extend(extract_microdata(doc, base));
+    out
 }
 
 /// Best-effort logo URL. Walks header / logo-class images and the
@@ -402,12 +487,20 @@ pub(crate) fn extract_footer_blocks(doc: &Html) -> Vec<ContentBlock> {
                 let level = heading_level_from_tag(tag);
                 let text = element_text(&el);
                 if !text.is_empty() && seen_texts.insert(text.clone()) {
-                    blocks.push(ContentBlock::Heading { level, text });
+                    blocks.push(ContentBlock::Heading {
+                        level,
+                        text,
+                        markdown: None,
+                    });
                 }
             } else if tag == "p" {
                 let text = element_text(&el);
                 if text.len() > 5 && seen_texts.insert(text.clone()) {
-                    blocks.push(ContentBlock::Paragraph { text });
+                    blocks.push(ContentBlock::Paragraph {
+                        text,
+                        links: Vec::new(),
+                        markdown: None,
+                    });
                 }
             } else if matches!(tag, "ul" | "ol") {
                 let parent_is_list = el
@@ -423,7 +516,10 @@ pub(crate) fn extract_footer_blocks(doc: &Html) -> Vec<ContentBlock> {
                     .filter(|s| !s.is_empty())
                     .collect();
                 if !items.is_empty() {
-                    blocks.push(ContentBlock::List { items });
+                    blocks.push(ContentBlock::List {
+                        items,
+                        markdown_items: Vec::new(),
+                    });
                 }
             }
         }
@@ -437,7 +533,6 @@ pub(crate) fn extract_style_text(doc: &Html) -> String {
         buf.push_str(&el.text().collect::<String>());
         buf.push('\n');
     }
-    use crate::selectors::SEL_BODY;
     if let Some(body) = doc.select(&SEL_BODY).next() {
         for node in body.descendants() {
             if let Some(el) = ElementRef::wrap(node) {
@@ -493,12 +588,12 @@ pub(crate) fn extract_stylesheet_urls(doc: &Html, base: &Url) -> Vec<String> {
 
 /// All internal anchor hrefs (same-host as base_url), resolved to absolute.
 /// Used to build the link graph.
-pub(crate) fn extract_internal_links(doc: &Html, base: &Url) -> Vec<String> {
+pub(crate) fn extract_internal_links(doc: &Html, base: &Url, strict_host: bool) -> Vec<String> {
     let mut out: Vec<String> = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
-    let Some(base_host) = base.host_str() else {
+    if base.host_str().is_none() {
         return out;
-    };
+    }
     for el in doc.select(&SEL_LINK) {
         let Some(href) = el.value().attr("href") else {
             continue;
@@ -511,7 +606,7 @@ pub(crate) fn extract_internal_links(doc: &Html, base: &Url) -> Vec<String> {
             continue;
         }
         let Ok(abs) = base.join(href) else { continue };
-        if abs.host_str() != Some(base_host) {
+        if abs.host_str().is_none() || !same_site(&abs, base, strict_host) {
             continue;
         }
         // Strip fragment for stable comparison.
@@ -525,70 +620,800 @@ pub(crate) fn extract_internal_links(doc: &Html, base: &Url) -> Vec<String> {
     out
 }
 
-pub(crate) async fn download_image(
-    client: &Client,
-    img_url: &str,
-    output_dir: &str,
-) -> Option<String> {
-    let lower_url = img_url.to_lowercase();
-    let tracking_domains = [
-        "googletagmanager",
-        "google-analytics",
-        "facebook.com/tr",
-        "doubleclick",
-        "analytics",
-        "tracking",
-        "pixel",
-        "beacon",
-    ];
-    for domain in &tracking_domains {
-        if lower_url.contains(domain) {
-            return None;
+/// Visible text of an anchor; for icon links the `aria-label`, `title` or
+/// alt text of a wrapped image.
+fn anchor_text(el: &ElementRef) -> String {
+    let text = element_text(el);
+    if !text.is_empty() {
+        return text;
+    }
+    ["aria-label", "title"]
+        .iter()
+        .find_map(|a| el.value().attr(a))
+        .or_else(|| el.select(&SEL_IMG).find_map(|img| img.value().attr("alt")))
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
+
+/// `InlineLink`s of a paragraph, in document order. `javascript:` and
+/// unresolvable hrefs are dropped.
+fn inline_links(el: &ElementRef, page_url: &Url) -> Vec<InlineLink> {
+    el.select(&SEL_LINK)
+        .filter_map(|a| {
+            let href = a.value().attr("href")?.trim();
+            if href.is_empty() || href.starts_with("javascript:") {
+                return None;
+            }
+            Some(InlineLink {
+                text: anchor_text(&a),
+                href: page_url.join(href).ok()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Class-name parts (split on `-` / `_`) that style an element as a
+/// button, e.g. `btn`, `btn-primary`, `wp-block-button__link`.
+const CTA_CLASS_PARTS: [&str; 3] = ["btn", "button", "cta"];
+
+/// Class-name parts / attributes of UI controls that look like buttons but
+/// aren't calls to action: menu toggles, carousel arrows, consent banners.
+const CONTROL_CLASS_PARTS: [&str; 12] = [
+    "close",
+    "toggle",
+    "toggler",
+    "hamburger",
+    "menu",
+    "prev",
+    "next",
+    "slick",
+    "swiper",
+    "carousel",
+    "cookie",
+    "consent",
+];
+
+/// Longest label a CTA can have; longer "buttons" are cards or teasers.
+const CTA_MAX_CHARS: usize = 60;
+
+/// `Cta` block for a button-styled `<a>` or a `<button>` outside a form.
+/// Controls (`aria-expanded` / `aria-controls` / `aria-haspopup`, toggle /
+/// carousel / consent classes), icon-only buttons and overlong labels are
+/// skipped.
+fn cta_block(el: &ElementRef, page_url: &Url) -> Option<ContentBlock> {
+    let v = el.value();
+    let class_parts: Vec<String> = v
+        .classes()
+        .flat_map(|c| c.split(['-', '_']))
+        .filter(|p| !p.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let has_part = |parts: &[&str]| class_parts.iter().any(|p| parts.contains(&p.as_str()));
+    let is_control = ["aria-expanded", "aria-controls", "aria-haspopup"]
+        .iter()
+        .any(|a| v.attr(a).is_some())
+        || has_part(&CONTROL_CLASS_PARTS[..]);
+    if is_control {
+        return None;
+    }
+    let target = match v.name() {
+        "a" => {
+            if !has_part(&CTA_CLASS_PARTS[..]) && v.attr("role") != Some("button") {
+                return None;
+            }
+            v.attr("href")
+        }
+        _ => {
+            let in_form = v.attr("form").is_some()
+                || el
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .any(|a| a.value().name() == "form");
+            if in_form {
+                return None;
+            }
+            v.attr("data-href")
+                .or_else(|| v.attr("data-url"))
+                .or_else(|| v.attr("onclick").and_then(onclick_location))
         }
+    };
+    let text = element_text(el);
+    if text.is_empty() || text.chars().count() > CTA_MAX_CHARS {
+        return None;
+    }
+    let href = target
+        .map(str::trim)
+        .filter(|h| !h.is_empty() && *h != "#" && !h.starts_with("javascript:"))
+        .and_then(|h| page_url.join(h).ok())
+        .map(|u| u.to_string());
+    Some(ContentBlock::Cta { text, href })
+}
+
+/// The URL in an `onclick="location.href='/signup'"` /
+/// `window.location = "…"` handler.
+fn onclick_location(js: &str) -> Option<&str> {
+    let rest = &js[js.find("location")?..];
+    let rest = rest[rest.find('=')? + 1..].trim_start();
+    let quote = rest.chars().next().filter(|c| matches!(c, '\'' | '"'))?;
+    let rest = &rest[1..];
+    Some(&rest[..rest.find(quote)?])
+}
+
+/// Every http(s) anchor on the page with its anchor text, rel tokens and
+/// internal/external classification (see [`same_site`]). Used both for
+/// link discovery during the crawl and for `PageData.links`.
+pub(crate) fn extract_page_links(doc: &Html, base: &Url, strict_host: bool) -> Vec<PageLink> {
+    let mut out: Vec<PageLink> = Vec::new();
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    for el in doc.select(&SEL_LINK) {
+        let Some(href) = el.value().attr("href") else {
+            continue;
+        };
+        if href.starts_with("javascript:")
+            || href.starts_with("mailto:")
+            || href.starts_with("tel:")
+            || href.starts_with('#')
+        {
+            continue;
+        }
+        let Ok(mut abs) = base.join(href) else {
+            continue;
+        };
+        // `file:` links only count on local pages (`dump-it --url ./site`).
+        if !matches!(abs.scheme(), "http" | "https") && abs.scheme() != base.scheme() {
+            continue;
+        }
+        abs.set_fragment(None);
+        let text = anchor_text(&el);
+        let rel = el
+            .value()
+            .attr("rel")
+            .map(|r| r.split_whitespace().map(str::to_ascii_lowercase).collect())
+            .unwrap_or_default();
+        let url = abs.to_string();
+        if !seen.insert((url.clone(), text.clone())) {
+            continue;
+        }
+        out.push(PageLink {
+            internal: same_site(&abs, base, strict_host),
+            url,
+            text,
+            rel,
+        });
+    }
+    out
+}
+
+/// Lazy-loader attributes carrying the real image URL (WP Rocket, a3,
+/// Lazy Load by WP Media, jQuery lazyload, lazysizes, Shopify themes).
+const LAZY_SRC_ATTRS: [&str; 4] = ["data-lazy-src", "data-src", "data-original", "data-lazy"];
+const LAZY_SRCSET_ATTRS: [&str; 2] = ["data-lazy-srcset", "data-srcset"];
+
+/// Stand-ins lazy loaders put in `src` until JS swaps the real URL in.
+const PLACEHOLDER_IMAGE_MARKERS: [&str; 9] = [
+    "1x1",
+    "placeholder",
+    "blank.gif",
+    "spacer.gif",
+    "transparent.gif",
+    "grey.gif",
+    "loading.gif",
+    "lazyload",
+    "lazy-load",
+];
+
+fn is_placeholder_image(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    lower.starts_with("data:") || PLACEHOLDER_IMAGE_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// `<img>` URL candidates in preference order: lazy-loader attributes
+/// first (`src` is usually a placeholder until JS runs, `loading="lazy"`
+/// or not), then the `<noscript>` twin many lazy loaders emit, then
+/// `src` / `srcset`. Placeholders are filtered by the caller.
+fn img_candidates(img: &ElementRef) -> Vec<String> {
+    let attr = |name: &str| img.value().attr(name).filter(|v| !v.trim().is_empty());
+    let mut out: Vec<String> = LAZY_SRC_ATTRS
+        .iter()
+        .filter_map(|a| attr(a))
+        .map(str::to_string)
+        .collect();
+    for a in LAZY_SRCSET_ATTRS {
+        if let Some(srcset) = attr(a) {
+            out.extend(parse_srcset(srcset).into_iter().map(|(u, _)| u.to_string()));
+        }
+    }
+    out.extend(noscript_img_candidates(img));
+    out.extend(attr("src").map(str::to_string));
+    if let Some(srcset) = attr("srcset") {
+        out.extend(parse_srcset(srcset).into_iter().map(|(u, _)| u.to_string()));
+    }
+    out
+}
+
+/// `src` / `srcset` of the `<img>` in a `<noscript>` right after `img`.
+/// With scripting on, the parser keeps `<noscript>` content as text, so
+/// it's re-parsed as a fragment.
+fn noscript_img_candidates(img: &ElementRef) -> Vec<String> {
+    let Some(noscript) = img
+        .next_siblings()
+        .filter_map(ElementRef::wrap)
+        .next()
+        .filter(|e| e.value().name() == "noscript")
+    else {
+        return Vec::new();
+    };
+    let text: String = noscript.text().collect();
+    let markup = if text.contains("<img") {
+        text
+    } else {
+        noscript.inner_html()
+    };
+    let fragment = Html::parse_fragment(&markup);
+    let Some(inner) = fragment.select(&SEL_IMG_ANY).next() else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    out.extend(inner.value().attr("src").map(str::to_string));
+    if let Some(srcset) = inner.value().attr("srcset") {
+        out.extend(parse_srcset(srcset).into_iter().map(|(u, _)| u.to_string()));
     }
+    out
+}
 
-    let mut hasher = Sha256::new();
-    hasher.update(img_url.as_bytes());
-    let hash = format!("{:x}", hasher.finalize());
-    let extension = image_extension_from_url(img_url);
-    let filename = format!("{}.{}", &hash[..16], extension);
-    let filepath = format!("{output_dir}/{filename}");
+/// Art direction for the `<picture>` around `img`, as a browser with a
+/// `viewport`-px window would resolve it: the first `<source>` whose
+/// `media` matches, its best-fitting `srcset` candidate leading. Returns
+/// (that source's candidates, every other source's URLs) — the latter
+/// rank below the `<img>`'s own candidates as fallbacks.
+fn picture_candidates(img: &ElementRef, viewport: u32) -> (Vec<String>, Vec<String>) {
+    let Some(picture) = img
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|p| p.value().name() == "picture")
+    else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut chosen: Vec<String> = Vec::new();
+    let mut others: Vec<String> = Vec::new();
+    let sources = picture
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|c| c.value().name() == "source");
+    for source in sources {
+        let srcset = ["srcset", "data-srcset", "data-lazy-srcset"]
+            .iter()
+            .find_map(|a| source.value().attr(a))
+            .unwrap_or("");
+        let entries = parse_srcset(srcset);
+        if entries.is_empty() {
+            continue;
+        }
+        let media_ok = source
+            .value()
+            .attr("media")
+            .is_none_or(|m| media_matches(m, viewport));
+        if chosen.is_empty() && media_ok {
+            chosen = order_srcset_for_viewport(&entries, viewport);
+        } else {
+            others.extend(entries.iter().map(|(u, _)| u.to_string()));
+        }
+    }
+    (chosen, others)
+}
 
-    if Path::new(&filepath).exists() {
-        return Some(normalize_path(&filepath));
+/// `<video>` → [`ContentBlock::Video`]; `None` without any source URL.
+fn video_block(el: &ElementRef, page_url: &Url) -> Option<ContentBlock> {
+    let absolute = |raw: &str| -> Option<String> {
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with("blob:") {
+            return None;
+        }
+        page_url.join(raw).ok().map(|u| u.to_string())
+    };
+    let mut sources: Vec<VideoSource> = Vec::new();
+    let direct = el.value().attr("src").or(el.value().attr("data-src"));
+    if let Some(url) = direct.and_then(absolute) {
+        sources.push(VideoSource {
+            url,
+            mime_type: None,
+            local_path: None,
+        });
     }
+    for source in el.select(&SEL_VIDEO_SOURCE) {
+        let raw = source
+            .value()
+            .attr("src")
+            .or(source.value().attr("data-src"));
+        let Some(url) = raw.and_then(absolute) else {
+            continue;
+        };
+        if sources.iter().any(|s| s.url == url) {
+            continue;
+        }
+        let mime_type = source
+            .value()
+            .attr("type")
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        sources.push(VideoSource {
+            url,
+            mime_type,
+            local_path: None,
+        });
+    }
+    if sources.is_empty() {
+        return None;
+    }
+    let captions = el
+        .select(&SEL_VIDEO_TRACK)
+        .filter_map(|track| {
+            let kind = track.value().attr("kind").unwrap_or("subtitles");
+            if !matches!(kind, "captions" | "subtitles") {
+                return None;
+            }
+            let attr = |name| {
+                track
+                    .value()
+                    .attr(name)
+                    .map(|v: &str| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            Some(VideoCaption {
+                url: absolute(track.value().attr("src")?)?,
+                kind: kind.to_string(),
+                srclang: attr("srclang"),
+                label: attr("label"),
+            })
+        })
+        .collect();
+    Some(ContentBlock::Video {
+        sources,
+        poster_url: el.value().attr("poster").and_then(absolute),
+        poster_local_path: None,
+        captions,
+        title: el.value().attr("title").unwrap_or("").to_string(),
+    })
+}
+
+/// Stand-in for hidden field values under `--redact-hidden-values`.
+const REDACTED_VALUE: &str = "[redacted]";
+
+/// `Form` block for a `<form>`: action resolved against the page, fields
+/// with their labels, submit text and inferred purpose. Hidden inputs are
+/// only recorded with `--include-hidden-fields`, value included.
+fn form_block(el: &ElementRef, doc: &Html, page_url: &Url, options: &PageOptions) -> ContentBlock {
+    let action_raw = el.value().attr("action").unwrap_or("");
+    let action = if action_raw.is_empty() {
+        String::new()
+    } else {
+        page_url
+            .join(action_raw)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| action_raw.to_string())
+    };
+    let method = el.value().attr("method").unwrap_or("get").to_uppercase();
+
+    let mut fields: Vec<FormField> = Vec::new();
+    // Per field: its outermost step container and nearest fieldset.
+    let mut groups = Vec::new();
+    // (type, name) of a radio / checkbox → its field and first choice.
+    let mut choice_groups: HashMap<(String, String), (usize, String)> = HashMap::new();
+    for input in el.select(&SEL_INPUT) {
+        let field_type = field_type(&input);
+        if matches!(field_type.as_str(), "submit" | "button" | "reset" | "image") {
+            continue;
+        }
+        let hidden = field_type == "hidden";
+        if hidden && !options.include_hidden_fields {
+            continue;
+        }
+        let name = input.value().attr("name").unwrap_or("").to_string();
+        let placeholder = input.value().attr("placeholder").unwrap_or("").to_string();
+        let required = input.value().attr("required").is_some();
+
+        let mut label = String::new();
+        if let Some(id) = input.value().attr("id") {
+            if let Ok(label_sel) = Selector::parse(&format!("label[for='{id}']")) {
+                if let Some(label_elem) = doc.select(&label_sel).next() {
+                    label = element_text(&label_elem);
+                }
+            }
+        }
+        if label.is_empty() {
+            for anc in input.ancestors() {
+                if let Some(anc_el) = ElementRef::wrap(anc) {
+                    if anc_el.value().name() == "label" {
+                        label = element_text(&anc_el);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut choices = Vec::new();
+        if input.value().name() == "select" {
+            for option in input.select(&SEL_OPTION) {
+                let opt_text = element_text(&option);
+                if !opt_text.is_empty() {
+                    choices.push(opt_text);
+                }
+            }
+        }
+        let value = hidden.then(|| {
+            if options.redact_hidden_values {
+                REDACTED_VALUE.to_string()
+            } else {
+                input.value().attr("value").unwrap_or("").to_string()
+            }
+        });
 
-    match fetch_with_retry(client, img_url, 2).await {
-        Some(response) if response.status().is_success() => {
-            if let Ok(bytes) = response.bytes().await {
-                if bytes.len() < 1024 {
-                    return None;
+        if matches!(field_type.as_str(), "radio" | "checkbox") && !name.is_empty() {
+            let choice = if label.is_empty() {
+                input.value().attr("value").unwrap_or("").to_string()
+            } else {
+                label.clone()
+            };
+            let key = (field_type.clone(), name.clone());
+            if let Some((i, first)) = choice_groups.get(&key) {
+                // Second input of the group: the field now stands for the
+                // whole group, its own label becomes the first option.
+                let field = &mut fields[*i];
+                if field.options.is_empty() {
+                    field.options.push(first.clone());
+                    field.label = choice_group_label(&input, el);
                 }
-                if fs::write(&filepath, &bytes).await.is_ok() {
-                    return Some(normalize_path(&filepath));
+                if !choice.is_empty() && !field.options.contains(&choice) {
+                    field.options.push(choice);
                 }
+                field.required |= required;
+                continue;
+            }
+            choice_groups.insert(key, (fields.len(), choice));
+        }
+
+        let mut step = None;
+        let mut fieldset = None;
+        for anc in input.ancestors().filter_map(ElementRef::wrap) {
+            if anc.id() == el.id() {
+                break;
+            }
+            if is_step_container(&anc) {
+                step = Some(anc);
+            }
+            if fieldset.is_none() && anc.value().name() == "fieldset" {
+                fieldset = Some(anc);
             }
         }
-        _ => {}
+        groups.push((step, fieldset));
+        let attr = |a: &str| {
+            input
+                .value()
+                .attr(a)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let length = |a: &str| attr(a).and_then(|v| v.parse::<u32>().ok());
+        let accept = if field_type == "file" {
+            attr("accept")
+        } else {
+            None
+        };
+        let multiple = input.value().attr("multiple").is_some()
+            && matches!(field_type.as_str(), "file" | "email" | "select");
+        fields.push(FormField {
+            field_type,
+            name,
+            label,
+            placeholder,
+            required,
+            options: choices,
+            value,
+            pattern: attr("pattern"),
+            min: attr("min"),
+            max: attr("max"),
+            minlength: length("minlength"),
+            maxlength: length("maxlength"),
+            step: attr("step"),
+            inputmode: attr("inputmode"),
+            accept,
+            multiple,
+        });
     }
-    None
+
+    let mut submit_text = String::from("Submit");
+    if let Some(submit_btn) = el.select(&SEL_SUBMIT).next() {
+        if submit_btn.value().name() == "input" {
+            submit_text = submit_btn
+                .value()
+                .attr("value")
+                .unwrap_or("Submit")
+                .to_string();
+        } else {
+            let t = element_text(&submit_btn);
+            if !t.is_empty() {
+                submit_text = t;
+            }
+        }
+    }
+
+    let purpose = classify_form_purpose(&fields, &submit_text, &action);
+    let (sections, multi_step) = form_sections(el, &groups);
+    ContentBlock::Form {
+        action,
+        method,
+        fields,
+        submit_text,
+        purpose,
+        sections,
+        multi_step,
+    }
+}
+
+/// `<input>` types browsers implement; anything else renders as `text`.
+const INPUT_TYPES: [&str; 22] = [
+    "button",
+    "checkbox",
+    "color",
+    "date",
+    "datetime-local",
+    "email",
+    "file",
+    "hidden",
+    "image",
+    "month",
+    "number",
+    "password",
+    "radio",
+    "range",
+    "reset",
+    "search",
+    "submit",
+    "tel",
+    "text",
+    "time",
+    "url",
+    "week",
+];
+
+/// `field_type` of an `<input>` / `<textarea>` / `<select>`: the input's
+/// `type` lowercased, with unknown and missing types as `text` the way
+/// browsers treat them; the tag name for the others.
+fn field_type(input: &ElementRef) -> String {
+    if input.value().name() != "input" {
+        return input.value().name().to_string();
+    }
+    let ty = input
+        .value()
+        .attr("type")
+        .unwrap_or("text")
+        .trim()
+        .to_ascii_lowercase();
+    if INPUT_TYPES.contains(&ty.as_str()) {
+        ty
+    } else {
+        "text".to_string()
+    }
+}
+
+/// Label of a radio / checkbox group: the `<legend>` of its fieldset, else
+/// the `aria-label` of a `role="radiogroup"` / `role="group"` wrapper.
+fn choice_group_label(input: &ElementRef, form: &ElementRef) -> String {
+    for anc in input.ancestors().filter_map(ElementRef::wrap) {
+        if anc.id() == form.id() {
+            break;
+        }
+        if anc.value().name() == "fieldset" {
+            if let Some(legend) = anc
+                .children()
+                .filter_map(ElementRef::wrap)
+                .find(|c| c.value().name() == "legend")
+            {
+                return element_text(&legend);
+            }
+        }
+        if matches!(anc.value().attr("role"), Some("radiogroup" | "group")) {
+            if let Some(label) = anc.value().attr("aria-label") {
+                return label.trim().to_string();
+            }
+        }
+    }
+    String::new()
+}
+
+/// Class names of form-plugin page wrappers (Gravity Forms, WPForms).
+const STEP_CLASSES: [&str; 2] = ["gform_page", "wpforms-page"];
+
+/// Button labels that advance a wizard (en, de, cs, fr, es).
+const NEXT_LABELS: [&str; 7] = [
+    "next",
+    "continue",
+    "weiter",
+    "další",
+    "pokračovat",
+    "suivant",
+    "siguiente",
+];
+
+/// `data-step`, a `step` class part (`form-step`, `step_2`) or a
+/// form-plugin page wrapper.
+fn is_step_container(el: &ElementRef) -> bool {
+    let v = el.value();
+    v.attr("data-step").is_some()
+        || v.classes().any(|c| {
+            STEP_CLASSES.contains(&c) || c.split(['-', '_']).any(|p| p.eq_ignore_ascii_case("step"))
+        })
+}
+
+/// Whether the form has a "Next" / "Continue" button, by label or class.
+fn has_next_button(form: &ElementRef) -> bool {
+    form.select(&SEL_FORM_BUTTON).any(|b| {
+        let label = match b.value().name() {
+            "input" => b.value().attr("value").unwrap_or("").to_lowercase(),
+            _ => element_text(&b).to_lowercase(),
+        };
+        let label = label.trim_matches(|c: char| !c.is_alphanumeric());
+        NEXT_LABELS.iter().any(|n| label.starts_with(n))
+            || b.value()
+                .classes()
+                .any(|c| c.split(['-', '_']).any(|p| p.eq_ignore_ascii_case("next")))
+    })
+}
+
+/// Group the form's fields (`groups[i]` = step container and fieldset of
+/// `fields[i]`) into sections. Two or more step containers make a
+/// multi-step form with one section per step; otherwise every fieldset is
+/// a section, numbered as steps when there are several and a "Next"
+/// button. Sections follow document order.
+fn form_sections(
+    form: &ElementRef,
+    groups: &[(Option<ElementRef>, Option<ElementRef>)],
+) -> (Vec<FormSection>, bool) {
+    fn collect<'a>(
+        keys: impl Iterator<Item = Option<ElementRef<'a>>>,
+    ) -> Vec<(ElementRef<'a>, Vec<usize>)> {
+        let mut out: Vec<(ElementRef, Vec<usize>)> = Vec::new();
+        for (i, key) in keys.enumerate() {
+            let Some(key) = key else { continue };
+            match out.iter_mut().find(|(k, _)| k.id() == key.id()) {
+                Some((_, fields)) => fields.push(i),
+                None => out.push((key, vec![i])),
+            }
+        }
+        out
+    }
+    let steps = collect(groups.iter().map(|(s, _)| *s));
+    let (found, multi_step) = if steps.len() >= 2 {
+        (steps, true)
+    } else {
+        let fieldsets = collect(groups.iter().map(|(_, f)| *f));
+        let multi_step = fieldsets.len() >= 2 && has_next_button(form);
+        (fieldsets, multi_step)
+    };
+    let sections = found
+        .into_iter()
+        .enumerate()
+        .map(|(i, (container, fields))| FormSection {
+            legend: container
+                .select(&SEL_FORM_SECTION_TITLE)
+                .next()
+                .map(|t| element_text(&t))
+                .unwrap_or_default(),
+            step: multi_step.then_some(i + 1),
+            fields,
+        })
+        .collect();
+    (sections, multi_step)
 }
 
-pub(crate) async fn extract_content_blocks(
-    client: &Client,
+/// Containers `content_root` considers (plus `[role=main]`).
+const ROOT_TAGS: [&str; 5] = ["body", "main", "article", "section", "div"];
+
+/// Score multiplier for `main` / `article` / `[role=main]`, so they beat
+/// the text-only div nested inside them that leaves out the title.
+const SEMANTIC_ROOT_BONUS: f64 = 1.1;
+
+/// The element holding the page's content: the container with the most
+/// non-link text, discounted by its link density as `(1 - links / text)²`.
+/// A `<main>` that also wraps sidebars, menus and related-post lists
+/// loses to the tighter block inside it; ties go to the innermost
+/// wrapper. Text under `skip` (nav, header, footer, scripts, … or the
+/// `--skip-selectors` list) doesn't count. Pages without any text fall
+/// back to the first `main` / `article`, else `body`.
+fn content_root<'a>(doc: &'a Html, skip: &Selector) -> Option<ElementRef<'a>> {
+    let body = doc.select(&SEL_BODY).next()?;
+    // element → (text chars, chars of that inside links)
+    let mut totals = HashMap::new();
+    for node in body.descendants() {
+        let Some(text) = node.value().as_text() else {
+            continue;
+        };
+        let chars: usize = text.split_whitespace().map(|w| w.chars().count() + 1).sum();
+        if chars == 0 {
+            continue;
+        }
+        let ancestors: Vec<ElementRef> = node.ancestors().filter_map(ElementRef::wrap).collect();
+        if ancestors.iter().any(|a| skip.matches(a)) {
+            continue;
+        }
+        let in_link = ancestors.iter().any(|a| a.value().name() == "a");
+        for a in ancestors {
+            let entry = totals.entry(a.id()).or_insert((0usize, 0usize));
+            entry.0 += chars;
+            if in_link {
+                entry.1 += chars;
+            }
+        }
+    }
+    let score = |el: &ElementRef| -> f64 {
+        let Some(&(text, links)) = totals.get(&el.id()) else {
+            return 0.0;
+        };
+        let density = links as f64 / text as f64;
+        let semantic = matches!(el.value().name(), "main" | "article")
+            || el.value().attr("role") == Some("main");
+        let bonus = if semantic { SEMANTIC_ROOT_BONUS } else { 1.0 };
+        (text - links) as f64 * (1.0 - density).powi(2) * bonus
+    };
+
+    let (mut best, mut best_score) = (body, score(&body));
+    if best_score == 0.0 {
+        return doc.select(&SEL_MAIN).next().or(Some(body));
+    }
+    for node in body.descendants().skip(1) {
+        let Some(el) = ElementRef::wrap(node) else {
+            continue;
+        };
+        if !ROOT_TAGS.contains(&el.value().name()) && el.value().attr("role") != Some("main") {
+            continue;
+        }
+        let s = score(&el);
+        if s >= best_score {
+            (best, best_score) = (el, s);
+        }
+    }
+    Some(best)
+}
+
+/// Content blocks of the main region: the first `--content-selector`
+/// match, else `content_root`. Image blocks come back with an empty
+/// `local_path` (inline SVGs excepted — they're written here); the image
+/// phase downloads them once the pages are in.
+pub(crate) fn extract_content_blocks(
     doc: &Html,
     page_url: &Url,
     output_dir: &str,
     max_images: usize,
+    options: &PageOptions,
 ) -> Vec<ContentBlock> {
-    let content_root = doc
-        .select(&SEL_MAIN)
-        .next()
-        .or_else(|| doc.select(&crate::selectors::SEL_BODY).next());
-    let Some(content_root) = content_root else {
+    let skip: &Selector = options.skip_selector.as_ref().unwrap_or(&SEL_SKIP);
+    let chosen = options
+        .content_selector
+        .as_ref()
+        .and_then(|sel| doc.select(sel).next());
+    if options.content_selector.is_some() && chosen.is_none() {
+        tracing::debug!("--content-selector matched nothing on {page_url}; scoring instead");
+    }
+    let Some(content_root) = chosen.or_else(|| content_root(doc, skip)) else {
         return Vec::new();
     };
 
+    // CTAs get blocks of their own, so a paragraph or heading around one
+    // leaves its label out, and a CTA inside another isn't a second one.
+    let is_cta = |el: &ElementRef| {
+        matches!(el.value().name(), "a" | "button") && cta_block(el, page_url).is_some()
+    };
+
+    // Heading / paragraph / list text stays plain; --rich-text adds the
+    // Markdown-formatted copy alongside it.
+    let block_markdown = |el: &ElementRef| {
+        options
+            .rich_text
+            .then(|| inline_markdown(el, page_url, &is_cta))
+            .filter(|md| !md.is_empty())
+    };
+
     let mut blocks: Vec<ContentBlock> = Vec::new();
     let mut seen_image_urls: HashSet<String> = HashSet::new();
     let mut images_kept: usize = 0;
@@ -612,7 +1437,7 @@ pub(crate) async fn extract_content_blocks(
         let Some(el) = ElementRef::wrap(node) else {
             continue;
         };
-        if element_in_skip_zone(&el) {
+        if element_in_skip_zone(&el, skip) {
             continue;
         }
         let tag = el.value().name();
@@ -629,14 +1454,31 @@ pub(crate) async fn extract_content_blocks(
 
         if matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6") {
             let level = heading_level_from_tag(tag);
-            let text = element_text(&el);
+            let text = element_text_skipping(&el, &is_cta);
             if !text.is_empty() {
-                blocks.push(ContentBlock::Heading { level, text });
+                blocks.push(ContentBlock::Heading {
+                    level,
+                    text,
+                    markdown: block_markdown(&el),
+                });
             }
         } else if tag == "p" {
-            let text = element_text(&el);
+            let text = element_text_skipping(&el, &is_cta);
             if !text.is_empty() && text.len() > 20 {
-                blocks.push(ContentBlock::Paragraph { text });
+                let links = inline_links(&el, page_url);
+                blocks.push(ContentBlock::Paragraph {
+                    text,
+                    links,
+                    markdown: block_markdown(&el),
+                });
+            }
+        } else if matches!(tag, "a" | "button") {
+            let nested = el
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|a| is_cta(&a));
+            if let Some(cta) = cta_block(&el, page_url).filter(|_| !nested) {
+                blocks.push(cta);
             }
         } else if tag == "iframe" {
             let src_raw = el
@@ -652,11 +1494,19 @@ pub(crate) async fn extract_content_blocks(
                 .map(|u| u.to_string())
                 .unwrap_or_else(|_| src_raw.to_string());
             let title = el.value().attr("title").unwrap_or("").to_string();
-            let provider = embed_provider_from_src(&src).to_string();
+            let provider = embed_provider_from_src(&src);
+            let (id, url) = match embed_id_and_url(provider, &src) {
+                Some((id, url)) => (Some(id), Some(url)),
+                None => (None, None),
+            };
             blocks.push(ContentBlock::Embed {
-                provider,
+                provider: provider.to_string(),
                 src,
                 title,
+                id,
+                url,
+                author_name: None,
+                thumbnail_url: None,
             });
         } else if tag == "svg" {
             let svg_outer = el.html();
@@ -694,42 +1544,11 @@ pub(crate) async fn extract_content_blocks(
                 original_url: format!("inline-svg://{short}"),
                 local_path: normalize_path(&filepath),
                 alt_text: alt,
+                svg_markup: None,
+                fallback_urls: Vec::new(),
             });
         } else if tag == "img" {
-            // <picture><source srcset></picture> best candidate.
-            let picture_source = el
-                .parent()
-                .and_then(ElementRef::wrap)
-                .filter(|p| p.value().name() == "picture")
-                .and_then(|picture| {
-                    let mut best: Option<String> = None;
-                    let mut best_w: u32 = 0;
-                    for child in picture.children() {
-                        if let Some(c_el) = ElementRef::wrap(child) {
-                            if c_el.value().name() == "source" {
-                                if let Some(ss) = c_el.value().attr("srcset") {
-                                    for part in ss.split(',') {
-                                        let bits: Vec<&str> = part.split_whitespace().collect();
-                                        let url = bits.first().copied().unwrap_or("");
-                                        let w: u32 = bits
-                                            .get(1)
-                                            .and_then(|s| s.trim_end_matches('w').parse().ok())
-                                            .unwrap_or(0);
-                                        if !url.is_empty() && (best.is_none() || w > best_w) {
-                                            best = Some(url.to_string());
-                                            best_w = w;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    best
-                });
-
-            let primary = el.value().attr("src").map(str::to_string);
-            let data_src = el.value().attr("data-src").map(str::to_string);
-            let srcset = el.value().attr("srcset").map(str::to_string);
+            let (picture_pick, picture_rest) = picture_candidates(&el, options.image_viewport);
             let mut alt = el.value().attr("alt").unwrap_or("").to_string();
             // Fall back to <figcaption> when alt is empty and the image
             // sits inside a <figure>. Most figures use caption-as-description.
@@ -749,41 +1568,43 @@ pub(crate) async fn extract_content_blocks(
             }
 
             let mut candidates: Vec<String> = Vec::new();
-            candidates.extend(picture_source);
-            candidates.extend(primary);
-            candidates.extend(data_src);
-            if let Some(srcset_str) = srcset {
-                for part in srcset_str.split(',') {
-                    if let Some(first) = part.split_whitespace().next() {
-                        candidates.push(first.to_string());
-                    }
-                }
-            }
+            candidates.extend(picture_pick);
+            candidates.extend(img_candidates(&el));
+            candidates.extend(picture_rest);
 
+            let mut usable: Vec<String> = Vec::new();
             for src in candidates {
-                if let Ok(abs) = page_url.join(&src) {
-                    let url_str = abs.to_string();
-                    if url_str.starts_with("data:")
-                        || url_str.contains("1x1")
-                        || url_str.contains("placeholder")
-                    {
-                        continue;
-                    }
-                    if !seen_image_urls.insert(url_str.clone()) {
-                        break;
-                    }
-                    if cap_images && images_kept >= max_images {
-                        break;
-                    }
-                    images_kept += 1;
-                    blocks.push(ContentBlock::Image {
-                        original_url: url_str,
-                        local_path: String::new(),
-                        alt_text: alt.clone(),
-                    });
-                    break;
+                let Ok(abs) = page_url.join(&src) else {
+                    continue;
+                };
+                let url_str = abs.to_string();
+                if is_placeholder_image(&url_str) {
+                    continue;
+                }
+                if !usable.contains(&url_str) {
+                    usable.push(url_str);
                 }
             }
+            // First usable candidate is the image; the rest are download
+            // fallbacks for the image phase.
+            let mut usable = usable.into_iter();
+            let Some(url_str) = usable.next() else {
+                continue;
+            };
+            if !seen_image_urls.insert(url_str.clone()) {
+                continue;
+            }
+            if cap_images && images_kept >= max_images {
+                continue;
+            }
+            images_kept += 1;
+            blocks.push(ContentBlock::Image {
+                original_url: url_str,
+                local_path: String::new(),
+                alt_text: alt,
+                svg_markup: None,
+                fallback_urls: usable.collect(),
+            });
         } else if matches!(tag, "ul" | "ol") {
             let parent_is_list = el
                 .parent()
@@ -792,103 +1613,29 @@ pub(crate) async fn extract_content_blocks(
             if parent_is_list {
                 continue;
             }
-            let items: Vec<String> = el
+            let (items, markdown_items): (Vec<String>, Vec<String>) = el
                 .select(&SEL_LI)
-                .map(|li| element_text(&li))
-                .filter(|s| !s.is_empty())
-                .collect();
+                .map(|li| {
+                    (
+                        element_text_skipping(&li, &is_cta),
+                        block_markdown(&li).unwrap_or_default(),
+                    )
+                })
+                .filter(|(text, _)| !text.is_empty())
+                .unzip();
             if !items.is_empty() {
-                blocks.push(ContentBlock::List { items });
-            }
-        } else if tag == "form" {
-            let action_raw = el.value().attr("action").unwrap_or("");
-            let action = if action_raw.is_empty() {
-                String::new()
-            } else {
-                page_url
-                    .join(action_raw)
-                    .map(|u| u.to_string())
-                    .unwrap_or_else(|_| action_raw.to_string())
-            };
-            let method = el.value().attr("method").unwrap_or("get").to_uppercase();
-
-            let mut fields = Vec::new();
-            for input in el.select(&SEL_INPUT) {
-                let field_type = input
-                    .value()
-                    .attr("type")
-                    .unwrap_or(input.value().name())
-                    .to_string();
-                if matches!(field_type.as_str(), "hidden" | "submit" | "button") {
-                    continue;
-                }
-                let name = input.value().attr("name").unwrap_or("").to_string();
-                let placeholder = input.value().attr("placeholder").unwrap_or("").to_string();
-                let required = input.value().attr("required").is_some();
-
-                let mut label = String::new();
-                if let Some(id) = input.value().attr("id") {
-                    if let Ok(label_sel) = Selector::parse(&format!("label[for='{id}']")) {
-                        if let Some(label_elem) = doc.select(&label_sel).next() {
-                            label = element_text(&label_elem);
-                        }
-                    }
-                }
-                if label.is_empty() {
-                    for anc in input.ancestors() {
-                        if let Some(anc_el) = ElementRef::wrap(anc) {
-                            if anc_el.value().name() == "label" {
-                                label = element_text(&anc_el);
-                                break;
-                            }
-                        }
-                    }
-                }
-
-                let mut options = Vec::new();
-                if input.value().name() == "select" {
-                    for option in input.select(&SEL_OPTION) {
-                        let opt_text = element_text(&option);
-                        if !opt_text.is_empty() {
-                            options.push(opt_text);
-                        }
-                    }
-                }
-
-                fields.push(FormField {
-                    field_type,
-                    name,
-                    label,
-                    placeholder,
-                    required,
-                    options,
-                });
-            }
-
-            let mut submit_text = String::from("Submit");
-            if let Some(submit_btn) = el.select(&SEL_SUBMIT).next() {
-                if submit_btn.value().name() == "input" {
-                    submit_text = submit_btn
-                        .value()
-                        .attr("value")
-                        .unwrap_or("Submit")
-                        .to_string();
+                let markdown_items = if options.rich_text {
+                    markdown_items
                 } else {
-                    let t = element_text(&submit_btn);
-                    if !t.is_empty() {
-                        submit_text = t;
-                    }
-                }
+                    Vec::new()
+                };
+                blocks.push(ContentBlock::List {
+                    items,
+                    markdown_items,
+                });
             }
-
-            let purpose = classify_form_purpose(&fields, &submit_text, &action);
-            blocks.push(ContentBlock::Form {
-                action,
-                method,
-                fields,
-                submit_text,
-                purpose,
-            });
+        } else if tag == "form" {
+            blocks.push(form_block(&el, doc, page_url, options));
         } else if tag == "pre" {
             // Detect language from `<code class="language-rust">` or
             // `<pre class="hljs rust">` etc.
@@ -959,7 +1706,9 @@ pub(crate) async fn extract_content_blocks(
                     .filter(|s| !s.is_empty());
                 blocks.push(ContentBlock::Quote { text, cite });
             }
-        } else if tag == "video" || tag == "audio" {
+        } else if tag == "video" {
+            blocks.extend(video_block(&el, page_url));
+        } else if tag == "audio" {
             let primary = el.value().attr("src").map(str::to_string);
             let from_source = el
                 .select(&SEL_VIDEO_SOURCE)
@@ -1093,52 +1842,525 @@ pub(crate) async fn extract_content_blocks(
         }
     }
 
-    let blocks = crate::util::dedup_adjacent_long_text(blocks);
+    crate::util::dedup_adjacent_long_text(blocks)
+}
 
-    let mut download_futs = Vec::new();
-    for (idx, block) in blocks.iter().enumerate() {
-        if let ContentBlock::Image { original_url, .. } = block {
-            if original_url.starts_with("inline-svg://") {
-                continue;
-            }
-            let url = original_url.clone();
-            let dir = output_dir.to_string();
-            download_futs.push(async move { (idx, download_image(client, &url, &dir).await) });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_links_keep_targets_in_order() {
+        let doc = Html::parse_fragment(
+            "<p>See <a href='/pricing'>our plans</a>, <a href='#faq'>the FAQ</a>, \
+             <a href='mailto:hi@x.com'><img alt='Mail us'></a> or \
+             <a href='javascript:void(0)'>nothing</a>.</p>",
+        );
+        let p = doc.select(&Selector::parse("p").unwrap()).next().unwrap();
+        let base = Url::parse("https://x.com/about").unwrap();
+        let links: Vec<(String, String)> = inline_links(&p, &base)
+            .into_iter()
+            .map(|l| (l.text, l.href))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("our plans".into(), "https://x.com/pricing".into()),
+                ("the FAQ".into(), "https://x.com/about#faq".into()),
+                ("Mail us".into(), "mailto:hi@x.com".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lazy_images_prefer_the_real_url_over_the_placeholder() {
+        let doc = Html::parse_document(
+            r#"<img id="rocket" src="/img/blank.gif" data-lazy-src="/img/a.jpg"
+                data-srcset="/img/a-400.jpg 400w, /img/a-800.jpg 800w">
+            <img id="noscript" src="data:image/gif;base64,R0lGOD" class="lazyload">
+            <noscript><img src="/img/b.jpg" srcset="/img/b-2x.jpg 2x"></noscript>"#,
+        );
+        let candidates = |id: &str| {
+            let selector = Selector::parse(&format!("#{id}")).unwrap();
+            img_candidates(&doc.select(&selector).next().unwrap())
+        };
+        assert_eq!(
+            candidates("rocket"),
+            [
+                "/img/a.jpg",
+                "/img/a-400.jpg",
+                "/img/a-800.jpg",
+                "/img/blank.gif"
+            ]
+        );
+        assert_eq!(
+            candidates("noscript"),
+            [
+                "/img/b.jpg",
+                "/img/b-2x.jpg",
+                "data:image/gif;base64,R0lGOD"
+            ]
+        );
+        assert!(is_placeholder_image("https://x.com/img/blank.gif"));
+        assert!(is_placeholder_image("https://x.com/Lazy-Load/a.png"));
+        assert!(is_placeholder_image("data:image/gif;base64,R0lGOD"));
+        assert!(!is_placeholder_image("https://x.com/img/a.jpg"));
+    }
+
+    #[test]
+    fn page_links_keep_text_rel_and_site() {
+        let doc = Html::parse_document(
+            r#"<a href="/about#team">About us</a> <a href="/about">About us</a>
+            <a href="/about">Team</a> <a href="https://www.x.com/cart" aria-label="Cart"></a>
+            <a href="https://x.com/home"><img src="/logo.png" alt="Home  page"></a>
+            <a href="https://ads.test/" rel="Sponsored NOFOLLOW">Deal</a>
+            <a href="mailto:a@x.com">Mail</a> <a href="javascript:void(0)">Menu</a>"#,
+        );
+        let base = Url::parse("https://x.com/").unwrap();
+        let links = extract_page_links(&doc, &base, false);
+        let summary: Vec<_> = links
+            .iter()
+            .map(|l| (l.url.as_str(), l.text.as_str(), l.internal))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("https://x.com/about", "About us", true),
+                ("https://x.com/about", "Team", true),
+                ("https://www.x.com/cart", "Cart", true),
+                ("https://x.com/home", "Home page", true),
+                ("https://ads.test/", "Deal", false),
+            ]
+        );
+        assert_eq!(links[4].rel, ["sponsored", "nofollow"]);
+        assert!(links[..4].iter().all(|l| l.rel.is_empty()));
+        let strict = extract_page_links(&doc, &base, true);
+        assert!(!strict[2].internal);
+    }
+
+    #[test]
+    fn content_and_skip_selectors_override_the_defaults() {
+        let doc = Html::parse_document(
+            "<body><main><div class='lead'><p>Lead paragraph of the post.</p></div>\
+             <p>The body of the post, long enough to carry the page.</p>\
+             <div class='ads'><p>Buy the premium plan today.</p></div>\
+             <nav><p>Browse the full archive of posts.</p></nav></main></body>",
+        );
+        let base = Url::parse("https://x.com/").unwrap();
+        let texts = |options: &PageOptions| -> Vec<String> {
+            extract_content_blocks(&doc, &base, "out", 0, options)
+                .into_iter()
+                .filter_map(|b| match b {
+                    ContentBlock::Paragraph { text, .. } => Some(text),
+                    _ => None,
+                })
+                .collect()
+        };
+        let selector = |css: &str| Some(Selector::parse(css).unwrap());
+
+        let lead = PageOptions {
+            content_selector: selector(".lead"),
+            ..PageOptions::default()
+        };
+        assert_eq!(texts(&lead), ["Lead paragraph of the post."]);
+
+        // No match: the scored root, with the built-in skip list.
+        let missing = PageOptions {
+            content_selector: selector("#post-body"),
+            ..PageOptions::default()
+        };
+        let all = texts(&missing);
+        assert_eq!(all.len(), 3, "{all:?}");
+        assert!(!all.iter().any(|t| t.starts_with("Browse")), "{all:?}");
+
+        // A custom list replaces the built-in one: nav is content again.
+        let skip_ads = PageOptions {
+            skip_selector: selector(&format!("{}, .ads", crate::selectors::ALWAYS_SKIPPED)),
+            ..PageOptions::default()
+        };
+        let kept = texts(&skip_ads);
+        assert!(!kept.iter().any(|t| t.starts_with("Buy")), "{kept:?}");
+        assert!(kept.iter().any(|t| t.starts_with("Browse")), "{kept:?}");
+    }
+
+    #[test]
+    fn rich_text_keeps_block_text_plain() {
+        let doc = Html::parse_document(
+            "<main><h2>The <em>plan</em></h2>\
+             <p>Read <a href='/docs'>the <strong>docs</strong></a> before you start.</p>\
+             <ul><li>One <code>cmd</code></li><li>Two</li></ul></main>",
+        );
+        let base = Url::parse("https://x.com/").unwrap();
+        let options = PageOptions {
+            rich_text: true,
+            ..PageOptions::default()
+        };
+        let blocks = extract_content_blocks(&doc, &base, "out", 0, &options);
+        let ContentBlock::Heading { text, markdown, .. } = &blocks[0] else {
+            panic!("expected a heading");
+        };
+        assert_eq!(text, "The plan");
+        assert_eq!(markdown.as_deref(), Some("The *plan*"));
+        let ContentBlock::Paragraph { text, markdown, .. } = &blocks[1] else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(text, "Read the docs before you start.");
+        assert_eq!(
+            markdown.as_deref(),
+            Some("Read [the **docs**](https://x.com/docs) before you start.")
+        );
+        let ContentBlock::List {
+            items,
+            markdown_items,
+        } = &blocks[2]
+        else {
+            panic!("expected a list");
+        };
+        assert_eq!(items, &["One cmd", "Two"]);
+        assert_eq!(markdown_items, &["One `cmd`", "Two"]);
+
+        let blocks = extract_content_blocks(&doc, &base, "out", 0, &PageOptions::default());
+        assert!(matches!(
+            &blocks[1],
+            ContentBlock::Paragraph { markdown: None, .. }
+        ));
+    }
+
+    #[test]
+    fn cta_blocks_skip_controls_and_form_buttons() {
+        let doc = Html::parse_fragment(
+            "<a class='btn btn-primary' href='/signup'>Start free trial</a>\
+             <a class='wp-block-button__link' href='#'>Learn more</a>\
+             <a href='/about'>About</a>\
+             <button onclick=\"location.href='/demo'\">Book a demo</button>\
+             <button class='navbar-toggler' aria-expanded='false'>Menu</button>\
+             <form><button type='submit'>Send</button></form>",
+        );
+        let base = Url::parse("https://x.com/").unwrap();
+        let ctas: Vec<(String, Option<String>)> = doc
+            .select(&Selector::parse("a, button").unwrap())
+            .filter_map(|el| match cta_block(&el, &base) {
+                Some(ContentBlock::Cta { text, href }) => Some((text, href)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ctas,
+            vec![
+                (
+                    "Start free trial".into(),
+                    Some("https://x.com/signup".into())
+                ),
+                ("Learn more".into(), None),
+                ("Book a demo".into(), Some("https://x.com/demo".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn cta_labels_are_not_repeated_in_their_parent_or_nested_ctas() {
+        let doc = Html::parse_document(
+            "<main><p>Ready to ship your next site faster? \
+             <a class='btn btn-primary' href='/signup'>Start free trial</a></p>\
+             <p><a class='button' href='/demo'><button>Book a demo</button></a></p>\
+             <ul><li>No credit card needed</li>\
+             <li><a class='cta' href='/pricing'>See pricing</a></li></ul></main>",
+        );
+        let base = Url::parse("https://x.com/").unwrap();
+        let blocks = extract_content_blocks(&doc, &base, "out", 0, &PageOptions::default());
+        let summary: Vec<String> = blocks
+            .iter()
+            .map(|b| match b {
+                ContentBlock::Paragraph { text, .. } => format!("p: {text}"),
+                ContentBlock::Cta { text, .. } => format!("cta: {text}"),
+                ContentBlock::List { items, .. } => format!("list: {}", items.join(" / ")),
+                _ => "other".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "p: Ready to ship your next site faster?",
+                "cta: Start free trial",
+                "cta: Book a demo",
+                "list: No credit card needed",
+                "cta: See pricing",
+            ]
+        );
+    }
+
+    #[test]
+    fn content_root_skips_link_heavy_chrome_inside_main() {
+        let body = "Plenty of real article text goes here, sentence after sentence. ".repeat(8);
+        let links = "<li><a href='/x'>Another related post with a long title</a></li>".repeat(12);
+        let html = format!(
+            "<body><main><aside class='sidebar'><ul>{links}</ul></aside>\
+             <div class='content'><p>{body}</p></div></main></body>"
+        );
+        let doc = Html::parse_document(&html);
+        let root = content_root(&doc, &SEL_SKIP).unwrap();
+        assert_eq!(root.value().attr("class"), Some("content"));
+
+        // The title sits outside the text div: the article still wins.
+        let html = format!(
+            "<body><div class='teaser'><article><a href='/a'>Teaser</a></article></div>\
+             <article class='post'><h1>Title</h1><div class='entry'><p>{body}</p></div>\
+             </article></body>"
+        );
+        let doc = Html::parse_document(&html);
+        let root = content_root(&doc, &SEL_SKIP).unwrap();
+        assert_eq!(root.value().attr("class"), Some("post"));
+    }
+
+    #[test]
+    fn meta_map_keeps_every_tag() {
+        let doc = Html::parse_document(
+            "<head><meta charset='utf-8'><meta name='Description' content=' Hi '>\
+             <meta property='article:tag' content='rust'><meta property='article:tag' content='web'>\
+             <meta http-equiv='Content-Security-Policy' content=\"default-src 'self'\">\
+             <meta itemprop='price' content='9'></head>",
+        );
+        let meta = extract_meta_map(&doc);
+        assert_eq!(meta["charset"], vec!["utf-8"]);
+        assert_eq!(meta["description"], vec!["Hi"]);
+        assert_eq!(meta["article:tag"], vec!["rust", "web"]);
+        assert_eq!(
+            meta["http-equiv:content-security-policy"],
+            vec!["default-src 'self'"]
+        );
+        assert_eq!(meta.len(), 4);
+    }
+
+    #[test]
+    fn link_relations_split_rel_tokens() {
+        let doc = Html::parse_document(
+            "<head><link rel='canonical' href='/p'><link rel='amphtml' href='/p/amp'>\
+             <link rel='alternate' type='application/rss+xml' title='Feed' href='/feed'>\
+             <link rel='Preload Prefetch' as='font' href='https://cdn.x.com/f.woff2'>\
+             <link rel='canonical' href='/p'><link rel='icon'></head>",
+        );
+        let base = Url::parse("https://x.com/blog/").unwrap();
+        let rels = extract_link_relations(&doc, &base);
+        let pairs: Vec<(&str, &str)> = rels
+            .iter()
+            .map(|r| (r.rel.as_str(), r.href.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("canonical", "https://x.com/p"),
+                ("amphtml", "https://x.com/p/amp"),
+                ("alternate", "https://x.com/feed"),
+                ("preload", "https://cdn.x.com/f.woff2"),
+                ("prefetch", "https://cdn.x.com/f.woff2"),
+            ]
+        );
+        assert_eq!(rels[2].media_type.as_deref(), Some("application/rss+xml"));
+        assert_eq!(rels[3].as_type.as_deref(), Some("font"));
+    }
+
+    #[test]
+    fn amp_documents_are_recognised() {
+        for (html, amp) in [
+            ("<html amp lang='en'><body></body></html>", true),
+            ("<html ⚡><body></body></html>", true),
+            ("<html lang='en'><body amp></body></html>", false),
+        ] {
+            assert_eq!(is_amp_document(&Html::parse_document(html)), amp, "{html}");
         }
     }
-    let download_results: Vec<(usize, Option<String>)> = future::join_all(download_futs).await;
-    let mut idx_to_path: HashMap<usize, Option<String>> = download_results.into_iter().collect();
-
-    let mut final_blocks = Vec::with_capacity(blocks.len());
-    for (i, block) in blocks.into_iter().enumerate() {
-        match block {
-            ContentBlock::Image {
-                original_url,
-                local_path,
-                alt_text,
-            } if original_url.starts_with("inline-svg://") => {
-                final_blocks.push(ContentBlock::Image {
-                    original_url,
-                    local_path,
-                    alt_text,
-                });
-            }
-            ContentBlock::Image {
-                original_url,
-                alt_text,
+
+    /// `(legend, step, field indices)` of each section, and `multi_step`.
+    #[allow(clippy::type_complexity)]
+    fn sections_of(html: &str) -> (Vec<(String, Option<usize>, Vec<usize>)>, bool) {
+        let doc = Html::parse_fragment(html);
+        let form = doc
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://x.com/").unwrap();
+        match form_block(&form, &doc, &base, &PageOptions::default()) {
+            ContentBlock::Form {
+                sections,
+                multi_step,
                 ..
-            } => {
-                if let Some(Some(path)) = idx_to_path.remove(&i) {
-                    final_blocks.push(ContentBlock::Image {
-                        original_url,
-                        local_path: path,
-                        alt_text,
-                    });
-                }
-            }
-            other => final_blocks.push(other),
+            } => (
+                sections
+                    .into_iter()
+                    .map(|s| (s.legend, s.step, s.fields))
+                    .collect(),
+                multi_step,
+            ),
+            _ => unreachable!(),
         }
     }
 
-    final_blocks
+    #[test]
+    fn radio_and_checkbox_groups_merge_into_one_field() {
+        let doc = Html::parse_fragment(
+            "<form><fieldset><legend>Plan</legend>\
+             <label><input type='radio' name='plan' value='s'> Starter</label>\
+             <label><input type='radio' name='plan' value='p' required> Pro</label></fieldset>\
+             <input type='checkbox' name='topics[]' value='news'>\
+             <input type='checkbox' name='topics[]' value='offers'>\
+             <label><input type='checkbox' name='terms'> I agree</label></form>",
+        );
+        let form = doc
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://x.com/").unwrap();
+        let ContentBlock::Form { fields, .. } =
+            form_block(&form, &doc, &base, &PageOptions::default())
+        else {
+            unreachable!()
+        };
+        let fields: Vec<(&str, &str, Vec<String>, bool)> = fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.label.as_str(),
+                    f.options.clone(),
+                    f.required,
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("plan", "Plan", vec!["Starter".into(), "Pro".into()], true),
+                ("topics[]", "", vec!["news".into(), "offers".into()], false),
+                ("terms", "I agree", vec![], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn hidden_fields_are_opt_in_and_redactable() {
+        let doc = Html::parse_fragment(
+            "<form><input type='hidden' name='csrf' value='t0k3n'>\
+             <input name='email'></form>",
+        );
+        let form = doc
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://x.com/").unwrap();
+        let fields = |include_hidden_fields, redact_hidden_values| {
+            let options = PageOptions {
+                include_hidden_fields,
+                redact_hidden_values,
+                ..PageOptions::default()
+            };
+            let ContentBlock::Form { fields, .. } = form_block(&form, &doc, &base, &options) else {
+                unreachable!()
+            };
+            fields
+                .into_iter()
+                .map(|f| match f.value {
+                    Some(value) => format!("{}={value}", f.name),
+                    None => f.name,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fields(false, false), ["email"]);
+        assert_eq!(fields(true, false), ["csrf=t0k3n", "email"]);
+        assert_eq!(fields(true, true), ["csrf=[redacted]", "email"]);
+    }
+
+    #[test]
+    fn form_fields_keep_validation_attributes() {
+        let doc = Html::parse_fragment(
+            "<form><input name='zip' pattern='[0-9]{5}' inputmode='numeric' maxlength='5'>\
+             <input type='date' name='from' min='2026-01-01' max='2026-12-31' step='7'>\
+             <textarea name='msg' minlength='20' maxlength='lots'></textarea></form>",
+        );
+        let form = doc
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://x.com/").unwrap();
+        let ContentBlock::Form { fields, .. } =
+            form_block(&form, &doc, &base, &PageOptions::default())
+        else {
+            unreachable!()
+        };
+        assert_eq!(fields[0].pattern.as_deref(), Some("[0-9]{5}"));
+        assert_eq!(fields[0].inputmode.as_deref(), Some("numeric"));
+        assert_eq!(fields[0].maxlength, Some(5));
+        assert_eq!(fields[1].min.as_deref(), Some("2026-01-01"));
+        assert_eq!(fields[1].max.as_deref(), Some("2026-12-31"));
+        assert_eq!(fields[1].step.as_deref(), Some("7"));
+        assert_eq!((fields[2].minlength, fields[2].maxlength), (Some(20), None));
+    }
+
+    #[test]
+    fn form_field_types_are_normalized() {
+        let doc = Html::parse_fragment(
+            "<form><input type='File' name='cv' accept='.pdf,image/*' multiple>\
+             <input type='color' name='c'><input type='datetime' name='d'><input name='n'>\
+             <input type='email' name='cc' multiple><select name='s' multiple></select>\
+             <input type='image' src='go.png'><input type='reset'></form>",
+        );
+        let form = doc
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://x.com/").unwrap();
+        let ContentBlock::Form { fields, .. } =
+            form_block(&form, &doc, &base, &PageOptions::default())
+        else {
+            unreachable!()
+        };
+        let types: Vec<(&str, bool)> = fields
+            .iter()
+            .map(|f| (f.field_type.as_str(), f.multiple))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("file", true),
+                ("color", false),
+                ("text", false),
+                ("text", false),
+                ("email", true),
+                ("select", true),
+            ]
+        );
+        assert_eq!(fields[0].accept.as_deref(), Some(".pdf,image/*"));
+    }
+
+    #[test]
+    fn form_fields_group_by_fieldset_and_step() {
+        let (sections, multi_step) = sections_of(
+            "<form><input name='q'>\
+             <fieldset><legend>Billing</legend><input name='street'><input name='city'></fieldset>\
+             <fieldset><legend>Shipping</legend><input name='ship_to'></fieldset>\
+             <button type='submit'>Order</button></form>",
+        );
+        assert!(!multi_step);
+        assert_eq!(
+            sections,
+            vec![
+                ("Billing".into(), None, vec![1, 2]),
+                ("Shipping".into(), None, vec![3]),
+            ]
+        );
+
+        let (sections, multi_step) = sections_of(
+            "<form><div class='form-step'><h3>About you</h3><input name='name'>\
+             <fieldset><legend>Contact</legend><input name='email'></fieldset></div>\
+             <div class='form-step'><textarea name='message'></textarea></div>\
+             <button type='button'>Next &rarr;</button></form>",
+        );
+        assert!(multi_step);
+        assert_eq!(
+            sections,
+            vec![
+                ("About you".into(), Some(1), vec![0, 1]),
+                (String::new(), Some(2), vec![2]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::model::{DataSource, PageData};
+use crate::scrape::Scraper;
+use crate::util::is_disallowed_by_robots;
+
+/// Well-known API manifest paths: (path, kind, a key the JSON answer must
+/// have). The key keeps HTML error pages served with a 200 out.
+const API_PROBES: &[(&str, &str, &str)] = &[
+    ("/wp-json/", "wordpress_rest", "namespaces"),
+    ("/.well-known/api-catalog", "api_catalog", "linkset"),
+    ("/openapi.json", "openapi", "openapi"),
+    ("/swagger.json", "openapi", "swagger"),
+    (
+        "/.well-known/openid-configuration",
+        "openid_configuration",
+        "issuer",
+    ),
+];
+
+/// Kind of a `<link>` that points at a machine-readable source.
+fn link_kind(rel: &str, media_type: Option<&str>) -> Option<&'static str> {
+    let media_type = media_type.map(|t| t.split(';').next().unwrap_or(t).trim().to_lowercase());
+    match (rel, media_type.as_deref()) {
+        ("alternate", Some("application/rss+xml")) => Some("rss"),
+        ("alternate", Some("application/atom+xml")) => Some("atom"),
+        ("alternate", Some("application/feed+json")) => Some("json_feed"),
+        ("https://api.w.org/", _) => Some("wordpress_rest"),
+        ("sitemap", _) => Some("sitemap"),
+        _ => None,
+    }
+}
+
+/// Whether a probe answer is the manifest `key` names: a JSON object with
+/// that key.
+fn is_manifest(content_type: &str, body: &str, key: &str) -> bool {
+    if content_type.contains("html") {
+        return false;
+    }
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .is_some_and(|json| json.get(key).is_some())
+}
+
+/// The sitemaps robots.txt lists, plus the one discovery found elsewhere.
+pub(crate) fn sitemap_sources(robots_sitemaps: &[String], found: Option<&str>) -> Vec<DataSource> {
+    let listed = robots_sitemaps
+        .iter()
+        .map(|url| (url.as_str(), "robots.txt"));
+    let found = found
+        .filter(|url| !robots_sitemaps.iter().any(|s| s == url))
+        .map(|url| (url, "well_known"));
+    listed
+        .chain(found)
+        .map(|(url, how)| DataSource {
+            kind: "sitemap".to_string(),
+            url: url.to_string(),
+            title: None,
+            found: how.to_string(),
+            pages: 0,
+        })
+        .collect()
+}
+
+/// Ask `API_PROBES` on the site's root, skipping the paths robots.txt
+/// disallows.
+pub(crate) async fn probe_apis(
+    scraper: &Scraper,
+    base_url: &Url,
+    robots_rules: &[String],
+) -> Vec<DataSource> {
+    let mut found = Vec::new();
+    for (path, kind, key) in API_PROBES {
+        let Ok(url) = base_url.join(path) else {
+            continue;
+        };
+        if is_disallowed_by_robots(url.as_str(), robots_rules) {
+            continue;
+        }
+        let Some((content_type, body)) = scraper.fetch_manifest(url.as_str()).await else {
+            continue;
+        };
+        if is_manifest(&content_type, &body, key) {
+            found.push(DataSource {
+                kind: kind.to_string(),
+                url: url.to_string(),
+                title: None,
+                found: "well_known".to_string(),
+                pages: 0,
+            });
+        }
+    }
+    found
+}
+
+/// `discovered` (sitemaps, probes) merged with the sources the pages
+/// `<link>`: one entry per URL, the most-linked first, then by kind.
+pub(crate) fn data_sources(pages: &[PageData], discovered: Vec<DataSource>) -> Vec<DataSource> {
+    let mut sources = discovered;
+    let mut at: HashMap<String, usize> = sources
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.url.clone(), i))
+        .collect();
+    for page in pages {
+        let mut counted = Vec::new();
+        for link in &page.link_relations {
+            let Some(kind) = link_kind(&link.rel, link.media_type.as_deref()) else {
+                continue;
+            };
+            if counted.contains(&link.href) {
+                continue;
+            }
+            counted.push(link.href.clone());
+            let i = *at.entry(link.href.clone()).or_insert_with(|| {
+                sources.push(DataSource {
+                    kind: kind.to_string(),
+                    url: link.href.clone(),
+                    title: None,
+                    found: "link".to_string(),
+                    pages: 0,
+                });
+                sources.len() - 1
+            });
+            let source = &mut sources[i];
+            source.pages += 1;
+            if source.title.is_none() {
+                source.title = link.title.clone().filter(|t| !t.trim().is_empty());
+            }
+        }
+    }
+    sources.sort_by(|a, b| {
+        b.pages
+            .cmp(&a.pages)
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.url.cmp(&b.url))
+    });
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, link_relations: serde_json::Value) -> PageData {
+        let mut page = crate::model::test_page(url);
+        page.link_relations = serde_json::from_value(link_relations).unwrap();
+        page
+    }
+
+    #[test]
+    fn feeds_and_manifests_are_listed_once() {
+        let home = page(
+            "https://x.com/",
+            serde_json::json!([
+                {"rel": "alternate", "href": "https://x.com/feed/",
+                 "type": "application/rss+xml", "title": "X » Feed"},
+                {"rel": "https://api.w.org/", "href": "https://x.com/wp-json/"},
+                {"rel": "stylesheet", "href": "https://x.com/style.css", "type": "text/css"},
+                {"rel": "alternate", "href": "https://x.com/de/", "hreflang": "de"}
+            ]),
+        );
+        let post = page(
+            "https://x.com/post/",
+            serde_json::json!([
+                {"rel": "alternate", "href": "https://x.com/feed/", "type": "application/rss+xml"},
+                {"rel": "alternate", "href": "https://x.com/feed/", "type": "application/rss+xml"},
+                {"rel": "alternate", "href": "https://x.com/post/feed/",
+                 "type": "application/atom+xml; charset=utf-8"}
+            ]),
+        );
+        let mut discovered = sitemap_sources(
+            &["https://x.com/sitemap_index.xml".to_string()],
+            Some("https://x.com/sitemap_index.xml"),
+        );
+        discovered.push(DataSource {
+            kind: "wordpress_rest".to_string(),
+            url: "https://x.com/wp-json/".to_string(),
+            title: None,
+            found: "well_known".to_string(),
+            pages: 0,
+        });
+        let sources = data_sources(&[home, post], discovered);
+        let summary: Vec<_> = sources
+            .iter()
+            .map(|s| (s.kind.as_str(), s.url.as_str(), s.found.as_str(), s.pages))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("rss", "https://x.com/feed/", "link", 2),
+                ("atom", "https://x.com/post/feed/", "link", 1),
+                ("wordpress_rest", "https://x.com/wp-json/", "well_known", 1),
+                (
+                    "sitemap",
+                    "https://x.com/sitemap_index.xml",
+                    "robots.txt",
+                    0
+                ),
+            ]
+        );
+        assert_eq!(sources[0].title.as_deref(), Some("X » Feed"));
+
+        assert!(is_manifest(
+            "application/json",
+            r#"{"namespaces": ["wp/v2"]}"#,
+            "namespaces"
+        ));
+        assert!(!is_manifest(
+            "text/html",
+            r#"{"namespaces": []}"#,
+            "namespaces"
+        ));
+        assert!(!is_manifest(
+            "application/json",
+            r#"{"code": "rest_disabled"}"#,
+            "namespaces"
+        ));
+        assert!(!is_manifest("", "<!doctype html>", "openapi"));
+    }
+}
@@ -0,0 +1,306 @@
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use serde_json::Value;
+
+use crate::model::{ContentBlock, PageData};
+
+type XmlWriter = Writer<Vec<u8>>;
+
+/// Usable as an element name as-is: starts with a letter or `_`, then
+/// letters, digits, `_`, `-`, `.`, and isn't in the reserved `xml…` range.
+/// Keys like JSON-LD's `@context` aren't.
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_ascii_lowercase().starts_with("xml")
+}
+
+/// Drop the control characters XML 1.0 can't carry, even escaped.
+fn xml_text(text: &str) -> String {
+    text.chars()
+        .filter(|&c| c >= ' ' || matches!(c, '\t' | '\n' | '\r'))
+        .collect()
+}
+
+/// Element name of an array item: an object tagged with a `type` (content
+/// blocks) is named after it, `<heading>`, `<paragraph>`, …; anything else
+/// is an `<item>`.
+fn item_tag(item: &Value) -> (&str, Option<&str>) {
+    match item.get("type").and_then(Value::as_str) {
+        Some(tag) if item.is_object() && is_xml_name(tag) => (tag, Some("type")),
+        _ => ("item", None),
+    }
+}
+
+/// Write `value` as the element `key`. Objects become one child per field
+/// (minus `skip`), arrays one child per item, scalars text; nulls are left
+/// out altogether.
+fn write_value(
+    w: &mut XmlWriter,
+    key: &str,
+    value: &Value,
+    skip: Option<&str>,
+) -> anyhow::Result<()> {
+    // `<key>`, or `<field name="key">` when the key isn't a valid name.
+    let tag = if is_xml_name(key) { key } else { "field" };
+    let mut start = BytesStart::new(tag);
+    if tag != key {
+        start.push_attribute(("name", xml_text(key).as_str()));
+    }
+    let end = BytesEnd::new(tag);
+    match value {
+        Value::Null => return Ok(()),
+        Value::Object(fields) if fields.keys().all(|k| Some(k.as_str()) == skip) => {
+            w.write_event(Event::Empty(start))?;
+        }
+        Value::Array(items) if items.is_empty() => w.write_event(Event::Empty(start))?,
+        Value::Object(fields) => {
+            w.write_event(Event::Start(start))?;
+            for (name, field) in fields {
+                if Some(name.as_str()) != skip {
+                    write_value(w, name, field, None)?;
+                }
+            }
+            w.write_event(Event::End(end))?;
+        }
+        Value::Array(items) => {
+            w.write_event(Event::Start(start))?;
+            for item in items {
+                let (tag, skip) = item_tag(item);
+                write_value(w, tag, item, skip)?;
+            }
+            w.write_event(Event::End(end))?;
+        }
+        Value::String(s) if s.is_empty() => w.write_event(Event::Empty(start))?,
+        scalar => {
+            let text = match scalar {
+                Value::String(s) => xml_text(s),
+                other => other.to_string(),
+            };
+            w.write_event(Event::Start(start))?;
+            w.write_event(Event::Text(BytesText::new(&text)))?;
+            w.write_event(Event::End(end))?;
+        }
+    }
+    Ok(())
+}
+
+/// `pages` (already serialised) as `<pages><page>…</page></pages>`.
+fn values_to_xml(pages: &[Value]) -> anyhow::Result<String> {
+    let mut w = Writer::new_with_indent(Vec::new(), b' ', 2);
+    w.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    w.write_event(Event::Start(BytesStart::new("pages")))?;
+    for page in pages {
+        write_value(&mut w, "page", page, None)?;
+    }
+    w.write_event(Event::End(BytesEnd::new("pages")))?;
+    let mut xml = String::from_utf8(w.into_inner())?;
+    xml.push('\n');
+    Ok(xml)
+}
+
+/// `--format xml`: scraped.json's pages as XML. Fields keep their JSON
+/// names as elements, content blocks are named after their type, other
+/// array items are `<item>`s, and keys that aren't element names
+/// (`@type`) become `<field name="@type">`.
+pub(crate) fn pages_to_xml(pages: &[PageData]) -> anyhow::Result<String> {
+    let values = pages
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+    values_to_xml(&values)
+}
+
+/// `--format yaml`: scraped.json's pages as one YAML sequence.
+pub(crate) fn pages_to_yaml(pages: &[PageData]) -> anyhow::Result<String> {
+    Ok(serde_yaml::to_string(pages)?)
+}
+
+/// Tables of `--format sql`, dropped in reverse order so the foreign keys
+/// never point at a missing table.
+const SQL_SCHEMA: &str = "\
+DROP TABLE IF EXISTS images;
+DROP TABLE IF EXISTS blocks;
+DROP TABLE IF EXISTS pages;
+
+CREATE TABLE pages (
+  id INTEGER PRIMARY KEY,
+  url TEXT NOT NULL,
+  title TEXT NOT NULL,
+  meta_title TEXT NOT NULL,
+  meta_description TEXT NOT NULL,
+  canonical_url TEXT,
+  language TEXT,
+  summary TEXT NOT NULL,
+  plain_text TEXT NOT NULL,
+  content_hash TEXT NOT NULL,
+  total_words INTEGER NOT NULL,
+  token_estimate INTEGER NOT NULL
+);
+
+CREATE TABLE blocks (
+  page_id INTEGER NOT NULL REFERENCES pages (id),
+  position INTEGER NOT NULL,
+  block_type TEXT NOT NULL,
+  heading_level INTEGER,
+  text TEXT,
+  data TEXT NOT NULL,
+  PRIMARY KEY (page_id, position)
+);
+
+CREATE TABLE images (
+  page_id INTEGER NOT NULL REFERENCES pages (id),
+  block_position INTEGER NOT NULL,
+  original_url TEXT NOT NULL,
+  local_path TEXT NOT NULL,
+  alt_text TEXT NOT NULL,
+  PRIMARY KEY (page_id, block_position)
+);
+";
+
+/// Turns on `NO_BACKSLASH_ESCAPES` for the loading session. A `/*!`
+/// comment is only run by MySQL (and MariaDB); PostgreSQL and SQLite skip
+/// it and the empty statement after it.
+const MYSQL_MODE: &str = concat!(
+    "/*!40101 SET SESSION sql_mode = ",
+    "CONCAT_WS(',', NULLIF(@@sql_mode, ''), 'NO_BACKSLASH_ESCAPES') */;\n"
+);
+
+/// Standard SQL string literal: quotes doubled, NULs dropped (PostgreSQL
+/// rejects them in text). Backslashes stay as they are, which MySQL only
+/// reads literally under `NO_BACKSLASH_ESCAPES`: see [`MYSQL_MODE`].
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\0', "").replace('\'', "''"))
+}
+
+fn sql_opt(text: Option<&str>) -> String {
+    text.map_or_else(|| "NULL".to_string(), sql_string)
+}
+
+/// `--format sql`: CREATE TABLE + INSERT statements for `pages`, `blocks`
+/// (one row per content block, in order, with its JSON in `data`) and
+/// `images` (the image blocks, by block position), in one transaction, in the SQL that
+/// PostgreSQL, MySQL and SQLite all accept: `psql -f scraped.sql` or
+/// `sqlite3 site.db < scraped.sql` loads the dump. Re-loading replaces it.
+pub(crate) fn pages_to_sql(pages: &[PageData]) -> anyhow::Result<String> {
+    let mut sql = String::from(MYSQL_MODE);
+    sql.push_str("BEGIN;\n\n");
+    sql.push_str(SQL_SCHEMA);
+    for (id, page) in pages.iter().enumerate() {
+        let id = id + 1;
+        sql.push_str(&format!(
+            "\nINSERT INTO pages VALUES ({id}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+            sql_string(&page.url),
+            sql_string(&page.title),
+            sql_string(&page.meta_title),
+            sql_string(&page.meta_description),
+            sql_opt(page.canonical_url.as_deref()),
+            sql_opt(page.language.as_deref()),
+            sql_string(&page.summary),
+            sql_string(&page.plain_text),
+            sql_string(&page.content_hash),
+            page.total_words,
+            page.token_estimate,
+        ));
+        for (position, block) in page.content_blocks.iter().enumerate() {
+            let data = serde_json::to_value(block)?;
+            let block_type = data["type"].as_str().unwrap_or_default();
+            let (level, text) = match block {
+                ContentBlock::Heading { level, text, .. } => {
+                    (level.to_string(), Some(text.clone()))
+                }
+                ContentBlock::Cta { text, .. } => ("NULL".to_string(), Some(text.clone())),
+                other => ("NULL".to_string(), crate::chunks::block_text(other)),
+            };
+            sql.push_str(&format!(
+                "INSERT INTO blocks VALUES ({id}, {position}, {}, {level}, {}, {});\n",
+                sql_string(block_type),
+                sql_opt(text.as_deref()),
+                sql_string(&data.to_string()),
+            ));
+            if let ContentBlock::Image {
+                original_url,
+                local_path,
+                alt_text,
+                ..
+            } = block
+            {
+                sql.push_str(&format!(
+                    "INSERT INTO images VALUES ({id}, {position}, {}, {}, {});\n",
+                    sql_string(original_url),
+                    sql_string(local_path),
+                    sql_string(alt_text),
+                ));
+            }
+        }
+    }
+    sql.push_str("\nCOMMIT;\n");
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_names_blocks_by_type_and_keeps_odd_keys() {
+        let page = serde_json::json!({
+            "url": "https://x.com/?a=1&b=2",
+            "language": null,
+            "total_words": 3,
+            "nav_links": [],
+            "content_blocks": [
+                {"type": "heading", "level": 1, "text": "Fish & <Chips>"},
+                {"type": "list", "items": ["one", "two\u{1}"]},
+            ],
+            "structured_data": [{"@type": "Organization", "name": "X"}],
+        });
+        let xml = values_to_xml(&[page]).unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(xml.contains("<url>https://x.com/?a=1&amp;b=2</url>"));
+        assert!(!xml.contains("<language"));
+        assert!(xml.contains("<total_words>3</total_words>"));
+        assert!(xml.contains("<nav_links/>"));
+        assert!(xml.contains("<heading>\n"));
+        assert!(xml.contains("<level>1</level>"));
+        assert!(xml.contains("<text>Fish &amp; &lt;Chips&gt;</text>"));
+        assert!(!xml.contains("<type>"));
+        assert!(xml.contains("<item>two</item>"));
+        assert!(xml.contains("<field name=\"@type\">Organization</field>"));
+
+        // Well-formed: the reader checks every end tag against its start.
+        let mut reader = quick_xml::Reader::from_str(&xml);
+        while !matches!(reader.read_event().unwrap(), Event::Eof) {}
+    }
+
+    #[test]
+    fn sql_dump_has_a_row_per_page_block_and_image() {
+        let mut page = crate::model::test_page("https://x.com/o'brien");
+        page.title = "O'Brien".to_string();
+        page.meta_description = "C:\\new".to_string();
+        page.language = Some("en".to_string());
+        page.content_blocks = serde_json::from_value(serde_json::json!([
+            {"type": "heading", "level": 2, "text": "About"},
+            {"type": "image", "original_url": "https://x.com/a.png",
+             "local_path": "images/a.png", "alt_text": ""},
+            {"type": "list", "items": ["a", "b"]},
+        ]))
+        .unwrap();
+        page.total_words = 1;
+        let sql = pages_to_sql(&[page]).unwrap();
+        assert!(sql.starts_with(MYSQL_MODE) && sql.ends_with("COMMIT;\n"));
+        assert!(sql[MYSQL_MODE.len()..].starts_with("BEGIN;"));
+        assert!(sql.contains(
+            r"INSERT INTO pages VALUES (1, 'https://x.com/o''brien', 'O''Brien', '', 'C:\new', NULL, 'en',"
+        ));
+        assert!(sql.contains("INSERT INTO blocks VALUES (1, 0, 'heading', 2, 'About', '{"));
+        assert!(sql.contains("INSERT INTO blocks VALUES (1, 2, 'list', NULL, '- a\n- b', '{"));
+        assert!(sql.contains(
+            "INSERT INTO images VALUES (1, 1, 'https://x.com/a.png', 'images/a.png', '');"
+        ));
+        assert_eq!(sql.matches("INSERT INTO blocks").count(), 3);
+    }
+}
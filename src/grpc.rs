@@ -0,0 +1,367 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use clap::{CommandFactory, FromArgMatches, Parser};
+use serde_json::Value;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::cli::RunArgs;
+use crate::model::ContentBlock;
+
+#[allow(clippy::all)]
+pub(crate) mod proto {
+    tonic::include_proto!("dump_it.v1");
+}
+
+use proto::dump_it_server::{DumpIt, DumpItServer};
+
+/// `StartRequest.args`, parsed with the CLI's own flags.
+#[derive(Parser)]
+#[command(name = "dump-it", no_binary_name = true)]
+struct StartArgs {
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+/// Pages waiting for a slow client before the crawl backs off.
+const STREAM_BUFFER: usize = 32;
+
+/// The run flags a remote client may set, by clap id: what to crawl and
+/// how to extract it. Everything else is refused — flags that reach the
+/// server's disk (`--output`, `--ca-cert`, `--graphql-query`, …), launch
+/// programs on it (`--solve-challenges`, `--screenshots`) or reconfigure
+/// its network (`--resolve`, `--insecure`).
+const ALLOWED_FLAGS: &[&str] = &[
+    "concurrency",
+    "timeout",
+    "connect_timeout",
+    "read_timeout",
+    "asset_timeout",
+    "page_budget",
+    "max_depth",
+    "max_pages",
+    "section_budgets",
+    "max_query_variants",
+    "js_wait",
+    "js_wait_selector",
+    "no_default_excludes",
+    "excludes",
+    "includes",
+    "no_extract_brand",
+    "no_fetch_css",
+    "keywords",
+    "no_js",
+    "skip_noindex",
+    "skip_nofollow_links",
+    "strict_host",
+    "keep_duplicates",
+    "boilerplate_threshold",
+    "strip_boilerplate",
+    "capture_404",
+    "skip_soft_404",
+    "delay",
+    "crawl_with_http",
+    "max_images_per_page",
+    "inline_svg_max",
+    "image_viewport",
+    "include_hidden_fields",
+    "redact_hidden_values",
+    "content_selector",
+    "skip_selectors",
+    "rich_text",
+    "preset",
+    "preset_only",
+    "amp",
+    "compare_mobile",
+    "user_agent",
+    "max_body_size",
+    "modified_since",
+];
+
+/// The first flag of `matches` a client set that isn't in
+/// [`ALLOWED_FLAGS`], `--long` spelled.
+fn disallowed_flag(args: &RunArgs, matches: &clap::ArgMatches) -> Option<String> {
+    if let Some(flag) = args.process_wide_flag() {
+        return Some(flag.to_string());
+    }
+    matches
+        .ids()
+        .map(clap::Id::as_str)
+        .filter(|id| matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine))
+        .find(|id| !ALLOWED_FLAGS.contains(id))
+        .map(|id| format!("--{}", id.replace('_', "-")))
+}
+
+/// Refuse what a remote client can't ask of the server: flags outside
+/// [`ALLOWED_FLAGS`] and targets that aren't web URLs (a local folder
+/// would be read from the server's disk).
+#[allow(clippy::result_large_err)]
+fn check_request(
+    request: &proto::StartRequest,
+    args: &RunArgs,
+    matches: &clap::ArgMatches,
+) -> Result<(), Status> {
+    if let Some(flag) = disallowed_flag(args, matches) {
+        return Err(Status::invalid_argument(format!(
+            "{flag} is not supported over gRPC"
+        )));
+    }
+    let targets = std::iter::once(&request.url)
+        .filter(|u| !u.is_empty())
+        .chain(&request.urls);
+    for target in targets {
+        let web = url::Url::parse(target)
+            .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.has_host());
+        if !web {
+            return Err(Status::invalid_argument(format!(
+                "{target} is not an http(s) URL"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Parse `StartRequest.args` and check them, see [`check_request`].
+#[allow(clippy::result_large_err)]
+fn parse_request(request: &proto::StartRequest) -> Result<RunArgs, Status> {
+    let matches = StartArgs::command()
+        .try_get_matches_from(&request.args)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let args = StartArgs::from_arg_matches(&matches)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?
+        .run;
+    check_request(request, &args, &matches)?;
+    Ok(args)
+}
+
+#[derive(Default)]
+struct Service {
+    /// Held for the length of a crawl: the HAR recorder, cassette and
+    /// `--export` sinks are process-wide, so crawls can't overlap.
+    running: Arc<Mutex<()>>,
+}
+
+fn to_proto_block(block: &ContentBlock) -> proto::ContentBlock {
+    let json = serde_json::to_value(block).unwrap_or_default();
+    let (level, text, url) = match block {
+        ContentBlock::Heading { level, text, .. } => (u32::from(*level), Some(text.clone()), None),
+        ContentBlock::Cta { text, href } => (0, Some(text.clone()), href.clone()),
+        ContentBlock::Image { original_url, .. } => (0, None, Some(original_url.clone())),
+        ContentBlock::Embed { src, .. } | ContentBlock::Media { src, .. } => {
+            (0, None, Some(src.clone()))
+        }
+        ContentBlock::Video { sources, .. } => (0, None, sources.first().map(|s| s.url.clone())),
+        other => (0, crate::chunks::block_text(other), None),
+    };
+    proto::ContentBlock {
+        block_type: json["type"].as_str().unwrap_or_default().to_string(),
+        level,
+        text: text.unwrap_or_default(),
+        url: url.unwrap_or_default(),
+        json: json.to_string(),
+    }
+}
+
+/// A scraped page as sent over the wire: the fields typed consumers need
+/// most, plus the whole page as JSON.
+fn to_proto(page: &crate::model::PageData) -> proto::PageData {
+    proto::PageData {
+        url: page.url.clone(),
+        title: page.title.clone(),
+        meta_title: page.meta_title.clone(),
+        meta_description: page.meta_description.clone(),
+        canonical_url: page.canonical_url.clone(),
+        language: page.language.clone(),
+        content_blocks: page.content_blocks.iter().map(to_proto_block).collect(),
+        plain_text: page.plain_text.clone(),
+        content_hash: page.content_hash.clone(),
+        total_words: page.total_words as u64,
+        internal_links_out: page.internal_links_out.clone(),
+        json: serde_json::to_string(page).unwrap_or_default(),
+    }
+}
+
+/// Discover and scrape like a run, without the bundle: every page goes to
+/// `tx` the moment it's extracted.
+async fn crawl(
+    request: proto::StartRequest,
+    mut args: RunArgs,
+    tx: mpsc::Sender<Result<proto::PageData, Status>>,
+) -> anyhow::Result<()> {
+    // What the crawl writes (inline SVGs) goes to a directory of the
+    // server's own, removed with the crawl. Crawls don't overlap, so one
+    // per process will do.
+    let work_dir = std::env::temp_dir().join(format!("dump-it-grpc-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&work_dir);
+    args.output = work_dir.join("scraped.json").to_string_lossy().into_owned();
+    let site = Some(request.url).filter(|u| !u.is_empty());
+    let (url, source) = crate::url_source(site, request.urls, &args)?;
+    // One page in hand at a time: `tx` is the buffer for a slow client.
+    let (page_tx, mut page_rx) = mpsc::channel::<Value>(1);
+    crate::sink::start(vec![crate::sink::Sink::Channel(page_tx)]).await?;
+
+    let forward = async {
+        while let Some(doc) = page_rx.recv().await {
+            let Ok(page) = serde_json::from_value::<crate::model::PageData>(doc) else {
+                continue;
+            };
+            if tx.send(Ok(to_proto(&page))).await.is_err() {
+                break;
+            }
+        }
+    };
+    let scrape = async {
+        let result = async {
+            let discovered = crate::discover(&url, &args, source).await?;
+            let output_dir = work_dir.join("images");
+            std::fs::create_dir_all(&output_dir)?;
+            discovered
+                .scraper
+                .scrape_all(
+                    discovered.urls,
+                    crate::util::normalize_path(&output_dir.to_string_lossy()),
+                    None,
+                )
+                .await;
+            anyhow::Ok(())
+        }
+        .await;
+        // Closes the channel, which ends `forward` once it's drained.
+        crate::sink::finish().await;
+        result
+    };
+    let result = tokio::select! {
+        // The client went away: drop the rest of the crawl.
+        _ = tx.closed() => {
+            crate::sink::finish().await;
+            Ok(())
+        }
+        (_, result) = async { tokio::join!(forward, scrape) } => result,
+    };
+    // Credentials the crawl scoped to its site (from the server's
+    // `DUMPIT_*` settings) don't outlive it.
+    crate::auth::reset();
+    let _ = std::fs::remove_dir_all(&work_dir);
+    result
+}
+
+#[tonic::async_trait]
+impl DumpIt for Service {
+    type ScrapeStream = ReceiverStream<Result<proto::PageData, Status>>;
+
+    async fn scrape(
+        &self,
+        request: Request<proto::StartRequest>,
+    ) -> Result<Response<Self::ScrapeStream>, Status> {
+        let guard = Arc::clone(&self.running)
+            .try_lock_owned()
+            .map_err(|_| Status::unavailable("a crawl is already running"))?;
+        let request = request.into_inner();
+        if request.url.is_empty() && request.urls.is_empty() {
+            return Err(Status::invalid_argument("url or urls is required"));
+        }
+        let args = parse_request(&request)?;
+        progress!("🛰️  Scrape request for {}", request.url);
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            let _guard = guard;
+            if let Err(e) = crawl(request, args, tx.clone()).await {
+                tracing::warn!("gRPC crawl failed: {e:#}");
+                let _ = tx.send(Err(Status::internal(format!("{e:#}")))).await;
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// `dump-it grpc`: serve `DumpIt` (proto/dump_it.proto) until killed.
+pub(crate) async fn run_grpc(bind: SocketAddr) -> anyhow::Result<()> {
+    progress!("🛰️  gRPC service dump_it.v1.DumpIt on {bind}");
+    tonic::transport::Server::builder()
+        .add_service(DumpItServer::new(Service::default()))
+        .serve(bind)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_map_onto_the_proto_with_their_json() {
+        let mut page = crate::model::test_page("https://x.com/");
+        page.title = "Home".to_string();
+        page.meta_title = "Home".to_string();
+        page.canonical_url = Some("https://x.com/".to_string());
+        page.content_blocks = serde_json::from_value(serde_json::json!([
+            {"type": "heading", "level": 1, "text": "Welcome"},
+            {"type": "image", "original_url": "https://x.com/a.png",
+             "local_path": "", "alt_text": "A"},
+            {"type": "cta", "text": "Buy", "href": "https://x.com/buy"},
+        ]))
+        .unwrap();
+        page.total_words = 2;
+        let proto = to_proto(&page);
+        assert_eq!(proto.canonical_url.as_deref(), Some("https://x.com/"));
+        assert_eq!(proto.language, None);
+        let blocks = &proto.content_blocks;
+        assert_eq!(
+            (blocks[0].block_type.as_str(), blocks[0].level),
+            ("heading", 1)
+        );
+        assert_eq!(blocks[0].text, "Welcome");
+        assert_eq!(blocks[1].url, "https://x.com/a.png");
+        assert_eq!(
+            (blocks[2].text.as_str(), blocks[2].url.as_str()),
+            ("Buy", "https://x.com/buy")
+        );
+        let json: Value = serde_json::from_str(&proto.json).unwrap();
+        assert_eq!(json["title"], "Home");
+
+        let args = StartArgs::try_parse_from(["--max-pages", "5", "--no-js"]).unwrap();
+        assert!(args.run.no_js);
+        assert!(StartArgs::try_parse_from(["--no-such-flag"]).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn requests_cant_touch_server_state() {
+        let request = |url: &str| proto::StartRequest {
+            url: url.to_string(),
+            ..Default::default()
+        };
+        let check = |url: &str, flags: &[&str]| {
+            let mut request = request(url);
+            request.args = flags.iter().map(|f| f.to_string()).collect();
+            parse_request(&request).map(|_| ())
+        };
+        assert!(check(
+            "https://x.com/",
+            &["--no-js", "-m", "5", "--exclude", "/blog"]
+        )
+        .is_ok());
+        for flags in [
+            &["--har", "x.har"][..],
+            &["--cache-dir", "cache"],
+            &["--replay", "tapes"],
+            &["--header", "Authorization: Bearer x"],
+            &["--output", "/etc/dump/scraped.json"],
+            &["-o", "x.json"],
+            &["--ca-cert", "/etc/shadow"],
+            &["--solve-challenges"],
+            &["--graphql-query", "/etc/passwd"],
+        ] {
+            let err = check("https://x.com/", flags).unwrap_err();
+            assert!(
+                err.message().contains("not supported over gRPC"),
+                "{flags:?}"
+            );
+        }
+        for target in ["./site", "file:///etc/", "/var/www"] {
+            assert!(check(target, &[]).is_err());
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+
+use crate::util::{now_unix, unix_to_rfc3339};
+
+/// `--har`: where the log goes and the entries so far. `None` while
+/// recording is off, which leaves [`send`] to the cassette alone.
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+struct Recorder {
+    path: PathBuf,
+    entries: Vec<Entry>,
+    /// Lowercase names of the run's `--header` / `--site-header` /
+    /// `DUMPIT_*` headers, redacted on top of `REDACTED_HEADERS`.
+    secret_headers: Vec<String>,
+}
+
+/// Header values that never go into the log: credentials and cookies,
+/// either way.
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Index of a response's entry, stashed in its extensions so the body
+/// reader can fill in sizes and receive time.
+#[derive(Clone, Copy)]
+struct EntryId(usize);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    /// Milliseconds from sending the request to the last body byte read.
+    time: f64,
+    request: HarRequest,
+    response: HarResponse,
+    cache: serde_json::Value,
+    timings: Timings,
+    /// Transport error when no response came back (HAR custom field).
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// URL the response came from after reqwest followed redirects.
+    #[serde(rename = "_finalUrl", skip_serializing_if = "Option::is_none")]
+    final_url: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<serde_json::Value>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<serde_json::Value>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    /// Bytes on the wire: Content-Length until the body has been read,
+    /// -1 without one.
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+/// Phases reqwest can't see into (`blocked`, `dns`, `connect`, `ssl`)
+/// are -1; `wait` runs to the response headers, `receive` to the body end.
+#[derive(Serialize)]
+struct Timings {
+    blocked: f64,
+    dns: f64,
+    connect: f64,
+    ssl: f64,
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+fn ms(d: Duration) -> f64 {
+    (d.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0
+}
+
+fn name_values(headers: &HeaderMap, secret_headers: &[String]) -> Vec<NameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| NameValue {
+            name: name.to_string(),
+            value: if REDACTED_HEADERS.contains(&name.as_str())
+                || secret_headers.iter().any(|s| s == name.as_str())
+            {
+                "<redacted>".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            },
+        })
+        .collect()
+}
+
+/// `2024-03-01T12:00:00.123Z`.
+fn started_date_time() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_millis());
+    let secs = unix_to_rfc3339(now_unix());
+    format!("{}.{millis:03}Z", secs.trim_end_matches('Z'))
+}
+
+/// Start recording every request made through [`send`], for
+/// [`write_log`] to save to `path`. `headers` are the run's configured
+/// `Name: value` headers; their values are redacted wherever they show up.
+pub(crate) fn enable(path: PathBuf, headers: &[String]) {
+    let secret_headers = headers
+        .iter()
+        .filter_map(|h| h.split_once(':'))
+        .map(|(name, _)| name.trim().to_ascii_lowercase())
+        .collect();
+    *RECORDER.lock().unwrap() = Some(Recorder {
+        path,
+        entries: Vec::new(),
+        secret_headers,
+    });
+}
+
+/// Send `request`, logging it and its response (or error) while `--har`
+/// is on. Request headers are those set on the request itself; a
+/// client's default headers (User-Agent, Accept-Language, `--header`)
+/// are added by reqwest below this point and don't show up.
+pub(crate) async fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let Some(secret_headers) = RECORDER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|r| r.secret_headers.clone())
+    else {
+        return crate::auth::execute(&client, request).await;
+    };
+    let url = request.url().clone();
+    let har_request = HarRequest {
+        method: request.method().to_string(),
+        url: url.to_string(),
+        http_version: format!("{:?}", request.version()),
+        cookies: Vec::new(),
+        headers: name_values(request.headers(), &secret_headers),
+        query_string: url
+            .query_pairs()
+            .map(|(name, value)| NameValue {
+                name: name.into_owned(),
+                value: value.into_owned(),
+            })
+            .collect(),
+        headers_size: -1,
+        body_size: request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map_or(0, |b| b.len() as i64),
+    };
+    let started_date_time = started_date_time();
+    let started = Instant::now();
+    let mut result = crate::auth::execute(&client, request).await;
+    let wait = started.elapsed();
+
+    let mut entry = Entry {
+        started_date_time,
+        time: ms(wait),
+        request: har_request,
+        response: HarResponse {
+            status: 0,
+            status_text: String::new(),
+            http_version: String::new(),
+            cookies: Vec::new(),
+            headers: Vec::new(),
+            content: Content {
+                size: -1,
+                mime_type: String::new(),
+            },
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+        cache: serde_json::json!({}),
+        timings: Timings {
+            blocked: -1.0,
+            dns: -1.0,
+            connect: -1.0,
+            ssl: -1.0,
+            send: 0.0,
+            wait: ms(wait),
+            receive: 0.0,
+        },
+        error: None,
+        final_url: None,
+    };
+    match &result {
+        Ok(resp) => {
+            let version = format!("{:?}", resp.version());
+            entry.request.http_version = version.clone();
+            entry.response = HarResponse {
+                status: resp.status().as_u16(),
+                status_text: resp.status().canonical_reason().unwrap_or("").to_string(),
+                http_version: version,
+                cookies: Vec::new(),
+                headers: name_values(resp.headers(), &secret_headers),
+                content: Content {
+                    size: -1,
+                    mime_type: resp
+                        .headers()
+                        .get(CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("")
+                        .to_string(),
+                },
+                redirect_url: resp
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_string(),
+                headers_size: -1,
+                body_size: resp.content_length().map_or(-1, |n| n as i64),
+            };
+            entry.final_url = (*resp.url() != url).then(|| resp.url().to_string());
+        }
+        Err(e) => entry.error = Some(e.to_string()),
+    }
+
+    let mut recorder = RECORDER.lock().unwrap();
+    let Some(recorder) = recorder.as_mut() else {
+        return result;
+    };
+    recorder.entries.push(entry);
+    let id = EntryId(recorder.entries.len() - 1);
+    if let Ok(resp) = &mut result {
+        resp.extensions_mut().insert(id);
+    }
+    result
+}
+
+/// Times the read of a response body and records its size on the
+/// response's entry. Responses not logged by [`send`] are ignored.
+pub(crate) struct BodyRead {
+    id: Option<EntryId>,
+    started: Instant,
+}
+
+impl BodyRead {
+    /// Call before reading the body of `resp`.
+    pub(crate) fn start(resp: &Response) -> Self {
+        Self {
+            id: resp.extensions().get::<EntryId>().copied(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The body is in: `wire_bytes` as received, `decoded_bytes` after
+    /// Content-Encoding decoding.
+    pub(crate) fn finish(self, wire_bytes: u64, decoded_bytes: u64) {
+        let Some(EntryId(index)) = self.id else {
+            return;
+        };
+        let receive = self.started.elapsed();
+        let mut recorder = RECORDER.lock().unwrap();
+        let Some(entry) = recorder.as_mut().and_then(|r| r.entries.get_mut(index)) else {
+            return;
+        };
+        entry.response.body_size = wire_bytes as i64;
+        entry.response.content.size = decoded_bytes as i64;
+        entry.timings.receive = ms(receive);
+        entry.time += ms(receive);
+    }
+}
+
+/// Save the log when `--har` is on. Called once the run is over, failed
+/// runs included: the log is most useful for figuring out why a fetch went
+/// wrong.
+pub(crate) fn write_log() -> anyhow::Result<Option<(PathBuf, usize)>> {
+    let Some(recorder) = RECORDER.lock().unwrap().take() else {
+        return Ok(None);
+    };
+    let count = recorder.entries.len();
+    let log = serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": {"name": "dump-it", "version": env!("CARGO_PKG_VERSION")},
+            "pages": [],
+            "entries": recorder.entries,
+        }
+    });
+    if let Some(parent) = recorder.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&recorder.path, serde_json::to_string_pretty(&log)?)?;
+    Ok(Some((recorder.path, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, AUTHORIZATION, SET_COOKIE, USER_AGENT};
+
+    #[test]
+    fn credentials_are_kept_out_of_the_log() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer sk-secret"));
+        headers.insert(USER_AGENT, HeaderValue::from_static("dump-it"));
+        headers.insert(SET_COOKIE, HeaderValue::from_static("session=abc"));
+        headers.insert("x-api-key", HeaderValue::from_static("k1"));
+        let logged: Vec<(String, String)> = name_values(&headers, &["x-api-key".to_string()])
+            .into_iter()
+            .map(|h| (h.name, h.value))
+            .collect();
+        assert_eq!(
+            logged,
+            vec![
+                ("authorization".to_string(), "<redacted>".to_string()),
+                ("user-agent".to_string(), "dump-it".to_string()),
+                ("set-cookie".to_string(), "<redacted>".to_string()),
+                ("x-api-key".to_string(), "<redacted>".to_string()),
+            ]
+        );
+
+        let started = started_date_time();
+        assert_eq!(started.len(), "2024-03-01T12:00:00.123Z".len());
+        assert!(started.ends_with('Z') && started.contains('.'));
+    }
+}
@@ -0,0 +1,555 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use reqwest::{Client, Method, Request, Response, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::util::{now_unix, parse_http_date};
+
+/// `--cache-dir`; `None` while caching is off, which makes [`execute`] a
+/// plain `execute`.
+static CACHE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// `--offline`: answer from the cache whatever its freshness, never
+/// from the network.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Responses served without asking the origin.
+static FRESH: AtomicUsize = AtomicUsize::new(0);
+/// Stale responses the origin confirmed with a `304`.
+static REVALIDATED: AtomicUsize = AtomicUsize::new(0);
+/// Full responses fetched (and stored when allowed).
+static FETCHED: AtomicUsize = AtomicUsize::new(0);
+/// `--offline` requests the cache had no response for.
+static MISSING: AtomicUsize = AtomicUsize::new(0);
+
+/// Statuses cacheable by default (RFC 9110 §15.1), the ones heuristic
+/// freshness applies to.
+const CACHEABLE: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Upper bound of heuristic freshness (10% of the time since
+/// `Last-Modified`), so a years-old page isn't trusted for months.
+const HEURISTIC_MAX: u64 = 24 * 3600;
+
+/// Bodies over this size go past the cache: it holds a whole body in
+/// memory to store it. Checked on `Content-Length` up front, and on the
+/// bytes read for a body without one.
+const MAX_BODY: u64 = 50 * 1024 * 1024;
+
+/// Hop-by-hop headers, never stored (RFC 9111 §3.1).
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers of a stored response a `304` doesn't replace: they describe
+/// the stored body.
+const BODY_HEADERS: &[&str] = &["content-length", "content-encoding", "content-type"];
+
+/// One stored response, `<key>.json` next to its body in `<key>.body`.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    /// URL the response came from after redirects.
+    final_url: String,
+    status: u16,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    /// The request headers the response's `Vary` names, as [`fingerprint`]s
+    /// of what was sent (`None` when absent): `Vary: Cookie` mustn't put a
+    /// session cookie on disk. Only headers set on the request itself and
+    /// per-site credentials are seen; a client's defaults are the same for
+    /// every request of a run.
+    vary: Vec<(String, Option<String>)>,
+    /// Unix time the response, or the latest `304` for it, came in.
+    stored_at: u64,
+}
+
+/// `Cache-Control` directives of `headers`, lower-cased, with unquoted
+/// values.
+fn directives(headers: &[(String, String)]) -> Vec<(String, Option<String>)> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("cache-control"))
+        .flat_map(|(_, value)| value.split(','))
+        .filter_map(|directive| {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim().trim_matches('"').to_string())),
+                None => (directive, None),
+            };
+            let name = name.trim().to_ascii_lowercase();
+            (!name.is_empty()).then_some((name, value))
+        })
+        .collect()
+}
+
+/// SHA-256 of a request header value, hex.
+fn fingerprint(value: &[u8]) -> String {
+    Sha256::digest(value)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP.contains(&name.as_str()))
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+impl Entry {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn has_directive(&self, name: &str) -> bool {
+        directives(&self.headers).iter().any(|(n, _)| n == name)
+    }
+
+    /// Seconds the response stays fresh (RFC 9111 §4.2.1): `max-age`, else
+    /// `Expires` − `Date` (an invalid `Expires` means already stale), else
+    /// 10% of the time since `Last-Modified`, capped at a day. A private
+    /// cache ignores `s-maxage`.
+    fn freshness_lifetime(&self) -> u64 {
+        if let Some((_, value)) = directives(&self.headers)
+            .into_iter()
+            .find(|(n, _)| n == "max-age")
+        {
+            return value.and_then(|v| v.parse().ok()).unwrap_or(0);
+        }
+        let date = self
+            .header("date")
+            .and_then(parse_http_date)
+            .unwrap_or(self.stored_at);
+        if let Some(expires) = self.header("expires") {
+            return parse_http_date(expires).map_or(0, |e| e.saturating_sub(date));
+        }
+        match self.header("last-modified").and_then(parse_http_date) {
+            Some(modified) if CACHEABLE.contains(&self.status) => {
+                (date.saturating_sub(modified) / 10).min(HEURISTIC_MAX)
+            }
+            _ => 0,
+        }
+    }
+
+    /// The response's age at `now` (RFC 9111 §4.2.3): the larger of its
+    /// `Age` and how old its `Date` was when it came in, plus the time
+    /// since.
+    fn age(&self, now: u64) -> u64 {
+        let age_value = self
+            .header("age")
+            .and_then(|a| a.trim().parse().ok())
+            .unwrap_or(0);
+        let apparent = self
+            .header("date")
+            .and_then(parse_http_date)
+            .map_or(0, |date| self.stored_at.saturating_sub(date));
+        apparent.max(age_value) + now.saturating_sub(self.stored_at)
+    }
+
+    /// Whether the response may be used at `now` without validation.
+    /// `no-cache` means always validate.
+    fn is_fresh(&self, now: u64) -> bool {
+        !self.has_directive("no-cache") && self.age(now) < self.freshness_lifetime()
+    }
+
+    /// Whether the response may be stored at all (RFC 9111 §3): a
+    /// cacheable status, no `no-store`, no `Vary: *`.
+    fn is_storable(&self) -> bool {
+        CACHEABLE.contains(&self.status)
+            && !self.has_directive("no-store")
+            && !self
+                .header("vary")
+                .is_some_and(|v| v.split(',').any(|f| f.trim() == "*"))
+    }
+
+    /// Whether `request` asks for the same variant: every header the
+    /// response varies on has the value it had when stored.
+    fn matches(&self, request: &Request) -> bool {
+        self.vary.iter().all(|(name, value)| {
+            let sent = request.headers().get(name.as_str());
+            sent.map(|v| fingerprint(v.as_bytes())).as_deref() == value.as_deref()
+        })
+    }
+
+    /// Validators for a conditional request (RFC 9110 §13.1): the ETag as
+    /// `If-None-Match`, `Last-Modified` as `If-Modified-Since`.
+    fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let validators = [
+            (IF_NONE_MATCH, self.header("etag")),
+            (IF_MODIFIED_SINCE, self.header("last-modified")),
+        ];
+        for (name, value) in validators {
+            if let Some(value) = value.and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
+
+    /// Take in the headers of a `304` for this response (RFC 9111 §4.3.4)
+    /// and restart its age.
+    fn refresh(&mut self, headers: &HeaderMap, now: u64) {
+        let updates: Vec<(String, String)> = header_pairs(headers)
+            .into_iter()
+            .filter(|(name, _)| !BODY_HEADERS.contains(&name.as_str()))
+            .collect();
+        self.headers
+            .retain(|(name, _)| !updates.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)));
+        self.headers.extend(updates);
+        self.stored_at = now;
+    }
+
+    fn response(&self, body: impl Into<reqwest::Body>) -> Option<Response> {
+        let mut builder = http::Response::builder()
+            .status(self.status)
+            .version(crate::cassette::parse_version(&self.http_version))
+            .url(Url::parse(&self.final_url).ok()?);
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder.body(body.into()).ok().map(Response::from)
+    }
+}
+
+fn read_entry(dir: &Path, key: &str) -> Option<(Entry, Vec<u8>)> {
+    let entry =
+        serde_json::from_slice(&std::fs::read(dir.join(format!("{key}.json"))).ok()?).ok()?;
+    let body = std::fs::read(dir.join(format!("{key}.body"))).ok()?;
+    Some((entry, body))
+}
+
+fn write_entry(dir: &Path, key: &str, entry: &Entry, body: Option<&[u8]>) -> anyhow::Result<()> {
+    if let Some(body) = body {
+        write_atomically(&dir.join(format!("{key}.body")), body)?;
+    }
+    write_atomically(
+        &dir.join(format!("{key}.json")),
+        serde_json::to_string_pretty(entry)?.as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Write via a `.part` sibling + rename, so a concurrent read or an
+/// interrupted run never sees a truncated file. The sibling's name is
+/// unique: the same URL may be stored twice at once.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let part = path.with_extension(format!("{}-{n}.part", std::process::id()));
+    let written = std::fs::write(&part, bytes).and_then(|()| std::fs::rename(&part, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&part);
+    }
+    written
+}
+
+/// Read `resp`'s body as long as it stays within `limit` bytes. Past it,
+/// the response comes back as `Err`, its body made of what was read so
+/// far and the rest, still streaming.
+async fn read_within(mut resp: Response, limit: u64) -> reqwest::Result<Result<Vec<u8>, Response>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > limit {
+            let mut builder = http::Response::builder()
+                .status(resp.status())
+                .version(resp.version())
+                .url(resp.url().clone());
+            if let Some(headers) = builder.headers_mut() {
+                *headers = resp.headers().clone();
+            }
+            let read = futures::stream::once(async { Ok(body.into()) });
+            let rest = futures::stream::unfold(resp, |mut resp| async move {
+                resp.chunk().await.transpose().map(|chunk| (chunk, resp))
+            });
+            let body = reqwest::Body::wrap_stream(read.chain(rest));
+            let resp = builder.body(body).expect("parts of a live response");
+            return Ok(Err(Response::from(resp)));
+        }
+    }
+    Ok(Ok(body))
+}
+
+/// Turn the cache in `dir` on for the rest of the run, creating it if
+/// needed; `offline` needs it to exist. Process-wide, like the cassette.
+pub(crate) fn start(dir: &Path, offline: bool) -> anyhow::Result<()> {
+    if offline {
+        if !dir.is_dir() {
+            anyhow::bail!("cache {} does not exist", dir.display());
+        }
+    } else {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cache {}", dir.display()))?;
+    }
+    OFFLINE.store(offline, Ordering::Relaxed);
+    *CACHE_DIR.lock().unwrap() = Some(dir.to_path_buf());
+    Ok(())
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.lock().unwrap().clone()
+}
+
+/// What the cache did this run, for the closing summary; `None` while it
+/// is off.
+pub(crate) fn summary() -> Option<String> {
+    cache_dir()?;
+    let fresh = FRESH.load(Ordering::Relaxed);
+    if OFFLINE.load(Ordering::Relaxed) {
+        let missing = MISSING.load(Ordering::Relaxed);
+        return Some(format!("{fresh} served offline, {missing} not cached"));
+    }
+    Some(format!(
+        "{fresh} fresh, {} revalidated (304), {} fetched",
+        REVALIDATED.load(Ordering::Relaxed),
+        FETCHED.load(Ordering::Relaxed)
+    ))
+}
+
+/// `--offline`: the stored response for a GET of `request`'s URL, stale
+/// or not, or a `404` (it never reached a server) like a replay's miss.
+fn offline_response(dir: &Path, request: &Request) -> Response {
+    let url = request.url().as_str();
+    if request.method() == Method::GET {
+        let stored = read_entry(dir, &crate::cassette::key("GET", url, &[]))
+            .and_then(|(entry, body)| entry.response(body));
+        if let Some(resp) = stored {
+            FRESH.fetch_add(1, Ordering::Relaxed);
+            return resp;
+        }
+    }
+    MISSING.fetch_add(1, Ordering::Relaxed);
+    tracing::warn!("Not in the cache: {} {url}", request.method());
+    crate::cassette::not_recorded(request.url())
+}
+
+/// Execute `request`, through the cache when `--cache-dir` is on. A GET
+/// with a fresh stored response is answered from disk; a stale one with
+/// validators goes out as a conditional request, and a `304` serves the
+/// stored body. Storable responses are read in full, stored and handed
+/// back over the saved bytes; everything else, bodies that turn out to be
+/// over `MAX_BODY` included, streams as usual. Under
+/// `--offline` nothing goes to the network.
+pub(crate) async fn execute(client: &Client, mut request: Request) -> reqwest::Result<Response> {
+    if OFFLINE.load(Ordering::Relaxed) {
+        if let Some(dir) = cache_dir() {
+            return Ok(offline_response(&dir, &request));
+        }
+    }
+    let Some(dir) = cache_dir().filter(|_| request.method() == Method::GET) else {
+        return client.execute(request).await;
+    };
+    let url = request.url().to_string();
+    let key = crate::cassette::key("GET", &url, &[]);
+    let now = now_unix();
+    let cached = read_entry(&dir, &key).filter(|(entry, _)| entry.matches(&request));
+    if let Some((entry, body)) = &cached {
+        if entry.is_fresh(now) {
+            if let Some(resp) = entry.response(body.clone()) {
+                FRESH.fetch_add(1, Ordering::Relaxed);
+                return Ok(resp);
+            }
+        }
+        request.headers_mut().extend(entry.conditional_headers());
+    }
+    let sent = request.headers().clone();
+
+    let resp = client.execute(request).await?;
+    let now = now_unix();
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((mut entry, body)) = cached {
+            entry.refresh(resp.headers(), now);
+            if let Err(e) = write_entry(&dir, &key, &entry, None) {
+                tracing::warn!("Failed to update the cache entry of {url}: {e}");
+            }
+            if let Some(stored) = entry.response(body) {
+                REVALIDATED.fetch_add(1, Ordering::Relaxed);
+                return Ok(stored);
+            }
+        }
+        return Ok(resp);
+    }
+    FETCHED.fetch_add(1, Ordering::Relaxed);
+
+    let headers = header_pairs(resp.headers());
+    let vary = headers
+        .iter()
+        .filter(|(name, _)| name == "vary")
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let value = sent.get(name.as_str()).map(|v| fingerprint(v.as_bytes()));
+            (name, value)
+        })
+        .collect();
+    let entry = Entry {
+        url,
+        final_url: resp.url().to_string(),
+        status: resp.status().as_u16(),
+        http_version: format!("{:?}", resp.version()),
+        headers,
+        vary,
+        stored_at: now,
+    };
+    if !entry.is_storable() || resp.content_length().is_some_and(|len| len > MAX_BODY) {
+        // A response that may not be stored replaces nothing either.
+        let _ = std::fs::remove_file(dir.join(format!("{key}.json")));
+        return Ok(resp);
+    }
+    let body = match read_within(resp, MAX_BODY).await? {
+        Ok(body) => body,
+        Err(resp) => {
+            let _ = std::fs::remove_file(dir.join(format!("{key}.json")));
+            return Ok(resp);
+        }
+    };
+    if let Err(e) = write_entry(&dir, &key, &entry, Some(&body)) {
+        tracing::warn!("Failed to cache {}: {e}", entry.url);
+    }
+    Ok(entry.response(body).expect("parts of a live response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(headers: &[(&str, &str)]) -> Entry {
+        Entry {
+            url: "https://x.com/a".into(),
+            final_url: "https://x.com/a".into(),
+            status: 200,
+            http_version: "HTTP/1.1".into(),
+            headers: headers
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            vary: Vec::new(),
+            stored_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn freshness_and_revalidation_follow_rfc_9111() {
+        let t = 1_700_000_000;
+        let e = entry(&[
+            ("cache-control", "public, max-age=60"),
+            ("age", "20"),
+            ("etag", "\"v1\""),
+        ]);
+        assert!(e.is_fresh(t + 30));
+        assert!(!e.is_fresh(t + 45));
+        let conditional = e.conditional_headers();
+        assert_eq!(conditional[IF_NONE_MATCH], "\"v1\"");
+        assert!(conditional.get(IF_MODIFIED_SINCE).is_none());
+
+        // Expires counts from Date; heuristic freshness from Last-Modified.
+        let e = entry(&[
+            ("date", "Tue, 14 Nov 2023 22:13:20 GMT"),
+            ("expires", "Tue, 14 Nov 2023 23:13:20 GMT"),
+        ]);
+        assert_eq!(e.freshness_lifetime(), 3600);
+        let e = entry(&[
+            ("date", "Tue, 14 Nov 2023 22:13:20 GMT"),
+            ("last-modified", "Sat, 04 Nov 2023 22:13:20 GMT"),
+        ]);
+        assert_eq!(e.freshness_lifetime(), HEURISTIC_MAX);
+        assert_eq!(entry(&[("expires", "0")]).freshness_lifetime(), 0);
+        assert!(!entry(&[("cache-control", "max-age=600, no-cache")]).is_fresh(t));
+
+        assert!(!entry(&[("cache-control", "no-store")]).is_storable());
+        assert!(!entry(&[("vary", "Accept-Encoding, *")]).is_storable());
+        let mut e = entry(&[]);
+        e.status = 500;
+        assert!(!e.is_storable());
+
+        let mut e = entry(&[
+            ("content-type", "text/html"),
+            ("cache-control", "max-age=0"),
+        ]);
+        let mut not_modified = HeaderMap::new();
+        not_modified.insert("cache-control", HeaderValue::from_static("max-age=300"));
+        not_modified.insert("content-type", HeaderValue::from_static("text/plain"));
+        e.refresh(&not_modified, t + 1000);
+        assert_eq!(e.header("content-type"), Some("text/html"));
+        assert!(e.is_fresh(t + 1200));
+        assert!(!e.is_fresh(t + 1400));
+    }
+
+    #[tokio::test]
+    async fn offline_runs_answer_from_the_cache_only() {
+        let dir = std::env::temp_dir().join(format!("dump-it-httpcache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Long stale, and varying on a header the request doesn't send.
+        let mut stored = entry(&[("cache-control", "max-age=60"), ("vary", "cookie")]);
+        stored.vary = vec![("cookie".into(), Some(fingerprint(b"session=1")))];
+        let key = crate::cassette::key("GET", "https://x.com/a", &[]);
+        write_entry(&dir, &key, &stored, Some(b"<h1>Cached</h1>")).unwrap();
+
+        let url = Url::parse("https://x.com/a").unwrap();
+        let resp = offline_response(&dir, &Request::new(Method::GET, url.clone()));
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "<h1>Cached</h1>");
+        let resp = offline_response(&dir, &Request::new(Method::HEAD, url));
+        assert_eq!(resp.status(), 404);
+        let other = Url::parse("https://x.com/b").unwrap();
+        let resp = offline_response(&dir, &Request::new(Method::GET, other));
+        assert_eq!(resp.status(), 404);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn bodies_over_the_limit_stream_on_unstored() {
+        let chunked = || {
+            let chunks = ["<p>one</p>", "<p>two</p>", "<p>three</p>"]
+                .map(|c| Ok::<_, std::io::Error>(c.as_bytes().to_vec()));
+            let body = reqwest::Body::wrap_stream(futures::stream::iter(chunks));
+            Response::from(http::Response::builder().status(200).body(body).unwrap())
+        };
+        let body = read_within(chunked(), 64).await.unwrap().unwrap();
+        assert_eq!(body, b"<p>one</p><p>two</p><p>three</p>");
+
+        let resp = read_within(chunked(), 12).await.unwrap().unwrap_err();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "<p>one</p><p>two</p><p>three</p>"
+        );
+    }
+
+    #[test]
+    fn entries_are_written_whole() {
+        let dir = std::env::temp_dir().join(format!("dump-it-httpcache-w-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_entry(&dir, "k", &entry(&[]), Some(b"v1")).unwrap();
+        write_entry(&dir, "k", &entry(&[]), Some(b"v2")).unwrap();
+        assert_eq!(read_entry(&dir, "k").unwrap().1, b"v2");
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["k.body", "k.json"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,1095 @@
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::fs;
+use url::Url;
+
+use crate::cli::ImageLayout;
+use crate::eta::Throughput;
+use crate::model::{ContentBlock, FailedImage, PageData};
+use crate::selectors::DEFAULT_IMAGE_DENY_PATTERNS;
+use crate::util::{
+    extension_from_content_type, fetch_with_retry, fetchable_from, image_extension_from_url,
+    normalize_path, RateLimiter,
+};
+
+/// Image-download knobs, shared by content images and og:image.
+#[derive(Default, Clone)]
+pub(crate) struct ImageOptions {
+    /// `--verify-images`: fully decode a cached file before reusing it, on
+    /// top of the size check against `Content-Length`.
+    pub verify_cached: bool,
+    /// `--image-concurrency`: parallel image downloads.
+    pub concurrency: usize,
+    /// `--image-delay`: minimum gap between image requests, in ms.
+    pub delay_ms: u64,
+    /// `--image-retries`: retries per candidate URL on a timeout,
+    /// connection error or 5xx, with exponential backoff.
+    pub retries: u32,
+    /// Lowercased deny patterns: the built-in tracker list (unless
+    /// `--no-default-image-denylist`) plus `--image-deny`.
+    pub deny: Vec<String>,
+    /// Lowercased `--image-allow` patterns; a match overrides `deny`.
+    pub allow: Vec<String>,
+    /// `--min-image-bytes`: smaller downloads are discarded as spacers.
+    pub min_bytes: usize,
+    /// `--min-image-size`: minimum decoded width × height.
+    pub min_size: Option<(u32, u32)>,
+    /// `--inline-svg-max`: SVGs up to this many bytes are also embedded
+    /// in their block as `svg_markup`. 0 = off.
+    pub inline_svg_max: u64,
+    /// `--image-layout`: how files under `images/` are named.
+    pub layout: ImageLayout,
+    /// `--sitemap-media`: also download the images and video thumbnails
+    /// the sitemap lists for each page.
+    pub sitemap_media: bool,
+}
+
+impl ImageOptions {
+    pub fn from_args(args: &crate::cli::RunArgs) -> Self {
+        let mut deny: Vec<String> = if args.no_default_image_denylist {
+            Vec::new()
+        } else {
+            DEFAULT_IMAGE_DENY_PATTERNS
+                .iter()
+                .map(|s| (*s).to_string())
+                .collect()
+        };
+        deny.extend(args.image_deny.iter().map(|p| p.to_lowercase()));
+        // A dimension floor replaces the byte heuristic unless both are
+        // asked for.
+        let default_min_bytes = if args.min_image_size.is_some() {
+            0
+        } else {
+            1024
+        };
+        Self {
+            verify_cached: args.verify_images,
+            concurrency: args.image_concurrency.max(1),
+            delay_ms: args.image_delay,
+            retries: args.image_retries,
+            deny,
+            allow: args.image_allow.iter().map(|p| p.to_lowercase()).collect(),
+            min_bytes: args.min_image_bytes.unwrap_or(default_min_bytes),
+            min_size: args.min_image_size,
+            inline_svg_max: args.inline_svg_max,
+            layout: args.image_layout,
+            sitemap_media: args.sitemap_media,
+        }
+    }
+
+    /// Allow patterns win over deny patterns; anything matching neither
+    /// is downloaded.
+    fn url_allowed(&self, url: &str) -> bool {
+        let lower = url.to_lowercase();
+        self.allow.iter().any(|p| lower.contains(p.as_str()))
+            || !self.deny.iter().any(|p| lower.contains(p.as_str()))
+    }
+}
+
+/// One distinct image to fetch: its fallback candidates (merged across
+/// pages) and the first page that referenced it.
+pub(crate) struct ImageJob {
+    fallback_urls: Vec<String>,
+    page_url: String,
+}
+
+/// Content-image, video-poster and og:image URLs of `pages`, plus with
+/// `sitemap_media` their sitemap images and video thumbnails,
+/// deduplicated. Inline SVGs are already on disk.
+pub(crate) fn image_jobs(pages: &[PageData], sitemap_media: bool) -> BTreeMap<String, ImageJob> {
+    let mut jobs: BTreeMap<String, ImageJob> = BTreeMap::new();
+    let mut add = |url: &str, fallbacks: &[String], page_url: &str| {
+        if !fetchable_from(page_url, url) {
+            return;
+        }
+        let fallbacks: Vec<&String> = fallbacks
+            .iter()
+            .filter(|f| fetchable_from(page_url, f))
+            .collect();
+        let job = jobs.entry(url.to_string()).or_insert_with(|| ImageJob {
+            fallback_urls: Vec::new(),
+            page_url: page_url.to_string(),
+        });
+        for f in fallbacks {
+            if !job.fallback_urls.contains(f) {
+                job.fallback_urls.push(f.clone());
+            }
+        }
+    };
+    for page in pages {
+        for block in &page.content_blocks {
+            match block {
+                ContentBlock::Image {
+                    original_url,
+                    fallback_urls,
+                    ..
+                } if !original_url.starts_with("inline-svg://") => {
+                    add(original_url, fallback_urls, &page.url);
+                }
+                ContentBlock::Video {
+                    poster_url: Some(poster),
+                    ..
+                } => add(poster, &[], &page.url),
+                _ => {}
+            }
+        }
+        if let Some(og) = &page.og_image_url {
+            add(og, &[], &page.url);
+        }
+        if let Some(meta) = page.sitemap.as_ref().filter(|_| sitemap_media) {
+            for image in &meta.images {
+                add(&image.loc, &[], &page.url);
+            }
+            for thumbnail in meta.videos.iter().filter_map(|v| v.thumbnail_loc.as_ref()) {
+                add(thumbnail, &[], &page.url);
+            }
+        }
+    }
+    jobs
+}
+
+/// Where a job's image ended up: the candidate that downloaded, and its
+/// local path.
+pub(crate) struct Downloaded {
+    pub url: String,
+    pub local_path: String,
+}
+
+/// Result of the image phase, keyed by each job's primary URL.
+#[derive(Default)]
+pub(crate) struct ImageDownloads {
+    pub downloaded: HashMap<String, Downloaded>,
+    pub failed: Vec<FailedImage>,
+}
+
+/// Reasons that mean "not an image worth keeping", not a failure.
+fn is_filtered(reason: &str) -> bool {
+    matches!(reason, "filtered" | "too_small")
+}
+
+/// The image phase: every URL once, on a worker pool of its own so page
+/// fetches never wait on images. A job whose primary URL fails moves on
+/// to its fallback candidates; jobs where every candidate failed for a
+/// network / disk reason are reported in `failed`.
+pub(crate) async fn download_all(
+    client: &Client,
+    jobs: BTreeMap<String, ImageJob>,
+    output_dir: &str,
+    options: &ImageOptions,
+) -> ImageDownloads {
+    let limiter = RateLimiter::new(options.delay_ms);
+    // The brand favicon / logo are written as `favicon.<ext>` /
+    // `logo.<ext>` next to the content images; keep those names free.
+    // Names earlier runs gave out stay with their URLs.
+    let stored = load_manifest(output_dir);
+    let mut claimed: HashMap<String, String> = ["favicon", "logo"]
+        .map(|n| (n.to_string(), String::new()))
+        .into();
+    for (file, url) in &stored {
+        claimed
+            .entry(strip_extension(file).to_string())
+            .or_insert_with(|| url.clone());
+    }
+    let claims: NameClaims = Mutex::new(claimed);
+    let claims = &claims;
+    let manifest = Mutex::new(stored);
+    let manifest = &manifest;
+    let throughput = Mutex::new(Throughput::new(jobs.len(), Instant::now()));
+    let throughput = &throughput;
+    let results: Vec<_> = stream::iter(jobs)
+        .map(|(url, job)| {
+            let limiter = limiter.clone();
+            async move {
+                let _admission = crate::memory::admit().await;
+                let mut result = Err("filtered");
+                for candidate in std::iter::once(&url).chain(&job.fallback_urls) {
+                    if let Some(limiter) = &limiter {
+                        limiter.wait().await;
+                    }
+                    let stored =
+                        download_image(client, candidate, output_dir, options, claims, manifest);
+                    match stored.await {
+                        Ok(local_path) => {
+                            if candidate != &url {
+                                tracing::debug!("Image {url} fell back to {candidate}");
+                            }
+                            result = Ok(Downloaded {
+                                url: candidate.clone(),
+                                local_path,
+                            });
+                            break;
+                        }
+                        Err(reason) => result = Err(reason),
+                    }
+                }
+                crate::eta::record_and_report(throughput, "images");
+                (url, job.page_url, result)
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .collect()
+        .await;
+    save_manifest(
+        output_dir,
+        &manifest.lock().unwrap_or_else(|e| e.into_inner()),
+    );
+
+    let mut out = ImageDownloads::default();
+    for (url, page_url, result) in results {
+        match result {
+            Ok(done) => {
+                out.downloaded.insert(url, done);
+            }
+            Err(reason) if is_filtered(reason) => {}
+            Err(reason) => out.failed.push(FailedImage {
+                url,
+                page_url,
+                reason: reason.to_string(),
+            }),
+        }
+    }
+    out.failed.sort_by(|a, b| a.url.cmp(&b.url));
+    out
+}
+
+/// Point content images, video posters and og:image at their downloads.
+/// A fallback that succeeded replaces `original_url`. Content images that
+/// failed are dropped, as they always were; `content_hash` is recomputed
+/// for pages whose images changed. Small SVGs get their markup inlined
+/// when `--inline-svg-max` asks for it.
+pub(crate) fn attach_downloads(
+    pages: &mut [PageData],
+    downloads: &ImageDownloads,
+    options: &ImageOptions,
+) {
+    for page in pages {
+        let mut changed = false;
+        page.content_blocks.retain_mut(|block| match block {
+            ContentBlock::Image {
+                original_url,
+                local_path,
+                ..
+            } if !original_url.starts_with("inline-svg://") => {
+                match downloads.downloaded.get(original_url.as_str()) {
+                    Some(done) => {
+                        if *original_url != done.url {
+                            *original_url = done.url.clone();
+                            changed = true;
+                        }
+                        *local_path = done.local_path.clone();
+                        true
+                    }
+                    None => {
+                        changed = true;
+                        false
+                    }
+                }
+            }
+            _ => true,
+        });
+        for block in &mut page.content_blocks {
+            if let ContentBlock::Video {
+                poster_url: Some(poster),
+                poster_local_path,
+                ..
+            } = block
+            {
+                *poster_local_path = downloads
+                    .downloaded
+                    .get(poster.as_str())
+                    .map(|d| d.local_path.clone());
+            }
+        }
+        if options.inline_svg_max > 0 {
+            for block in &mut page.content_blocks {
+                if let ContentBlock::Image {
+                    local_path,
+                    svg_markup,
+                    ..
+                } = block
+                {
+                    if svg_markup.is_none() && local_path.ends_with(".svg") {
+                        *svg_markup = read_small_svg(local_path, options.inline_svg_max);
+                    }
+                }
+            }
+        }
+        if changed {
+            page.content_hash = crate::util::hash_blocks(&page.content_blocks);
+        }
+        page.og_image_local_path = page
+            .og_image_url
+            .as_ref()
+            .and_then(|u| downloads.downloaded.get(u))
+            .map(|d| d.local_path.clone());
+        if let Some(meta) = page.sitemap.as_mut() {
+            for image in &mut meta.images {
+                image.local_path = downloads
+                    .downloaded
+                    .get(&image.loc)
+                    .map(|d| d.local_path.clone());
+            }
+            for video in &mut meta.videos {
+                video.thumbnail_local_path = video
+                    .thumbnail_loc
+                    .as_ref()
+                    .and_then(|u| downloads.downloaded.get(u))
+                    .map(|d| d.local_path.clone());
+            }
+        }
+    }
+}
+
+fn read_small_svg(path: &str, max_bytes: u64) -> Option<String> {
+    let len = std::fs::metadata(path).ok()?.len();
+    if len > max_bytes {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+/// Names claimed so far, by this run or (through the manifest) earlier
+/// ones: file stem → the URL that owns it.
+type NameClaims = Mutex<HashMap<String, String>>;
+
+/// `images/.manifest.json`: the URL of every file stored under `images/`,
+/// by path relative to it, kept across runs. A mirrored or original name
+/// belongs to one URL, and a file left by a previous run is only reused
+/// for the URL that stored it.
+const MANIFEST: &str = ".manifest.json";
+
+type Manifest = BTreeMap<String, String>;
+
+fn load_manifest(output_dir: &str) -> Manifest {
+    std::fs::read_to_string(Path::new(output_dir).join(MANIFEST))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_manifest(output_dir: &str, manifest: &Manifest) {
+    if manifest.is_empty() {
+        return;
+    }
+    let path = Path::new(output_dir).join(MANIFEST);
+    let written = serde_json::to_string_pretty(manifest)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(&path, json));
+    if let Err(e) = written {
+        tracing::warn!("failed to write {}: {e}", path.display());
+    }
+}
+
+/// `name` for `img_url` if no other URL has it, else `name` with a short
+/// hash of the URL appended.
+fn claim(claims: &NameClaims, name: String, img_url: &str, hash: &str) -> String {
+    let mut claims = claims.lock().unwrap_or_else(|e| e.into_inner());
+    match claims.get(&name) {
+        Some(owner) if owner != img_url => format!("{name}-{}", &hash[..8]),
+        Some(_) => name,
+        None => {
+            claims.insert(name.clone(), img_url.to_string());
+            name
+        }
+    }
+}
+
+/// Path segment made safe for the filesystem: a `%XX` escape and
+/// anything but ASCII alphanumerics, `-`, `_` and inner dots become `_`,
+/// capped at 80 chars.
+fn sanitize_segment(segment: &str) -> String {
+    let mut cleaned = String::with_capacity(segment.len());
+    let mut chars = segment.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+            cleaned.push(c);
+            continue;
+        }
+        if c == '%' {
+            for _ in 0..2 {
+                chars.next_if(char::is_ascii_hexdigit);
+            }
+        }
+        cleaned.push('_');
+    }
+    let cleaned: String = cleaned.trim_start_matches('.').chars().take(80).collect();
+    if cleaned.is_empty() {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// File name without its extension — the stored extension comes from
+/// the `Content-Type`.
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() <= 5 => stem,
+        _ => name,
+    }
+}
+
+/// Where `img_url` is stored under `images/`, without the extension.
+/// `hash` is the hex SHA-256 of the URL. Mirrored paths of URLs with a
+/// query get a short hash suffix so `?w=400` and `?w=800` don't collide;
+/// mirrored and `original` names go to the first URL that claims them,
+/// and any other URL that comes out the same (`a b.jpg` / `a_b.jpg`,
+/// `logo.png` / `logo.svg`) gets the hash suffix.
+fn local_stem(img_url: &str, hash: &str, layout: ImageLayout, claims: &NameClaims) -> String {
+    let parsed = Url::parse(img_url).ok();
+    let segments: Vec<&str> = parsed
+        .as_ref()
+        .and_then(|u| u.path_segments())
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    match layout {
+        ImageLayout::Hash => hash[..16].to_string(),
+        ImageLayout::Mirror => {
+            let Some(url) = parsed.as_ref() else {
+                return hash[..16].to_string();
+            };
+            let mut parts = vec![sanitize_segment(url.host_str().unwrap_or("local"))];
+            match segments.split_last() {
+                Some((file, dirs)) => {
+                    parts.extend(dirs.iter().map(|d| sanitize_segment(d)));
+                    parts.push(sanitize_segment(strip_extension(file)));
+                }
+                None => parts.push("index".to_string()),
+            }
+            let mut stem = parts.join("/");
+            if url.query().is_some() {
+                stem = format!("{stem}-{}", &hash[..8]);
+            }
+            claim(claims, stem, img_url, hash)
+        }
+        ImageLayout::Original => {
+            let name = segments
+                .last()
+                .map(|f| sanitize_segment(strip_extension(f)))
+                .unwrap_or_else(|| "image".to_string());
+            claim(claims, name, img_url, hash)
+        }
+    }
+}
+
+/// Download one image into `output_dir`. The error is the reason it was
+/// not stored: `filtered` / `too_small` (deliberately skipped) or
+/// `http_error` / `fetch_failed` / `write_failed`.
+async fn download_image(
+    client: &Client,
+    img_url: &str,
+    output_dir: &str,
+    options: &ImageOptions,
+    claims: &NameClaims,
+    manifest: &Mutex<Manifest>,
+) -> Result<String, &'static str> {
+    if !options.url_allowed(img_url) {
+        return Err("filtered");
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(img_url.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    let stem = local_stem(img_url, &hash, options.layout, claims);
+    let stem = stem.as_str();
+
+    if let Some((cached, file)) = find_cached(output_dir, stem) {
+        // A hash name is the URL's own; any other is only this URL's
+        // file if the manifest says so.
+        let recorded = manifest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&file)
+            .map(String::as_str)
+            == Some(img_url);
+        let owned = recorded || options.layout == ImageLayout::Hash;
+        if owned && cached_image_is_valid(client, img_url, &cached, recorded, options).await {
+            manifest
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(file, img_url.to_string());
+            return Ok(normalize_path(&cached.to_string_lossy()));
+        }
+        // Another URL's file is left alone.
+        if owned {
+            tracing::info!("Re-downloading incomplete cached image: {img_url}");
+            let _ = fs::remove_file(&cached).await;
+        }
+    }
+
+    let response = match fetch_with_retry(client, img_url, options.retries).await {
+        Some(r) if r.status().is_success() => r,
+        Some(r) => {
+            tracing::debug!("Image HTTP {}: {img_url}", r.status());
+            return Err("http_error");
+        }
+        None => return Err("fetch_failed"),
+    };
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+    let content_type_ext = content_type
+        .as_deref()
+        .and_then(extension_from_content_type);
+    let body_read = crate::har::BodyRead::start(&response);
+    let bytes = response.bytes().await.map_err(|_| "fetch_failed")?;
+    body_read.finish(bytes.len() as u64, bytes.len() as u64);
+    // Content-Type first: the URL of a resizing proxy or CDN rarely names
+    // the format it serves. SVGs served without one, or with a generic
+    // one, are sniffed.
+    let extension = match content_type_ext {
+        Some(ext) => ext,
+        None if generic_content_type(content_type.as_deref()) && looks_like_svg(&bytes) => "svg",
+        None => image_extension_from_url(img_url),
+    };
+    // Icons and logos are routinely a few hundred bytes of SVG; the
+    // spacer heuristics only make sense for raster images.
+    if extension != "svg" {
+        if bytes.len() < options.min_bytes {
+            return Err("too_small");
+        }
+        if let Some((min_w, min_h)) = options.min_size {
+            if let Some((w, h)) = image_dimensions(&bytes) {
+                if w < min_w || h < min_h {
+                    tracing::debug!("Image {w}x{h} below --min-image-size: {img_url}");
+                    return Err("too_small");
+                }
+            }
+        }
+    } else if bytes.is_empty() {
+        return Err("too_small");
+    }
+    let filepath = format!("{output_dir}/{stem}.{extension}");
+    if let Some(parent) = Path::new(&filepath).parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|_| "write_failed")?;
+    }
+    write_atomically(Path::new(&filepath), &bytes)
+        .await
+        .map_err(|_| "write_failed")?;
+    manifest
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(format!("{stem}.{extension}"), img_url.to_string());
+    Ok(normalize_path(&filepath))
+}
+
+/// Extensions a downloaded image can be stored under.
+const IMAGE_EXTENSIONS: [&str; 8] = ["jpg", "png", "webp", "gif", "svg", "avif", "bmp", "ico"];
+
+/// A previous run's file named `stem`, and its name relative to
+/// `output_dir`. The extension depends on the `Content-Type` it was
+/// served with, so every candidate is probed.
+fn find_cached(output_dir: &str, stem: &str) -> Option<(std::path::PathBuf, String)> {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| format!("{stem}.{ext}"))
+        .map(|file| (Path::new(output_dir).join(&file), file))
+        .find(|(path, _)| path.is_file())
+}
+
+/// A missing `Content-Type`, or one that says nothing about the format
+/// (`application/octet-stream`, `text/plain`, bare XML).
+fn generic_content_type(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return true;
+    };
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    [
+        "",
+        "application/octet-stream",
+        "binary/octet-stream",
+        "text/plain",
+        "text/xml",
+        "application/xml",
+    ]
+    .iter()
+    .any(|generic| essence.eq_ignore_ascii_case(generic))
+}
+
+/// Whether a body is an SVG document: it opens with `<svg`, or with an
+/// XML declaration whose document has an `<svg` root near the top.
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg"))
+}
+
+/// Width × height from the image header, without decoding pixels. `None`
+/// for SVG and anything the `image` build can't read.
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// `--min-image-size` parser: `WxH`, or a single number for both.
+pub(crate) fn parse_min_size(s: &str) -> Result<(u32, u32), String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<u32>()
+            .map_err(|_| format!("expected WxH (e.g. 100x100), got {s:?}"))
+    };
+    match s.split_once(['x', 'X', '×']) {
+        Some((w, h)) => Ok((parse(w)?, parse(h)?)),
+        None => parse(s).map(|n| (n, n)),
+    }
+}
+
+/// Write via a `.part` sibling + rename, so an interrupted run never
+/// leaves a truncated file under the final name.
+async fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let part = path.with_extension("part");
+    fs::write(&part, bytes).await?;
+    fs::rename(&part, path).await
+}
+
+/// Whether an image left by a previous run can be reused. Empty files are
+/// rejected outright. A file the manifest `recorded` was renamed into
+/// place whole, so it's trusted; any other, and every file under
+/// `--verify-images`, needs a HEAD request's `Content-Length` to match
+/// its size (skipped when the server doesn't send one, or sends an
+/// encoded length). With `--verify-images` the file must also decode.
+async fn cached_image_is_valid(
+    client: &Client,
+    img_url: &str,
+    path: &Path,
+    recorded: bool,
+    options: &ImageOptions,
+) -> bool {
+    let Ok(meta) = fs::metadata(path).await else {
+        return false;
+    };
+    if meta.len() == 0 {
+        return false;
+    }
+    let revalidate = !recorded || options.verify_cached;
+    let head = if revalidate {
+        crate::har::send(client.head(img_url)).await.ok()
+    } else {
+        None
+    };
+    if let Some(resp) = head {
+        let headers = resp.headers();
+        let encoded = headers.contains_key(reqwest::header::CONTENT_ENCODING);
+        let expected = headers
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        if resp.status().is_success() && !encoded {
+            if let Some(expected) = expected.filter(|&n| n > 0) {
+                if expected != meta.len() {
+                    tracing::debug!(
+                        "Cached image size {} ≠ Content-Length {expected}: {img_url}",
+                        meta.len()
+                    );
+                    return false;
+                }
+            }
+        }
+    }
+    if options.verify_cached {
+        let path = path.to_path_buf();
+        return tokio::task::spawn_blocking(move || image_decodes(&path))
+            .await
+            .unwrap_or(false);
+    }
+    true
+}
+
+/// Full decode of a cached file. Formats the `image` build can't read
+/// (SVG, AVIF) pass — there's nothing to check them against.
+fn image_decodes(path: &Path) -> bool {
+    let Ok(reader) = image::ImageReader::open(path).and_then(|r| r.with_guessed_format()) else {
+        return false;
+    };
+    match reader.format() {
+        Some(format) if format.reading_enabled() => reader.decode().is_ok(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(url: &str, fallback_urls: &[&str]) -> ContentBlock {
+        ContentBlock::Image {
+            original_url: url.to_string(),
+            local_path: String::new(),
+            alt_text: String::new(),
+            svg_markup: None,
+            fallback_urls: fallback_urls.iter().map(|u| u.to_string()).collect(),
+        }
+    }
+
+    fn local_paths(page: &PageData) -> Vec<(&str, &str)> {
+        page.content_blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Image {
+                    original_url,
+                    local_path,
+                    ..
+                } => Some((original_url.as_str(), local_path.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shared_images_download_once_and_failed_ones_are_dropped() {
+        let mut home = crate::model::test_page("https://x.com/");
+        home.content_blocks = vec![
+            image("https://x.com/logo.png", &[]),
+            image("https://x.com/hero.jpg", &[]),
+            image("inline-svg://0", &[]),
+        ];
+        home.og_image_url = Some("https://x.com/og.png".to_string());
+        let mut about = crate::model::test_page("https://x.com/about");
+        about.content_blocks = vec![image("https://x.com/logo.png", &[])];
+        let mut pages = [home, about];
+
+        let jobs = image_jobs(&pages, false);
+        let urls: Vec<&str> = jobs.keys().map(String::as_str).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://x.com/hero.jpg",
+                "https://x.com/logo.png",
+                "https://x.com/og.png"
+            ]
+        );
+        assert_eq!(jobs["https://x.com/logo.png"].page_url, "https://x.com/");
+
+        let downloaded = |url: &str, path: &str| {
+            let done = Downloaded {
+                url: url.to_string(),
+                local_path: path.to_string(),
+            };
+            (url.to_string(), done)
+        };
+        let downloads = ImageDownloads {
+            downloaded: [
+                downloaded("https://x.com/logo.png", "out/images/logo.png"),
+                downloaded("https://x.com/og.png", "out/images/og.png"),
+            ]
+            .into(),
+            failed: Vec::new(),
+        };
+        let hash_before = pages[0].content_hash.clone();
+        attach_downloads(&mut pages, &downloads, &ImageOptions::default());
+        assert_eq!(
+            local_paths(&pages[0]),
+            [
+                ("https://x.com/logo.png", "out/images/logo.png"),
+                ("inline-svg://0", "")
+            ]
+        );
+        assert_ne!(pages[0].content_hash, hash_before);
+        assert_eq!(
+            pages[0].og_image_local_path.as_deref(),
+            Some("out/images/og.png")
+        );
+        assert_eq!(
+            local_paths(&pages[1]),
+            [("https://x.com/logo.png", "out/images/logo.png")]
+        );
+    }
+
+    #[test]
+    fn image_filters_are_specific_and_allow_wins() {
+        let options = ImageOptions {
+            deny: DEFAULT_IMAGE_DENY_PATTERNS
+                .iter()
+                .map(|s| s.to_string())
+                .chain(["/ads/".to_string()])
+                .collect(),
+            allow: vec!["/ads/hero".to_string()],
+            ..Default::default()
+        };
+        assert!(!options.url_allowed("https://www.facebook.com/tr?id=1&ev=PageView"));
+        assert!(!options.url_allowed("https://www.google-analytics.com/collect?v=1"));
+        assert!(options.url_allowed("https://cdn.x.com/img/analytics-dashboard.png"));
+        assert!(options.url_allowed("https://cdn.x.com/pixel-art/cat.jpg"));
+        assert!(!options.url_allowed("https://x.com/ads/banner.jpg"));
+        assert!(options.url_allowed("https://x.com/ads/hero.jpg"));
+    }
+
+    #[test]
+    fn min_size_parses_and_reads_png_header() {
+        assert_eq!(parse_min_size("100x80"), Ok((100, 80)));
+        assert_eq!(parse_min_size("64"), Ok((64, 64)));
+        assert!(parse_min_size("wide").is_err());
+        let mut png = Vec::new();
+        image::RgbImage::new(300, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(image_dimensions(&png), Some((300, 2)));
+        assert_eq!(image_dimensions(b"<svg width='1' height='1'/>"), None);
+    }
+
+    #[test]
+    fn image_layouts_name_files() {
+        let claims = NameClaims::default();
+        let hash = "0123456789abcdef0123456789abcdef";
+        let stem = |url: &str, layout| local_stem(url, hash, layout, &claims);
+        assert_eq!(
+            stem("https://cdn.x.com/a/b.jpg", ImageLayout::Hash),
+            "0123456789abcdef"
+        );
+        assert_eq!(
+            stem(
+                "https://cdn.x.com/up loads/2024/Hero.Photo.JPG",
+                ImageLayout::Mirror
+            ),
+            "cdn.x.com/up_loads/2024/Hero.Photo"
+        );
+        assert_eq!(
+            stem("https://cdn.x.com/../b.png?w=400", ImageLayout::Mirror),
+            "cdn.x.com/b-01234567"
+        );
+        assert_eq!(
+            stem("https://x.com/a/logo.svg", ImageLayout::Original),
+            "logo"
+        );
+        assert_eq!(
+            stem("https://x.com/a/logo.svg", ImageLayout::Original),
+            "logo"
+        );
+        assert_eq!(
+            stem("https://x.com/b/logo.png", ImageLayout::Original),
+            "logo-01234567"
+        );
+        // Mirrored URLs that sanitize or strip to the same name.
+        assert_eq!(
+            stem("https://cdn.x.com/a b.jpg", ImageLayout::Mirror),
+            "cdn.x.com/a_b"
+        );
+        assert_eq!(
+            stem("https://cdn.x.com/a_b.png", ImageLayout::Mirror),
+            "cdn.x.com/a_b-01234567"
+        );
+    }
+
+    #[test]
+    fn manifest_keeps_names_with_their_urls_across_runs() {
+        let dir = std::env::temp_dir().join(format!("dump-it-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir_str = dir.to_str().unwrap();
+        let earlier: Manifest = [(
+            "hero.jpg".to_string(),
+            "https://x.com/a/hero.jpg".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        save_manifest(dir_str, &earlier);
+        let loaded = load_manifest(dir_str);
+        assert_eq!(loaded, earlier);
+
+        // The next run's claims start from the manifest.
+        let claims = NameClaims::new(
+            loaded
+                .iter()
+                .map(|(file, url)| (strip_extension(file).to_string(), url.clone()))
+                .collect(),
+        );
+        let hash = "0123456789abcdef0123456789abcdef";
+        let stem = |url: &str| local_stem(url, hash, ImageLayout::Original, &claims);
+        assert_eq!(stem("https://x.com/b/hero.jpg"), "hero-01234567");
+        assert_eq!(stem("https://x.com/a/hero.jpg"), "hero");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn only_unrecorded_cached_images_are_revalidated() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Every HEAD says the image is 5 bytes, which the cached file isn't.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/a.png", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.read(&mut [0; 4096]).await;
+                let response = "HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\n";
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let dir = std::env::temp_dir().join(format!("dump-it-revalidate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.png");
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        let client = Client::new();
+        let plain = ImageOptions::default();
+        let verify = ImageOptions {
+            verify_cached: true,
+            ..ImageOptions::default()
+        };
+
+        assert!(cached_image_is_valid(&client, &url, &path, true, &plain).await);
+        assert!(!cached_image_is_valid(&client, &url, &path, false, &plain).await);
+        assert!(!cached_image_is_valid(&client, &url, &path, true, &verify).await);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sitemap_media_is_downloaded_only_when_asked() {
+        use crate::model::{SitemapImage, SitemapMeta, SitemapVideo};
+        let mut page = crate::model::test_page("https://x.com/");
+        page.sitemap = Some(SitemapMeta {
+            images: vec![SitemapImage {
+                loc: "https://x.com/gallery.jpg".to_string(),
+                ..SitemapImage::default()
+            }],
+            videos: vec![
+                SitemapVideo {
+                    thumbnail_loc: Some("https://x.com/thumb.jpg".to_string()),
+                    ..SitemapVideo::default()
+                },
+                SitemapVideo::default(),
+            ],
+            ..SitemapMeta::default()
+        });
+        let mut pages = [page];
+        assert!(image_jobs(&pages, false).is_empty());
+        let jobs = image_jobs(&pages, true);
+        let urls: Vec<&str> = jobs.keys().map(String::as_str).collect();
+        assert_eq!(
+            urls,
+            ["https://x.com/gallery.jpg", "https://x.com/thumb.jpg"]
+        );
+
+        let downloads = ImageDownloads {
+            downloaded: [(
+                "https://x.com/thumb.jpg".to_string(),
+                Downloaded {
+                    url: "https://x.com/thumb.jpg".to_string(),
+                    local_path: "out/images/thumb.jpg".to_string(),
+                },
+            )]
+            .into(),
+            failed: Vec::new(),
+        };
+        attach_downloads(&mut pages, &downloads, &ImageOptions::default());
+        let meta = pages[0].sitemap.as_ref().unwrap();
+        assert_eq!(meta.images[0].local_path, None);
+        assert_eq!(
+            meta.videos[0].thumbnail_local_path.as_deref(),
+            Some("out/images/thumb.jpg")
+        );
+        assert_eq!(meta.videos[1].thumbnail_local_path, None);
+    }
+
+    #[tokio::test]
+    async fn failed_images_are_retried_then_fall_back() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // `/flaky.png` fails once; `/ok.png` and `/flaky.png` are images,
+        // everything else is a 404.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let flaky_hits = AtomicUsize::new(0);
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut head = [0; 4096];
+                let n = socket.read(&mut head).await.unwrap_or(0);
+                let head = String::from_utf8_lossy(&head[..n]).to_string();
+                let path = head.split_whitespace().nth(1).unwrap_or("");
+                let status = match path {
+                    "/flaky.png" if flaky_hits.fetch_add(1, Ordering::SeqCst) == 0 => "503",
+                    "/ok.png" | "/flaky.png" => "200",
+                    _ => "404",
+                };
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: image/png\r\ncontent-length: 4\r\n\
+                     connection: close\r\n\r\npng!"
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let url = |path: &str| format!("{base}{path}");
+        let mut page = crate::model::test_page(&url("/"));
+        page.content_blocks = vec![
+            image(&url("/broken.png"), &[&url("/gone.png"), &url("/ok.png")]),
+            image(&url("/flaky.png"), &[]),
+            image(&url("/missing.png"), &[]),
+        ];
+        let mut pages = [page];
+        let dir = std::env::temp_dir().join(format!("dump-it-fallback-{}", std::process::id()));
+        let options = ImageOptions {
+            concurrency: 2,
+            retries: 1,
+            ..ImageOptions::default()
+        };
+
+        let downloads = download_all(
+            &Client::new(),
+            image_jobs(&pages, false),
+            dir.to_str().unwrap(),
+            &options,
+        )
+        .await;
+        assert_eq!(
+            downloads.downloaded[&url("/broken.png")].url,
+            url("/ok.png")
+        );
+        assert!(downloads.downloaded.contains_key(&url("/flaky.png")));
+        let [failed] = &downloads.failed[..] else {
+            panic!("one failed image expected");
+        };
+        assert_eq!(failed.url, url("/missing.png"));
+        assert_eq!(failed.page_url, url("/"));
+        assert_eq!(failed.reason, "http_error");
+
+        attach_downloads(&mut pages, &downloads, &options);
+        let originals: Vec<&str> = local_paths(&pages[0]).iter().map(|(u, _)| *u).collect();
+        assert_eq!(originals, [url("/ok.png"), url("/flaky.png")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn truncated_png_fails_decode_check() {
+        let dir = std::env::temp_dir().join(format!("dump-it-images-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut png = Vec::new();
+        image::RgbImage::new(64, 64)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let full = dir.join("full.png");
+        let cut = dir.join("cut.png");
+        std::fs::write(&full, &png).unwrap();
+        std::fs::write(&cut, &png[..png.len() / 2]).unwrap();
+        assert!(image_decodes(&full));
+        assert!(!image_decodes(&cut));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn only_untyped_svg_documents_are_sniffed() {
+        assert!(looks_like_svg(
+            b"  <svg xmlns=\"http://www.w3.org/2000/svg\"/>"
+        ));
+        assert!(looks_like_svg(
+            b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<svg viewBox=\"0 0 1 1\"/>"
+        ));
+        assert!(!looks_like_svg(b"<html><body><svg/></body></html>"));
+        assert!(!looks_like_svg(b"<?xml version=\"1.0\"?><rss/>"));
+        assert!(!looks_like_svg(b"\x89PNG\r\n\x1a\n<svg"));
+
+        assert!(generic_content_type(None));
+        assert!(generic_content_type(Some("application/octet-stream")));
+        assert!(generic_content_type(Some("Text/Plain; charset=utf-8")));
+        assert!(!generic_content_type(Some("text/html")));
+    }
+}
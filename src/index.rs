@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use anyhow::Context;
+use tantivy::collector::TopDocs;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, Index, IndexWriter, TantivyDocument};
+
+use crate::model::PageData;
+use crate::search::SearchHit;
+
+/// Indexing heap shared by tantivy's writer threads. Its minimum is 15 MB
+/// per thread; 64 MB keeps a few threads busy without competing with Chrome.
+const WRITER_HEAP_BYTES: usize = 64 * 1024 * 1024;
+
+/// Title matches outrank body matches; the description sits in between.
+const TITLE_BOOST: f32 = 3.0;
+const DESCRIPTION_BOOST: f32 = 1.5;
+
+/// Fields of the `--index` schema. Everything is stored so hits can be
+/// printed (and snippets highlighted) without going back to scraped.json.
+pub(crate) struct IndexFields {
+    /// Exact-match, not tokenized.
+    pub url: Field,
+    pub title: Field,
+    pub description: Field,
+    /// `PageData.plain_text`.
+    pub body: Field,
+}
+
+impl IndexFields {
+    pub fn from_schema(schema: &Schema) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: schema.get_field("url")?,
+            title: schema.get_field("title")?,
+            description: schema.get_field("description")?,
+            body: schema.get_field("body")?,
+        })
+    }
+}
+
+fn schema() -> Schema {
+    let mut builder = Schema::builder();
+    builder.add_text_field("url", STRING | STORED);
+    builder.add_text_field("title", TEXT | STORED);
+    builder.add_text_field("description", TEXT | STORED);
+    builder.add_text_field("body", TEXT | STORED);
+    builder.build()
+}
+
+/// Build a tantivy index over `pages` in `dir`, replacing one left by a
+/// previous run. Refuses a non-empty directory that isn't an index, so a
+/// mistyped `--index .` can't wipe unrelated files. Returns the number
+/// of documents indexed.
+pub(crate) fn build_index(dir: &Path, pages: &[PageData]) -> anyhow::Result<usize> {
+    if dir.exists() {
+        let is_empty = std::fs::read_dir(dir)?.next().is_none();
+        if !is_empty && !dir.join("meta.json").exists() {
+            anyhow::bail!(
+                "--index {} is a non-empty directory that isn't a search index",
+                dir.display()
+            );
+        }
+        std::fs::remove_dir_all(dir)
+            .with_context(|| format!("failed to clear old index at {}", dir.display()))?;
+    }
+    std::fs::create_dir_all(dir)?;
+    let schema = schema();
+    let fields = IndexFields::from_schema(&schema)?;
+    let index = Index::create_in_dir(dir, schema)
+        .with_context(|| format!("failed to create index at {}", dir.display()))?;
+    let mut writer: IndexWriter = index.writer(WRITER_HEAP_BYTES)?;
+    for page in pages {
+        writer.add_document(doc!(
+            fields.url => page.url.clone(),
+            fields.title => page.title.clone(),
+            fields.description => page.meta_description.clone(),
+            fields.body => page.plain_text.clone(),
+        ))?;
+    }
+    writer.commit()?;
+    Ok(pages.len())
+}
+
+/// Whether `dir` holds a tantivy index (as written by `build_index`).
+pub(crate) fn is_index(dir: &Path) -> bool {
+    dir.join("meta.json").is_file()
+}
+
+/// Top `limit` pages for `query` in tantivy query syntax (`"a phrase"`,
+/// `title:pricing`, `-excluded`). Parsing is lenient: a stray quote or
+/// colon degrades to plain terms instead of failing.
+pub(crate) fn search_index(
+    dir: &Path,
+    query: &str,
+    limit: usize,
+    snippet_chars: usize,
+) -> anyhow::Result<Vec<SearchHit>> {
+    let index = Index::open_in_dir(dir)
+        .with_context(|| format!("failed to open index at {}", dir.display()))?;
+    let fields = IndexFields::from_schema(&index.schema())?;
+    let searcher = index.reader()?.searcher();
+    let mut parser =
+        QueryParser::for_index(&index, vec![fields.title, fields.description, fields.body]);
+    parser.set_field_boost(fields.title, TITLE_BOOST);
+    parser.set_field_boost(fields.description, DESCRIPTION_BOOST);
+    let (query, _) = parser.parse_query_lenient(query);
+    let top = searcher.search(&query, &TopDocs::with_limit(limit))?;
+    let mut snippets = SnippetGenerator::create(&searcher, &*query, fields.body)?;
+    snippets.set_max_num_chars(snippet_chars);
+
+    let mut hits = Vec::with_capacity(top.len());
+    for (score, address) in top {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        let stored = |field: Field| {
+            doc.get_first(field)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let snippet = snippets.snippet_from_doc(&doc);
+        hits.push(SearchHit {
+            url: stored(fields.url),
+            title: stored(fields.title),
+            score,
+            snippet: snippet.fragment().to_string(),
+            highlights: snippet
+                .highlighted()
+                .iter()
+                .map(|h| (h.start, h.end))
+                .collect(),
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_pages_rank_title_matches_first() {
+        let dir = std::env::temp_dir().join(format!("dump-it-index-{}", std::process::id()));
+        let page = |url: &str, title: &str, text: &str| {
+            let mut page = crate::model::test_page(url);
+            page.title = title.to_string();
+            page.plain_text = text.to_string();
+            page
+        };
+        let pages = [
+            page("https://x.com/", "Home", "See our pricing for teams."),
+            page("https://x.com/pricing", "Pricing", "Plans for every team."),
+            page("https://x.com/about", "About", "Founded in 2010."),
+        ];
+        assert_eq!(build_index(&dir, &pages).unwrap(), 3);
+        assert!(is_index(&dir));
+        // Built again over its own output, the index is replaced.
+        build_index(&dir, &pages).unwrap();
+
+        let hits = search_index(&dir, "pricing", 10, 100).unwrap();
+        let urls: Vec<&str> = hits.iter().map(|h| h.url.as_str()).collect();
+        assert_eq!(urls, ["https://x.com/pricing", "https://x.com/"]);
+        let snippet = &hits[1];
+        let (start, end) = snippet.highlights[0];
+        assert_eq!(&snippet.snippet[start..end], "pricing");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "keep me").unwrap();
+        assert!(build_index(&dir, &pages).is_err());
+        assert!(dir.join("notes.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,500 @@
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+
+use anyhow::Context;
+use serde_json::Value;
+use url::Url;
+
+use crate::cli::{CrawlMode, RunArgs};
+use crate::jsonpath::JsonPath;
+use crate::model::{Generator, JsonApiDump, JsonRecord, SkippedPage};
+use crate::scrape::{HttpOptions, PageOptions, Scraper};
+
+/// `--json-field` parser: `NAME=JSONPATH`.
+pub(crate) fn parse_field(s: &str) -> Result<(String, JsonPath), String> {
+    let (name, path) = s
+        .split_once('=')
+        .filter(|(name, _)| !name.trim().is_empty())
+        .ok_or_else(|| format!("`{s}` is not like \"title=$.attributes.title\""))?;
+    Ok((name.trim().to_string(), crate::jsonpath::parse(path)?))
+}
+
+/// One API request: a page URL, and under `--mode graphql` the variables
+/// the query is POSTed with.
+#[derive(Clone, Debug, PartialEq)]
+struct ApiRequest {
+    url: Url,
+    variables: Option<serde_json::Map<String, Value>>,
+}
+
+impl std::fmt::Display for ApiRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)?;
+        if let Some(variables) = &self.variables {
+            write!(f, " {}", Value::Object(variables.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+/// How responses become records and where the next page is (`--json-*`).
+struct JsonApiOptions {
+    records: JsonPath,
+    fields: Vec<(String, JsonPath)>,
+    next: Option<JsonPath>,
+    cursor: Option<JsonPath>,
+    cursor_param: String,
+}
+
+/// A matched value as a field: one value as is, several as an array.
+fn field_value(matches: Vec<&Value>) -> Option<Value> {
+    match matches.as_slice() {
+        [] => None,
+        [one] => Some((*one).clone()),
+        many => Some(Value::Array(many.iter().map(|v| (*v).clone()).collect())),
+    }
+}
+
+/// A string, number or boolean as text; `None` for null, empty strings
+/// and containers.
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl JsonApiOptions {
+    fn from_args(args: &RunArgs) -> Self {
+        Self {
+            records: args
+                .json_records
+                .clone()
+                .unwrap_or_else(|| crate::jsonpath::parse("$").expect("`$` is a JSONPath")),
+            fields: args.json_fields.clone(),
+            next: args.json_next.clone(),
+            cursor: args.json_cursor.clone(),
+            cursor_param: args.json_cursor_param.clone(),
+        }
+    }
+
+    /// The records of one response from `page_url`.
+    fn records(&self, page_url: &Url, body: &Value) -> Vec<JsonRecord> {
+        let items = self.records.select(body).into_iter().flat_map(|m| match m {
+            Value::Array(items) => items.iter().collect(),
+            item => vec![item],
+        });
+        items
+            .map(|item| {
+                let mut record = JsonRecord {
+                    source_url: page_url.to_string(),
+                    url: None,
+                    title: None,
+                    fields: BTreeMap::new(),
+                    data: self.fields.is_empty().then(|| item.clone()),
+                };
+                for (name, path) in &self.fields {
+                    let Some(value) = field_value(path.select(item)) else {
+                        continue;
+                    };
+                    match name.as_str() {
+                        "url" => {
+                            record.url = scalar_text(&value)
+                                .and_then(|u| page_url.join(&u).ok())
+                                .map(String::from)
+                        }
+                        "title" => record.title = scalar_text(&value),
+                        _ => {
+                            record.fields.insert(name.clone(), value);
+                        }
+                    }
+                }
+                record
+            })
+            .collect()
+    }
+
+    /// The request after `page`, a page of the crawl from `start`:
+    /// `--json-next` resolved against the page URL, or `start` with the
+    /// `--json-cursor` value as `--json-cursor-param`, a query variable
+    /// under GraphQL and a URL parameter otherwise. `None` at the end.
+    fn next_page(&self, start: &ApiRequest, page: &ApiRequest, body: &Value) -> Option<ApiRequest> {
+        if let Some(next) = &self.next {
+            let next = scalar_text(next.first(body)?)?;
+            return Some(ApiRequest {
+                url: page.url.join(&next).ok()?,
+                variables: page.variables.clone(),
+            });
+        }
+        let cursor = scalar_text(self.cursor.as_ref()?.first(body)?)?;
+        if let Some(variables) = &start.variables {
+            let mut variables = variables.clone();
+            variables.insert(self.cursor_param.clone(), Value::String(cursor));
+            return Some(ApiRequest {
+                url: start.url.clone(),
+                variables: Some(variables),
+            });
+        }
+        let mut url = start.url.clone();
+        let pairs: Vec<(String, String)> = start
+            .url
+            .query_pairs()
+            .filter(|(name, _)| *name != self.cursor_param)
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair(&self.cursor_param, &cursor);
+        Some(ApiRequest {
+            url,
+            variables: None,
+        })
+    }
+
+    /// Fetch every start request and the pages after it, `max_requests`
+    /// in all. Pagination stops at the last page, a page without records
+    /// or one already fetched.
+    async fn crawl<F, Fut>(
+        &self,
+        starts: &[ApiRequest],
+        max_requests: usize,
+        mut fetch: F,
+    ) -> JsonApiDump
+    where
+        F: FnMut(ApiRequest) -> Fut,
+        Fut: Future<Output = Result<Value, &'static str>>,
+    {
+        let mut dump = JsonApiDump {
+            generator: Generator::current(),
+            sources: starts.iter().map(ApiRequest::to_string).collect(),
+            requests: Vec::new(),
+            records: Vec::new(),
+            skipped_pages: Vec::new(),
+        };
+        let mut fetched = HashSet::new();
+        'starts: for start in starts {
+            let mut page = Some(start.clone());
+            while let Some(request) = page.take() {
+                if dump.requests.len() >= max_requests {
+                    progress!("🛑 --max-pages {max_requests} reached: not following {request}");
+                    break 'starts;
+                }
+                if !fetched.insert(request.to_string()) {
+                    tracing::warn!("Pagination of {start} leads back to {request}; stopping there");
+                    break;
+                }
+                dump.requests.push(request.to_string());
+                match fetch(request.clone()).await {
+                    Ok(body) => {
+                        let records = self.records(&request.url, &body);
+                        tracing::info!("{request}: {} records", records.len());
+                        if !records.is_empty() {
+                            page = self.next_page(start, &request, &body);
+                        }
+                        dump.records.extend(records);
+                    }
+                    Err(reason) => dump.skipped_pages.push(SkippedPage {
+                        url: request.to_string(),
+                        reason: reason.to_string(),
+                        challenge: None,
+                    }),
+                }
+            }
+        }
+        dump
+    }
+}
+
+/// Check a GraphQL response for `errors`: logged, and failing the page
+/// only when they come without `data`.
+fn graphql_errors(request: &ApiRequest, response: &Value) -> Result<(), &'static str> {
+    let messages: Vec<&str> = response
+        .get("errors")
+        .and_then(Value::as_array)
+        .map(|errors| {
+            errors
+                .iter()
+                .map(|e| {
+                    e.get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("(no message)")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if messages.is_empty() {
+        return Ok(());
+    }
+    if response.get("data").is_some_and(|data| !data.is_null()) {
+        tracing::warn!(
+            "GraphQL errors for {request} (partial data kept): {}",
+            messages.join("; ")
+        );
+        Ok(())
+    } else {
+        tracing::error!("GraphQL errors for {request}: {}", messages.join("; "));
+        Err("graphql_error")
+    }
+}
+
+/// `--mode json-api` / `--mode graphql`: fetch the endpoints of `source`
+/// (or `url`) page by page and write their records to `--output` instead
+/// of a page bundle.
+pub(crate) async fn run_json_api(
+    url: &str,
+    args: &RunArgs,
+    source: crate::UrlSource,
+) -> anyhow::Result<()> {
+    let urls = match source {
+        crate::UrlSource::List(urls) => urls,
+        crate::UrlSource::Discover => vec![url.to_string()],
+    };
+    let graphql = if args.mode == CrawlMode::Graphql {
+        let path = args
+            .graphql_query
+            .as_ref()
+            .context("--mode graphql needs --graphql-query FILE")?;
+        if args.json_next.is_some() {
+            anyhow::bail!("--mode graphql pages with --json-cursor, not --json-next");
+        }
+        let query = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read --graphql-query {}", path.display()))?;
+        Some(query)
+    } else {
+        None
+    };
+    let variables = match (&graphql, args.graphql_variables.as_deref()) {
+        (None, _) => None,
+        (Some(_), None) => Some(serde_json::Map::new()),
+        (Some(_), Some(json)) => {
+            match serde_json::from_str(json).context("--graphql-variables is not JSON")? {
+                Value::Object(variables) => Some(variables),
+                _ => anyhow::bail!("--graphql-variables must be a JSON object"),
+            }
+        }
+    };
+    let starts = urls
+        .iter()
+        .map(|u| {
+            Ok(ApiRequest {
+                url: Url::parse(u).with_context(|| format!("invalid API URL {u}"))?,
+                variables: variables.clone(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    crate::start_transport(url, args)?;
+    if graphql.is_some() {
+        progress!("🧾 GraphQL mode: {} endpoint(s)", starts.len());
+    } else {
+        progress!("🧾 JSON API mode: {} endpoint(s)", starts.len());
+    }
+
+    // API responses have no challenge page for Chrome to solve.
+    let mut http_options = HttpOptions::from_args(args)?;
+    http_options.solve_challenges = false;
+    crate::auth::scope_headers(url, args);
+    let scraper = Scraper::new(
+        1,
+        args.timeout,
+        args.js_wait,
+        None,
+        false,
+        true,
+        args.delay,
+        0,
+        args.user_agent.as_deref(),
+        &http_options,
+        &PageOptions::from_args(args)?,
+    )?;
+    let options = JsonApiOptions::from_args(args);
+    let dump = options
+        .crawl(&starts, args.max_pages, |request| {
+            let (scraper, query) = (&scraper, graphql.as_deref());
+            async move {
+                let Some(query) = query else {
+                    return scraper.fetch_json(request.url.as_str(), None).await;
+                };
+                let body = serde_json::json!({"query": query, "variables": request.variables});
+                let response = scraper
+                    .fetch_json(request.url.as_str(), Some(&body))
+                    .await?;
+                graphql_errors(&request, &response)?;
+                Ok(response)
+            }
+        })
+        .await;
+    progress!(
+        "✅ {} records from {} API requests ({} failed)",
+        dump.records.len(),
+        dump.requests.len(),
+        dump.skipped_pages.len()
+    );
+
+    let json = serde_json::to_string_pretty(&dump)?;
+    if args.output == "-" {
+        println!("{json}");
+        progress!("💾 Output written to stdout");
+    } else {
+        let path = std::path::Path::new(&args.output);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, json)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        progress!("💾 Output saved to: {}", args.output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn api_pages_become_records() {
+        let args = crate::cli::Args::parse_from([
+            "dump-it",
+            "--url",
+            "https://cms.test/api/posts?limit=2",
+            "--mode",
+            "json-api",
+            "--json-records",
+            "$.data",
+            "--json-field",
+            "title=$.attributes.title",
+            "--json-field",
+            "url=$.attributes.slug",
+            "--json-field",
+            "tags=$.tags[*].name",
+            "--json-cursor",
+            "$.meta.next",
+        ]);
+        let options = JsonApiOptions::from_args(&args.run);
+        let pages = [
+            (
+                "https://cms.test/api/posts?limit=2",
+                r#"{"data": [{"attributes": {"title": "One", "slug": "/one"},
+                             "tags": [{"name": "a"}, {"name": "b"}]},
+                            {"attributes": {"title": "Two", "slug": "two"}}],
+                   "meta": {"next": "c2"}}"#,
+            ),
+            (
+                "https://cms.test/api/posts?limit=2&cursor=c2",
+                r#"{"data": [{"attributes": {"title": "Three"}, "tags": [{"name": "c"}]}],
+                   "meta": {"next": "c3"}}"#,
+            ),
+            (
+                "https://cms.test/api/posts?limit=2&cursor=c3",
+                r#"{"data": [], "meta": {"next": "c3"}}"#,
+            ),
+        ];
+        let fetch = |request: ApiRequest| {
+            let body = pages
+                .iter()
+                .find(|(page, _)| *page == request.url.as_str())
+                .map(|(_, body)| serde_json::from_str::<Value>(body).unwrap());
+            async move { body.ok_or("http_error") }
+        };
+        let start = ApiRequest {
+            url: Url::parse("https://cms.test/api/posts?limit=2").unwrap(),
+            variables: None,
+        };
+        let dump = options.crawl(std::slice::from_ref(&start), 10, fetch).await;
+
+        assert_eq!(dump.requests.len(), 3);
+        assert!(dump.skipped_pages.is_empty());
+        let titles: Vec<_> = dump
+            .records
+            .iter()
+            .filter_map(|r| r.title.as_deref())
+            .collect();
+        assert_eq!(titles, ["One", "Two", "Three"]);
+        assert_eq!(dump.records[0].url.as_deref(), Some("https://cms.test/one"));
+        assert_eq!(
+            dump.records[1].url.as_deref(),
+            Some("https://cms.test/api/two")
+        );
+        assert_eq!(
+            dump.records[0].fields["tags"],
+            serde_json::json!(["a", "b"])
+        );
+        assert_eq!(dump.records[2].fields["tags"], "c");
+        assert_eq!(
+            dump.records[2].source_url,
+            "https://cms.test/api/posts?limit=2&cursor=c2"
+        );
+        assert!(dump.records[0].data.is_none());
+
+        // --max-pages caps the requests.
+        let dump = options.crawl(&[start], 1, fetch).await;
+        assert_eq!(dump.requests.len(), 1);
+        assert_eq!(dump.records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn graphql_cursors_go_in_variables() {
+        let args = crate::cli::Args::parse_from([
+            "dump-it",
+            "--url",
+            "https://shop.test/graphql",
+            "--mode",
+            "graphql",
+            "--graphql-query",
+            "products.graphql",
+            "--json-records",
+            "$.data.products.nodes",
+            "--json-field",
+            "title=$.name",
+            "--json-cursor",
+            "$.data.products.pageInfo.endCursor",
+            "--json-cursor-param",
+            "after",
+        ]);
+        let options = JsonApiOptions::from_args(&args.run);
+        let fetch = |request: ApiRequest| {
+            let after = request.variables.as_ref().unwrap().get("after").cloned();
+            let body = match after.as_ref().and_then(Value::as_str) {
+                None => serde_json::json!({"data": {"products": {
+                    "nodes": [{"name": "Mug"}, {"name": "Cap"}],
+                    "pageInfo": {"endCursor": "Y3Vyc29y"}}}}),
+                Some("Y3Vyc29y") => serde_json::json!({"data": null, "errors": [
+                    {"message": "Throttled"}]}),
+                Some(other) => panic!("unexpected cursor {other}"),
+            };
+            let result = graphql_errors(&request, &body).map(|()| body);
+            async move { result }
+        };
+        let mut variables = serde_json::Map::new();
+        variables.insert("first".into(), 2.into());
+        let start = ApiRequest {
+            url: Url::parse("https://shop.test/graphql").unwrap(),
+            variables: Some(variables),
+        };
+        let dump = options.crawl(&[start], 10, fetch).await;
+
+        assert_eq!(dump.records.len(), 2);
+        assert_eq!(dump.records[1].title.as_deref(), Some("Cap"));
+        assert_eq!(
+            dump.requests,
+            [
+                r#"https://shop.test/graphql {"first":2}"#,
+                r#"https://shop.test/graphql {"after":"Y3Vyc29y","first":2}"#,
+            ]
+        );
+        assert_eq!(dump.skipped_pages.len(), 1);
+        assert_eq!(dump.skipped_pages[0].reason, "graphql_error");
+
+        // Errors alongside data keep the page.
+        let partial = serde_json::json!({"data": {"products": null},
+                                         "errors": [{"message": "partial"}]});
+        let request = ApiRequest {
+            url: Url::parse("https://shop.test/graphql").unwrap(),
+            variables: None,
+        };
+        assert_eq!(graphql_errors(&request, &partial), Ok(()));
+    }
+}
@@ -0,0 +1,169 @@
+use serde_json::Value;
+
+/// A JSONPath expression, the subset API dumps need: `$`, `.key`,
+/// `['key']`, `[N]` (negative from the end), `[*]` / `.*` and `..`
+/// (recursive descent), e.g. `$.data[*].attributes.title` or `$..id`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct JsonPath {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    /// The node and everything below it, for `..`.
+    Descendants,
+}
+
+/// Parse a JSONPath (clap value parser).
+pub(crate) fn parse(s: &str) -> Result<JsonPath, String> {
+    let err = |what: &str| format!("`{s}` is not a JSONPath: {what}");
+    let rest = s
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| err("it starts with $"))?;
+    let chars: Vec<char> = rest.chars().collect();
+    let mut steps = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                i += 1;
+                if chars.get(i) == Some(&'.') {
+                    steps.push(Step::Descendants);
+                    i += 1;
+                    if chars.get(i) == Some(&'[') {
+                        continue;
+                    }
+                }
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                match name.as_str() {
+                    "" => return Err(err("a `.` needs a key after it")),
+                    "*" => steps.push(Step::Wildcard),
+                    _ => steps.push(Step::Key(name)),
+                }
+            }
+            '[' => {
+                let close = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|at| i + at)
+                    .ok_or_else(|| err("unclosed ["))?;
+                let inner: String = chars[i + 1..close].iter().collect();
+                let inner = inner.trim();
+                let quoted = ['\'', '"'].into_iter().find_map(|q| {
+                    inner
+                        .strip_prefix(q)
+                        .and_then(|k| k.strip_suffix(q))
+                        .filter(|_| inner.len() >= 2)
+                });
+                if let Some(key) = quoted {
+                    steps.push(Step::Key(key.to_string()));
+                } else if inner == "*" {
+                    steps.push(Step::Wildcard);
+                } else {
+                    let index = inner
+                        .parse()
+                        .map_err(|_| err(&format!("[{inner}] is not an index, * or 'key'")))?;
+                    steps.push(Step::Index(index));
+                }
+                i = close + 1;
+            }
+            c => return Err(err(&format!("unexpected `{c}`"))),
+        }
+    }
+    Ok(JsonPath { steps })
+}
+
+fn descendants<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    match value {
+        Value::Array(items) => items.iter().for_each(|v| descendants(v, out)),
+        Value::Object(map) => map.values().for_each(|v| descendants(v, out)),
+        _ => {}
+    }
+}
+
+impl JsonPath {
+    /// The values the path matches in `root`, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut nodes = vec![root];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for node in nodes {
+                match step {
+                    Step::Key(key) => next.extend(node.get(key)),
+                    Step::Index(index) => {
+                        let items = node.as_array().map_or(&[][..], Vec::as_slice);
+                        let at = if *index < 0 {
+                            items.len().checked_sub(index.unsigned_abs() as usize)
+                        } else {
+                            Some(*index as usize)
+                        };
+                        next.extend(at.and_then(|at| items.get(at)));
+                    }
+                    Step::Wildcard => match node {
+                        Value::Array(items) => next.extend(items),
+                        Value::Object(map) => next.extend(map.values()),
+                        _ => {}
+                    },
+                    Step::Descendants => descendants(node, &mut next),
+                }
+            }
+            nodes = next;
+        }
+        nodes
+    }
+
+    /// The first value the path matches.
+    pub fn first<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        self.select(root).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_select_like_jsonpath() {
+        let doc = serde_json::json!({
+            "data": [
+                {"id": 1, "attributes": {"title": "First"}},
+                {"id": 2, "attributes": {"title": "Second"}, "tags": [{"id": 9}]}
+            ],
+            "links": {"next": "/api/posts?page=2"},
+            "meta": {"next-cursor": "abc"}
+        });
+        let titles = parse("$.data[*].attributes.title").unwrap().select(&doc);
+        assert_eq!(
+            titles,
+            [&serde_json::json!("First"), &serde_json::json!("Second")]
+        );
+        let next = parse("$.links.next").unwrap();
+        assert_eq!(next.first(&doc).unwrap(), "/api/posts?page=2");
+        assert_eq!(
+            parse("$['meta']['next-cursor']")
+                .unwrap()
+                .first(&doc)
+                .unwrap(),
+            "abc"
+        );
+        assert_eq!(parse("$.data[-1].id").unwrap().first(&doc).unwrap(), 2);
+        assert!(parse("$.data[5].id").unwrap().first(&doc).is_none());
+        assert_eq!(parse("$..id").unwrap().select(&doc).len(), 3);
+        assert_eq!(parse("$.data..[0].id").unwrap().first(&doc).unwrap(), 1);
+        assert_eq!(parse("$").unwrap().select(&doc), [&doc]);
+        assert_eq!(parse("$.meta.*").unwrap().select(&doc).len(), 1);
+        assert!(parse("data.id").is_err());
+        assert!(parse("$.data[").is_err());
+        assert!(parse("$.data[x]").is_err());
+        assert!(parse("$.").is_err());
+    }
+}
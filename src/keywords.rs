@@ -0,0 +1,140 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{Keyword, PageData};
+
+/// English function words plus the marketing filler that tops every page
+/// ("learn more", "click here"). Non-English sites still get usable
+/// results: IDF pushes words that appear on every page to the bottom.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as",
+    "at", "be", "because", "been", "before", "being", "below", "between", "both", "but", "by",
+    "can", "could", "did", "do", "does", "doing", "down", "during", "each", "few", "for", "from",
+    "further", "get", "had", "has", "have", "having", "he", "her", "here", "hers", "him", "his",
+    "how", "i", "if", "in", "into", "is", "it", "its", "just", "more", "most", "my", "no", "nor",
+    "not", "now", "of", "off", "on", "once", "only", "or", "other", "our", "ours", "out", "over",
+    "own", "same", "she", "should", "so", "some", "such", "than", "that", "the", "their", "theirs",
+    "them", "then", "there", "these", "they", "this", "those", "through", "to", "too", "under",
+    "until", "up", "us", "very", "was", "we", "were", "what", "when", "where", "which", "while",
+    "who", "whom", "why", "will", "with", "would", "you", "your", "yours", "click", "learn",
+    "read", "see", "new", "use", "one", "like", "make", "need", "want", "many", "much", "every",
+];
+
+/// Minimum characters for a single-word term.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Two-word phrases must repeat on a page to count, otherwise every
+/// adjacent pair of content words would be a candidate.
+const MIN_PHRASE_COUNT: usize = 2;
+
+/// Term → count for one page's plain text: single words plus two-word
+/// phrases of adjacent content words. Phrases never span a line (block)
+/// break or a stopword.
+fn term_counts(text: &str, stopwords: &HashSet<&str>) -> HashMap<String, usize> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut phrases: HashMap<String, usize> = HashMap::new();
+    for line in text.lines() {
+        let tokens: Vec<Option<String>> = line
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                let w = w.to_lowercase();
+                let keep = w.chars().count() >= MIN_TERM_CHARS
+                    && !w.chars().all(|c| c.is_numeric())
+                    && !stopwords.contains(w.as_str());
+                keep.then_some(w)
+            })
+            .collect();
+        for t in tokens.iter().flatten() {
+            *counts.entry(t.clone()).or_default() += 1;
+        }
+        for pair in tokens.windows(2) {
+            if let [Some(a), Some(b)] = pair {
+                *phrases.entry(format!("{a} {b}")).or_default() += 1;
+            }
+        }
+    }
+    counts.extend(phrases.into_iter().filter(|(_, c)| *c >= MIN_PHRASE_COUNT));
+    counts
+}
+
+fn round_score(s: f64) -> f64 {
+    (s * 10_000.0).round() / 10_000.0
+}
+
+/// TF-IDF over every page's `plain_text`. Fills `PageData.keywords` with
+/// the top `top_n` terms per page and returns the site-wide top `top_n`,
+/// ranked by the sum of per-page scores (terms that matter on many pages).
+/// TF is normalised by the page's word count; IDF is the smoothed
+/// `ln((1 + N) / (1 + df)) + 1`, so a one-page run degrades to plain TF.
+pub(crate) fn extract_keywords(pages: &mut [PageData], top_n: usize) -> Vec<Keyword> {
+    let stopwords: HashSet<&str> = STOPWORDS.iter().copied().collect();
+    let per_page: Vec<HashMap<String, usize>> = pages
+        .iter()
+        .map(|p| term_counts(&p.plain_text, &stopwords))
+        .collect();
+
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for counts in &per_page {
+        for term in counts.keys() {
+            *df.entry(term.as_str()).or_default() += 1;
+        }
+    }
+    let n = pages.len() as f64;
+
+    let mut site: HashMap<&str, f64> = HashMap::new();
+    for (page, counts) in pages.iter_mut().zip(&per_page) {
+        let words = page.plain_text.split_whitespace().count().max(1) as f64;
+        let mut scored: Vec<(&str, f64)> = counts
+            .iter()
+            .map(|(term, c)| {
+                let idf = ((1.0 + n) / (1.0 + df[term.as_str()] as f64)).ln() + 1.0;
+                (term.as_str(), *c as f64 / words * idf)
+            })
+            .collect();
+        for (term, score) in &scored {
+            *site.entry(*term).or_default() += score;
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        page.keywords = scored
+            .into_iter()
+            .take(top_n)
+            .map(|(term, score)| Keyword {
+                term: term.to_string(),
+                score: round_score(score),
+            })
+            .collect();
+    }
+
+    let mut site: Vec<(&str, f64)> = site.into_iter().collect();
+    site.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    site.into_iter()
+        .take(top_n)
+        .map(|(term, score)| Keyword {
+            term: term.to_string(),
+            score: round_score(score),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn term_counts_skips_stopwords_and_keeps_repeated_phrases() {
+        let stop: HashSet<&str> = STOPWORDS.iter().copied().collect();
+        let counts = term_counts(
+            "The coffee grinder is great.\nOur coffee grinder ships in 2024\nof the beans",
+            &stop,
+        );
+        assert_eq!(counts.get("coffee"), Some(&2));
+        assert_eq!(counts.get("coffee grinder"), Some(&2));
+        assert_eq!(counts.get("beans"), Some(&1));
+        // Stopwords, short tokens and numbers are dropped.
+        assert!(!counts.contains_key("the"));
+        assert!(!counts.contains_key("is"));
+        assert!(!counts.contains_key("2024"));
+        // Single-occurrence phrase doesn't qualify.
+        assert!(!counts.contains_key("grinder ships"));
+    }
+}
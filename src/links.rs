@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use lol_html::{element, HtmlRewriter, Settings};
+use url::Url;
+
+use crate::model::PageLink;
+
+/// What link discovery needs from a page: its `<a href>`s and the robots
+/// meta tag that may forbid following them.
+pub(crate) struct LinkScan {
+    /// Resolved like [`crate::extract::extract_page_links`], without
+    /// anchor text.
+    pub links: Vec<PageLink>,
+    /// `<meta name="robots">` content, lower-cased.
+    pub meta_robots: Option<String>,
+}
+
+/// Scan `html` for links with lol_html's tokenizer instead of building a
+/// DOM: discovery reads two kinds of tags and nothing else, so the tree
+/// scraper would build for a large page is pure overhead here. The body is
+/// already fully buffered; this saves the DOM, not the download. Relative
+/// URLs resolve against `base` (`<base href>` is ignored, as it is for
+/// extraction), and `strict_host` decides which links are `internal`.
+pub(crate) fn scan_links(html: &str, base: &Url, strict_host: bool) -> LinkScan {
+    let mut hrefs: Vec<(String, Vec<String>)> = Vec::new();
+    let mut meta_robots: Option<String> = None;
+    {
+        let mut rewriter = HtmlRewriter::new(
+            Settings {
+                element_content_handlers: vec![
+                    element!("a[href]", |el| {
+                        let rel = el
+                            .get_attribute("rel")
+                            .map(|r| r.split_whitespace().map(str::to_ascii_lowercase).collect())
+                            .unwrap_or_default();
+                        hrefs.push((el.get_attribute("href").unwrap_or_default(), rel));
+                        Ok(())
+                    }),
+                    element!("meta[name]", |el| {
+                        if meta_robots.is_none()
+                            && el.get_attribute("name").as_deref() == Some("robots")
+                        {
+                            let content = el.get_attribute("content").unwrap_or_default();
+                            let content = content.trim();
+                            if !content.is_empty() {
+                                meta_robots = Some(content.to_lowercase());
+                            }
+                        }
+                        Ok(())
+                    }),
+                ],
+                ..Settings::new()
+            },
+            |_: &[u8]| {},
+        );
+        // Only fails on a memory limit, which isn't set; whatever was read up
+        // to a failure is still good.
+        if rewriter.write(html.as_bytes()).is_ok() {
+            let _ = rewriter.end();
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    for (href, rel) in hrefs {
+        let href = href.trim();
+        if href.starts_with("javascript:")
+            || href.starts_with("mailto:")
+            || href.starts_with("tel:")
+            || href.starts_with('#')
+        {
+            continue;
+        }
+        let Ok(mut abs) = base.join(href) else {
+            continue;
+        };
+        if !matches!(abs.scheme(), "http" | "https") && abs.scheme() != base.scheme() {
+            continue;
+        }
+        abs.set_fragment(None);
+        let url = abs.to_string();
+        if !seen.insert(url.clone()) {
+            continue;
+        }
+        links.push(PageLink {
+            internal: crate::util::same_site(&abs, base, strict_host),
+            url,
+            text: String::new(),
+            rel,
+        });
+    }
+    LinkScan { links, meta_robots }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_matches_dom_link_extraction() {
+        let html = r##"<html><head><meta name="robots" content=" NoIndex, Follow ">
+            </head><body><nav><a href="/about#team">About</a> <a href="#top">Top</a>
+            <a href="mailto:x@y.cz">Mail</a></nav>
+            <main><p>See <a href="docs/start">docs</a> and
+            <a href="https://other.org/" rel="nofollow UGC">this</a>.</p>
+            <a href="/about">About again</a></main></body></html>"##;
+        let base = Url::parse("https://x.com/guide/").unwrap();
+        let scan = scan_links(html, &base, false);
+        assert_eq!(scan.meta_robots.as_deref(), Some("noindex, follow"));
+        let urls: Vec<&str> = scan.links.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://x.com/about",
+                "https://x.com/guide/docs/start",
+                "https://other.org/"
+            ]
+        );
+        assert!(scan.links[2].is_unendorsed());
+        assert!(!scan.links[2].internal);
+
+        let doc = scraper::Html::parse_document(html);
+        let mut dom: Vec<String> = crate::extract::extract_page_links(&doc, &base, false)
+            .into_iter()
+            .map(|l| l.url)
+            .collect();
+        let mut seen = HashSet::new();
+        dom.retain(|url| seen.insert(url.clone()));
+        assert_eq!(dom, urls);
+    }
+}
@@ -0,0 +1,264 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use reqwest::{Response, ResponseBuilderExt};
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+/// Folders `file://` requests may read from: those of the local targets
+/// the run was given. Empty (every `file://` URL answers `404`) unless
+/// the target is local, so a remote page can't point the scraper at the
+/// disk.
+static ROOTS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Largest local file served; anything bigger answers `413`.
+const MAX_FILE: u64 = 100 * 1024 * 1024;
+
+/// Let `file://` requests read under local target `target`: the folder
+/// itself, or a file's parent folder. Anything but a `file://` URL of an
+/// existing path is ignored.
+pub(crate) fn allow(target: &str) {
+    let Some(path) = Url::parse(target)
+        .ok()
+        .filter(|u| u.scheme() == "file")
+        .and_then(|u| u.to_file_path().ok())
+        .and_then(|p| std::fs::canonicalize(p).ok())
+    else {
+        return;
+    };
+    let root = if path.is_dir() {
+        path
+    } else {
+        match path.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => return,
+        }
+    };
+    let mut roots = ROOTS.lock().unwrap();
+    if !roots.contains(&root) {
+        roots.push(root);
+    }
+}
+
+/// Whether a URL found during discovery (sitemap `<loc>`, URL list,
+/// robots.txt) may be scraped: web URLs always, `file:` URLs only when
+/// the crawl target is itself local — a web site's sitemap can't send the
+/// crawl to the disk.
+pub(crate) fn discoverable(url: &str, local_target: bool) -> bool {
+    match Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => u.has_host(),
+        Ok(u) => local_target && u.scheme() == "file",
+        Err(_) => false,
+    }
+}
+
+/// Whether Chrome may open `url`: a web URL, or a `file://` URL under
+/// the local targets' folders. Chrome reads files itself, so this is its
+/// counterpart of the check in [`file_response`].
+pub(crate) fn navigable(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => u.has_host(),
+        Ok(u) if u.scheme() == "file" => u
+            .to_file_path()
+            .ok()
+            .and_then(|p| std::fs::canonicalize(p).ok())
+            .is_some_and(|p| ROOTS.lock().unwrap().iter().any(|r| p.starts_with(r))),
+        _ => false,
+    }
+}
+
+/// `file://` URL of a target given as a local path (or already as a
+/// `file://` URL); `None` for anything else. Directories get a trailing
+/// slash so relative links resolve inside them.
+pub(crate) fn target_url(target: &str) -> Option<String> {
+    if target.starts_with("file:") {
+        return Url::parse(target).ok().map(String::from);
+    }
+    if target.contains("://") {
+        return None;
+    }
+    let path = std::fs::canonicalize(target).ok()?;
+    let url = if path.is_dir() {
+        Url::from_directory_path(&path)
+    } else {
+        Url::from_file_path(&path)
+    };
+    url.ok().map(String::from)
+}
+
+fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"))
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, out)?;
+        } else if is_html(&path) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Pages of a local target: every `.html` / `.htm` file under a directory
+/// (hidden ones skipped), sorted, or the file itself.
+pub(crate) fn html_files(target: &Url) -> anyhow::Result<Vec<String>> {
+    let root = target
+        .to_file_path()
+        .map_err(|_| anyhow::anyhow!("{target} is not a local path"))?;
+    if !root.is_dir() {
+        return Ok(vec![target.to_string()]);
+    }
+    let mut files = Vec::new();
+    walk(&root, &mut files).with_context(|| format!("failed to list {}", root.display()))?;
+    files.sort();
+    if files.is_empty() {
+        anyhow::bail!("no HTML files in {}", root.display());
+    }
+    Ok(files
+        .iter()
+        .filter_map(|p| Url::from_file_path(p).ok())
+        .map(String::from)
+        .collect())
+}
+
+/// Content-Type for a local file, by extension.
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The regular file `url` names under an allowed root (a directory
+/// stands for its `index.html`), resolved through symlinks and `..`.
+async fn allowed_file(url: &Url) -> Option<(PathBuf, u64)> {
+    let mut path = url.to_file_path().ok()?;
+    if tokio::fs::metadata(&path).await.ok()?.is_dir() {
+        path.push("index.html");
+    }
+    let path = tokio::fs::canonicalize(&path).await.ok()?;
+    let inside = ROOTS.lock().unwrap().iter().any(|r| path.starts_with(r));
+    let meta = tokio::fs::metadata(&path).await.ok()?;
+    (inside && meta.is_file()).then_some((path, meta.len()))
+}
+
+/// Answer a request for a `file://` URL from disk, as a web server would:
+/// `200` with the file (a directory serves its `index.html`), `413` past
+/// [`MAX_FILE`], `404` when there is none or it lies outside the local
+/// targets' folders.
+pub(crate) async fn file_response(url: &Url) -> Response {
+    let mut status = 404;
+    let mut body = Vec::new();
+    let mut content_type = None;
+    if let Some((path, len)) = allowed_file(url).await {
+        if len > MAX_FILE {
+            status = 413;
+        } else if let Ok(file) = tokio::fs::File::open(&path).await {
+            // `take` holds the read to the size checked, should the file grow.
+            if file.take(MAX_FILE).read_to_end(&mut body).await.is_ok() {
+                status = 200;
+                content_type = Some(self::content_type(&path));
+            } else {
+                body.clear();
+            }
+        }
+    }
+    let mut builder = http::Response::builder().url(url.clone()).status(status);
+    if let Some(content_type) = content_type {
+        builder = builder.header(reqwest::header::CONTENT_TYPE, content_type);
+    }
+    Response::from(builder.body(body).expect("static response parts"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn local_sites_are_listed_and_served_from_disk() {
+        let root = std::env::temp_dir().join(format!("dump-it-local-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("blog")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("index.html"), "<h1>Home</h1>").unwrap();
+        std::fs::write(root.join("blog/post.htm"), "<h1>Post</h1>").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        std::fs::write(root.join(".git/x.html"), "").unwrap();
+
+        let target = Url::parse(&target_url(root.to_str().unwrap()).unwrap()).unwrap();
+        assert!(target.as_str().starts_with("file://") && target.as_str().ends_with('/'));
+        let pages = html_files(&target).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].ends_with("/blog/post.htm") && pages[1].ends_with("/index.html"));
+
+        // Nothing is served before the target's folder is allowed.
+        assert_eq!(file_response(&target).await.status(), 404);
+        assert!(!navigable(target.as_str()));
+        allow(target.as_str());
+        assert!(navigable(target.as_str()));
+        assert!(navigable("https://example.com/"));
+        assert!(!navigable("file:///etc/passwd"));
+        assert!(!navigable("javascript:alert(1)"));
+        let home = file_response(&target).await;
+        assert_eq!(home.status(), 200);
+        assert_eq!(home.headers()["content-type"], "text/html");
+        let logo = file_response(&target.join("logo.png").unwrap()).await;
+        assert_eq!(logo.headers()["content-type"], "image/png");
+        assert_eq!(
+            file_response(&target.join("gone.html").unwrap())
+                .await
+                .status(),
+            404
+        );
+        // Outside the folder, through `..` or not, and devices stay unread.
+        let escape = Url::parse(&format!("{target}blog/../../")).unwrap();
+        assert_eq!(file_response(&escape).await.status(), 404);
+        let zero = Url::parse("file:///dev/zero").unwrap();
+        assert_eq!(file_response(&zero).await.status(), 404);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn web_sitemaps_cant_list_local_files() {
+        assert!(discoverable("https://example.com/a", false));
+        assert!(!discoverable("file:///etc/passwd", false));
+        assert!(!discoverable("data:text/html,hi", false));
+        assert!(!discoverable("https://", false));
+        assert!(discoverable("file:///srv/site/a.html", true));
+        assert!(!discoverable("ftp://example.com/a", true));
+    }
+}
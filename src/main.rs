@@ -1,38 +1,118 @@
+// The hand-written `output::build_schema_json` is one large `json!` literal.
+#![recursion_limit = "256"]
+
 use anyhow::Context;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use url::Url;
 
+/// Progress lines go to stdout — except under `dump-it sitemap`, whose
+/// stdout is the URL list itself, and `--output -`, whose stdout is the
+/// scraped JSON.
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for progress lines, see [`PROGRESS_TO_STDERR`]; while the
+/// `--tui` dashboard is open they go to its log pane instead. Defined
+/// ahead of the modules so they can use it too.
+macro_rules! progress {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        if !$crate::tui::log_progress(&line) {
+            if $crate::PROGRESS_TO_STDERR.load(::std::sync::atomic::Ordering::Relaxed) {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }};
+}
+
+mod auth;
+mod batch;
+mod boilerplate;
 mod brand;
+mod budget;
+mod cassette;
+mod challenge;
 mod chrome;
+mod chunks;
 mod cli;
+mod config;
 mod contact;
+mod dns;
+mod dump;
+mod elasticsearch;
+mod embed;
+mod eta;
+mod export;
 mod extract;
+mod feeds;
+mod formats;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod har;
+mod httpcache;
+mod images;
+mod index;
+mod jsonapi;
+mod jsonpath;
+mod keywords;
+mod links;
+mod local;
+mod meilisearch;
+mod memory;
+mod merge;
+mod microdata;
+mod mobile;
 mod model;
+mod oembed;
 mod output;
+mod paywall;
+mod product;
+#[cfg(any(feature = "kafka", feature = "nats"))]
+mod queue;
 mod scrape;
+mod search;
 mod selectors;
+mod serve;
+mod sink;
+mod sitemap;
+mod soft404;
+mod tui;
+mod urllist;
 mod util;
+mod videos;
 
+use crate::boilerplate::detect_boilerplate;
 use crate::brand::{
     aggregate_brand_palette, detect_webfont_urls, dominant_colors_from_image, download_asset,
     fetch_external_css, merge_webfont_families,
 };
+use crate::budget::{QueryVariants, SectionBudgets};
 use crate::chrome::capture_screenshot;
-use crate::cli::Args;
-use crate::extract::download_image;
-use crate::model::ScrapedData;
+use crate::cli::{Args, Command, RunArgs};
+use crate::export::ExportSettings;
+use crate::images::{attach_downloads, download_all, image_jobs, ImageOptions};
+use crate::model::{Generator, ScrapedData, SitemapMeta, SCHEMA_VERSION};
+use crate::oembed::enrich_embeds;
 use crate::output::{
-    aggregate_contact, build_asset_manifest, build_compact, build_hreflang_groups, build_index_md,
-    build_schema_json, build_site_data, detect_frameworks_from_html, detect_quality_flags,
-    detect_quality_warnings, detect_sections, detect_templates, page_to_markdown,
+    aggregate_contact, build_asset_manifest, build_compact, build_hreflang_clusters,
+    build_hreflang_groups, build_index_md, build_link_report, build_schema_json, build_site_data,
+    collapse_duplicate_pages, detect_frameworks_from_html, detect_quality_flags,
+    detect_quality_warnings, detect_sections, detect_templates, write_scraped_json,
 };
-use crate::scrape::Scraper;
+use crate::scrape::{FetchedPage, HttpOptions, PageOptions, Scraper, UrlFilter};
+use crate::sitemap::SitemapEntry;
+use crate::tui::{CrawlMonitor, Dashboard};
+use crate::urllist::read_url_list;
 use crate::util::{
-    build_exclude_patterns, build_include_patterns, canonicalize_url, is_disallowed_by_robots,
-    normalize_path, url_matches_excludes, url_matches_includes, url_priority, url_to_host_slug,
-    url_to_slug,
+    build_exclude_patterns, build_include_patterns, canonicalize_url, fetchable_from,
+    is_disallowed_by_robots, normalize_path, parse_w3c_datetime, sitemaps_on_site, unix_to_rfc3339,
+    url_matches_excludes, url_matches_includes, url_priority, url_to_host_slug, url_to_slug,
 };
+use crate::videos::download_videos;
 
 /// Write a minimal "crashed before output" index.md when main() fails
 /// before reaching the normal output path. Round L fix for Martinus.cz:
@@ -66,23 +146,110 @@ fn write_crash_bundle(output_dir: &std::path::Path, target_url: &str, error_mess
     let _ = std::fs::write(path, body);
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let mut args = Args::parse();
+/// Where `discover` gets the page list from.
+enum UrlSource {
+    /// The sitemap (the target itself when it is one, else the ones
+    /// robots.txt lists or a well-known location), falling back to a link
+    /// crawl.
+    Discover,
+    /// Exactly these URLs (`dump-it scrape`, `--url-list`).
+    List(Vec<String>),
+}
 
-    // --test-run reroutes output to test_runs/<host>/ unless the user passed
-    // a custom --output path. Comparison is against the literal default so
-    // "user explicitly set output" is the meaningful escape hatch.
-    if args.test_run && args.output == "output/scraped.json" {
-        let host_slug = url_to_host_slug(&args.url);
-        args.output = format!("test_runs/{host_slug}/scraped.json");
+/// Result of the discovery phase: the scraper it configured and the
+/// filtered, capped URL list.
+struct Discovered {
+    scraper: Scraper,
+    urls: Vec<String>,
+    /// Orphan-page detection only makes sense when the URL list came from
+    /// a sitemap; the crawler only ever finds linked pages.
+    from_sitemap: bool,
+    cross_domain_warning: Option<String>,
+    /// Sitemap `lastmod` / `changefreq` / `priority` by canonical URL.
+    sitemap_meta: HashMap<String, SitemapMeta>,
+    /// URLs left out at discovery that belong in `skipped_pages`.
+    skipped: Vec<crate::model::SkippedPage>,
+    /// The sitemaps found, for `site.json:data_sources`.
+    data_sources: Vec<crate::model::DataSource>,
+    /// robots.txt Disallow rules (empty with --ignore-robots).
+    robots_rules: Vec<String>,
+}
+
+/// `--modified-since`: drop URLs whose sitemap `lastmod`, or failing
+/// that whose `Last-Modified` header, is older than `since`. URLs with
+/// neither are kept — unknown counts as changed.
+async fn retain_modified_since(
+    scraper: &Scraper,
+    urls: &mut Vec<String>,
+    sitemap_meta: &HashMap<String, SitemapMeta>,
+    since: u64,
+    concurrency: usize,
+) {
+    use futures::stream::{self, StreamExt};
+
+    let lastmod = |u: &String| {
+        sitemap_meta
+            .get(&canonicalize_url(u))
+            .and_then(|m| m.lastmod.as_deref())
+            .and_then(parse_w3c_datetime)
+    };
+    let unknown: Vec<String> = urls
+        .iter()
+        .filter(|u| lastmod(u).is_none())
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        progress!(
+            "🕒 Checking Last-Modified of {} URLs without a sitemap lastmod...",
+            unknown.len()
+        );
     }
+    let header_dates: HashMap<String, u64> = stream::iter(unknown)
+        .map(|u| async move { scraper.last_modified(&u).await.map(|t| (u, t)) })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|r| async move { r })
+        .collect()
+        .await;
 
-    // Initialise tracing. Level: --quiet → warn, --verbose → debug, else info.
-    // Honour RUST_LOG if set so power-users can target specific modules.
-    let level_filter = if args.quiet {
+    let before = urls.len();
+    urls.retain(|u| {
+        lastmod(u)
+            .or_else(|| header_dates.get(u).copied())
+            .is_none_or(|t| t >= since)
+    });
+    if urls.len() != before {
+        progress!(
+            "🕒 --modified-since {}: skipping {} unchanged URLs",
+            unix_to_rfc3339(since),
+            before - urls.len()
+        );
+    }
+}
+
+/// Page URLs of a sitemap; entries that carry metadata are recorded in
+/// `meta` under their canonical URL.
+fn sitemap_urls(
+    entries: Vec<SitemapEntry>,
+    meta: &mut HashMap<String, SitemapMeta>,
+) -> Vec<String> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            if entry.meta != SitemapMeta::default() {
+                meta.entry(canonicalize_url(&entry.loc))
+                    .or_insert(entry.meta);
+            }
+            entry.loc
+        })
+        .collect()
+}
+
+/// Initialise tracing. Level: --quiet → warn, --verbose → debug, else info.
+/// Honour RUST_LOG if set so power-users can target specific modules.
+fn init_tracing(quiet: bool, verbose: bool) {
+    let level_filter = if quiet {
         "warn"
-    } else if args.verbose {
+    } else if verbose {
         "debug"
     } else {
         "info"
@@ -93,54 +260,229 @@ async fn main() -> anyhow::Result<()> {
         .with_env_filter(env_filter)
         .with_target(false)
         .without_time()
-        .with_writer(std::io::stderr)
+        .with_writer(crate::tui::log_writer)
         .init();
+}
 
-    // Pre-create the output directory immediately, BEFORE any Chrome /
-    // network activity. Round L regression: Martinus.cz crashed during
-    // sitemap fetch (headless_chrome transport timeout) and never reached
-    // the normal create_dir_all step, leaving the user with zero output
-    // files and no idea what went wrong. Now: dir exists + a placeholder
-    // index.md is written immediately; the real index.md overwrites it on
-    // success, but if the run crashes mid-flight the placeholder stays so
-    // the user sees an explicit "scrape did not complete" message rather
-    // than an empty / missing folder.
-    let output_path = std::path::Path::new(&args.output).to_path_buf();
-    let initial_output_dir = output_path
-        .parent()
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|| std::path::PathBuf::from("."));
-    let _ = std::fs::create_dir_all(&initial_output_dir);
-    write_crash_bundle(
-        &initial_output_dir,
-        &args.url,
-        "(scrape did not complete — check stderr for the actual error; \
-         re-run with --verbose for per-step diagnostics)",
-    );
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = parse_args()?;
+    init_tracing(args.quiet, args.verbose);
+    let result = match args.command {
+        Some(command) => run_command(command).await,
+        None => match url_source(args.url, Vec::new(), &args.run) {
+            Ok((url, source)) => run_scrape(url, args.run, source).await,
+            Err(e) => Err(e),
+        },
+    };
+    // After the run, failed ones included: --har is how fetch failures get
+    // debugged.
+    match har::write_log() {
+        Ok(Some((path, count))) => progress!("🌐 HAR: {} ({count} requests)", path.display()),
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to write the HAR log: {e}"),
+    }
+    result
+}
+
+/// Parse the command line with `--profile` expanded (see `config`).
+/// Repeated flags keep their last value, which is how the command line
+/// overrides a profile.
+fn parse_args() -> anyhow::Result<Args> {
+    let mut command = Args::command()
+        .args_override_self(true)
+        .arg(
+            clap::Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .value_name("NAME")
+                .help("Apply [profiles.NAME] of the config file; flags given here win"),
+        )
+        .arg(
+            clap::Arg::new("config")
+                .long("config")
+                .global(true)
+                .value_name("PATH")
+                .help("Config file for --profile [default: ./dump-it.toml, then ~/.config/dump-it/config.toml]"),
+        );
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|s| s.get_name().to_string())
+        .collect();
+    for name in &names {
+        command = command.mut_subcommand(name, |s| s.args_override_self(true));
+    }
+    let argv = config::expand_profile(std::env::args().collect(), |args| {
+        1 + usize::from(args.get(1).is_some_and(|a| names.contains(a)))
+    })?;
+    let matches = command.get_matches_from(argv);
+    Ok(Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
+}
+
+/// The site URL and page source of a run: `urls` plus any `--url-list`
+/// entries (paths resolved against the site URL) when there are some, the
+/// HTML files of a local site, else discovery. Without `site`, the first
+/// listed URL stands in.
+fn url_source(
+    site: Option<String>,
+    urls: Vec<String>,
+    args: &RunArgs,
+) -> anyhow::Result<(String, UrlSource)> {
+    // `--output -` keeps stdout for the JSON from the first progress line on.
+    if args.output == "-" {
+        PROGRESS_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+    // Local folders and files become `file://` URLs.
+    let site = site.map(|s| local::target_url(&s).unwrap_or(s));
+    let mut urls: Vec<String> = urls
+        .into_iter()
+        .map(|u| local::target_url(&u).unwrap_or(u))
+        .collect();
+    for target in site.iter().chain(&urls) {
+        local::allow(target);
+    }
+    if let Some(path) = &args.url_list {
+        let base = site.as_deref().or(urls.first().map(String::as_str));
+        let listed = read_url_list(path, args.url_column.as_deref(), base)?;
+        progress!("📄 {} URLs from {}", listed.len(), path.display());
+        urls.extend(listed);
+    } else if let Some(dir) = site.as_deref().filter(|s| {
+        urls.is_empty() && s.starts_with("file:") && !s.contains("sitemap") && !s.ends_with(".xml")
+    }) {
+        // A local site has no server to crawl: its pages are its files.
+        let files = local::html_files(&Url::parse(dir)?)?;
+        progress!("📁 {} HTML files in {dir}", files.len());
+        urls = files;
+    }
+    let site = site
+        .or_else(|| urls.first().cloned())
+        .context("--url is required")?;
+    let source = if urls.is_empty() {
+        UrlSource::Discover
+    } else {
+        UrlSource::List(urls)
+    };
+    Ok((site, source))
+}
 
-    println!("🚀 Starting scraper...");
-    println!("Target: {}", args.url);
-    println!("Concurrency: {}", args.concurrency);
+async fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Crawl { url, run } => {
+            let (url, source) = url_source(Some(url), Vec::new(), &run)?;
+            run_scrape(url, run, source).await?
+        }
+        Command::Scrape { urls, run } => {
+            let (target, source) = url_source(None, urls, &run)?;
+            run_scrape(target, run, source).await?
+        }
+        Command::Sitemap { url, run } => {
+            PROGRESS_TO_STDERR.store(true, Ordering::Relaxed);
+            let (url, source) = url_source(Some(url), Vec::new(), &run)?;
+            let discovered = discover(&url, &run, source).await?;
+            for u in &discovered.urls {
+                println!("{u}");
+            }
+        }
+        Command::Extract { base_url, run } => run_extract(&base_url, &run).await?,
+        Command::Export { input, export } => export::run_export(&input, export).await?,
+        Command::Audit { input, strict } => dump::run_audit(&input, strict)?,
+        Command::Diff { old, new } => dump::run_diff(&old, &new)?,
+        Command::Merge { inputs, output } => merge::run_merge(&inputs, &output)?,
+        Command::Serve { input, bind } => serve::run_serve(&input, bind).await?,
+        #[cfg(feature = "grpc")]
+        Command::Grpc { bind } => grpc::run_grpc(bind).await?,
+        #[cfg(not(feature = "grpc"))]
+        Command::Grpc { .. } => {
+            anyhow::bail!("the grpc subcommand needs dump-it built with `--features grpc`")
+        }
+        Command::Batch {
+            jobs,
+            sites,
+            budget,
+        } => batch::run_batch(&jobs, sites, budget).await?,
+        Command::Schema => {
+            let schema = schemars::schema_for!(ScrapedData);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        Command::Search {
+            query,
+            input,
+            index,
+            limit,
+        } => search::run_search(&query, &input, index.as_deref(), limit)?,
+    }
+    Ok(())
+}
+
+/// The layers every request of a run of `url` goes through: `--har`,
+/// the cassette, the HTTP cache and OAuth2 tokens.
+fn start_transport(url: &str, args: &RunArgs) -> anyhow::Result<()> {
+    if let Some(path) = &args.har {
+        let mut headers = config::request_headers(&args.headers);
+        headers.extend(args.site_headers.iter().map(|(_, h)| h.clone()));
+        har::enable(path.clone(), &headers);
+    }
+    if let Some(dir) = &args.record {
+        cassette::start(cassette::Mode::Record, dir)?;
+        progress!("📼 Recording responses to {}", dir.display());
+    } else if let Some(dir) = &args.replay {
+        cassette::start(cassette::Mode::Replay, dir)?;
+        progress!("📼 Replaying responses from {} (no network)", dir.display());
+    }
+    if let Some(dir) = &args.cache_dir {
+        httpcache::start(dir, args.offline)?;
+        if args.offline {
+            progress!("🗄️  Offline: answering from {} (no network)", dir.display());
+        } else {
+            progress!("🗄️  HTTP cache: {}", dir.display());
+        }
+    }
+    // Replayed and offline responses were fetched with whatever token the
+    // recording run had.
+    if args.replay.is_none() && !args.offline {
+        auth::start_oauth(url, args)?;
+    }
+    Ok(())
+}
+
+/// Build the scraper, apply robots.txt, and collect the URLs to scrape:
+/// from `source`, then canonicalised, deduplicated, filtered by
+/// exclude / include / robots and capped at --max-pages.
+async fn discover(url: &str, args: &RunArgs, source: UrlSource) -> anyhow::Result<Discovered> {
+    start_transport(url, args)?;
+    // A replay or an offline run is --no-js: Chrome would go to the
+    // network, and the pages it rendered while recording are in the
+    // cassette.
+    let no_js = args.no_js || args.replay.is_some() || args.offline;
+    progress!("🚀 Starting scraper...");
+    progress!("Target: {url}");
+    progress!("Concurrency: {}", args.concurrency);
 
     let extract_brand = !args.no_extract_brand;
-    let fetch_css = !args.no_fetch_css;
     // Build the scraper first with no rate limit; we may set one after
     // fetching robots.txt if Crawl-delay is present and --delay is 0.
     let mut effective_delay_ms = args.delay;
+    let http_options = HttpOptions::from_args(args)?;
+    let page_options = PageOptions::from_args(args)?;
+    auth::scope_headers(url, args);
     let scraper = Scraper::new(
         args.concurrency,
         args.timeout,
         args.js_wait,
         args.js_wait_selector.clone(),
         extract_brand,
-        args.no_js,
+        no_js,
         effective_delay_ms,
         args.max_images_per_page,
         args.user_agent.as_deref(),
-        &args.headers,
+        &http_options,
+        &page_options,
     )?;
-    if args.no_js {
-        println!("⚡ --no-js mode: using plain HTTP fetch (Chrome not launched)");
+    if no_js {
+        progress!("⚡ --no-js mode: using plain HTTP fetch (Chrome not launched)");
+        if args.solve_challenges {
+            tracing::warn!("--solve-challenges is ignored when --no-js is set (it needs Chrome)");
+        }
         if args.screenshots {
             tracing::warn!(
                 "--screenshots is ignored when --no-js is set (Chrome needed for capture)"
@@ -149,86 +491,111 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // --- Robots.txt -------------------------------------------------------
+    let mut robots_sitemaps: Vec<String> = Vec::new();
     let robots_rules: Vec<String> = if args.ignore_robots {
         Vec::new()
     } else {
-        let base_url = Url::parse(&args.url).ok();
+        // Local sites (`file://`) have no robots.txt.
+        let base_url = Url::parse(url).ok().filter(Url::has_host);
         match base_url {
             Some(b) => {
                 let rules = scraper.fetch_robots_rules(&b).await;
                 if !rules.disallow.is_empty() {
-                    println!(
+                    progress!(
                         "🤖 robots.txt: {} Disallow rule(s) honoured",
                         rules.disallow.len()
                     );
                 }
                 if let Some(cd) = rules.crawl_delay_ms {
                     if effective_delay_ms == 0 {
-                        println!(
+                        progress!(
                             "⏱  robots.txt: Crawl-delay {} ms honoured (override with --delay)",
                             cd
                         );
                         effective_delay_ms = cd;
                     }
                 }
+                robots_sitemaps = sitemaps_on_site(rules.sitemaps, &b, args.strict_host);
                 rules.disallow
             }
             None => Vec::new(),
         }
     };
     // Rebuild the scraper if Crawl-delay raised our effective delay.
-    let scraper = if effective_delay_ms != args.delay {
+    let mut scraper = if effective_delay_ms != args.delay {
         Scraper::new(
             args.concurrency,
             args.timeout,
             args.js_wait,
             args.js_wait_selector.clone(),
             extract_brand,
-            args.no_js,
+            no_js,
             effective_delay_ms,
             args.max_images_per_page,
             args.user_agent.as_deref(),
-            &args.headers,
+            &http_options,
+            &page_options,
         )?
     } else {
         scraper
     };
-    let excludes = build_exclude_patterns(&args);
-    let include_patterns = build_include_patterns(&args);
+    let excludes = build_exclude_patterns(args);
+    let include_patterns = build_include_patterns(args);
     if !excludes.is_empty() {
-        println!("🚫 URL excludes: {} patterns active", excludes.len());
+        progress!("🚫 URL excludes: {} patterns active", excludes.len());
     }
     if !include_patterns.is_empty() {
-        println!("✅ URL includes: {} patterns active", include_patterns.len());
+        progress!(
+            "✅ URL includes: {} patterns active",
+            include_patterns.len()
+        );
     }
 
-    let raw_urls = if args.url.contains("sitemap") || args.url.ends_with(".xml") {
-        println!("📋 Parsing sitemap...");
-        scraper.fetch_sitemap(&args.url).await?
+    // Orphan-page detection only makes sense when the URL list came from
+    // a sitemap; the crawler only ever finds linked pages.
+    let mut from_sitemap = true;
+    let mut variants = QueryVariants::new(args.max_query_variants);
+    let mut sitemap_meta: HashMap<String, SitemapMeta> = HashMap::new();
+    let mut found_sitemap: Option<String> = None;
+    let raw_urls = if let UrlSource::List(urls) = source {
+        from_sitemap = false;
+        urls
+    } else if url.contains("sitemap") || url.ends_with(".xml") {
+        progress!("📋 Parsing sitemap...");
+        found_sitemap = Some(url.to_string());
+        sitemap_urls(scraper.fetch_sitemap(url).await?, &mut sitemap_meta)
     } else {
-        let base_url = Url::parse(&args.url).context("invalid target URL")?;
-        let host = base_url
-            .host_str()
-            .ok_or_else(|| anyhow::anyhow!("URL has no host component"))?;
-        let sitemap_url = format!("{}://{}/sitemap.xml", base_url.scheme(), host);
-
-        println!("🔍 Looking for sitemap at: {sitemap_url}");
-        match scraper.fetch_sitemap(&sitemap_url).await {
-            Ok(urls) if urls.len() > 1 => {
-                println!("✓ Found sitemap with {} URLs", urls.len());
-                urls
+        let base_url = Url::parse(url).context("invalid target URL")?;
+        if base_url.host_str().is_none() {
+            anyhow::bail!("URL has no host component");
+        }
+
+        progress!(
+            "🔍 Looking for a sitemap \
+             (robots.txt, /sitemap.xml, /sitemap_index.xml, /wp-sitemap.xml)"
+        );
+        match scraper.discover_sitemap(&base_url, &robots_sitemaps).await {
+            Some((source, entries)) => {
+                progress!("✓ Found sitemap with {} URLs ({source})", entries.len());
+                if source != "robots.txt" {
+                    found_sitemap = Some(source);
+                }
+                sitemap_urls(entries, &mut sitemap_meta)
             }
-            _ => {
-                println!("⚠️  No sitemap found, starting crawler...");
+            None => {
+                progress!("⚠️  No sitemap found, starting crawler...");
+                from_sitemap = false;
                 if args.crawl_with_http {
-                    println!("⚡ --crawl-with-http: link discovery uses plain HTTP");
+                    progress!("⚡ --crawl-with-http: link discovery uses plain HTTP");
                 }
                 scraper
                     .crawl(
-                        &args.url,
+                        url,
                         args.max_depth,
                         args.max_pages,
                         &excludes,
+                        &args.section_budgets,
+                        &mut variants,
                         args.crawl_with_http,
                     )
                     .await
@@ -236,14 +603,23 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    // A sitemap, URL list or robots.txt can't point the crawl at the
+    // disk: `file:` URLs are only scraped for a local target.
+    let local_target = url.starts_with("file:");
+    let raw_urls: Vec<String> = raw_urls
+        .into_iter()
+        .filter(|u| local::discoverable(u, local_target))
+        .collect();
+
     // Cross-domain sitemap detection. Some merged / acquired companies
     // (damejidlo.cz → foodora.cz) leave a sitemap.xml that points 100%
     // at the new host. The bundle ends up named after the OLD domain but
     // contains URLs for a different site. Warn the user + record a
     // quality_warning so the agent doesn't blindly trust the bundle name.
-    let target_host = Url::parse(&args.url)
-        .ok()
-        .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_string()));
+    let target_host = Url::parse(url).ok().and_then(|u| {
+        u.host_str()
+            .map(|h| h.trim_start_matches("www.").to_string())
+    });
     let mut cross_domain_warning: Option<String> = None;
     if let Some(ref host) = target_host {
         let cross: usize = raw_urls
@@ -275,16 +651,33 @@ async fn main() -> anyhow::Result<()> {
                     .max_by_key(|(_, c)| **c)
                     .map(|(h, _)| h.clone())
                     .unwrap_or_default();
-                println!(
+                progress!(
                     "🌐 Cross-domain sitemap: {pct}% of URLs point at {foreign_top} (target host = {host}). The bundle name reflects the input URL but the content is from a different domain — likely a merger/redirect."
                 );
-                cross_domain_warning = Some(format!(
-                    "cross_domain_sitemap:{pct}%_urls_at_{foreign_top}"
-                ));
+                cross_domain_warning =
+                    Some(format!("cross_domain_sitemap:{pct}%_urls_at_{foreign_top}"));
             }
         }
     }
 
+    // Section budgets before the --max-pages cap, so the sections left
+    // over share it. The crawler already kept to them while crawling.
+    let raw_urls = if !args.section_budgets.is_empty() {
+        let mut sections = SectionBudgets::new(&args.section_budgets);
+        let kept = raw_urls.into_iter().filter(|u| sections.admit(u)).collect();
+        sections.report();
+        kept
+    } else {
+        raw_urls
+    };
+    // Same for the query-string variant cap, on canonical URLs so
+    // tracking parameters don't count as variants.
+    let raw_urls: Vec<String> = raw_urls
+        .into_iter()
+        .filter(|u| variants.admit(&canonicalize_url(u)))
+        .collect();
+    let skipped = variants.finish();
+
     // Apply the --max-pages cap to sitemap mode too. Crawler mode already
     // caps internally, but sitemap mode used to scrape every URL the
     // sitemap returned, which made --max-pages a footgun.
@@ -296,7 +689,7 @@ async fn main() -> anyhow::Result<()> {
     // caps, losing the agent its primary lead-capture target.
     let raw_urls = if raw_urls.len() > args.max_pages {
         let dropped = raw_urls.len() - args.max_pages;
-        println!(
+        progress!(
             "📦 Capping {} sitemap URLs to --max-pages={} (drop {} URLs, prioritising home/contact/about/legal)",
             raw_urls.len(),
             args.max_pages,
@@ -321,7 +714,7 @@ async fn main() -> anyhow::Result<()> {
         .filter(|u| url_matches_includes(u, &include_patterns))
         .collect();
     if urls.len() != total_before_filter {
-        println!(
+        progress!(
             "🧹 Filtered {} URLs (canonicalisation + exclude/include)",
             total_before_filter - urls.len()
         );
@@ -330,15 +723,215 @@ async fn main() -> anyhow::Result<()> {
         let before = urls.len();
         urls.retain(|u| !is_disallowed_by_robots(u, &robots_rules));
         if urls.len() != before {
-            println!(
+            progress!(
                 "🤖 Filtered {} URLs disallowed by robots.txt",
                 before - urls.len()
             );
         }
     }
 
+    if let Some(since) = args.modified_since {
+        retain_modified_since(&scraper, &mut urls, &sitemap_meta, since, args.concurrency).await;
+    }
+
+    let total = urls.len();
+    progress!("📊 Found {total} URLs to scrape");
+    scraper.url_filter = UrlFilter {
+        excludes,
+        includes: include_patterns,
+        disallow: robots_rules.clone(),
+    };
+
+    let data_sources = feeds::sitemap_sources(&robots_sitemaps, found_sitemap.as_deref());
+
+    Ok(Discovered {
+        scraper,
+        urls,
+        from_sitemap,
+        cross_domain_warning,
+        sitemap_meta,
+        skipped,
+        data_sources,
+        robots_rules,
+    })
+}
+
+/// `dump-it extract`: the page extractor over HTML on stdin, PageData JSON
+/// on stdout. Inline SVGs still land in the --output directory's
+/// `images/`, like in a scrape.
+async fn run_extract(base_url: &str, args: &RunArgs) -> anyhow::Result<()> {
+    use std::io::Read;
+    PROGRESS_TO_STDERR.store(true, Ordering::Relaxed);
+    Url::parse(base_url).context("invalid --base-url")?;
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .context("failed to read HTML from stdin")?;
+    let html = crate::util::decode_charset(None, &bytes);
+
+    // Nothing is fetched, so there is no challenge to solve.
+    let mut http_options = HttpOptions::from_args(args)?;
+    http_options.solve_challenges = false;
+    auth::scope_headers(base_url, args);
+    // The mobile fetch and the AMP variant would be fetches too.
+    let page_options = PageOptions {
+        compare_mobile: false,
+        amp: crate::cli::AmpMode::Canonical,
+        ..PageOptions::from_args(args)?
+    };
+    let scraper = Scraper::new(
+        1,
+        args.timeout,
+        args.js_wait,
+        None,
+        !args.no_extract_brand,
+        true,
+        0,
+        args.max_images_per_page,
+        args.user_agent.as_deref(),
+        &http_options,
+        &page_options,
+    )?;
+    let output_dir = std::path::Path::new(&args.output)
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join("images");
+    std::fs::create_dir_all(&output_dir)?;
+    let mut page = scraper
+        .extract_page(
+            base_url.to_string(),
+            FetchedPage::from_html(html, base_url),
+            &normalize_path(&output_dir.to_string_lossy()),
+            None,
+        )
+        .await
+        .map_err(|reason| anyhow::anyhow!("extraction failed: {reason}"))?;
+    derive_page_fields(&mut page);
+    println!("{}", serde_json::to_string_pretty(&page)?);
+    Ok(())
+}
+
+/// Fill in a page's derived fields: sections, quality flags, token
+/// estimate, summary and the assets it references.
+fn derive_page_fields(page: &mut crate::model::PageData) {
+    page.sections = detect_sections(&page.content_blocks);
+    page.quality_flags = detect_quality_flags(page);
+
+    // Rough token estimate (~4 chars / token).
+    page.token_estimate = page.plain_text.chars().count() / 4;
+
+    // One-line summary: meta_description > first paragraph > first heading.
+    page.summary = if !page.meta_description.is_empty() {
+        page.meta_description
+            .chars()
+            .take(200)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    } else {
+        let first_p = page.content_blocks.iter().find_map(|b| match b {
+            crate::model::ContentBlock::Paragraph { text, .. } => Some(text.as_str()),
+            _ => None,
+        });
+        let first_h = page.content_blocks.iter().find_map(|b| match b {
+            crate::model::ContentBlock::Heading { text, .. } => Some(text.as_str()),
+            _ => None,
+        });
+        first_p
+            .or(first_h)
+            .map(|s| s.chars().take(200).collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut assets: Vec<String> = page
+        .content_blocks
+        .iter()
+        .filter_map(|b| match b {
+            crate::model::ContentBlock::Image { local_path, .. } if !local_path.is_empty() => {
+                Some(local_path.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    if let Some(og) = &page.og_image_local_path {
+        assets.push(og.clone());
+    }
+    assets.sort();
+    assets.dedup();
+    page.page_assets = assets;
+}
+
+/// A full scrape: discovery, per-page extraction, site aggregation and
+/// every output file.
+async fn run_scrape(url: String, mut args: RunArgs, source: UrlSource) -> anyhow::Result<()> {
+    if args.mode != cli::CrawlMode::Html {
+        return jsonapi::run_json_api(&url, &args, source).await;
+    }
+    // Validate before the crawl so a typo doesn't cost a full run.
+    let mut exports = ExportSettings::new(args.export.clone())?;
+    exports.start_sinks().await?;
+
+    // `--output -`: the JSON goes to stdout (progress already goes to
+    // stderr, see `url_source`); the rest of the bundle is written to the
+    // default directory.
+    let to_stdout = args.output == "-";
+    if to_stdout {
+        args.output = "output/scraped.json".to_string();
+    }
+
+    // --test-run reroutes output to test_runs/<host>/ unless the user passed
+    // a custom --output path. Comparison is against the literal default so
+    // "user explicitly set output" is the meaningful escape hatch.
+    if args.test_run && args.output == "output/scraped.json" {
+        let host_slug = url_to_host_slug(&url);
+        args.output = format!("test_runs/{host_slug}/scraped.json");
+    }
+
+    // Pre-create the output directory immediately, BEFORE any Chrome /
+    // network activity. Round L regression: Martinus.cz crashed during
+    // sitemap fetch (headless_chrome transport timeout) and never reached
+    // the normal create_dir_all step, leaving the user with zero output
+    // files and no idea what went wrong. Now: dir exists + a placeholder
+    // index.md is written immediately; the real index.md overwrites it on
+    // success, but if the run crashes mid-flight the placeholder stays so
+    // the user sees an explicit "scrape did not complete" message rather
+    // than an empty / missing folder.
+    let output_path = std::path::Path::new(&args.output).to_path_buf();
+    let initial_output_dir = output_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let _ = std::fs::create_dir_all(&initial_output_dir);
+    write_crash_bundle(
+        &initial_output_dir,
+        &url,
+        "(scrape did not complete — check stderr for the actual error; \
+         re-run with --verbose for per-step diagnostics)",
+    );
+
+    if let Some(mb) = args.max_memory_mb {
+        memory::set_limit(mb);
+    }
+
+    let Discovered {
+        scraper,
+        urls,
+        from_sitemap,
+        cross_domain_warning,
+        sitemap_meta,
+        skipped: discovery_skipped,
+        mut data_sources,
+        robots_rules,
+    } = discover(&url, &args, source).await?;
+    if args.probe_apis {
+        if let Some(base_url) = Url::parse(&url).ok().filter(Url::has_host) {
+            data_sources.extend(feeds::probe_apis(&scraper, &base_url, &robots_rules).await);
+        }
+    }
     let total = urls.len();
-    println!("📊 Found {total} URLs to scrape");
+    let extract_brand = !args.no_extract_brand;
+    let fetch_css = !args.no_fetch_css;
+    let image_options = ImageOptions::from_args(&args);
 
     let output_path = std::path::Path::new(&args.output);
     let output_dir = output_path.parent().unwrap_or(std::path::Path::new("."));
@@ -347,96 +940,207 @@ async fn main() -> anyhow::Result<()> {
     std::fs::create_dir_all(&images_dir)?;
     let images_dir_str = normalize_path(&images_dir.to_string_lossy());
 
-    let (mut pages, skipped_pages) = scraper.scrape_all(urls, images_dir_str.clone()).await;
-
-    // --- Per-page derived data: sections / quality / assets / hash / summary ---
+    let monitor = args.tui.then(|| CrawlMonitor::new(&urls));
+    let dashboard = monitor.clone().and_then(Dashboard::start);
+    let (mut pages, mut skipped_pages) = scraper
+        .scrape_all(urls, images_dir_str.clone(), monitor.as_deref())
+        .await;
+    skipped_pages.extend(discovery_skipped);
+    if let Some(dashboard) = dashboard {
+        dashboard.close();
+    }
     for page in pages.iter_mut() {
-        page.sections = detect_sections(&page.content_blocks);
-        page.quality_flags = detect_quality_flags(page);
-
-        // Content hash — first 16 hex chars of SHA-256(plain_text). Lets the
-        // agent dedup boilerplate across pages and detect change vs prior run.
-        if !page.plain_text.is_empty() {
-            use sha2::{Digest, Sha256};
-            let mut hasher = Sha256::new();
-            hasher.update(page.plain_text.as_bytes());
-            let hex = format!("{:x}", hasher.finalize());
-            page.content_hash = hex[..16].to_string();
+        page.sitemap = sitemap_meta.get(&canonicalize_url(&page.url)).cloned();
+    }
+
+    // --- 404 probes: the site's error page for --capture-404, and the
+    // error template of each host with suspect pages for soft 404s ------
+    // A fixed probe under --record / --replay / --cache-dir, so a replay or
+    // an --offline run finds it.
+    let fixed_probe = args.record.is_some() || args.replay.is_some() || args.cache_dir.is_some();
+    let probe_token = if fixed_probe {
+        "cassette".to_string()
+    } else {
+        format!(
+            "{:x}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        )
+    };
+    let probe_path = format!(
+        "/dump-it-probe-{}",
+        &probe_token[..probe_token.len().min(12)]
+    );
+    let mut error_pages: Vec<crate::model::PageData> = Vec::new();
+    if args.capture_404 {
+        if let Some(base) = Url::parse(&url).ok().filter(Url::has_host) {
+            let probe_url = format!(
+                "{}://{}{probe_path}",
+                base.scheme(),
+                base.host_str().unwrap_or_default()
+            );
+            progress!("🔎 Probing 404 template at {probe_url}");
+            match scraper.scrape_page(probe_url, &images_dir_str).await {
+                Ok(mut p) => {
+                    // Re-derive sections / quality flags so the 404 page has them too.
+                    p.sections = detect_sections(&p.content_blocks);
+                    p.quality_flags = detect_quality_flags(&p);
+                    error_pages.push(p);
+                }
+                Err(_) => tracing::warn!("404 probe failed (no body returned)"),
+            }
+        }
+    }
+    let mut templates: HashMap<String, soft404::ErrorTemplate> = HashMap::new();
+    for (host, scheme) in soft404::suspected_hosts(&pages) {
+        let probe_url = format!("{scheme}://{host}{probe_path}");
+        progress!("🔎 Probing soft-404 template at {probe_url}");
+        if let Some(template) = scraper
+            .probe_error_template(&probe_url, &images_dir_str)
+            .await
+        {
+            templates.insert(host, template);
         }
+    }
 
-        // Rough token estimate (~4 chars / token).
-        page.token_estimate = page.plain_text.chars().count() / 4;
-
-        // One-line summary: meta_description > first paragraph > first heading.
-        page.summary = if !page.meta_description.is_empty() {
-            page.meta_description
-                .chars()
-                .take(200)
-                .collect::<String>()
-                .trim()
-                .to_string()
-        } else {
-            let first_p = page.content_blocks.iter().find_map(|b| match b {
-                crate::model::ContentBlock::Paragraph { text } => Some(text.as_str()),
-                _ => None,
-            });
-            let first_h = page.content_blocks.iter().find_map(|b| match b {
-                crate::model::ContentBlock::Heading { text, .. } => Some(text.as_str()),
-                _ => None,
-            });
-            first_p
-                .or(first_h)
-                .map(|s| s.chars().take(200).collect::<String>().trim().to_string())
-                .unwrap_or_default()
-        };
+    // --- Soft 404s: error pages served with 200 -------------------------
+    let soft_404s = soft404::detect_soft_404s(&mut pages, &templates, args.skip_soft_404);
+    if !args.skip_soft_404 {
+        let flagged = pages.iter().filter(|p| p.soft_404.is_some()).count();
+        if flagged > 0 {
+            progress!("🚫 Flagged {flagged} soft-404 pages");
+        }
+    } else if !soft_404s.is_empty() {
+        progress!("🚫 Skipped {} soft-404 pages", soft_404s.len());
+    }
+    skipped_pages.extend(soft_404s);
+    if !args.capture_404 {
+        error_pages.clear();
+    }
 
-        let mut assets: Vec<String> = page
-            .content_blocks
-            .iter()
-            .filter_map(|b| match b {
-                crate::model::ContentBlock::Image { local_path, .. } if !local_path.is_empty() => {
-                    Some(local_path.clone())
-                }
-                _ => None,
-            })
-            .collect();
-        if let Some(og) = &page.og_image_local_path {
-            assets.push(og.clone());
+    // --- Images: content images + og:image, on their own worker pool ----
+    let image_jobs = image_jobs(&pages, image_options.sitemap_media);
+    let mut failed_images = Vec::new();
+    if !image_jobs.is_empty() {
+        progress!(
+            "🖼️  Downloading {} images ({} at a time)...",
+            image_jobs.len(),
+            image_options.concurrency
+        );
+        let downloads =
+            download_all(&scraper.client, image_jobs, &images_dir_str, &image_options).await;
+        attach_downloads(&mut pages, &downloads, &image_options);
+        if !downloads.failed.is_empty() {
+            progress!("⚠️  {} images failed to download", downloads.failed.len());
         }
-        assets.sort();
-        assets.dedup();
-        page.page_assets = assets;
+        failed_images = downloads.failed;
     }
 
-    // --- Download og:image per page (deduplicated) -----------------------
-    let unique_og_urls: std::collections::HashSet<String> = pages
-        .iter()
-        .filter_map(|p| p.og_image_url.clone())
-        .collect();
-    if !unique_og_urls.is_empty() {
-        let mut og_url_to_path: std::collections::HashMap<String, String> =
-            std::collections::HashMap::new();
-        for og_url in &unique_og_urls {
-            if let Some(path) = download_image(&scraper.client, og_url, &images_dir_str).await {
-                og_url_to_path.insert(og_url.clone(), path);
-            }
+    // --- Embeds: opt-in oEmbed title / author / thumbnail ---------------
+    if args.oembed {
+        enrich_embeds(&scraper.client, &mut pages, image_options.concurrency).await;
+    }
+
+    // --- Videos: opt-in, first source under the size cap ----------------
+    if args.video_max_bytes > 0 {
+        let videos_dir = output_dir.join("videos");
+        std::fs::create_dir_all(&videos_dir)?;
+        let stored = download_videos(
+            &scraper.client,
+            &mut pages,
+            &normalize_path(&videos_dir.to_string_lossy()),
+            args.video_max_bytes,
+            image_options.concurrency,
+            image_options.retries,
+        )
+        .await;
+        if stored > 0 {
+            progress!("🎬 Downloaded {stored} videos");
         }
-        for page in pages.iter_mut() {
-            if let Some(og_url) = &page.og_image_url {
-                if let Some(path) = og_url_to_path.get(og_url) {
-                    page.og_image_local_path = Some(path.clone());
-                }
-            }
+    }
+
+    // --- Exact-duplicate collapse ------------------------------------------
+    if !args.keep_duplicates {
+        let before = pages.len();
+        pages = collapse_duplicate_pages(pages);
+        if pages.len() < before {
+            progress!(
+                "🪞 Collapsed {} duplicate pages into aliases",
+                before - pages.len()
+            );
+        }
+    }
+
+    // --- Boilerplate: blocks repeated across most pages -----------------
+    let boilerplate = detect_boilerplate(
+        &mut pages,
+        args.boilerplate_threshold,
+        args.strip_boilerplate,
+    );
+    if !boilerplate.is_empty() {
+        progress!(
+            "🧱 {} {} boilerplate blocks (on >{}% of pages)",
+            if args.strip_boilerplate {
+                "Stripped"
+            } else {
+                "Flagged"
+            },
+            boilerplate.len(),
+            args.boilerplate_threshold
+        );
+    }
+
+    // --- Preset records (--preset product) ------------------------------
+    if args.preset.is_some() {
+        let with_products = pages.iter().filter(|p| !p.products.is_empty()).count();
+        let products: usize = pages.iter().map(|p| p.products.len()).sum();
+        progress!("🛒 {products} products on {with_products} pages");
+        if args.preset_only {
+            product::strip_generic_blocks(&mut pages);
         }
     }
 
+    if args.compare_mobile {
+        let compared = pages
+            .iter()
+            .filter(|p| p.mobile_comparison.is_some())
+            .count();
+        let gaps = pages
+            .iter()
+            .filter_map(|p| p.mobile_comparison.as_ref())
+            .filter(|c| c.content_parity < mobile::MOBILE_PARITY_MIN)
+            .count();
+        progress!("📱 Mobile comparison: {gaps} of {compared} pages lose content on mobile");
+    }
+
+    let gated = pages.iter().filter(|p| p.access_wall.is_some()).count();
+    if gated > 0 {
+        progress!(
+            "🔒 {gated} pages look paywalled or login-walled — their content may be incomplete"
+        );
+    }
+
+    // --- Per-page derived data: sections / quality / assets / summary ---
+    for page in pages.iter_mut() {
+        derive_page_fields(page);
+    }
+
+    // --- Keywords: TF-IDF across the whole run ----------------------------
+    let site_keywords = if args.keywords > 0 {
+        crate::keywords::extract_keywords(&mut pages, args.keywords)
+    } else {
+        Vec::new()
+    };
+
     // --- Screenshots (optional, requires Chrome) -------------------------
     if args.screenshots && !pages.is_empty() {
         if let Some(browser) = scraper.browser.as_ref() {
             let screenshots_dir = output_dir.join("screenshots");
             std::fs::create_dir_all(&screenshots_dir)?;
             let concurrency = args.concurrency.max(2);
-            println!(
+            progress!(
                 "📸 Capturing screenshots ({} pages, concurrency={})...",
                 pages.len(),
                 concurrency
@@ -523,45 +1227,20 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // --- 404 capture (optional) ------------------------------------------
-    let mut error_pages: Vec<crate::model::PageData> = Vec::new();
-    if args.capture_404 {
-        if let Ok(base) = Url::parse(&args.url) {
-            let probe_token = format!(
-                "{:x}",
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_nanos())
-                    .unwrap_or(0)
-            );
-            let probe_url = format!(
-                "{}://{}/dump-it-probe-{}",
-                base.scheme(),
-                base.host_str().unwrap_or(""),
-                &probe_token[..probe_token.len().min(12)]
-            );
-            println!("🔎 Probing 404 template at {probe_url}");
-            if let Some(mut p) = scraper.scrape_page(probe_url, &images_dir_str).await {
-                // Re-derive sections / quality flags so the 404 page has them too.
-                p.sections = detect_sections(&p.content_blocks);
-                p.quality_flags = detect_quality_flags(&p);
-                error_pages.push(p);
-            } else {
-                tracing::warn!("404 probe failed (no body returned)");
-            }
-        }
-    }
-
     let result = ScrapedData {
+        schema_version: SCHEMA_VERSION.to_string(),
+        generator: Generator::current(),
         total_pages: pages.len(),
         pages,
     };
 
     // --- Build site-wide aggregate ---
-    let mut site_data = build_site_data(&result.pages, &args.url);
+    let mut site_data = build_site_data(&result.pages, &url, args.strict_host);
+    site_data.keywords = site_keywords;
 
     // --- Template-page grouping --------------------------------------------
     site_data.templates = detect_templates(&result.pages);
+    site_data.boilerplate = boilerplate;
 
     // --- Bundle-level quality warnings ------------------------------------
     // SPA loading-shell detection: when >=80% of pages share the same
@@ -576,23 +1255,64 @@ async fn main() -> anyhow::Result<()> {
 
     // --- Skipped pages (render-failed / bot-protected) --------------------
     site_data.skipped_pages = skipped_pages;
-    if !site_data.skipped_pages.is_empty() {
-        let total_attempted = site_data.total_pages + site_data.skipped_pages.len();
-        let pct = (site_data.skipped_pages.len() as f64 / total_attempted as f64 * 100.0)
-            .round() as u32;
+    site_data.challenges = challenge::challenge_hosts(&site_data.skipped_pages, &result.pages);
+    if !site_data.challenges.is_empty() {
+        let hosts: Vec<String> = site_data
+            .challenges
+            .iter()
+            .map(|c| match c.passed {
+                0 => format!("{} {} ({})", c.host, c.pages, c.vendor),
+                passed => format!(
+                    "{} {} ({}, {passed} passed in Chrome)",
+                    c.host, c.pages, c.vendor
+                ),
+            })
+            .collect();
+        progress!("🛡️  Bot-protection challenges: {}", hosts.join(", "));
+    }
+    let attempted_skips = site_data
+        .skipped_pages
+        .iter()
+        .filter(|s| s.was_attempted())
+        .count();
+    if attempted_skips > 0 {
+        let total_attempted = site_data.total_pages + attempted_skips;
+        let pct = (attempted_skips as f64 / total_attempted as f64 * 100.0).round() as u32;
         if pct >= 50 {
             site_data
                 .quality_warnings
                 .push(format!("partial_scrape:{pct}%_pages_skipped"));
         }
     }
+    site_data.failed_images = failed_images;
+
+    // --- External domains + orphan pages ----------------------------------
+    site_data.link_report = build_link_report(&result.pages, from_sitemap, args.strict_host);
 
     // --- Hreflang locale clusters -----------------------------------------
     site_data.hreflang_groups = build_hreflang_groups(&result.pages);
+    site_data.hreflang_clusters = build_hreflang_clusters(&result.pages);
 
     // --- 404 / error pages ------------------------------------------------
     site_data.error_pages = error_pages;
 
+    // --- Feeds, sitemaps and API manifests ----------------------------------
+    site_data.data_sources = feeds::data_sources(&result.pages, data_sources);
+    if !site_data.data_sources.is_empty() {
+        let mut kinds: Vec<&str> = site_data
+            .data_sources
+            .iter()
+            .map(|s| s.kind.as_str())
+            .collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        progress!(
+            "📡 {} feeds / APIs found: {}",
+            site_data.data_sources.len(),
+            kinds.join(", ")
+        );
+    }
+
     // --- Framework detection from the first page's body ------------------
     // We don't keep page HTML around — detect by reading style_text + URL
     // hints aggregated across all pages. Approximate but cheap.
@@ -612,7 +1332,12 @@ async fn main() -> anyhow::Result<()> {
     site_data.frameworks = detect_frameworks_from_html(&combined_signature);
 
     // --- Download favicon + logo ----------------------------------------
-    if let Some(fav_url) = site_data.brand.favicon_url.clone() {
+    if let Some(fav_url) = site_data
+        .brand
+        .favicon_url
+        .clone()
+        .filter(|u| fetchable_from(&url, u))
+    {
         if let Some(path) =
             download_asset(&scraper.client, &fav_url, &images_dir_str, "favicon").await
         {
@@ -620,7 +1345,7 @@ async fn main() -> anyhow::Result<()> {
         }
     }
     if let Some(logo_url) = site_data.brand.logo_url.clone() {
-        if !logo_url.starts_with("inline-svg://") {
+        if !logo_url.starts_with("inline-svg://") && fetchable_from(&url, &logo_url) {
             if let Some(path) =
                 download_asset(&scraper.client, &logo_url, &images_dir_str, "logo").await
             {
@@ -637,14 +1362,19 @@ async fn main() -> anyhow::Result<()> {
         let all_sheet_urls: Vec<String> = result
             .pages
             .iter()
-            .flat_map(|p| p.stylesheet_urls.iter().cloned())
+            .flat_map(|p| {
+                p.stylesheet_urls
+                    .iter()
+                    .filter(|u| fetchable_from(&p.url, u))
+                    .cloned()
+            })
             .collect();
         let mut deduped_sheets: Vec<String> = all_sheet_urls.clone();
         deduped_sheets.sort();
         deduped_sheets.dedup();
 
         let external_css = if fetch_css && !deduped_sheets.is_empty() {
-            println!(
+            progress!(
                 "🎨 Fetching {} external stylesheets for brand mining...",
                 deduped_sheets.len().min(20)
             );
@@ -666,7 +1396,7 @@ async fn main() -> anyhow::Result<()> {
             if let Some(p) = candidate {
                 let from_img = dominant_colors_from_image(std::path::Path::new(p), 6);
                 if !from_img.is_empty() {
-                    println!(
+                    progress!(
                         "🎨 CSS palette thin ({}); adding {} colour(s) from {p}",
                         colors.len(),
                         from_img.len()
@@ -715,57 +1445,29 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // --- Emit master scraped.json ---------------------------------------
-    let json = serde_json::to_string_pretty(&result)?;
-    std::fs::write(&args.output, json)?;
-    site_data.output_files.push(
-        std::path::Path::new(&args.output)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| args.output.clone()),
-    );
+    if to_stdout {
+        write_scraped_json(&mut std::io::stdout().lock(), &result, args.export.jsonl)?;
+    } else {
+        let json = serde_json::to_string_pretty(&result)?;
+        std::fs::write(&args.output, json)?;
+        site_data.output_files.push(
+            std::path::Path::new(&args.output)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| args.output.clone()),
+        );
+    }
 
-    // --- Optional: streaming JSONL --------------------------------------
-    if args.jsonl {
-        let jsonl_path = output_dir.join("scraped.jsonl");
-        let mut buf = String::with_capacity(result.pages.len() * 1024);
-        for page in &result.pages {
-            buf.push_str(&serde_json::to_string(&page)?);
-            buf.push('\n');
-        }
-        std::fs::write(&jsonl_path, buf)?;
-        site_data.output_files.push("scraped.jsonl".to_string());
-    }
-
-    // --- Optional: split per-page JSON ----------------------------------
-    if args.split_pages {
-        let pages_dir = output_dir.join("pages");
-        std::fs::create_dir_all(&pages_dir)?;
-        for (i, page) in result.pages.iter().enumerate() {
-            let slug = url_to_slug(&page.url);
-            let filename = format!("{slug}.json");
-            let page_path = pages_dir.join(&filename);
-            let page_json = serde_json::to_string_pretty(&page)?;
-            std::fs::write(&page_path, page_json)?;
-            if let Some(s) = site_data.sitemap.get_mut(i) {
-                s.file = Some(format!("pages/{filename}"));
-            }
-        }
-        site_data.output_files.push("pages/".to_string());
-    }
-
-    // --- Optional: Markdown export per page -----------------------------
-    if args.markdown {
-        let md_dir = output_dir.join("markdown");
-        std::fs::create_dir_all(&md_dir)?;
-        for (i, page) in result.pages.iter().enumerate() {
-            let slug = url_to_slug(&page.url);
-            let md_path = md_dir.join(format!("{slug}.md"));
-            std::fs::write(&md_path, page_to_markdown(page))?;
-            if let Some(s) = site_data.sitemap.get_mut(i) {
-                s.markdown_file = Some(format!("markdown/{slug}.md"));
-            }
-        }
-        site_data.output_files.push("markdown/".to_string());
+    // --- Optional exports (JSONL, chunks, index, pages, locales, Markdown) ---
+    let exported = exports
+        .write(&result.pages, &result.generator.generated_at, output_dir)
+        .await?;
+    site_data.output_files.extend(exported.output_files);
+    for (entry, file) in site_data.sitemap.iter_mut().zip(exported.page_files) {
+        entry.file = Some(file);
+    }
+    for (entry, file) in site_data.sitemap.iter_mut().zip(exported.markdown_files) {
+        entry.markdown_file = Some(file);
     }
 
     // --- Emit contact.json + brand.json ----------------------------------
@@ -775,6 +1477,14 @@ async fn main() -> anyhow::Result<()> {
         serde_json::to_string_pretty(&site_data.contact)?,
     )?;
     site_data.output_files.push("contact.json".to_string());
+    if args.preset == Some(cli::Preset::Product) {
+        let products_path = output_dir.join("products.json");
+        std::fs::write(
+            &products_path,
+            serde_json::to_string_pretty(&product::site_products(&result.pages))?,
+        )?;
+        site_data.output_files.push("products.json".to_string());
+    }
     if extract_brand {
         let brand_path = output_dir.join("brand.json");
         std::fs::write(&brand_path, serde_json::to_string_pretty(&site_data.brand)?)?;
@@ -809,29 +1519,48 @@ async fn main() -> anyhow::Result<()> {
 
     let failed = total.saturating_sub(result.total_pages);
     if failed > 0 {
-        println!(
+        progress!(
             "✅ Done! Scraped {}/{} pages (✗ {failed} failed — check stderr for warnings)",
-            result.total_pages, total
+            result.total_pages,
+            total
         );
     } else {
-        println!("✅ Done! Scraped {}/{} pages", result.total_pages, total);
+        progress!("✅ Done! Scraped {}/{} pages", result.total_pages, total);
     }
-    println!("💾 Output saved to: {}", args.output);
-    println!("📄 Site summary: {}", site_path.display());
-    println!("📑 Index: {}", index_path.display());
-    println!("📞 Contact: {}", contact_path.display());
-    if extract_brand {
-        println!("🎨 Brand: {}", output_dir.join("brand.json").display());
+    let bw = &site_data.bandwidth;
+    if bw.pages_measured > 0 && bw.decoded_bytes > 0 {
+        let saved = 100.0 - bw.transfer_bytes as f64 / bw.decoded_bytes as f64 * 100.0;
+        progress!(
+            "📶 Bandwidth: {} KB on the wire, {} KB decoded ({saved:.0}% saved by compression, {} pages measured)",
+            bw.transfer_bytes / 1024,
+            bw.decoded_bytes / 1024,
+            bw.pages_measured
+        );
+        if !bw.uncompressed_pages.is_empty() {
+            progress!(
+                "⚠️  {} pages served uncompressed (see site.json:bandwidth.uncompressed_pages)",
+                bw.uncompressed_pages.len()
+            );
+        }
+    }
+    if let Some(summary) = httpcache::summary() {
+        progress!("🗄️  Cache: {summary}");
     }
-    println!("📦 Compact: {}", compact_path.display());
-    if args.split_pages {
-        println!("📂 Per-page files: {}", output_dir.join("pages").display());
+    if to_stdout {
+        progress!("💾 Output written to stdout");
+    } else {
+        progress!("💾 Output saved to: {}", args.output);
     }
-    if args.markdown {
-        println!("📝 Markdown: {}", output_dir.join("markdown").display());
+    progress!("📄 Site summary: {}", site_path.display());
+    progress!("📑 Index: {}", index_path.display());
+    progress!("📞 Contact: {}", contact_path.display());
+    if extract_brand {
+        progress!("🎨 Brand: {}", output_dir.join("brand.json").display());
     }
+    progress!("📦 Compact: {}", compact_path.display());
+    exports.print_summary(output_dir);
     if args.screenshots {
-        println!(
+        progress!(
             "📸 Screenshots: {}",
             output_dir.join("screenshots").display()
         );
@@ -0,0 +1,233 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::model::{ContentBlock, PageData};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often an enqueued batch's task is checked until MeiliSearch has
+/// processed it.
+const TASK_POLL: Duration = Duration::from_millis(250);
+
+/// `--export meilisearch` settings.
+pub(crate) struct Meilisearch {
+    client: Client,
+    /// Server root, e.g. `http://localhost:7700`.
+    base_url: String,
+    pub index: String,
+    /// Sent as a Bearer token. Optional: a server without a master key
+    /// needs none.
+    api_key: Option<String>,
+}
+
+/// Ranked by position: a hit in the title beats one in the body.
+const SEARCHABLE: &[&str] = &["title", "headings", "description", "text", "url"];
+const FILTERABLE: &[&str] = &["language", "host", "section"];
+const SORTABLE: &[&str] = &["words"];
+
+/// The page as a search document: the fields a site search shows and
+/// ranks on, not the whole `PageData`. `section` is the first path
+/// segment (`/blog/…` → `blog`, empty for the home page) for facets.
+pub(crate) fn document(page: &PageData) -> Value {
+    let parsed = url::Url::parse(&page.url).ok();
+    let host = parsed
+        .as_ref()
+        .and_then(|u| u.host_str())
+        .unwrap_or_default()
+        .to_string();
+    let section = parsed
+        .as_ref()
+        .and_then(|u| u.path_segments()?.next().map(str::to_string))
+        .unwrap_or_default();
+    let headings: Vec<&str> = page
+        .content_blocks
+        .iter()
+        .filter_map(|b| match b {
+            ContentBlock::Heading { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    json!({
+        "id": format!("{:x}", Sha256::digest(page.url.as_bytes())),
+        "url": page.url,
+        "title": page.title,
+        "description": page.meta_description,
+        "headings": headings,
+        "text": page.plain_text,
+        "language": page.language,
+        "host": host,
+        "section": section,
+        "words": page.total_words,
+    })
+}
+
+impl Meilisearch {
+    pub fn from_args(args: &crate::cli::ExportArgs) -> anyhow::Result<Self> {
+        url::Url::parse(&args.meili_url)
+            .with_context(|| format!("invalid --meili-url {}", args.meili_url))?;
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("failed to build MeiliSearch HTTP client")?;
+        Ok(Self {
+            client,
+            base_url: args.meili_url.trim_end_matches('/').to_string(),
+            index: args.meili_index.clone(),
+            api_key: std::env::var(&args.meili_api_key_env)
+                .ok()
+                .filter(|k| !k.trim().is_empty()),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        self.request_at(method, &format!("/indexes/{}{path}", self.index))
+    }
+
+    /// A request to `path` under the server root.
+    fn request_at(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{path}", self.base_url));
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn check(response: reqwest::Response, what: &str) -> anyhow::Result<()> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: Value = response.json().await.unwrap_or_default();
+        anyhow::bail!(
+            "{what} returned {status}: {}",
+            body["message"].as_str().unwrap_or_default()
+        );
+    }
+
+    /// Set the index's searchable / filterable / sortable attributes,
+    /// which also creates it when missing. MeiliSearch applies settings
+    /// asynchronously; documents sent meanwhile are queued behind them.
+    pub async fn configure(&self) -> anyhow::Result<()> {
+        let settings = json!({
+            "searchableAttributes": SEARCHABLE,
+            "filterableAttributes": FILTERABLE,
+            "sortableAttributes": SORTABLE,
+        });
+        let request = self
+            .request(reqwest::Method::PATCH, "/settings")
+            .json(&settings);
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("could not reach MeiliSearch at {}", self.base_url))?;
+        Self::check(response, "updating index settings").await
+    }
+
+    /// Add or replace `docs` (keyed by `id`), then wait for MeiliSearch to
+    /// process the enqueued task; returns how many documents it rejected.
+    pub async fn add_documents(&self, docs: &[Value]) -> anyhow::Result<usize> {
+        let request = self
+            .request(reqwest::Method::POST, "/documents?primaryKey=id")
+            .json(docs);
+        let response = request.send().await?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!(
+                "adding documents returned {status}: {}",
+                body["message"].as_str().unwrap_or_default()
+            );
+        }
+        let uid = body["taskUid"]
+            .as_u64()
+            .context("adding documents returned no taskUid")?;
+        let started = std::time::Instant::now();
+        loop {
+            let response = self
+                .request_at(reqwest::Method::GET, &format!("/tasks/{uid}"))
+                .send()
+                .await?;
+            let task: Value = response.json().await.context("unexpected task response")?;
+            if let Some(rejected) = rejected_by_task(&task, docs.len()) {
+                if task["status"] == "failed" {
+                    tracing::warn!(
+                        "MeiliSearch task {uid} failed: {}",
+                        task["error"]["message"].as_str().unwrap_or_default()
+                    );
+                }
+                return Ok(rejected);
+            }
+            if started.elapsed() > REQUEST_TIMEOUT {
+                anyhow::bail!("MeiliSearch task {uid} still not processed");
+            }
+            tokio::time::sleep(TASK_POLL).await;
+        }
+    }
+}
+
+/// Documents of a `sent`-document addition task that weren't indexed, once
+/// the task is done; `None` while it's enqueued or processing. A failed
+/// or canceled task indexed none.
+fn rejected_by_task(task: &Value, sent: usize) -> Option<usize> {
+    match task["status"].as_str()? {
+        "succeeded" => {
+            let indexed = task["details"]["indexedDocuments"].as_u64().unwrap_or(0) as usize;
+            Some(sent.saturating_sub(indexed))
+        }
+        "failed" | "canceled" => Some(sent),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_carry_search_fields_and_facets() {
+        let mut page = crate::model::test_page("https://docs.x.com/guides/start?v=2");
+        page.title = "Getting started".to_string();
+        page.meta_description = "First steps".to_string();
+        page.language = Some("en".to_string());
+        page.structured_data = vec![json!({"@type": "TechArticle"})];
+        page.content_blocks = serde_json::from_value(json!([
+            {"type": "heading", "level": 1, "text": "Getting started"},
+            {"type": "paragraph", "text": "Install it."},
+            {"type": "heading", "level": 2, "text": "Next"},
+        ]))
+        .unwrap();
+        page.plain_text = "Getting started\nInstall it.\nNext".to_string();
+        page.total_words = 5;
+        let doc = document(&page);
+        assert_eq!(doc["id"].as_str().unwrap().len(), 64);
+        assert_eq!(doc["headings"], json!(["Getting started", "Next"]));
+        assert_eq!(doc["host"], "docs.x.com");
+        assert_eq!(doc["section"], "guides");
+        assert_eq!(doc["words"], 5);
+        assert!(doc.get("structured_data").is_none());
+        for field in SEARCHABLE.iter().chain(FILTERABLE).chain(SORTABLE) {
+            assert!(doc.get(field).is_some(), "{field}");
+        }
+    }
+
+    #[test]
+    fn rejected_documents_come_from_the_finished_task() {
+        let task = |status: &str, indexed: u64| {
+            json!({
+                "status": status,
+                "details": {"receivedDocuments": 3, "indexedDocuments": indexed},
+            })
+        };
+        assert_eq!(rejected_by_task(&task("enqueued", 0), 3), None);
+        assert_eq!(rejected_by_task(&task("processing", 0), 3), None);
+        assert_eq!(rejected_by_task(&task("succeeded", 3), 3), Some(0));
+        assert_eq!(rejected_by_task(&task("succeeded", 2), 3), Some(1));
+        assert_eq!(rejected_by_task(&task("failed", 0), 3), Some(3));
+    }
+}
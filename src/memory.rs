@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// How often a paused fetch re-checks memory use.
+const POLL: Duration = Duration::from_millis(500);
+
+/// `--max-memory-mb` in kB; 0 while there's no budget.
+static LIMIT_KB: AtomicU64 = AtomicU64::new(0);
+
+/// Fetches / downloads admitted and not yet finished.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Set while intake is paused, so the pause is announced once.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Held for the length of one admitted fetch or download.
+pub(crate) struct Admission;
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// `VmRSS` of a `/proc/<pid>/status` file, in kB.
+fn vm_rss_kb(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Resident memory of `pid` and its descendants, in kB: dump-it plus the
+/// Chrome it launched, whose renderers hold most of a JS crawl's memory.
+fn tree_rss_kb(pid: &str) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let mut total = vm_rss_kb(&status).unwrap_or(0);
+    let tasks = std::fs::read_dir(format!("/proc/{pid}/task")).ok()?;
+    for task in tasks.flatten() {
+        let children = std::fs::read_to_string(task.path().join("children")).unwrap_or_default();
+        for child in children.split_whitespace() {
+            total += tree_rss_kb(child).unwrap_or(0);
+        }
+    }
+    Some(total)
+}
+
+/// Memory in use, in kB; `None` without `/proc` (non-Linux).
+fn used_kb() -> Option<u64> {
+    tree_rss_kb("self")
+}
+
+/// Start enforcing a budget of `mb` megabytes. Process-wide: under
+/// `batch` the sites share it.
+pub(crate) fn set_limit(mb: u64) {
+    if used_kb().is_none() {
+        tracing::warn!("--max-memory-mb needs /proc (Linux); ignored on this system");
+        return;
+    }
+    LIMIT_KB.store(mb.saturating_mul(1024), Ordering::Relaxed);
+}
+
+/// Take a slot in `in_flight` if `used` kB is under `limit`, or else only
+/// if nothing is in flight: the compare-exchange lets just one of the
+/// waiters that saw an idle crawl go ahead.
+fn try_admit(in_flight: &AtomicUsize, used: u64, limit: u64) -> bool {
+    if used < limit {
+        in_flight.fetch_add(1, Ordering::AcqRel);
+        return true;
+    }
+    in_flight
+        .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+}
+
+/// Wait for room under the memory budget before a fetch or download.
+/// Over budget, new work waits until memory drops back or nothing else is
+/// in flight; the last item always proceeds, so a crawl whose kept pages
+/// alone exceed the budget slows to one fetch at a time instead of
+/// stalling.
+pub(crate) async fn admit() -> Admission {
+    let limit = LIMIT_KB.load(Ordering::Relaxed);
+    if limit == 0 {
+        IN_FLIGHT.fetch_add(1, Ordering::AcqRel);
+        return Admission;
+    }
+    loop {
+        let used = used_kb().unwrap_or(0);
+        if try_admit(&IN_FLIGHT, used, limit) {
+            if used < limit && PAUSED.swap(false, Ordering::Relaxed) {
+                progress!("▶️  Memory back to {} MB, resuming", used / 1024);
+            }
+            return Admission;
+        }
+        if !PAUSED.swap(true, Ordering::Relaxed) {
+            progress!(
+                "⏸️  Memory at {} MB of {} MB: pausing new fetches",
+                used / 1024,
+                limit / 1024
+            );
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resident_memory_is_read_from_proc() {
+        let status = "Name:\tdump-it\nVmPeak:\t  912340 kB\nVmRSS:\t  204800 kB\nThreads:\t9\n";
+        assert_eq!(vm_rss_kb(status), Some(204_800));
+        assert_eq!(vm_rss_kb("Name:\tkthreadd\n"), None);
+        if cfg!(target_os = "linux") {
+            assert!(used_kb().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn over_budget_only_one_waiter_proceeds_when_idle() {
+        let in_flight = AtomicUsize::new(0);
+        assert!(try_admit(&in_flight, 900, 1_000));
+        assert!(try_admit(&in_flight, 900, 1_000));
+        assert_eq!(in_flight.load(Ordering::Relaxed), 2);
+
+        // Over budget with work in flight: wait.
+        assert!(!try_admit(&in_flight, 1_500, 1_000));
+        // Idle again: of several waiters that all see it, one goes ahead.
+        in_flight.store(0, Ordering::Relaxed);
+        let admitted = (0..4)
+            .filter(|_| try_admit(&in_flight, 1_500, 1_000))
+            .count();
+        assert_eq!(admitted, 1);
+        assert_eq!(in_flight.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::dump::load_dump;
+use crate::model::{ContentBlock, Generator, PageData, ScrapedData, SCHEMA_VERSION};
+use crate::output::{
+    aggregate_contact, build_asset_manifest, build_hreflang_clusters, build_hreflang_groups,
+    build_index_md, build_link_report, build_schema_json, build_site_data, detect_quality_warnings,
+    detect_templates,
+};
+use crate::util::normalize_path;
+
+/// Combine dumps page by page. Inputs are taken oldest run first (by
+/// `generated_at`; unknown dates count as oldest, ties keep argument
+/// order), and a page replaces every earlier page that shares its URL or
+/// one of its aliases — newest wins. Each page is returned with the index
+/// of the dump it came from.
+fn merge_pages(dumps: Vec<(String, Vec<PageData>)>) -> Vec<(usize, PageData)> {
+    let mut order: Vec<usize> = (0..dumps.len()).collect();
+    order.sort_by(|&a, &b| dumps[a].0.cmp(&dumps[b].0));
+    let mut dumps: Vec<Option<Vec<PageData>>> = dumps.into_iter().map(|(_, p)| Some(p)).collect();
+
+    let mut slots: Vec<Option<(usize, PageData)>> = Vec::new();
+    let mut by_url: HashMap<String, usize> = HashMap::new();
+    for source in order {
+        for page in dumps[source].take().unwrap_or_default() {
+            let urls: Vec<String> = std::iter::once(&page.url)
+                .chain(&page.aliases)
+                .cloned()
+                .collect();
+            let mut replaced: Vec<usize> =
+                urls.iter().filter_map(|u| by_url.get(u)).copied().collect();
+            replaced.sort_unstable();
+            replaced.dedup();
+            // The newer page takes the first replaced slot, keeping its
+            // position in the output; the others are emptied.
+            let slot = match replaced.first() {
+                Some(&first) => first,
+                None => {
+                    slots.push(None);
+                    slots.len() - 1
+                }
+            };
+            for &old in &replaced {
+                if let Some((_, old_page)) = slots[old].take() {
+                    for u in std::iter::once(&old_page.url).chain(&old_page.aliases) {
+                        by_url.remove(u);
+                    }
+                }
+            }
+            for u in urls {
+                by_url.insert(u, slot);
+            }
+            slots[slot] = Some((source, page));
+        }
+    }
+    slots.into_iter().flatten().collect()
+}
+
+/// Bundle subdirectories that hold page-referenced files.
+const ASSET_DIRS: [&str; 3] = ["images", "videos", "screenshots"];
+
+/// Copy a page's local file (`<dir>/images/…`, `<dir>/videos/…`,
+/// `<dir>/screenshots/…`) from its dump into the same place under
+/// `output`, and return the new path. The file is looked up inside the
+/// dump first, then at the recorded path. `None` if it can't be found.
+/// A different file already under that name (another dump's image that
+/// got the same mirrored or original name) keeps it, and this one goes
+/// next to it with a hash of its bytes appended.
+fn relocate_asset(path: &str, input: &Path, output: &Path) -> Option<String> {
+    let recorded = Path::new(path);
+    let components: Vec<_> = recorded.components().collect();
+    let start = components
+        .iter()
+        .position(|c| ASSET_DIRS.iter().any(|d| c.as_os_str() == *d))?;
+    let relative: PathBuf = components[start..].iter().collect();
+    let source = [input.join(&relative), recorded.to_path_buf()]
+        .into_iter()
+        .find(|p| p.is_file())?;
+    let mut dest = output.join(&relative);
+    std::fs::create_dir_all(dest.parent()?).ok()?;
+    if dest.exists() && !same_contents(&source, &dest) {
+        let bytes = std::fs::read(&source).ok()?;
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let stem = dest.file_stem()?.to_string_lossy();
+        let name = match dest.extension() {
+            Some(ext) => format!("{stem}-{}.{}", &hash[..8], ext.to_string_lossy()),
+            None => format!("{stem}-{}", &hash[..8]),
+        };
+        dest.set_file_name(name);
+    }
+    if !dest.exists() {
+        std::fs::copy(&source, &dest).ok()?;
+    }
+    Some(normalize_path(&dest.to_string_lossy()))
+}
+
+/// Whether two files hold the same bytes.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let len = |p: &Path| std::fs::metadata(p).map(|m| m.len()).ok();
+    len(a).is_some() && len(a) == len(b) && std::fs::read(a).ok() == std::fs::read(b).ok()
+}
+
+/// Point every local path of `page` into `output`, copying the files.
+/// Returns how many referenced files were missing; those paths are kept.
+fn relocate_page_assets(page: &mut PageData, input: &Path, output: &Path) -> usize {
+    let mut missing = 0;
+    let mut fix = |path: &mut String| {
+        if path.is_empty() {
+            return;
+        }
+        match relocate_asset(path, input, output) {
+            Some(new) => *path = new,
+            None => missing += 1,
+        }
+    };
+    for block in &mut page.content_blocks {
+        match block {
+            ContentBlock::Image { local_path, .. } => fix(local_path),
+            ContentBlock::Video {
+                sources,
+                poster_local_path,
+                ..
+            } => {
+                poster_local_path.iter_mut().for_each(&mut fix);
+                sources
+                    .iter_mut()
+                    .filter_map(|s| s.local_path.as_mut())
+                    .for_each(&mut fix);
+            }
+            _ => {}
+        }
+    }
+    page.og_image_local_path.iter_mut().for_each(&mut fix);
+    page.screenshot_desktop.iter_mut().for_each(&mut fix);
+    page.screenshot_mobile.iter_mut().for_each(&mut fix);
+    page.page_assets.iter_mut().for_each(&mut fix);
+    missing
+}
+
+/// `base_url` of the first input's site.json, else the origin of its
+/// first page.
+fn base_url(inputs: &[PathBuf], pages: &[PageData]) -> String {
+    let from_site = inputs.first().and_then(|dir| {
+        let text = std::fs::read_to_string(dir.join("site.json")).ok()?;
+        let site: serde_json::Value = serde_json::from_str(&text).ok()?;
+        site.get("base_url")?.as_str().map(str::to_string)
+    });
+    from_site
+        .or_else(|| {
+            let url = url::Url::parse(&pages.first()?.url).ok()?;
+            Some(format!("{}/", url.origin().ascii_serialization()))
+        })
+        .unwrap_or_default()
+}
+
+/// `dump-it merge`: combine several runs (per-section, per-locale) into
+/// one bundle in `output`. Pages are de-duplicated by URL and alias with
+/// the newest run winning; their images, videos and screenshots are
+/// copied along. Writes scraped.json, site.json, contact.json,
+/// schema.json and index.md.
+pub(crate) fn run_merge(inputs: &[PathBuf], output: &Path) -> anyhow::Result<()> {
+    let mut dumps = Vec::with_capacity(inputs.len());
+    let mut loaded = 0;
+    for input in inputs {
+        let dump = load_dump(input)?;
+        loaded += dump.pages.len();
+        dumps.push((dump.generated_at, dump.pages));
+    }
+    std::fs::create_dir_all(output)?;
+
+    let mut missing = 0;
+    let pages: Vec<PageData> = merge_pages(dumps)
+        .into_iter()
+        .map(|(source, mut page)| {
+            missing += relocate_page_assets(&mut page, &inputs[source], output);
+            page
+        })
+        .collect();
+
+    let base_url = base_url(inputs, &pages);
+    let result = ScrapedData {
+        schema_version: SCHEMA_VERSION.to_string(),
+        generator: Generator::current(),
+        total_pages: pages.len(),
+        pages,
+    };
+    let mut site_data = build_site_data(&result.pages, &base_url, false);
+    site_data.templates = detect_templates(&result.pages);
+    site_data.quality_warnings = detect_quality_warnings(&result.pages, &site_data.templates);
+    site_data.link_report = build_link_report(&result.pages, false, false);
+    site_data.hreflang_groups = build_hreflang_groups(&result.pages);
+    site_data.hreflang_clusters = build_hreflang_clusters(&result.pages);
+    site_data.contact = aggregate_contact(&result.pages);
+
+    std::fs::write(
+        output.join("scraped.json"),
+        serde_json::to_string_pretty(&result)?,
+    )?;
+    std::fs::write(
+        output.join("contact.json"),
+        serde_json::to_string_pretty(&site_data.contact)?,
+    )?;
+    std::fs::write(
+        output.join("schema.json"),
+        serde_json::to_string_pretty(&build_schema_json())?,
+    )?;
+    site_data.output_files = ["scraped.json", "contact.json", "schema.json"]
+        .map(String::from)
+        .to_vec();
+    site_data.assets = build_asset_manifest(output);
+    std::fs::write(
+        output.join("site.json"),
+        serde_json::to_string_pretty(&site_data)?,
+    )?;
+    site_data.output_files.push("site.json".to_string());
+    std::fs::write(
+        output.join("index.md"),
+        build_index_md(&site_data, &result.pages),
+    )?;
+
+    println!(
+        "🧬 Merged {} dumps: {loaded} pages → {} unique",
+        inputs.len(),
+        result.total_pages
+    );
+    if missing > 0 {
+        println!("⚠️  {missing} referenced files were not found and keep their old paths");
+    }
+    println!("📁 Output: {}", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, title: &str, aliases: &[&str]) -> PageData {
+        let mut page = crate::model::test_page(url);
+        page.aliases = aliases.iter().map(|a| a.to_string()).collect();
+        page.title = title.to_string();
+        page.meta_title = title.to_string();
+        page
+    }
+
+    #[test]
+    fn merge_pages_newest_run_wins_across_aliases() {
+        let newer = (
+            "2024-05-01T00:00:00Z".to_string(),
+            vec![
+                page("https://x.com/a", "A v2", &["https://x.com/a?ref=1"]),
+                page("https://x.com/c", "C", &[]),
+            ],
+        );
+        let older = (
+            "2024-01-01T00:00:00Z".to_string(),
+            vec![
+                page("https://x.com/a?ref=1", "A v1", &[]),
+                page("https://x.com/b", "B", &[]),
+            ],
+        );
+        let merged = merge_pages(vec![newer, older]);
+        let titles: Vec<(usize, &str)> = merged
+            .iter()
+            .map(|(src, p)| (*src, p.title.as_str()))
+            .collect();
+        assert_eq!(titles, vec![(0, "A v2"), (1, "B"), (0, "C")]);
+    }
+
+    #[test]
+    fn same_named_assets_of_two_dumps_both_survive() {
+        let root = std::env::temp_dir().join(format!("dump-it-merge-{}", std::process::id()));
+        let (one, two, out) = (root.join("one"), root.join("two"), root.join("out"));
+        for dir in [&one, &two] {
+            std::fs::create_dir_all(dir.join("images")).unwrap();
+        }
+        std::fs::write(one.join("images/logo.png"), "first").unwrap();
+        std::fs::write(two.join("images/logo.png"), "second").unwrap();
+
+        let first = relocate_asset("one/images/logo.png", &one, &out).unwrap();
+        let again = relocate_asset("one/images/logo.png", &one, &out).unwrap();
+        let second = relocate_asset("two/images/logo.png", &two, &out).unwrap();
+        assert_eq!(first, again);
+        assert!(first.ends_with("images/logo.png"));
+        assert_ne!(first, second);
+        assert!(second.contains("images/logo-") && second.ends_with(".png"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,213 @@
+use scraper::{ElementRef, Html};
+use serde_json::{Map, Value as JsonValue};
+use url::Url;
+
+use crate::selectors::{SEL_ITEMSCOPE, SEL_RDFA_TYPEOF};
+use crate::util::element_text;
+
+const SCHEMA_ORG: &str = "https://schema.org";
+
+/// The two attribute-based syntaxes: microdata (`itemscope` / `itemtype`
+/// / `itemprop`) and RDFa Lite (`typeof` / `property`).
+#[derive(Clone, Copy, PartialEq)]
+enum Syntax {
+    Microdata,
+    Rdfa,
+}
+
+impl Syntax {
+    fn is_item(self, el: &ElementRef) -> bool {
+        match self {
+            Syntax::Microdata => el.value().attr("itemscope").is_some(),
+            Syntax::Rdfa => el.value().attr("typeof").is_some(),
+        }
+    }
+
+    fn prop_attr(self) -> &'static str {
+        match self {
+            Syntax::Microdata => "itemprop",
+            Syntax::Rdfa => "property",
+        }
+    }
+
+    fn type_attr(self) -> &'static str {
+        match self {
+            Syntax::Microdata => "itemtype",
+            Syntax::Rdfa => "typeof",
+        }
+    }
+}
+
+/// Microdata and RDFa items on the page as JSON-LD-shaped objects, so
+/// they sit next to the `<script type="application/ld+json">` blobs in
+/// `structured_data` and the same `@type` lookups work: schema.org types
+/// and properties lose their vocabulary prefix (`https://schema.org/Product`
+/// and `schema:Product` → `Product`) and the item gets
+/// `"@context": "https://schema.org"`. Nested items become nested objects,
+/// repeated properties arrays. Only top-level items (no `itemprop` /
+/// `property` of their own) are returned.
+pub(crate) fn extract_microdata(doc: &Html, base: &Url) -> Vec<JsonValue> {
+    let mut out = Vec::new();
+    for (syntax, sel) in [
+        (Syntax::Microdata, &*SEL_ITEMSCOPE),
+        (Syntax::Rdfa, &*SEL_RDFA_TYPEOF),
+    ] {
+        for el in doc.select(sel) {
+            if el.value().attr(syntax.prop_attr()).is_some() {
+                continue;
+            }
+            let mut item = item(&el, syntax, base);
+            if !item.keys().any(|k| !k.starts_with('@')) {
+                continue;
+            }
+            if is_schema_org(&el, syntax) {
+                item.insert("@context".into(), SCHEMA_ORG.into());
+            }
+            out.push(JsonValue::Object(item));
+        }
+    }
+    out
+}
+
+fn item(el: &ElementRef, syntax: Syntax, base: &Url) -> Map<String, JsonValue> {
+    let mut obj = Map::new();
+    if let Some(t) = el
+        .value()
+        .attr(syntax.type_attr())
+        .and_then(|t| t.split_whitespace().next())
+    {
+        obj.insert("@type".into(), local_name(t).into());
+    }
+    for prop in el.descendants().skip(1).filter_map(ElementRef::wrap) {
+        let Some(names) = prop.value().attr(syntax.prop_attr()) else {
+            continue;
+        };
+        if owner(&prop, syntax).map(|o| o.id()) != Some(el.id()) {
+            continue;
+        }
+        let value = if syntax.is_item(&prop) {
+            JsonValue::Object(item(&prop, syntax, base))
+        } else {
+            property_value(&prop, base).into()
+        };
+        for name in names.split_whitespace() {
+            add_value(&mut obj, local_name(name), value.clone());
+        }
+    }
+    obj
+}
+
+/// The item a property belongs to: its nearest item ancestor.
+fn owner<'a>(prop: &ElementRef<'a>, syntax: Syntax) -> Option<ElementRef<'a>> {
+    prop.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|a| syntax.is_item(a))
+}
+
+/// Value of a non-item property: `content` wins (`<meta>`, and the
+/// `<span content="19.99">` sites use for machine-readable values), then
+/// the element's URL or value attribute, then its text.
+fn property_value(el: &ElementRef, base: &Url) -> String {
+    let v = el.value();
+    if let Some(content) = v.attr("content") {
+        return content.trim().to_string();
+    }
+    let url_attr = match v.name() {
+        "a" | "area" | "link" => Some("href"),
+        "img" | "audio" | "video" | "source" | "track" | "iframe" | "embed" => Some("src"),
+        "object" => Some("data"),
+        _ => v.attr("resource").map(|_| "resource"),
+    };
+    if let Some(raw) = url_attr.and_then(|a| v.attr(a)) {
+        return base
+            .join(raw.trim())
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| raw.trim().to_string());
+    }
+    let plain = match v.name() {
+        "time" => v.attr("datetime"),
+        "data" | "meter" => v.attr("value"),
+        _ => None,
+    };
+    plain
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| element_text(el))
+}
+
+fn add_value(obj: &mut Map<String, JsonValue>, name: &str, value: JsonValue) {
+    match obj.get_mut(name) {
+        None => {
+            obj.insert(name.to_string(), value);
+        }
+        Some(JsonValue::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = JsonValue::Array(vec![first, value]);
+        }
+    }
+}
+
+/// `https://schema.org/Product`, `http://schema.org/Product` and
+/// `schema:Product` → `Product`; other vocabularies stay as written.
+fn local_name(term: &str) -> &str {
+    ["https://schema.org/", "http://schema.org/", "schema:"]
+        .iter()
+        .find_map(|p| term.strip_prefix(p))
+        .unwrap_or(term)
+}
+
+/// Item typed from schema.org, directly or (RDFa) via a `vocab` on it or
+/// an ancestor.
+fn is_schema_org(el: &ElementRef, syntax: Syntax) -> bool {
+    let typed = el
+        .value()
+        .attr(syntax.type_attr())
+        .is_some_and(|t| t.contains("schema.org") || t.starts_with("schema:"));
+    typed
+        || (syntax == Syntax::Rdfa
+            && std::iter::once(*el)
+                .chain(el.ancestors().filter_map(ElementRef::wrap))
+                .find_map(|a| a.value().attr("vocab"))
+                .is_some_and(|v| v.contains("schema.org")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn microdata_and_rdfa_become_json_ld_objects() {
+        let doc = Html::parse_document(
+            "<div itemscope itemtype='https://schema.org/Product'>\
+               <h1 itemprop='name'>Kettle</h1><img itemprop='image' src='/k.jpg'>\
+               <div itemprop='offers' itemscope itemtype='https://schema.org/Offer'>\
+                 <span itemprop='price' content='19.99'>$19.99</span>\
+                 <meta itemprop='priceCurrency' content='USD'></div>\
+               <span itemprop='color'>Red</span><span itemprop='color'>Blue</span></div>\
+             <div vocab='https://schema.org/' typeof='LocalBusiness'>\
+               <span property='name'>Joe's Bakery</span>\
+               <a property='url' href='https://joes.example'>site</a></div>",
+        );
+        let base = Url::parse("https://shop.example/p/1").unwrap();
+        let items = extract_microdata(&doc, &base);
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!({
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Kettle",
+                    "image": "https://shop.example/k.jpg",
+                    "offers": {"@type": "Offer", "price": "19.99", "priceCurrency": "USD"},
+                    "color": ["Red", "Blue"],
+                }),
+                serde_json::json!({
+                    "@context": "https://schema.org",
+                    "@type": "LocalBusiness",
+                    "name": "Joe's Bakery",
+                    "url": "https://joes.example/",
+                }),
+            ]
+        );
+    }
+}
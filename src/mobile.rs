@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use crate::model::{ContentBlock, MobileComparison};
+use crate::util::{count_words, hash_blocks};
+
+/// User agent of the `--compare-mobile` fetch: iPhone Safari, since
+/// UA-sniffing sites key on `iPhone` / `Mobile`.
+pub(crate) const MOBILE_USER_AGENT: &str =
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) \
+     AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+
+/// Viewport of the mobile render — the same as the mobile screenshot.
+pub(crate) const MOBILE_VIEWPORT: (u32, u32) = (390, 844);
+
+/// Share of desktop blocks a mobile page may lack before it gets the
+/// `mobile_content_gap` quality flag.
+pub(crate) const MOBILE_PARITY_MIN: f64 = 0.9;
+
+/// One side of the comparison: what was extracted from one fetch.
+pub(crate) struct Variant<'a> {
+    pub title: &'a str,
+    pub canonical_url: Option<&'a str>,
+    pub blocks: &'a [ContentBlock],
+    pub internal_links: usize,
+    pub structured_data: usize,
+}
+
+fn headings(blocks: &[ContentBlock]) -> Vec<&str> {
+    blocks
+        .iter()
+        .filter_map(|b| match b {
+            ContentBlock::Heading { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn images(blocks: &[ContentBlock]) -> usize {
+    blocks
+        .iter()
+        .filter(|b| matches!(b, ContentBlock::Image { .. }))
+        .count()
+}
+
+/// Compare the desktop and mobile extraction of one page. `content_parity`
+/// is the share of desktop blocks (normalized like `content_hash`) that
+/// also appear on mobile; headings are listed by text either way.
+pub(crate) fn compare(desktop: &Variant, mobile: &Variant) -> MobileComparison {
+    let fingerprint = |b: &ContentBlock| hash_blocks(std::slice::from_ref(b));
+    let mobile_prints: HashSet<String> = mobile.blocks.iter().map(fingerprint).collect();
+    let kept = desktop
+        .blocks
+        .iter()
+        .filter(|b| mobile_prints.contains(&fingerprint(b)))
+        .count();
+    let content_parity = if desktop.blocks.is_empty() {
+        1.0
+    } else {
+        (kept as f64 / desktop.blocks.len() as f64 * 1000.0).round() / 1000.0
+    };
+
+    let desktop_headings = headings(desktop.blocks);
+    let mobile_headings = headings(mobile.blocks);
+    let only_in = |a: &[&str], b: &[&str]| -> Vec<String> {
+        a.iter()
+            .filter(|h| !b.contains(*h))
+            .map(|h| h.to_string())
+            .collect()
+    };
+
+    MobileComparison {
+        content_parity,
+        desktop_words: count_words(desktop.blocks),
+        mobile_words: count_words(mobile.blocks),
+        desktop_images: images(desktop.blocks),
+        mobile_images: images(mobile.blocks),
+        desktop_internal_links: desktop.internal_links,
+        mobile_internal_links: mobile.internal_links,
+        desktop_structured_data: desktop.structured_data,
+        mobile_structured_data: mobile.structured_data,
+        title_matches: desktop.title.trim() == mobile.title.trim(),
+        canonical_matches: desktop.canonical_url == mobile.canonical_url,
+        missing_headings: only_in(&desktop_headings, &mobile_headings),
+        extra_headings: only_in(&mobile_headings, &desktop_headings),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(text: &str) -> ContentBlock {
+        ContentBlock::Heading {
+            level: 2,
+            text: text.into(),
+            markdown: None,
+        }
+    }
+
+    fn paragraph(text: &str) -> ContentBlock {
+        ContentBlock::Paragraph {
+            text: text.into(),
+            links: Vec::new(),
+            markdown: None,
+        }
+    }
+
+    #[test]
+    fn mobile_variant_missing_blocks_lowers_parity() {
+        let desktop = [
+            heading("Pricing"),
+            paragraph("Three plans for every team size."),
+            heading("FAQ"),
+            paragraph("Answers to common questions."),
+        ];
+        let mobile = [
+            heading("Pricing"),
+            paragraph("Three   plans for every team size."),
+            heading("Download the app"),
+        ];
+        let side = |blocks| Variant {
+            title: "Plans",
+            canonical_url: Some("https://x.com/pricing"),
+            blocks,
+            internal_links: 10,
+            structured_data: 1,
+        };
+        let cmp = compare(&side(&desktop[..]), &side(&mobile[..]));
+        assert_eq!(cmp.content_parity, 0.5);
+        assert_eq!(cmp.missing_headings, vec!["FAQ"]);
+        assert_eq!(cmp.extra_headings, vec!["Download the app"]);
+        assert_eq!((cmp.desktop_words, cmp.mobile_words), (12, 10));
+        assert!(cmp.title_matches && cmp.canonical_matches);
+    }
+}
@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub(crate) struct FormField {
     pub field_type: String,
     pub name: String,
@@ -9,25 +10,86 @@ pub(crate) struct FormField {
     pub placeholder: String,
     pub required: bool,
     pub options: Vec<String>,
+    /// `value` of a hidden input (`--include-hidden-fields`); CSRF tokens,
+    /// tracking ids, form plugin ids. `[redacted]` with
+    /// `--redact-hidden-values`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// HTML validation attributes, verbatim. `min` / `max` / `step` stay
+    /// strings: they hold dates and times as well as numbers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minlength: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maxlength: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    /// `inputmode` keyboard hint (`numeric`, `tel`, `email`, …).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputmode: Option<String>,
+    /// `accept` of a file input: MIME types / extensions, verbatim
+    /// (`image/*,.pdf`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+    /// `multiple` on a file, email or select field.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub multiple: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// A `<fieldset>` or one step of a multi-step form.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct FormSection {
+    /// `<legend>`, else the first heading inside the section.
+    pub legend: String,
+    /// 1-based step number on multi-step forms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>,
+    /// Indices into the form's `fields`.
+    pub fields: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub(crate) enum ContentBlock {
     Heading {
         level: u8,
         text: String,
+        /// `text` with inline Markdown (links, emphasis, code), with
+        /// `--rich-text` only.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markdown: Option<String>,
     },
     Paragraph {
         text: String,
+        /// Links inside the paragraph, in document order.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        links: Vec<InlineLink>,
+        /// `text` with inline Markdown, with `--rich-text` only.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markdown: Option<String>,
     },
     Image {
         original_url: String,
         local_path: String,
         alt_text: String,
+        /// The SVG document itself, for SVGs up to `--inline-svg-max` bytes.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        svg_markup: Option<String>,
+        /// Unused `<picture>` / `data-src` / `srcset` candidates, tried in
+        /// order when `original_url` fails to download. Not serialised.
+        #[serde(skip)]
+        fallback_urls: Vec<String>,
     },
     List {
         items: Vec<String>,
+        /// `items` with inline Markdown, with `--rich-text` only.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        markdown_items: Vec<String>,
     },
     Form {
         action: String,
@@ -39,13 +101,33 @@ pub(crate) enum ContentBlock {
         /// types, placeholders.
         #[serde(default)]
         purpose: String,
+        /// Fields grouped by `<fieldset>`, or by step on multi-step forms.
+        /// `fields` stays the flat list; fields outside every section are
+        /// only there.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        sections: Vec<FormSection>,
+        /// Wizard-style form: step containers, or several fieldsets with a
+        /// "Next" button.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        multi_step: bool,
     },
     /// `<iframe>` + common video embeds (YouTube, Vimeo, Maps).
-    /// `provider` is the recognised platform or `iframe` fallback.
+    /// `provider` is the recognised platform or `iframe` fallback. For
+    /// YouTube / Vimeo / Spotify / SoundCloud, `id` is the content id and
+    /// `url` its public page; `title` falls back to the provider's oEmbed
+    /// title, which also supplies `author_name` / `thumbnail_url`.
     Embed {
         provider: String,
         src: String,
         title: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        author_name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thumbnail_url: Option<String>,
     },
     /// HTML `<table>` with structured rows + optional column headers.
     /// Captures classic table-based layouts (Hacker News, Wikipedia,
@@ -73,8 +155,8 @@ pub(crate) enum ContentBlock {
         #[serde(default, skip_serializing_if = "Option::is_none")]
         cite: Option<String>,
     },
-    /// `<video>` or `<audio>` element. `src` is the best-resolution `<source>`
-    /// or direct `src` attribute. `kind` is `"video"` or `"audio"`.
+    /// `<audio>` element. `src` is the first `<source>` or the direct `src`
+    /// attribute. `kind` is `"audio"`; `<video>` has its own block.
     Media {
         kind: String,
         src: String,
@@ -83,20 +165,75 @@ pub(crate) enum ContentBlock {
         #[serde(default, skip_serializing_if = "String::is_empty")]
         title: String,
     },
+    /// `<video>` element: every `<source>` (or the direct `src`) in
+    /// document order, the poster and the `<track>` captions. The poster
+    /// goes through the image phase; with `--video-max-bytes` the first
+    /// source that fits is downloaded too.
+    Video {
+        sources: Vec<VideoSource>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        poster_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        poster_local_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        captions: Vec<VideoCaption>,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        title: String,
+    },
     /// `<dl>` definition list — key/value pairs that aren't a table and
     /// aren't a heading/paragraph sequence.
     DefinitionList {
         items: Vec<DefinitionItem>,
     },
+    /// Call to action: an `<a>` styled as a button (`btn` / `button` /
+    /// `cta` class, `role="button"`) or a `<button>` outside a form.
+    /// `href` is resolved to an absolute URL; absent for buttons with no
+    /// `data-href` / `data-url` / `onclick` location target.
+    Cta {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        href: Option<String>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct VideoSource {
+    pub url: String,
+    /// The `<source type>` MIME type, e.g. `video/webm`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Set only with `--video-max-bytes`, on the source that was stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_path: Option<String>,
+}
+
+/// `<track kind="captions|subtitles">`.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct VideoCaption {
+    pub url: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srclang: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// An `<a href>` inside a paragraph: anchor text (or `aria-label` /
+/// `title` / image alt for icon links) and the resolved href. Unlike
+/// `PageLink`, `mailto:`, `tel:` and fragments are kept.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct InlineLink {
+    pub text: String,
+    pub href: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub(crate) struct DefinitionItem {
     pub term: String,
     pub description: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub(crate) struct NavLink {
     pub text: String,
     pub href: String,
@@ -114,13 +251,117 @@ pub(crate) struct NavLink {
     pub role: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// One `<a href>` on a page, resolved to an absolute http(s) URL with the
+/// fragment stripped.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct PageLink {
+    pub url: String,
+    /// Visible anchor text; falls back to `aria-label` / `title` / the
+    /// alt text of a wrapped image for icon links.
+    #[serde(default)]
+    pub text: String,
+    /// Lower-cased `rel` tokens (`nofollow`, `ugc`, `sponsored`, …).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rel: Vec<String>,
+    /// Same host as the page it was found on.
+    pub internal: bool,
+}
+
+impl PageLink {
+    /// `rel="nofollow"`, `ugc` or `sponsored` — the owner's hint that the
+    /// target isn't endorsed content.
+    pub fn is_unendorsed(&self) -> bool {
+        self.rel
+            .iter()
+            .any(|r| matches!(r.as_str(), "nofollow" | "ugc" | "sponsored"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub(crate) struct HreflangAlternate {
     pub lang: String,
     pub url: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// `--compare-mobile`: the page fetched again with a mobile user agent
+/// (and viewport, when rendering), against the desktop fetch.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct MobileComparison {
+    /// Share of desktop content blocks also present on mobile, 0–1.
+    pub content_parity: f64,
+    pub desktop_words: usize,
+    pub mobile_words: usize,
+    pub desktop_images: usize,
+    pub mobile_images: usize,
+    pub desktop_internal_links: usize,
+    pub mobile_internal_links: usize,
+    /// Structured-data items (JSON-LD, microdata, RDFa) on each.
+    pub desktop_structured_data: usize,
+    pub mobile_structured_data: usize,
+    pub title_matches: bool,
+    pub canonical_matches: bool,
+    /// Desktop headings the mobile page lacks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_headings: Vec<String>,
+    /// Mobile-only headings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_headings: Vec<String>,
+}
+
+/// One `<link rel>` relation. A `<link>` with several rel tokens
+/// (`rel="preload stylesheet"`) yields one entry per token.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct LinkRelation {
+    /// Lowercased rel token: `canonical`, `amphtml`, `alternate`,
+    /// `preload`, `prefetch`, `preconnect`, `manifest`, `icon`, …
+    pub rel: String,
+    /// Absolute URL.
+    pub href: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hreflang: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    /// `as` of a preload (`font`, `script`, `image`, …).
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub as_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// One product of a page (`--preset product`).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub(crate) struct ProductRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    /// Absolute URL of the product, when the data names one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Decimal amount with `.` as separator, e.g. `1299.00`; the lowest
+    /// price of an `AggregateOffer`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    /// ISO 4217 code, e.g. `EUR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// schema.org `ItemAvailability` name: `InStock`, `OutOfStock`,
+    /// `PreOrder`, …
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability: Option<String>,
+    /// Absolute image URLs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    /// "structured_data" (JSON-LD / microdata / RDFa) or "selectors"
+    /// (product meta tags and price / SKU markup).
+    pub source: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub(crate) struct PageSection {
     pub section_type: String, // "hero" | "features" | "cta" | "testimonials" | "team" | "faq" | "content"
     pub block_start: usize,
@@ -128,6 +369,69 @@ pub(crate) struct PageSection {
     pub summary: String,
 }
 
+/// One node of a page's heading outline. Children are the headings of a
+/// deeper level that follow it, up to the next heading of the same or a
+/// shallower level — a skipped level (h2 → h4) still nests.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct OutlineEntry {
+    pub level: u8,
+    pub text: String,
+    /// Index of the Heading block in `content_blocks`.
+    pub block_index: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Readability / thin-content signals computed alongside `total_words`.
+#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub(crate) struct ContentMetrics {
+    /// `total_words` at 230 words per minute, rounded up. 0 for empty pages.
+    pub reading_time_minutes: u32,
+    /// Visible body text bytes / HTML document bytes (0.0–1.0). Scripts,
+    /// styles and inline SVG don't count as text.
+    pub text_to_html_ratio: f64,
+    pub paragraph_count: usize,
+    pub heading_count: usize,
+    /// Average words per Paragraph block.
+    pub words_per_paragraph: f64,
+    /// Paragraph blocks per Heading block — high values mean walls of text
+    /// with no structure, 0 with headings present means headings only.
+    pub paragraphs_per_heading: f64,
+}
+
+/// A TF-IDF-ranked term or two-word phrase (`--keywords`).
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct Keyword {
+    pub term: String,
+    pub score: f64,
+}
+
+/// One line of chunks.jsonl (`--format chunks`): a slice of a page's text
+/// sized for embedding, with enough context to cite it back.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct Chunk {
+    /// `<url>#chunk-<chunk_index>`; stable while the page content is.
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    /// Headings enclosing the text, outermost first. Empty before the
+    /// page's first heading.
+    pub heading_path: Vec<String>,
+    /// 0-based position among the page's chunks.
+    pub chunk_index: usize,
+    /// 0-based heading-delimited section the chunk came from; chunks never
+    /// span two sections.
+    pub section_index: usize,
+    /// Character offsets of `text` within its section.
+    pub char_start: usize,
+    pub char_end: usize,
+    pub token_estimate: usize,
+    pub text: String,
+    /// Vector from the `--embed` endpoint; absent without `--embed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
 #[derive(Serialize, Clone)]
 pub(crate) struct PageTemplate {
     pub template_id: String,
@@ -137,9 +441,34 @@ pub(crate) struct PageTemplate {
     pub sample_page: String,
 }
 
-#[derive(Serialize, Deserialize)]
+/// A content block repeated across the run — cookie banner, newsletter
+/// CTA, footer inside `<main>` (`--boilerplate-threshold`).
+#[derive(Serialize, Clone)]
+pub(crate) struct BoilerplateBlock {
+    /// First 16 hex chars of the block's normalized hash (whitespace
+    /// collapsed, `local_path` dropped — same rules as `content_hash`).
+    pub fingerprint: String,
+    /// The block's `type`: `paragraph`, `form`, `image`, …
+    pub block_type: String,
+    /// Start of the block's text, to recognise it by.
+    pub sample: String,
+    pub page_count: usize,
+    /// `page_count` / total pages (0.0–1.0).
+    pub page_share: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub(crate) struct PageData {
     pub url: String,
+    /// Other URLs that produced byte-identical content blocks and were
+    /// collapsed into this page (`?sort=`, `/index.html`, print views, …).
+    /// Empty with `--keep-duplicates`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// `<lastmod>` / `<changefreq>` / `<priority>` from the sitemap entry
+    /// this URL came from. `None` in crawl mode or when the entry had none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sitemap: Option<SitemapMeta>,
     pub title: String,
     pub meta_title: String,
     pub meta_description: String,
@@ -158,19 +487,70 @@ pub(crate) struct PageData {
     /// `meta_robots_noindex` when relevant.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub meta_robots: Option<String>,
+    /// Every `<meta>` tag: `name` (lowercased), `property`, `http-equiv`
+    /// (as `http-equiv:<value>`, lowercased) or `charset` → its `content`
+    /// values in document order. Repeated tags (`og:image`, `article:tag`)
+    /// keep all values.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub meta: std::collections::BTreeMap<String, Vec<String>>,
+    /// Raw `X-Robots-Tag` response header lines (HTTP path only — Chrome
+    /// doesn't expose response headers). Combined with `meta_robots` for
+    /// the noindex / nofollow flags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub x_robots_tag: Vec<String>,
     #[serde(default)]
     pub hreflang_alternates: Vec<HreflangAlternate>,
+    /// Every `<link rel href>` in the document, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link_relations: Vec<LinkRelation>,
     pub nav_links: Vec<NavLink>,
     pub footer_blocks: Vec<ContentBlock>,
     pub structured_data: Vec<JsonValue>,
+    /// `--preset product`: the products the page offers, from schema.org
+    /// Product / Offer data or, without any, its price / SKU markup.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub products: Vec<ProductRecord>,
     pub content_blocks: Vec<ContentBlock>,
+    /// Indices into `content_blocks` of blocks found on more than
+    /// `--boilerplate-threshold` % of pages (see site.json:boilerplate).
+    /// Empty with `--strip-boilerplate`, which removes them instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boilerplate_blocks: Vec<usize>,
+    /// The page itself is AMP (`<html amp>` / `<html ⚡>`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_amp: bool,
+    /// `<link rel="amphtml">` target: the page's AMP variant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amp_url: Option<String>,
+    /// Under `--amp prefer` / `both`: the variant `content_blocks` came
+    /// from, `amp` or `canonical` (no variant, or its fetch failed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_variant: Option<String>,
+    /// `--amp both`: content blocks of the AMP variant.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub amp_content_blocks: Vec<ContentBlock>,
+    /// `--compare-mobile`: differences of the mobile fetch. `None` when
+    /// off or the mobile fetch failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mobile_comparison: Option<MobileComparison>,
+    /// The signal that marked this 200 response as an error page,
+    /// `error_template` or `not_found_phrase` (such pages are left out
+    /// under `--skip-soft-404`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_404: Option<String>,
+    /// The content looks gated and is probably incomplete: `paywall`
+    /// (subscribe prompt, paywall overlay or `isAccessibleForFree: false`)
+    /// or `login_wall` (log-in prompt or password field on a short page).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_wall: Option<String>,
     /// Concatenated text of every heading/paragraph/list-item block.
     /// Useful for full-text search and cheap LLM context.
     #[serde(default)]
     pub plain_text: String,
-    /// SHA-256 hex (first 16 chars) of the page's plain_text — lets the
-    /// agent spot boilerplate / duplicate content across pages and detect
-    /// changes between runs.
+    /// SHA-256 hex over the normalized content blocks (whitespace collapsed,
+    /// run-specific `local_path`s dropped) — stable across runs, so
+    /// downstream systems can detect changes without diffing structures.
+    /// Empty when the page has no blocks.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub content_hash: String,
     /// Rough LLM-token estimate (plain_text chars / 4) so an agent can
@@ -191,17 +571,29 @@ pub(crate) struct PageData {
     /// "this is a hero, that's a features grid, that's a CTA" hints.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sections: Vec<PageSection>,
+    /// Nested table of contents built from the Heading blocks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outline: Vec<OutlineEntry>,
+    /// Top TF-IDF terms for this page. Only with `--keywords N`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<Keyword>,
     /// SEO / accessibility flags ("no_h1", "no_meta_description",
     /// "images_missing_alt:3", "thin_content", "title_too_long", …).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub quality_flags: Vec<String>,
     pub total_words: usize,
+    #[serde(default)]
+    pub content_metrics: ContentMetrics,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_contact: Option<ContactInfo>,
     /// Internal anchor hrefs (resolved to absolute) pointing at pages on the
     /// same site. Used to build the link graph aggregate.
     #[serde(default)]
     pub internal_links_out: Vec<String>,
+    /// Every outgoing anchor (internal and external) with its text and rel,
+    /// in document order. Duplicate url + text pairs are collapsed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<PageLink>,
     /// Concatenated <style> block text — used post-scrape to mine colors
     /// and fonts. Not serialised (skipped) to keep page JSON readable.
     #[serde(skip)]
@@ -215,10 +607,162 @@ pub(crate) struct PageData {
     pub screenshot_desktop: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub screenshot_mobile: Option<String>,
+    /// Final HTTP status of the document (`200`, `203`, `206`, …). `None`
+    /// when Chrome rendered the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// Where the content actually came from when the request was
+    /// redirected (HTTP redirects, and JS / meta-refresh redirects on the
+    /// Chrome path). `url` stays the requested URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// `--solve-challenges`: vendor of the bot-protection challenge headless
+    /// Chrome got past to fetch this page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge_passed: Option<String>,
+    /// Bytes received on the wire for the HTML document (compressed size).
+    /// `None` when Chrome rendered the page — its network layer is opaque.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_bytes: Option<u64>,
+    /// Size of the decoded HTML document in bytes.
+    #[serde(default)]
+    pub decoded_bytes: u64,
+    /// `transfer_bytes / decoded_bytes`, rounded to 3 decimals: 0.2 is a
+    /// well-compressed page, 1.0 an uncompressed one. `None` when
+    /// `transfer_bytes` is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    /// `Content-Encoding` the server answered with (`gzip`, `br`,
+    /// `deflate`). `None` means uncompressed or unknown (Chrome path).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
 }
 
-#[derive(Serialize)]
+/// A page at `url` with every other field empty, for tests to fill in
+/// what they need.
+#[cfg(test)]
+pub(crate) fn test_page(url: &str) -> PageData {
+    serde_json::from_value(serde_json::json!({
+        "url": url,
+        "title": "",
+        "meta_title": "",
+        "meta_description": "",
+        "canonical_url": null,
+        "language": null,
+        "favicon_url": null,
+        "logo_url": null,
+        "nav_links": [],
+        "footer_blocks": [],
+        "structured_data": [],
+        "content_blocks": [],
+        "total_words": 0,
+    }))
+    .unwrap()
+}
+
+/// Optional fields of a sitemap `<url>` entry, kept verbatim apart from
+/// trimming; `priority` is dropped when it isn't a number.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
+pub(crate) struct SitemapMeta {
+    /// W3C datetime, e.g. `2024-03-01` or `2024-03-01T12:00:00+00:00`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lastmod: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changefreq: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f32>,
+    /// `<image:image>` entries (Google image sitemap extension).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<SitemapImage>,
+    /// `<video:video>` entries (Google video sitemap extension).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub videos: Vec<SitemapVideo>,
+    /// `<news:news>` (Google News sitemap extension).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub news: Option<SitemapNews>,
+}
+
+/// One `<image:image>` of a sitemap entry.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
+pub(crate) struct SitemapImage {
+    pub loc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// `--sitemap-media`: the downloaded file under `images/`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_path: Option<String>,
+}
+
+/// One `<video:video>` of a sitemap entry.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
+pub(crate) struct SitemapVideo {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_loc: Option<String>,
+    /// The media file itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_loc: Option<String>,
+    /// Embeddable player URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_loc: Option<String>,
+    /// Seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publication_date: Option<String>,
+    /// `--sitemap-media`: the downloaded thumbnail under `images/`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_local_path: Option<String>,
+}
+
+/// `<news:news>` of a sitemap entry.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, JsonSchema)]
+pub(crate) struct SitemapNews {
+    /// `<news:publication><news:name>`.
+    pub publication: String,
+    /// `<news:publication><news:language>`, ISO 639.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publication_date: Option<String>,
+    pub title: String,
+}
+
+/// Layout version of scraped.json. Bumped MAJOR when a field is removed,
+/// renamed or changes type, or a ContentBlock variant changes shape;
+/// MINOR when fields or block types are only added. Consumers should
+/// reject an unknown MAJOR and ignore unknown fields.
+pub(crate) const SCHEMA_VERSION: &str = "3.1";
+
+/// Which build of dump-it wrote the bundle, and when.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub(crate) struct Generator {
+    pub name: String,
+    pub version: String,
+    /// RFC 3339 UTC timestamp of the run.
+    pub generated_at: String,
+}
+
+impl Generator {
+    pub fn current() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: crate::util::now_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 pub(crate) struct ScrapedData {
+    pub schema_version: String,
+    pub generator: Generator,
     pub total_pages: usize,
     pub pages: Vec<PageData>,
 }
@@ -242,13 +786,13 @@ pub(crate) struct PageSummary {
     pub internal_links_in: usize,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, JsonSchema)]
 pub(crate) struct SocialLink {
     pub platform: String,
     pub url: String,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, JsonSchema)]
 pub(crate) struct ContactInfo {
     pub emails: Vec<String>,
     pub phones: Vec<String>,
@@ -329,6 +873,33 @@ pub(crate) struct HreflangGroup {
     pub urls: Vec<String>,
 }
 
+/// One piece of content across its translations: every `hreflang` →
+/// URL pair a page (and its alternates) declare, `x-default` included.
+#[derive(Serialize, Clone)]
+pub(crate) struct HreflangCluster {
+    pub alternates: std::collections::BTreeMap<String, String>,
+    /// Which of the alternates were actually scraped in this run.
+    pub scraped: Vec<String>,
+}
+
+/// A machine-readable source the site offers besides its HTML: a feed, a
+/// sitemap or an API manifest.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct DataSource {
+    /// "rss" | "atom" | "json_feed" | "sitemap" | "wordpress_rest" |
+    /// "openapi" | "api_catalog" | "openid_configuration".
+    pub kind: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Where it was found: "link" (a page's `<link>`), "robots.txt" or
+    /// "well_known" (a fixed path that answered).
+    pub found: String,
+    /// Scraped pages that `<link>` it, whatever `found` says: a probed
+    /// manifest the pages also link counts them too.
+    pub pages: usize,
+}
+
 #[derive(Serialize)]
 pub(crate) struct SiteData {
     pub base_url: String,
@@ -339,7 +910,14 @@ pub(crate) struct SiteData {
     pub contact: ContactInfo,
     pub brand: BrandPalette,
     pub templates: Vec<PageTemplate>,
+    /// Blocks on more than `--boilerplate-threshold` % of pages, most
+    /// widespread first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boilerplate: Vec<BoilerplateBlock>,
     pub hreflang_groups: Vec<HreflangGroup>,
+    /// Translation sets — cross-reference one page to its other locales.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hreflang_clusters: Vec<HreflangCluster>,
     pub sitemap: Vec<PageSummary>,
     pub total_pages: usize,
     pub assets: Vec<AssetEntry>,
@@ -358,11 +936,179 @@ pub(crate) struct SiteData {
     /// Pairs with `partial_scrape_bot_protected` warning above.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub skipped_pages: Vec<SkippedPage>,
+    /// `bot_protected` skips per host and challenge vendor, most pages
+    /// first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub challenges: Vec<ChallengeHost>,
+    /// Images whose every candidate URL failed to download (after
+    /// retries). Filtered images (tracking pixels, tiny files) aren't
+    /// listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_images: Vec<FailedImage>,
+    /// Wire vs decoded byte totals for the page documents.
+    pub bandwidth: BandwidthStats,
+    /// External domains referenced and orphan pages.
+    pub link_report: LinkReport,
+    /// Pages grouped by URL path segment, rooted at `/`.
+    pub tree: SiteTreeNode,
+    /// Site-wide top terms (`--keywords N`): per-page TF-IDF scores summed
+    /// across pages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<Keyword>,
+    /// Feeds, sitemaps and API manifests the site links or answers at a
+    /// well-known path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_sources: Vec<DataSource>,
+}
+
+/// One URL path segment of `site.json:tree`. Intermediate segments with
+/// no scraped page of their own still appear, titled from a JSON-LD
+/// `BreadcrumbList` when one names them.
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct SiteTreeNode {
+    /// Path segment; empty for the root.
+    pub segment: String,
+    /// Path from the root, e.g. `/blog/2024`.
+    pub path: String,
+    /// The scraped page at exactly this path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Page title, else the breadcrumb name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Scraped pages at or below this node.
+    pub page_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SiteTreeNode>,
+}
+
+/// An outside domain the scraped pages link to, for `link_report`.
+#[derive(Serialize, Clone)]
+pub(crate) struct ExternalDomain {
+    /// Host with any leading `www.` stripped.
+    pub domain: String,
+    /// Total anchors pointing at this domain.
+    pub links: usize,
+    /// Distinct pages linking to it at least once.
+    pub pages: usize,
+}
+
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct LinkReport {
+    /// Sorted by `links`, most-referenced first.
+    pub external_domains: Vec<ExternalDomain>,
+    /// Sitemap URLs no other scraped page links to. Only computed when the
+    /// URL list came from a sitemap — crawled pages are linked by
+    /// construction. The site root is never reported.
+    pub orphan_pages: Vec<String>,
+}
+
+/// Bandwidth totals over the pages whose wire size is known (plain-HTTP
+/// fetches). Chrome-rendered pages are not counted.
+#[derive(Serialize, Default, Clone)]
+pub(crate) struct BandwidthStats {
+    pub pages_measured: usize,
+    pub transfer_bytes: u64,
+    pub decoded_bytes: u64,
+    /// `transfer_bytes / decoded_bytes` over the measured pages; 1.0 means
+    /// nothing was compressed.
+    pub compression_ratio: f64,
+    /// Measured pages of at least `UNCOMPRESSED_MIN_BYTES` served without
+    /// a `Content-Encoding`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub uncompressed_pages: Vec<String>,
+}
+
+/// An image none of whose candidate URLs could be downloaded.
+#[derive(Serialize, Clone)]
+pub(crate) struct FailedImage {
+    pub url: String,
+    /// First page that referenced it.
+    pub page_url: String,
+    /// "http_error" | "fetch_failed" | "write_failed" — the last
+    /// candidate's failure.
+    pub reason: String,
 }
 
 #[derive(Serialize, Clone)]
 pub(crate) struct SkippedPage {
     pub url: String,
-    /// "bot_protected" | "render_failed" | "http_error" | "robots_disallow".
+    /// "bot_protected" | "render_failed" | "http_error" | "robots_disallow"
+    /// | "body_too_large" | "page_budget_exceeded" | "noindex" | "soft_404"
+    /// | "query_variants" | "invalid_json" | "graphql_error".
     pub reason: String,
+    /// `bot_protected` only: the vendor whose challenge page came back —
+    /// "cloudflare" | "perimeterx" | "akamai" | "datadome".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+}
+
+impl SkippedPage {
+    /// Whether a fetch was tried: `query_variants` URLs are left out at
+    /// discovery and don't count towards the partial-scrape rate.
+    pub fn was_attempted(&self) -> bool {
+        self.reason != "query_variants"
+    }
+}
+
+/// Pages of one host that got a bot-protection challenge instead of
+/// content (see `SkippedPage.challenge`).
+#[derive(Serialize, Clone)]
+pub(crate) struct ChallengeHost {
+    pub host: String,
+    pub vendor: String,
+    /// Pages skipped as `bot_protected`.
+    pub pages: usize,
+    /// `--solve-challenges`: pages whose challenge Chrome got past.
+    pub passed: usize,
+}
+
+/// `--mode json-api` output: the records of every API page fetched.
+#[derive(Serialize, Clone)]
+pub(crate) struct JsonApiDump {
+    pub generator: Generator,
+    /// The endpoints the crawl started from.
+    pub sources: Vec<String>,
+    /// Every API page fetched, pagination included, in order.
+    pub requests: Vec<String>,
+    pub records: Vec<JsonRecord>,
+    pub skipped_pages: Vec<SkippedPage>,
+}
+
+/// One item of an API response (`--json-records`).
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct JsonRecord {
+    /// The API page it came from.
+    pub source_url: String,
+    /// `--json-field url=…`, resolved against `source_url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `--json-field title=…`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The other `--json-field`s by name; a path matching several values
+    /// gives an array.
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub fields: std::collections::BTreeMap<String, JsonValue>,
+    /// The whole item, when no `--json-field` is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<JsonValue>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_follows_the_serialized_shape() {
+        let schema = serde_json::to_value(schemars::schema_for!(ScrapedData)).unwrap();
+        assert_eq!(schema["title"], "ScrapedData");
+        let definitions = &schema["definitions"];
+        assert!(definitions["ContentBlock"].is_object());
+        let page = &definitions["PageData"]["properties"];
+        assert!(page["url"].is_object());
+        assert!(page["links"].is_object());
+        // `#[serde(skip)]` fields never reach scraped.json.
+        assert!(page.get("style_text").is_none());
+    }
 }
@@ -0,0 +1,106 @@
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use url::Url;
+
+use crate::model::{ContentBlock, PageData};
+
+/// The subset of an oEmbed response we keep.
+#[derive(Deserialize, Default)]
+struct OEmbed {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    author_name: Option<String>,
+    #[serde(default)]
+    thumbnail_url: Option<String>,
+}
+
+/// oEmbed endpoint for the providers `embed_id_and_url` recognises.
+fn endpoint(provider: &str) -> Option<&'static str> {
+    match provider {
+        "youtube" => Some("https://www.youtube.com/oembed"),
+        "vimeo" => Some("https://vimeo.com/api/oembed.json"),
+        "spotify" => Some("https://open.spotify.com/oembed"),
+        "soundcloud" => Some("https://soundcloud.com/oembed"),
+        _ => None,
+    }
+}
+
+/// Fill in embeds from their provider's oEmbed endpoint: `author_name`,
+/// `thumbnail_url`, and `title` when the iframe had none. Each distinct
+/// embed URL is asked once; failures leave the block as extracted.
+/// `content_hash` is recomputed for pages that gained metadata.
+pub(crate) async fn enrich_embeds(client: &Client, pages: &mut [PageData], concurrency: usize) {
+    let mut wanted: BTreeSet<(String, String)> = BTreeSet::new();
+    for page in pages.iter() {
+        for block in &page.content_blocks {
+            if let ContentBlock::Embed {
+                provider,
+                url: Some(url),
+                ..
+            } = block
+            {
+                if endpoint(provider).is_some() {
+                    wanted.insert((provider.clone(), url.clone()));
+                }
+            }
+        }
+    }
+    if wanted.is_empty() {
+        return;
+    }
+
+    let found: HashMap<String, OEmbed> = stream::iter(wanted)
+        .map(|(provider, url)| async move {
+            let info = fetch_oembed(client, &provider, &url).await;
+            info.map(|info| (url, info))
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|r| async move { r })
+        .collect()
+        .await;
+
+    for page in pages.iter_mut() {
+        let mut changed = false;
+        for block in &mut page.content_blocks {
+            let ContentBlock::Embed {
+                title,
+                url: Some(url),
+                author_name,
+                thumbnail_url,
+                ..
+            } = block
+            else {
+                continue;
+            };
+            let Some(info) = found.get(url.as_str()) else {
+                continue;
+            };
+            if title.is_empty() {
+                if let Some(t) = info.title.as_ref() {
+                    title.clone_from(t);
+                }
+            }
+            author_name.clone_from(&info.author_name);
+            thumbnail_url.clone_from(&info.thumbnail_url);
+            changed = true;
+        }
+        if changed {
+            page.content_hash = crate::util::hash_blocks(&page.content_blocks);
+        }
+    }
+}
+
+async fn fetch_oembed(client: &Client, provider: &str, url: &str) -> Option<OEmbed> {
+    let endpoint =
+        Url::parse_with_params(endpoint(provider)?, &[("url", url), ("format", "json")]).ok()?;
+    let resp = crate::har::send(client.get(endpoint)).await.ok()?;
+    if !resp.status().is_success() {
+        tracing::debug!("oEmbed HTTP {} for {url}", resp.status());
+        return None;
+    }
+    let body = resp.text().await.ok()?;
+    serde_json::from_str(&body).ok()
+}
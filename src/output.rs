@@ -1,14 +1,15 @@
 use serde_json::Value as JsonValue;
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use url::Url;
 
 use crate::model::{
-    AssetEntry, BrandPalette, ContactInfo, ContentBlock, FrameworkHint, HreflangGroup, PageData,
-    PageSection, PageSummary, PageTemplate, ScrapedData, SiteData, SocialLink,
+    AssetEntry, BandwidthStats, BrandPalette, ContactInfo, ContentBlock, ExternalDomain, FormField,
+    FrameworkHint, HreflangCluster, HreflangGroup, LinkReport, PageData, PageSection, PageSummary,
+    PageTemplate, ScrapedData, SiteData, SiteTreeNode, SocialLink,
 };
-use crate::util::normalize_path;
+use crate::util::{canonicalize_url, normalize_path, same_site, site_key};
 
 pub(crate) fn categorize_page(url: &str, page: &PageData) -> String {
     let url_lc = url.to_lowercase();
@@ -111,7 +112,7 @@ fn summarize_section_blocks(blocks: &[ContentBlock]) -> String {
         }
     }
     for b in blocks {
-        if let ContentBlock::Paragraph { text } = b {
+        if let ContentBlock::Paragraph { text, .. } = b {
             return text.chars().take(80).collect();
         }
     }
@@ -205,7 +206,7 @@ fn detect_faq_run(blocks: &[ContentBlock], from: usize) -> Option<usize> {
     while i + 1 < n {
         let q_ok = matches!(
             &blocks[i],
-            ContentBlock::Heading { level, text }
+            ContentBlock::Heading { level, text, .. }
                 if (*level == 3 || *level == 4) && text.len() < 200
         );
         let a_ok = matches!(&blocks[i + 1], ContentBlock::Paragraph { .. });
@@ -302,6 +303,7 @@ pub(crate) fn detect_sections(blocks: &[ContentBlock]) -> Vec<PageSection> {
                     end += 1;
                 }
                 ContentBlock::Heading { level, .. } if *level >= 4 => end += 1,
+                ContentBlock::Cta { .. } => end += 1,
                 _ => break,
             }
         }
@@ -328,6 +330,22 @@ pub(crate) fn detect_sections(blocks: &[ContentBlock]) -> Vec<PageSection> {
             i += 1;
             continue;
         }
+        // Run of CTA buttons → CTA, summarised by their labels
+        if matches!(blocks[i], ContentBlock::Cta { .. }) {
+            let start = i;
+            let mut labels = Vec::new();
+            while let Some(ContentBlock::Cta { text, .. }) = blocks.get(i) {
+                labels.push(text.as_str());
+                i += 1;
+            }
+            sections.push(PageSection {
+                section_type: "cta".to_string(),
+                block_start: start,
+                block_end: i,
+                summary: labels.join(" / "),
+            });
+            continue;
+        }
         // Embed → embed section (often a YouTube hero or testimonial video)
         if matches!(blocks[i], ContentBlock::Embed { .. }) {
             let provider = match &blocks[i] {
@@ -415,7 +433,7 @@ pub(crate) fn detect_sections(blocks: &[ContentBlock]) -> Vec<PageSection> {
         while end < n {
             if matches!(
                 blocks[end],
-                ContentBlock::Form { .. } | ContentBlock::Embed { .. }
+                ContentBlock::Form { .. } | ContentBlock::Embed { .. } | ContentBlock::Cta { .. }
             ) {
                 break;
             }
@@ -442,6 +460,10 @@ pub(crate) fn detect_sections(blocks: &[ContentBlock]) -> Vec<PageSection> {
     sections
 }
 
+/// Documents smaller than this aren't worth compressing; below it a
+/// missing `Content-Encoding` is not reported.
+const UNCOMPRESSED_MIN_BYTES: u64 = 1024;
+
 /// Per-page SEO / accessibility quality flags. Cheap heuristics — the agent
 /// can decide whether to preserve or fix them.
 pub(crate) fn detect_quality_flags(page: &PageData) -> Vec<String> {
@@ -481,13 +503,13 @@ pub(crate) fn detect_quality_flags(page: &PageData) -> Vec<String> {
         flags.push("no_canonical".to_string());
     }
 
-    if let Some(robots) = &page.meta_robots {
-        if robots.contains("noindex") || robots.contains("none") {
-            flags.push("meta_robots_noindex".to_string());
-        }
-        if robots.contains("nofollow") {
-            flags.push("meta_robots_nofollow".to_string());
-        }
+    let (noindex, nofollow) =
+        crate::util::robots_directives(page.meta_robots.as_deref(), &page.x_robots_tag);
+    if noindex {
+        flags.push("meta_robots_noindex".to_string());
+    }
+    if nofollow {
+        flags.push("meta_robots_nofollow".to_string());
     }
 
     let images_no_alt = page
@@ -547,6 +569,37 @@ pub(crate) fn detect_quality_flags(page: &PageData) -> Vec<String> {
     if page.total_words < 100 && !page.content_blocks.is_empty() {
         flags.push("thin_content".to_string());
     }
+    // Markup-heavy pages: under 5% of the document is visible text.
+    let text_ratio = page.content_metrics.text_to_html_ratio;
+    if page.decoded_bytes > 0 && text_ratio < 0.05 {
+        flags.push(format!(
+            "low_text_to_html:{}%",
+            (text_ratio * 100.0).round() as u32
+        ));
+    }
+
+    if let Some(cmp) = &page.mobile_comparison {
+        if cmp.content_parity < crate::mobile::MOBILE_PARITY_MIN {
+            flags.push(format!(
+                "mobile_content_gap:{}%",
+                ((1.0 - cmp.content_parity) * 100.0).round() as u32
+            ));
+        }
+    }
+
+    if page.content_encoding.is_none()
+        && page.transfer_bytes.is_some()
+        && page.decoded_bytes >= UNCOMPRESSED_MIN_BYTES
+    {
+        flags.push("uncompressed_html".to_string());
+    }
+
+    if page.soft_404.is_some() {
+        flags.push("soft_404".to_string());
+    }
+    if let Some(wall) = &page.access_wall {
+        flags.push(wall.clone());
+    }
 
     // Parked-domain detection: body is essentially nothing but an iframe
     // pointing at a parked-page provider (afternic, sedo, rapidresultsearch,
@@ -606,7 +659,9 @@ fn page_signature(page: &PageData) -> String {
             ContentBlock::Code { .. } => "code".to_string(),
             ContentBlock::Quote { .. } => "quote".to_string(),
             ContentBlock::Media { kind, .. } => kind.clone(),
+            ContentBlock::Video { .. } => "video".to_string(),
             ContentBlock::DefinitionList { .. } => "dl".to_string(),
+            ContentBlock::Cta { .. } => "cta".to_string(),
         })
         .collect::<Vec<_>>()
         .join(",")
@@ -640,6 +695,216 @@ pub(crate) fn build_hreflang_groups(pages: &[PageData]) -> Vec<HreflangGroup> {
     groups
 }
 
+/// External domains referenced from `PageData.links`, plus — when the URL
+/// list came from a sitemap — pages no other scraped page links to.
+/// Self-links don't count as inbound; URLs are compared canonicalised and
+/// by [`site_key`], so a trailing slash, utm_ param or http/https twin
+/// doesn't hide a link.
+pub(crate) fn build_link_report(
+    pages: &[PageData],
+    from_sitemap: bool,
+    strict_host: bool,
+) -> LinkReport {
+    let mut domains: HashMap<String, (usize, HashSet<&str>)> = HashMap::new();
+    for p in pages {
+        for link in p.links.iter().filter(|l| !l.internal) {
+            let Some(host) = Url::parse(&link.url)
+                .ok()
+                .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+            else {
+                continue;
+            };
+            let entry = domains
+                .entry(host.trim_start_matches("www.").to_string())
+                .or_default();
+            entry.0 += 1;
+            entry.1.insert(p.url.as_str());
+        }
+    }
+    let mut external_domains: Vec<ExternalDomain> = domains
+        .into_iter()
+        .map(|(domain, (links, pages))| ExternalDomain {
+            domain,
+            links,
+            pages: pages.len(),
+        })
+        .collect();
+    external_domains.sort_by(|a, b| b.links.cmp(&a.links).then_with(|| a.domain.cmp(&b.domain)));
+
+    let mut orphan_pages = Vec::new();
+    if from_sitemap {
+        let key = |u: &str| site_key(&canonicalize_url(u), strict_host);
+        let mut linked: HashSet<String> = HashSet::new();
+        for p in pages {
+            let own = key(&p.url);
+            for target in &p.internal_links_out {
+                let target = key(target);
+                if target != own {
+                    linked.insert(target);
+                }
+            }
+        }
+        orphan_pages = pages
+            .iter()
+            .filter(|p| Url::parse(&p.url).map(|u| u.path() != "/").unwrap_or(true))
+            .filter(|p| {
+                std::iter::once(&p.url)
+                    .chain(&p.aliases)
+                    .all(|u| !linked.contains(&key(u)))
+            })
+            .map(|p| p.url.clone())
+            .collect();
+        orphan_pages.sort();
+    }
+
+    LinkReport {
+        external_domains,
+        orphan_pages,
+    }
+}
+
+/// The locale a page is written in: the `hreflang` it declares for its own
+/// URL, else `<html lang>`. Lower-cased; `None` when neither is present.
+pub(crate) fn page_locale(p: &PageData) -> Option<String> {
+    let own = canonicalize_url(&p.url);
+    p.hreflang_alternates
+        .iter()
+        .find(|a| a.lang != "x-default" && canonicalize_url(&a.url) == own)
+        .map(|a| &a.lang)
+        .or(p.language.as_ref())
+        .map(|l| l.to_lowercase())
+}
+
+/// Merge each page's alternates (plus the page itself) into translation
+/// sets. Pages that list each other reciprocally collapse into one
+/// cluster; clusters with a single locale are dropped.
+pub(crate) fn build_hreflang_clusters(pages: &[PageData]) -> Vec<HreflangCluster> {
+    let scraped: HashSet<String> = pages.iter().map(|p| canonicalize_url(&p.url)).collect();
+    let mut seen: HashSet<Vec<String>> = HashSet::new();
+    let mut clusters = Vec::new();
+    for p in pages.iter().filter(|p| !p.hreflang_alternates.is_empty()) {
+        let mut alternates: BTreeMap<String, String> = p
+            .hreflang_alternates
+            .iter()
+            .map(|a| (a.lang.clone(), a.url.clone()))
+            .collect();
+        if let Some(locale) = page_locale(p) {
+            alternates.entry(locale).or_insert_with(|| p.url.clone());
+        }
+        if alternates.keys().filter(|l| *l != "x-default").count() < 2 {
+            continue;
+        }
+        let mut key: Vec<String> = alternates.values().map(|u| canonicalize_url(u)).collect();
+        key.sort();
+        key.dedup();
+        if !seen.insert(key) {
+            continue;
+        }
+        let mut in_run: Vec<String> = alternates
+            .values()
+            .filter(|u| scraped.contains(&canonicalize_url(u)))
+            .cloned()
+            .collect();
+        in_run.sort();
+        in_run.dedup();
+        clusters.push(HreflangCluster {
+            alternates,
+            scraped: in_run,
+        });
+    }
+    clusters
+}
+
+/// One `locales/<locale>.json` file for `--split-locales`.
+#[derive(serde::Serialize)]
+pub(crate) struct LocaleDump<'a> {
+    pub locale: String,
+    pub total_pages: usize,
+    pub pages: Vec<&'a PageData>,
+}
+
+/// Partition pages by [`page_locale`]; pages with no detectable locale go
+/// under `und` (BCP 47 "undetermined"). Locales are reduced to
+/// `[a-z0-9_-]` so they're safe as file names.
+pub(crate) fn build_locale_dumps(pages: &[PageData]) -> Vec<LocaleDump<'_>> {
+    let mut by_locale: BTreeMap<String, Vec<&PageData>> = BTreeMap::new();
+    for p in pages {
+        let locale: String = page_locale(p)
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        let locale = if locale.is_empty() {
+            "und".to_string()
+        } else {
+            locale
+        };
+        by_locale.entry(locale).or_default().push(p);
+    }
+    by_locale
+        .into_iter()
+        .map(|(locale, pages)| LocaleDump {
+            locale,
+            total_pages: pages.len(),
+            pages,
+        })
+        .collect()
+}
+
+/// Collapse pages whose `content_hash` matches into one, recording
+/// the other URLs in `aliases`. The kept URL is the one whose canonical
+/// points at itself, else the shortest (then alphabetically first). Pages
+/// with no content blocks are left alone — empty shells aren't duplicates
+/// of each other in any useful sense. Input order is preserved.
+pub(crate) fn collapse_duplicate_pages(pages: Vec<PageData>) -> Vec<PageData> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, p) in pages.iter().enumerate() {
+        if !p.content_blocks.is_empty() {
+            groups.entry(p.content_hash.clone()).or_default().push(i);
+        }
+    }
+    let self_canonical = |p: &PageData| {
+        p.canonical_url
+            .as_deref()
+            .is_some_and(|c| canonicalize_url(c) == canonicalize_url(&p.url))
+    };
+    // index of kept page → indices folded into it
+    let mut folded: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idxs in groups.into_values().filter(|g| g.len() > 1) {
+        let keep = *idxs
+            .iter()
+            .min_by(|&&a, &&b| {
+                let (pa, pb) = (&pages[a], &pages[b]);
+                self_canonical(pb)
+                    .cmp(&self_canonical(pa))
+                    .then(pa.url.len().cmp(&pb.url.len()))
+                    .then(pa.url.cmp(&pb.url))
+            })
+            .expect("group has members");
+        folded.insert(keep, idxs.into_iter().filter(|&i| i != keep).collect());
+    }
+    let dropped: HashSet<usize> = folded.values().flatten().copied().collect();
+    let mut aliases: HashMap<usize, Vec<String>> = folded
+        .iter()
+        .map(|(&keep, others)| {
+            let mut urls: Vec<String> = others.iter().map(|&i| pages[i].url.clone()).collect();
+            urls.sort();
+            (keep, urls)
+        })
+        .collect();
+    pages
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !dropped.contains(i))
+        .map(|(i, mut p)| {
+            if let Some(urls) = aliases.remove(&i) {
+                p.aliases = urls;
+            }
+            p
+        })
+        .collect()
+}
+
 pub(crate) fn detect_templates(pages: &[PageData]) -> Vec<PageTemplate> {
     let mut by_sig: HashMap<String, Vec<&PageData>> = HashMap::new();
     for p in pages {
@@ -670,7 +935,7 @@ pub(crate) fn detect_templates(pages: &[PageData]) -> Vec<PageTemplate> {
             }
         })
         .collect();
-    templates.sort_by(|a, b| b.page_count.cmp(&a.page_count));
+    templates.sort_by_key(|t| std::cmp::Reverse(t.page_count));
     templates
 }
 
@@ -707,7 +972,129 @@ pub(crate) fn build_page_summary(page: &PageData) -> PageSummary {
     }
 }
 
-pub(crate) fn build_site_data(pages: &[PageData], base_url: &str) -> SiteData {
+/// Tree path of a page: its URL path segments, led by the host for pages
+/// off the base site ([`same_site`], so `www.` twins share a branch).
+fn tree_segments(url: &Url, base: Option<&Url>, strict_host: bool) -> Vec<String> {
+    let mut segments: Vec<String> = Vec::new();
+    if !base.is_some_and(|b| same_site(url, b, strict_host)) {
+        segments.push(url.host_str().unwrap_or_default().to_string());
+    }
+    if let Some(parts) = url.path_segments() {
+        segments.extend(parts.filter(|s| !s.is_empty()).map(str::to_string));
+    }
+    segments
+}
+
+/// `path → name` from every JSON-LD `BreadcrumbList` on the site, so
+/// sections without a page of their own still get a label.
+fn breadcrumb_names(
+    pages: &[PageData],
+    base: Option<&Url>,
+    strict_host: bool,
+) -> HashMap<String, String> {
+    fn walk(v: &JsonValue, base: Option<&Url>, strict: bool, out: &mut HashMap<String, String>) {
+        match v {
+            JsonValue::Array(items) => items.iter().for_each(|i| walk(i, base, strict, out)),
+            JsonValue::Object(map) => {
+                if map.get("@type").and_then(JsonValue::as_str) == Some("BreadcrumbList") {
+                    let elements = map.get("itemListElement").and_then(JsonValue::as_array);
+                    for el in elements.into_iter().flatten() {
+                        let item = el.get("item");
+                        let url = item.and_then(|i| {
+                            i.as_str()
+                                .or_else(|| i.get("@id").and_then(JsonValue::as_str))
+                                .or_else(|| i.get("url").and_then(JsonValue::as_str))
+                        });
+                        let name = el
+                            .get("name")
+                            .or_else(|| item.and_then(|i| i.get("name")))
+                            .and_then(JsonValue::as_str);
+                        let (Some(url), Some(name)) = (url, name) else {
+                            continue;
+                        };
+                        if let Ok(u) = Url::parse(url) {
+                            let path = tree_segments(&u, base, strict).join("/");
+                            out.entry(path).or_insert_with(|| name.trim().to_string());
+                        }
+                    }
+                }
+                map.values().for_each(|v| walk(v, base, strict, out));
+            }
+            _ => {}
+        }
+    }
+    let mut out = HashMap::new();
+    for p in pages {
+        for blob in &p.structured_data {
+            walk(blob, base, strict_host, &mut out);
+        }
+    }
+    out
+}
+
+/// `site.json:tree` — the site's information architecture from URL paths:
+/// one node per path segment, each carrying the page at that path (if
+/// scraped) and the number of pages beneath it.
+pub(crate) fn build_site_tree(
+    pages: &[PageData],
+    base_url: &str,
+    strict_host: bool,
+) -> SiteTreeNode {
+    #[derive(Default)]
+    struct Builder {
+        page: Option<(String, String)>,
+        here: usize,
+        children: BTreeMap<String, Builder>,
+    }
+    fn finish(
+        b: Builder,
+        segment: String,
+        path: String,
+        names: &HashMap<String, String>,
+    ) -> SiteTreeNode {
+        let key = path.trim_start_matches('/').to_string();
+        let children: Vec<SiteTreeNode> = b
+            .children
+            .into_iter()
+            .map(|(seg, child)| {
+                let child_path = format!("{}/{seg}", path.trim_end_matches('/'));
+                finish(child, seg, child_path, names)
+            })
+            .collect();
+        let (url, title) = match b.page {
+            Some((url, title)) if !title.is_empty() => (Some(url), Some(title)),
+            Some((url, _)) => (Some(url), names.get(&key).cloned()),
+            None => (None, names.get(&key).cloned()),
+        };
+        SiteTreeNode {
+            segment,
+            path,
+            url,
+            title,
+            page_count: b.here + children.iter().map(|c| c.page_count).sum::<usize>(),
+            children,
+        }
+    }
+
+    let base = Url::parse(base_url).ok();
+    let mut root = Builder::default();
+    for p in pages {
+        let Ok(url) = Url::parse(&p.url) else {
+            continue;
+        };
+        let mut node = &mut root;
+        for segment in tree_segments(&url, base.as_ref(), strict_host) {
+            node = node.children.entry(segment).or_default();
+        }
+        node.here += 1;
+        node.page
+            .get_or_insert_with(|| (p.url.clone(), p.title.clone()));
+    }
+    let names = breadcrumb_names(pages, base.as_ref(), strict_host);
+    finish(root, String::new(), "/".to_string(), &names)
+}
+
+pub(crate) fn build_site_data(pages: &[PageData], base_url: &str, strict_host: bool) -> SiteData {
     let language = pages.iter().find_map(|p| p.language.clone());
     let favicon_url = pages.iter().find_map(|p| p.favicon_url.clone());
     let primary_nav = pages
@@ -721,15 +1108,15 @@ pub(crate) fn build_site_data(pages: &[PageData], base_url: &str) -> SiteData {
 
     let mut sitemap: Vec<PageSummary> = pages.iter().map(build_page_summary).collect();
 
-    // Inbound-link counts.
+    // Inbound-link counts, keyed by site so http/https twins meet.
     let mut inbound: HashMap<String, usize> = HashMap::new();
     for p in pages {
         for target in &p.internal_links_out {
-            *inbound.entry(target.clone()).or_default() += 1;
+            *inbound.entry(site_key(target, strict_host)).or_default() += 1;
         }
     }
     for s in sitemap.iter_mut() {
-        if let Some(c) = inbound.get(&s.url) {
+        if let Some(c) = inbound.get(&site_key(&s.url, strict_host)) {
             s.internal_links_in = *c;
         }
     }
@@ -754,6 +1141,22 @@ pub(crate) fn build_site_data(pages: &[PageData], base_url: &str) -> SiteData {
         ..Default::default()
     };
 
+    // Only pages fetched over plain HTTP know their wire size; keep the
+    // ratio honest by summing decoded bytes over the same subset.
+    let mut bandwidth = BandwidthStats::default();
+    for p in pages {
+        if let Some(t) = p.transfer_bytes {
+            bandwidth.pages_measured += 1;
+            bandwidth.transfer_bytes += t;
+            bandwidth.decoded_bytes += p.decoded_bytes;
+            if p.content_encoding.is_none() && p.decoded_bytes >= UNCOMPRESSED_MIN_BYTES {
+                bandwidth.uncompressed_pages.push(p.url.clone());
+            }
+        }
+    }
+    bandwidth.compression_ratio =
+        crate::util::byte_ratio(bandwidth.transfer_bytes, bandwidth.decoded_bytes);
+
     SiteData {
         base_url: base_url.to_string(),
         language,
@@ -763,7 +1166,9 @@ pub(crate) fn build_site_data(pages: &[PageData], base_url: &str) -> SiteData {
         contact: ContactInfo::default(),
         brand,
         templates: Vec::new(),
+        boilerplate: Vec::new(),
         hreflang_groups: Vec::new(),
+        hreflang_clusters: Vec::new(),
         sitemap,
         total_pages: pages.len(),
         assets: Vec::new(),
@@ -771,6 +1176,13 @@ pub(crate) fn build_site_data(pages: &[PageData], base_url: &str) -> SiteData {
         output_files: Vec::new(),
         quality_warnings: Vec::new(),
         skipped_pages: Vec::new(),
+        challenges: Vec::new(),
+        failed_images: Vec::new(),
+        bandwidth,
+        link_report: LinkReport::default(),
+        tree: build_site_tree(pages, base_url, strict_host),
+        keywords: Vec::new(),
+        data_sources: Vec::new(),
     }
 }
 
@@ -1186,9 +1598,14 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
     }
     // Partial-scrape warning — when most pages were skipped by bot
     // protection or render failure, surface the skip rate prominently.
-    if !site.skipped_pages.is_empty() {
-        let total = site.total_pages + site.skipped_pages.len();
-        let pct = (site.skipped_pages.len() as f64 / total as f64 * 100.0).round() as u32;
+    let attempted_skips = site
+        .skipped_pages
+        .iter()
+        .filter(|s| s.was_attempted())
+        .count();
+    if attempted_skips > 0 {
+        let total = site.total_pages + attempted_skips;
+        let pct = (attempted_skips as f64 / total as f64 * 100.0).round() as u32;
         if pct >= 50 {
             let bot_count = site
                 .skipped_pages
@@ -1202,9 +1619,7 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
                 .count();
             out.push_str(&format!(
                 "## ⚠️ Partial scrape — {}/{} pages ({}%) blocked or unrenderable\n\n",
-                site.skipped_pages.len(),
-                total,
-                pct
+                attempted_skips, total, pct
             ));
             out.push_str(&format!(
                 "Skipped reasons: bot-protected = {bot_count}, render-failed = {render_count}. **The bundle is incomplete — verify the agent isn't rebuilding a partial site.** See `site.json:skipped_pages` for the full list.\n\n",
@@ -1230,6 +1645,59 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
     out.push_str("## At a glance\n\n");
     out.push_str(&format!("- **Base URL**: `{}`\n", site.base_url));
     out.push_str(&format!("- **Pages**: {}\n", site.total_pages));
+    if site.bandwidth.pages_measured > 0 {
+        out.push_str(&format!(
+            "- **Transfer**: {} KB on the wire / {} KB decoded ({} pages measured)\n",
+            site.bandwidth.transfer_bytes / 1024,
+            site.bandwidth.decoded_bytes / 1024,
+            site.bandwidth.pages_measured
+        ));
+        if !site.bandwidth.uncompressed_pages.is_empty() {
+            out.push_str(&format!(
+                "- **Uncompressed pages**: {} served without gzip / brotli\n",
+                site.bandwidth.uncompressed_pages.len()
+            ));
+        }
+    }
+    if !site.keywords.is_empty() {
+        let top: Vec<String> = site
+            .keywords
+            .iter()
+            .take(10)
+            .map(|k| format!("`{}`", k.term))
+            .collect();
+        out.push_str(&format!("- **Top keywords**: {}\n", top.join(", ")));
+    }
+    if !site.link_report.external_domains.is_empty() {
+        let top: Vec<String> = site
+            .link_report
+            .external_domains
+            .iter()
+            .take(5)
+            .map(|d| format!("`{}` ({}×)", d.domain, d.links))
+            .collect();
+        out.push_str(&format!("- **Top external domains**: {}\n", top.join(", ")));
+    }
+    let sections: Vec<String> = site
+        .tree
+        .children
+        .iter()
+        .filter(|c| c.page_count > 1)
+        .take(10)
+        .map(|c| format!("`{}` ({})", c.path, c.page_count))
+        .collect();
+    if !sections.is_empty() {
+        out.push_str(&format!(
+            "- **Sections**: {} (see `site.json` → `tree`)\n",
+            sections.join(", ")
+        ));
+    }
+    if !site.link_report.orphan_pages.is_empty() {
+        out.push_str(&format!(
+            "- **Orphan pages**: {} in the sitemap but never linked internally (see `site.json` → `link_report`)\n",
+            site.link_report.orphan_pages.len()
+        ));
+    }
     if let Some(lang) = &site.language {
         out.push_str(&format!("- **Language**: `{lang}`\n"));
     }
@@ -1357,6 +1825,19 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
         out.push('\n');
     }
 
+    if !site.data_sources.is_empty() {
+        out.push_str("## Feeds & APIs\n\n");
+        out.push_str("Machine-readable sources the site offers besides its HTML (all of them in `site.json:data_sources`):\n\n");
+        out.push_str("| Kind | URL | Found via | Pages |\n|------|-----|-----------|-------|\n");
+        for s in site.data_sources.iter().take(20) {
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                s.kind, s.url, s.found, s.pages
+            ));
+        }
+        out.push('\n');
+    }
+
     out.push_str("## Where to look\n\n");
     out.push_str("| File | What's in it |\n");
     out.push_str("|------|--------------|\n");
@@ -1369,6 +1850,9 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
     out.push_str(
         "| `brand.json` | Favicon, logo, color palette, fonts, CSS variables, webfont URLs |\n",
     );
+    if site.output_files.iter().any(|f| f == "products.json") {
+        out.push_str("| `products.json` | Product records (`--preset product`): name, price, currency, availability, SKU, images, page URL |\n");
+    }
     out.push_str("| `index.md` | This file |\n");
     out.push_str(
         "| `images/` | All downloaded assets (favicon, logo, content images, inline SVGs) |\n",
@@ -1417,36 +1901,80 @@ pub(crate) fn build_index_md(site: &SiteData, pages: &[PageData]) -> String {
     out
 }
 
-/// Convert a page's content_blocks to Markdown.
-pub(crate) fn page_to_markdown(page: &PageData) -> String {
-    let mut out = String::new();
-    out.push_str(&format!("# {}\n\n", page.title));
-    out.push_str(&format!("<!-- url: {} -->\n", page.url));
+/// YAML front matter for a Markdown page, in the shape Hugo / Jekyll /
+/// Astro content collections expect. Values are emitted as JSON strings,
+/// which are valid YAML double-quoted scalars — no escaping surprises for
+/// titles with colons or quotes. `extra` comes from `--front-matter`.
+fn markdown_front_matter(page: &PageData, scraped_at: &str, extra: &[(String, String)]) -> String {
+    let q = |s: &str| serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string());
+    let mut fields: Vec<(String, String)> = vec![
+        ("title".to_string(), q(&page.title)),
+        ("url".to_string(), q(&page.url)),
+    ];
     if let Some(canon) = &page.canonical_url {
-        out.push_str(&format!("<!-- canonical: {canon} -->\n"));
+        fields.push(("canonical".to_string(), q(canon)));
     }
     if let Some(lang) = &page.language {
-        out.push_str(&format!("<!-- lang: {lang} -->\n"));
+        fields.push(("lang".to_string(), q(lang)));
     }
     if !page.meta_description.is_empty() {
-        out.push_str(&format!(
-            "<!-- description: {} -->\n",
-            page.meta_description
-        ));
+        fields.push(("description".to_string(), q(&page.meta_description)));
+    }
+    fields.push(("scraped_at".to_string(), q(scraped_at)));
+    fields.push(("word_count".to_string(), page.total_words.to_string()));
+    fields.push((
+        "reading_time".to_string(),
+        page.content_metrics.reading_time_minutes.to_string(),
+    ));
+    // A `--front-matter` key that is already present replaces its value in
+    // place: YAML parsers reject duplicate keys.
+    for (key, value) in extra {
+        match fields.iter_mut().find(|(k, _)| k == key) {
+            Some(field) => field.1 = q(value),
+            None => fields.push((key.clone(), q(value))),
+        }
     }
-    out.push('\n');
+    let mut out = String::from("---\n");
+    for (key, value) in fields {
+        out.push_str(&format!("{key}: {value}\n"));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// Convert a page's content_blocks to Markdown.
+pub(crate) fn page_to_markdown(
+    page: &PageData,
+    scraped_at: &str,
+    front_matter: &[(String, String)],
+) -> String {
+    let mut out = markdown_front_matter(page, scraped_at, front_matter);
+    out.push_str(&format!("# {}\n\n", page.title));
 
     for block in &page.content_blocks {
         match block {
-            ContentBlock::Heading { level, text } => {
+            ContentBlock::Heading {
+                level,
+                text,
+                markdown,
+            } => {
                 let hashes: String = (0..*level).map(|_| '#').collect();
+                let text = markdown.as_deref().unwrap_or(text);
                 out.push_str(&format!("{hashes} {text}\n\n"));
             }
-            ContentBlock::Paragraph { text } => {
-                out.push_str(text);
+            ContentBlock::Paragraph { text, markdown, .. } => {
+                out.push_str(markdown.as_deref().unwrap_or(text));
                 out.push_str("\n\n");
             }
-            ContentBlock::List { items } => {
+            ContentBlock::List {
+                items,
+                markdown_items,
+            } => {
+                let items = if markdown_items.is_empty() {
+                    items
+                } else {
+                    markdown_items
+                };
                 for item in items {
                     out.push_str(&format!("- {item}\n"));
                 }
@@ -1456,6 +1984,7 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                 local_path,
                 alt_text,
                 original_url,
+                ..
             } => {
                 let alt = if alt_text.is_empty() {
                     "image"
@@ -1475,17 +2004,24 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                 fields,
                 submit_text,
                 purpose,
+                sections,
+                multi_step,
             } => {
                 out.push_str(&format!(
-                    "> **Form** ({purpose}) — {method} `{}`\n>\n",
+                    "> **{}** ({purpose}) — {method} `{}`\n>\n",
+                    if *multi_step {
+                        "Multi-step form"
+                    } else {
+                        "Form"
+                    },
                     if action.is_empty() {
                         "(no action)"
                     } else {
                         action.as_str()
                     }
                 ));
-                for f in fields {
-                    out.push_str(&format!(
+                let field_line = |f: &FormField| {
+                    format!(
                         "> - `{}` ({}{}): {}\n",
                         f.name,
                         f.field_type,
@@ -1495,7 +2031,29 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                         } else {
                             f.label.as_str()
                         },
-                    ));
+                    )
+                };
+                let mut in_section = vec![false; fields.len()];
+                for s in sections {
+                    let title = match (s.step, s.legend.is_empty()) {
+                        (Some(n), true) => format!("Step {n}"),
+                        (Some(n), false) => format!("Step {n}: {}", s.legend),
+                        (None, true) => "Fieldset".to_string(),
+                        (None, false) => s.legend.clone(),
+                    };
+                    out.push_str(&format!(">\n> *{title}*\n>\n"));
+                    for &i in &s.fields {
+                        if let Some(f) = fields.get(i) {
+                            in_section[i] = true;
+                            out.push_str(&field_line(f));
+                        }
+                    }
+                }
+                if !sections.is_empty() && in_section.contains(&false) {
+                    out.push_str(">\n");
+                }
+                for (f, _) in fields.iter().zip(&in_section).filter(|(_, &s)| !s) {
+                    out.push_str(&field_line(f));
                 }
                 out.push_str(&format!("> - submit: **{submit_text}**\n\n"));
             }
@@ -1503,6 +2061,8 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                 provider,
                 src,
                 title,
+                url,
+                ..
             } => {
                 out.push_str(&format!(
                     "> **Embed** ({}): [{}]({})\n\n",
@@ -1512,7 +2072,7 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                     } else {
                         title.as_str()
                     },
-                    src
+                    url.as_deref().unwrap_or(src)
                 ));
             }
             ContentBlock::Table {
@@ -1566,11 +2126,45 @@ pub(crate) fn page_to_markdown(page: &PageData) -> String {
                 }
                 out.push('\n');
             }
+            ContentBlock::Video {
+                sources,
+                poster_url,
+                poster_local_path,
+                captions,
+                title,
+            } => {
+                // A block read back from a hand-edited dump may have none.
+                let Some(first) = sources.first() else {
+                    continue;
+                };
+                let label = if title.is_empty() {
+                    "video"
+                } else {
+                    title.as_str()
+                };
+                let src = sources
+                    .iter()
+                    .find_map(|s| s.local_path.as_deref())
+                    .unwrap_or(&first.url);
+                out.push_str(&format!("> **video**: [{label}]({src})\n"));
+                if let Some(p) = poster_local_path.as_ref().or(poster_url.as_ref()) {
+                    out.push_str(&format!("> poster: {p}\n"));
+                }
+                for c in captions {
+                    let lang = c.srclang.as_deref().unwrap_or(&c.kind);
+                    out.push_str(&format!("> captions ({lang}): {}\n", c.url));
+                }
+                out.push('\n');
+            }
             ContentBlock::DefinitionList { items } => {
                 for item in items {
                     out.push_str(&format!("**{}**\n: {}\n\n", item.term, item.description));
                 }
             }
+            ContentBlock::Cta { text, href } => match href {
+                Some(href) => out.push_str(&format!("**[{text}]({href})**\n\n")),
+                None => out.push_str(&format!("**{text}**\n\n")),
+            },
         }
     }
     out
@@ -1618,12 +2212,14 @@ pub(crate) fn build_schema_json() -> JsonValue {
         "$defs": {
             "ContentBlock": {
                 "oneOf": [
-                    { "type": "object", "properties": { "type": {"const": "heading"}, "level": {"type": "integer", "minimum": 1, "maximum": 6}, "text": {"type": "string"} }, "required": ["type", "level", "text"] },
-                    { "type": "object", "properties": { "type": {"const": "paragraph"}, "text": {"type": "string"} }, "required": ["type", "text"] },
-                    { "type": "object", "properties": { "type": {"const": "list"}, "items": {"type": "array", "items": {"type": "string"}} }, "required": ["type", "items"] },
+                    { "type": "object", "properties": { "type": {"const": "heading"}, "level": {"type": "integer", "minimum": 1, "maximum": 6}, "text": {"type": "string"}, "markdown": {"type": "string"} }, "required": ["type", "level", "text"] },
+                    { "type": "object", "properties": { "type": {"const": "paragraph"}, "text": {"type": "string"}, "links": {"type": "array", "items": {"type": "object", "properties": {"text": {"type": "string"}, "href": {"type": "string"}}}}, "markdown": {"type": "string"} }, "required": ["type", "text"] },
+                    { "type": "object", "properties": { "type": {"const": "list"}, "items": {"type": "array", "items": {"type": "string"}}, "markdown_items": {"type": "array", "items": {"type": "string"}} }, "required": ["type", "items"] },
                     { "type": "object", "properties": { "type": {"const": "image"}, "original_url": {"type": "string"}, "local_path": {"type": "string"}, "alt_text": {"type": "string"} }, "required": ["type", "original_url", "local_path", "alt_text"] },
                     { "type": "object", "properties": { "type": {"const": "form"}, "action": {"type": "string"}, "method": {"type": "string"}, "fields": {"type": "array"}, "submit_text": {"type": "string"}, "purpose": {"type": "string", "enum": ["contact", "newsletter", "search", "login", "signup", "payment", "comment", "generic"]} }, "required": ["type", "action", "method", "fields", "submit_text"] },
-                    { "type": "object", "properties": { "type": {"const": "embed"}, "provider": {"type": "string"}, "src": {"type": "string"}, "title": {"type": "string"} }, "required": ["type", "provider", "src", "title"] }
+                    { "type": "object", "properties": { "type": {"const": "embed"}, "provider": {"type": "string"}, "src": {"type": "string"}, "title": {"type": "string"} }, "required": ["type", "provider", "src", "title"] },
+                    { "type": "object", "properties": { "type": {"const": "video"}, "sources": {"type": "array", "items": {"type": "object", "properties": {"url": {"type": "string"}, "mime_type": {"type": "string"}, "local_path": {"type": "string"}}}}, "poster_url": {"type": "string"}, "poster_local_path": {"type": "string"}, "captions": {"type": "array"}, "title": {"type": "string"} }, "required": ["type", "sources"] },
+                    { "type": "object", "properties": { "type": {"const": "cta"}, "text": {"type": "string"}, "href": {"type": "string"} }, "required": ["type", "text"] }
                 ]
             },
             "PageSection": {
@@ -1636,10 +2232,23 @@ pub(crate) fn build_schema_json() -> JsonValue {
                 },
                 "required": ["section_type", "block_start", "block_end", "summary"]
             },
+            "SiteTreeNode": {
+                "type": "object",
+                "properties": {
+                    "segment": {"type": "string"},
+                    "path": {"type": "string"},
+                    "url": {"type": "string"},
+                    "title": {"type": "string"},
+                    "page_count": {"type": "integer"},
+                    "children": {"type": "array", "items": {"$ref": "#/$defs/SiteTreeNode"}}
+                },
+                "required": ["segment", "path", "page_count"]
+            },
             "PageData": {
                 "type": "object",
                 "properties": {
                     "url": {"type": "string"},
+                    "aliases": {"type": "array", "items": {"type": "string"}},
                     "title": {"type": "string"},
                     "meta_title": {"type": "string"},
                     "meta_description": {"type": "string"},
@@ -1651,18 +2260,43 @@ pub(crate) fn build_schema_json() -> JsonValue {
                     "og_image_local_path": {"type": ["string", "null"]},
                     "twitter_card": {"type": ["string", "null"]},
                     "meta_robots": {"type": ["string", "null"]},
+                    "meta": {"type": "object", "description": "<meta> name / property / http-equiv:<value> / charset -> content values in document order", "additionalProperties": {"type": "array", "items": {"type": "string"}}},
+                    "x_robots_tag": {"type": "array", "items": {"type": "string"}},
                     "hreflang_alternates": {"type": "array", "items": {"type": "object", "properties": {"lang": {"type": "string"}, "url": {"type": "string"}}}},
+                    "link_relations": {"type": "array", "items": {"type": "object", "properties": {"rel": {"type": "string"}, "href": {"type": "string"}, "type": {"type": "string"}, "hreflang": {"type": "string"}, "media": {"type": "string"}, "as": {"type": "string"}, "title": {"type": "string"}}, "required": ["rel", "href"]}},
                     "nav_links": {"type": "array", "items": {"type": "object", "properties": {"text": {"type": "string"}, "href": {"type": "string"}}}},
                     "footer_blocks": {"type": "array", "items": {"$ref": "#/$defs/ContentBlock"}},
                     "structured_data": {"type": "array"},
+                    "products": {"type": "array", "items": {"type": "object", "properties": {"name": {"type": "string"}, "sku": {"type": "string"}, "brand": {"type": "string"}, "url": {"type": "string"}, "price": {"type": "string"}, "currency": {"type": "string"}, "availability": {"type": "string"}, "images": {"type": "array", "items": {"type": "string"}}, "source": {"type": "string"}}}},
                     "content_blocks": {"type": "array", "items": {"$ref": "#/$defs/ContentBlock"}},
                     "plain_text": {"type": "string"},
                     "page_assets": {"type": "array", "items": {"type": "string"}},
                     "sections": {"type": "array", "items": {"$ref": "#/$defs/PageSection"}},
+                    "keywords": {"type": "array", "items": {"type": "object", "properties": {"term": {"type": "string"}, "score": {"type": "number"}}}},
+                    "outline": {"type": "array", "items": {"type": "object", "properties": {"level": {"type": "integer"}, "text": {"type": "string"}, "block_index": {"type": "integer"}, "children": {"type": "array"}}}},
                     "quality_flags": {"type": "array", "items": {"type": "string"}},
                     "total_words": {"type": "integer"},
+                    "content_metrics": {"type": "object", "properties": {"reading_time_minutes": {"type": "integer"}, "text_to_html_ratio": {"type": "number"}, "paragraph_count": {"type": "integer"}, "heading_count": {"type": "integer"}, "words_per_paragraph": {"type": "number"}, "paragraphs_per_heading": {"type": "number"}}},
                     "page_contact": {"type": ["object", "null"]},
-                    "internal_links_out": {"type": "array", "items": {"type": "string"}}
+                    "internal_links_out": {"type": "array", "items": {"type": "string"}},
+                    "links": {"type": "array", "items": {"type": "object", "properties": {"url": {"type": "string"}, "text": {"type": "string"}, "rel": {"type": "array", "items": {"type": "string"}}, "internal": {"type": "boolean"}}}},
+                    "sitemap": {"type": "object", "description": "lastmod / changefreq / priority and image, video and news entries of the page's sitemap <url>"},
+                    "content_hash": {"type": "string"},
+                    "boilerplate_blocks": {"type": "array", "items": {"type": "integer"}},
+                    "is_amp": {"type": "boolean"},
+                    "amp_url": {"type": "string"},
+                    "content_variant": {"type": "string"},
+                    "amp_content_blocks": {"type": "array", "items": {"$ref": "#/$defs/ContentBlock"}},
+                    "mobile_comparison": {"type": "object"},
+                    "soft_404": {"type": "string"},
+                    "access_wall": {"type": "string"},
+                    "http_status": {"type": "integer"},
+                    "redirected_to": {"type": "string"},
+                    "challenge_passed": {"type": "string"},
+                    "transfer_bytes": {"type": "integer"},
+                    "decoded_bytes": {"type": "integer"},
+                    "compression_ratio": {"type": "number"},
+                    "content_encoding": {"type": "string"}
                 },
                 "required": ["url", "title", "content_blocks", "total_words"]
             }
@@ -1672,10 +2306,12 @@ pub(crate) fn build_schema_json() -> JsonValue {
             "scraped.json": {
                 "type": "object",
                 "properties": {
+                    "schema_version": {"type": "string", "description": "MAJOR.MINOR; MAJOR changes break consumers"},
+                    "generator": {"type": "object", "properties": {"name": {"type": "string"}, "version": {"type": "string"}, "generated_at": {"type": "string", "format": "date-time"}}},
                     "total_pages": {"type": "integer"},
                     "pages": {"type": "array", "items": {"$ref": "#/$defs/PageData"}}
                 },
-                "required": ["total_pages", "pages"]
+                "required": ["schema_version", "total_pages", "pages"]
             },
             "site.json": {
                 "type": "object",
@@ -1689,11 +2325,21 @@ pub(crate) fn build_schema_json() -> JsonValue {
                     "brand": {"type": "object"},
                     "templates": {"type": "array"},
                     "hreflang_groups": {"type": "array"},
+                    "hreflang_clusters": {"type": "array"},
                     "sitemap": {"type": "array"},
                     "total_pages": {"type": "integer"},
                     "assets": {"type": "array"},
                     "error_pages": {"type": "array"},
-                    "output_files": {"type": "array", "items": {"type": "string"}}
+                    "output_files": {"type": "array", "items": {"type": "string"}},
+                    "skipped_pages": {"type": "array", "items": {"type": "object", "properties": {"url": {"type": "string"}, "reason": {"type": "string"}}}},
+                    "boilerplate": {"type": "array"},
+                    "challenges": {"type": "array"},
+                    "failed_images": {"type": "array"},
+                    "bandwidth": {"type": "object"},
+                    "link_report": {"type": "object"},
+                    "tree": {"$ref": "#/$defs/SiteTreeNode"},
+                    "keywords": {"type": "array", "items": {"type": "object", "properties": {"term": {"type": "string"}, "score": {"type": "number"}}}},
+                    "data_sources": {"type": "array", "items": {"type": "object", "properties": {"kind": {"type": "string"}, "url": {"type": "string"}}}}
                 },
                 "required": ["base_url", "total_pages"]
             }
@@ -1749,22 +2395,34 @@ pub(crate) fn build_asset_manifest(output_dir: &Path) -> Vec<AssetEntry> {
     let mut out = Vec::new();
     let candidates: &[(&str, &str)] = &[
         ("images", "image"),
+        ("videos", "video"),
         ("screenshots", "screenshot"),
         ("pages", "page-json"),
         ("markdown", "page-md"),
         ("css", "stylesheet"),
     ];
     for (dir, kind_default) in candidates {
-        let d: PathBuf = output_dir.join(dir);
-        if !d.exists() {
-            continue;
+        // Walked recursively: `--image-layout mirror` nests images by
+        // host and path.
+        let mut pending: Vec<PathBuf> = vec![output_dir.join(dir)];
+        let mut entries = Vec::new();
+        while let Some(d) = pending.pop() {
+            let Ok(rd) = std::fs::read_dir(&d) else {
+                continue;
+            };
+            for entry in rd.flatten() {
+                if entry.path().is_dir() {
+                    pending.push(entry.path());
+                } else {
+                    entries.push(entry);
+                }
+            }
         }
-        let Ok(rd) = std::fs::read_dir(&d) else {
-            continue;
-        };
-        for entry in rd.flatten() {
+        for entry in entries {
             let p = entry.path();
-            if !p.is_file() {
+            // Dot files are bookkeeping (`images/.manifest.json`).
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !p.is_file() || hidden {
                 continue;
             }
             let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
@@ -1793,6 +2451,24 @@ pub(crate) fn build_asset_manifest(output_dir: &Path) -> Vec<AssetEntry> {
     out
 }
 
+/// `--output -`: the scraped JSON on `out` instead of scraped.json, as
+/// one page per line with `--jsonl` (no envelope, for `jq -c`).
+pub(crate) fn write_scraped_json(
+    out: &mut impl std::io::Write,
+    result: &ScrapedData,
+    jsonl: bool,
+) -> anyhow::Result<()> {
+    if jsonl {
+        for page in &result.pages {
+            writeln!(out, "{}", serde_json::to_string(page)?)?;
+        }
+    } else {
+        writeln!(out, "{}", serde_json::to_string_pretty(result)?)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1801,6 +2477,8 @@ mod tests {
     fn page(url: &str, title: &str, blocks: Vec<ContentBlock>) -> PageData {
         PageData {
             url: url.to_string(),
+            aliases: vec![],
+            sitemap: None,
             title: title.to_string(),
             meta_title: title.to_string(),
             meta_description: String::new(),
@@ -1812,25 +2490,48 @@ mod tests {
             og_image_local_path: None,
             twitter_card: None,
             meta_robots: None,
+            meta: Default::default(),
             hreflang_alternates: vec![],
+            link_relations: vec![],
             nav_links: vec![],
             footer_blocks: vec![],
             structured_data: vec![],
+            products: vec![],
             content_blocks: blocks,
+            boilerplate_blocks: vec![],
+            is_amp: false,
+            amp_url: None,
+            content_variant: None,
+            amp_content_blocks: vec![],
+            mobile_comparison: None,
+            soft_404: None,
+            access_wall: None,
             plain_text: String::new(),
             content_hash: String::new(),
             token_estimate: 0,
             summary: String::new(),
             page_assets: vec![],
             sections: vec![],
+            outline: vec![],
+            keywords: vec![],
             quality_flags: vec![],
             total_words: 0,
+            content_metrics: Default::default(),
             page_contact: None,
             internal_links_out: vec![],
+            links: vec![],
             style_text: String::new(),
             stylesheet_urls: vec![],
             screenshot_desktop: None,
             screenshot_mobile: None,
+            http_status: None,
+            redirected_to: None,
+            challenge_passed: None,
+            transfer_bytes: None,
+            compression_ratio: None,
+            decoded_bytes: 0,
+            content_encoding: None,
+            x_robots_tag: vec![],
         }
     }
 
@@ -1838,11 +2539,14 @@ mod tests {
         ContentBlock::Heading {
             level,
             text: text.to_string(),
+            markdown: None,
         }
     }
     fn p(text: &str) -> ContentBlock {
         ContentBlock::Paragraph {
             text: text.to_string(),
+            links: Vec::new(),
+            markdown: None,
         }
     }
 
@@ -1905,6 +2609,23 @@ mod tests {
         assert!(flags.contains(&"no_meta_description".to_string()));
     }
 
+    #[test]
+    fn uncompressed_pages_are_flagged_and_rolled_up() {
+        let mut plain = page("https://x.com/a", "A", vec![p("Hello")]);
+        plain.transfer_bytes = Some(40_000);
+        plain.decoded_bytes = 40_000;
+        let mut gzipped = page("https://x.com/b", "B", vec![p("Hello")]);
+        gzipped.transfer_bytes = Some(10_000);
+        gzipped.decoded_bytes = 40_000;
+        gzipped.content_encoding = Some("gzip".to_string());
+        assert!(detect_quality_flags(&plain).contains(&"uncompressed_html".to_string()));
+        assert!(!detect_quality_flags(&gzipped).contains(&"uncompressed_html".to_string()));
+
+        let site = build_site_data(&[plain, gzipped], "https://x.com/", false);
+        assert_eq!(site.bandwidth.compression_ratio, 0.625);
+        assert_eq!(site.bandwidth.uncompressed_pages, vec!["https://x.com/a"]);
+    }
+
     #[test]
     fn detect_quality_warnings_flags_spa_loading_shell() {
         // Brooklyn Brewery regression: 10 pages all sharing 4-block
@@ -1915,11 +2636,15 @@ mod tests {
                     original_url: "logo".to_string(),
                     local_path: "img/logo.png".to_string(),
                     alt_text: String::new(),
+                    svg_markup: None,
+                    fallback_urls: Vec::new(),
                 },
                 ContentBlock::Image {
                     original_url: "hero".to_string(),
                     local_path: "img/hero.png".to_string(),
                     alt_text: String::new(),
+                    svg_markup: None,
+                    fallback_urls: Vec::new(),
                 },
                 h(1, "Loading…"),
                 p("Please wait"),
@@ -1997,6 +2722,226 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_link_report_counts_domains_and_finds_orphans() {
+        use crate::model::PageLink;
+        let link = |url: &str, internal: bool| PageLink {
+            url: url.to_string(),
+            text: String::new(),
+            rel: vec![],
+            internal,
+        };
+        let mut home = page("https://x.com/", "Home", vec![]);
+        home.internal_links_out = vec!["https://x.com/about/".into()];
+        home.links = vec![
+            link("https://x.com/about/", true),
+            link("https://www.github.com/x", false),
+            link("https://github.com/y", false),
+        ];
+        let mut about = page("https://x.com/about", "About", vec![]);
+        about.internal_links_out = vec!["https://x.com/about".into()];
+        about.links = vec![link("https://github.com/x", false)];
+        let lonely = page("https://x.com/lonely", "Lonely", vec![]);
+        let pages = vec![home, about, lonely];
+
+        let report = build_link_report(&pages, true, false);
+        assert_eq!(report.external_domains.len(), 1);
+        assert_eq!(report.external_domains[0].domain, "github.com");
+        assert_eq!(report.external_domains[0].links, 3);
+        assert_eq!(report.external_domains[0].pages, 2);
+        // Root is exempt; /about is linked (trailing slash ignored); the
+        // self-link on /about doesn't rescue anything.
+        assert_eq!(
+            report.orphan_pages,
+            vec!["https://x.com/lonely".to_string()]
+        );
+
+        let report = build_link_report(&pages, false, false);
+        assert!(report.orphan_pages.is_empty());
+    }
+
+    #[test]
+    fn links_to_a_scheme_or_www_twin_count_unless_strict() {
+        let mut home = page("https://x.com/", "Home", vec![]);
+        home.internal_links_out = vec!["http://www.x.com/about".into()];
+        let about = page("https://x.com/about", "About", vec![]);
+        let pages = vec![home, about];
+
+        let report = build_link_report(&pages, true, false);
+        assert!(report.orphan_pages.is_empty());
+        let site = build_site_data(&pages, "https://x.com/", false);
+        let about = site.sitemap.iter().find(|s| s.url.ends_with("/about"));
+        assert_eq!(about.unwrap().internal_links_in, 1);
+
+        assert_eq!(
+            build_link_report(&pages, true, true).orphan_pages,
+            vec!["https://x.com/about".to_string()]
+        );
+        let site = build_site_data(&pages, "https://x.com/", true);
+        let about = site.sitemap.iter().find(|s| s.url.ends_with("/about"));
+        assert_eq!(about.unwrap().internal_links_in, 0);
+    }
+
+    #[test]
+    fn build_site_tree_groups_by_path_and_uses_breadcrumbs() {
+        let mut post = page("https://x.com/blog/2024/hello", "Hello", vec![]);
+        post.structured_data = vec![serde_json::json!({
+            "@type": "BreadcrumbList",
+            "itemListElement": [
+                { "position": 1, "name": "Blog", "item": "https://x.com/blog/" },
+                { "position": 2, "name": "2024", "item": { "@id": "https://x.com/blog/2024" } }
+            ]
+        })];
+        let pages = vec![
+            page("https://x.com/", "Home", vec![]),
+            post,
+            page("https://x.com/blog/2024/again?ref=1", "Again", vec![]),
+            page("https://cdn.x.com/terms", "Terms", vec![]),
+        ];
+
+        let tree = build_site_tree(&pages, "https://x.com/", false);
+        assert_eq!(tree.path, "/");
+        assert_eq!(tree.title.as_deref(), Some("Home"));
+        assert_eq!(tree.page_count, 4);
+        let paths: Vec<&str> = tree.children.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/blog", "/cdn.x.com"]);
+        let blog = &tree.children[0];
+        assert_eq!(blog.url, None);
+        assert_eq!(blog.title.as_deref(), Some("Blog"));
+        assert_eq!(blog.page_count, 2);
+        assert_eq!(blog.children[0].title.as_deref(), Some("2024"));
+        assert_eq!(blog.children[0].children.len(), 2);
+    }
+
+    #[test]
+    fn build_site_tree_merges_www_and_bare_hosts() {
+        let mut post = page("https://www.x.com/blog/hello", "Hello", vec![]);
+        post.structured_data = vec![serde_json::json!({
+            "@type": "BreadcrumbList",
+            "itemListElement": [{ "name": "Blog", "item": "https://www.x.com/blog" }]
+        })];
+        let pages = vec![
+            page("https://x.com/", "Home", vec![]),
+            post,
+            page("https://x.com/blog/again", "Again", vec![]),
+        ];
+
+        let tree = build_site_tree(&pages, "https://x.com/", false);
+        let paths: Vec<&str> = tree.children.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/blog"]);
+        assert_eq!(tree.children[0].title.as_deref(), Some("Blog"));
+        assert_eq!(tree.children[0].page_count, 2);
+
+        let tree = build_site_tree(&pages, "https://x.com/", true);
+        let paths: Vec<&str> = tree.children.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/blog", "/www.x.com"]);
+    }
+
+    #[test]
+    fn hreflang_clusters_and_locale_dumps() {
+        use crate::model::HreflangAlternate;
+        let alt = |lang: &str, url: &str| HreflangAlternate {
+            lang: lang.to_string(),
+            url: url.to_string(),
+        };
+        let alts = vec![
+            alt("en", "https://x.com/en/about"),
+            alt("de", "https://x.com/de/about"),
+            alt("x-default", "https://x.com/en/about"),
+        ];
+        let mut en = page("https://x.com/en/about", "About", vec![]);
+        en.hreflang_alternates = alts.clone();
+        let mut de = page("https://x.com/de/about", "Über uns", vec![]);
+        de.hreflang_alternates = alts;
+        let mut plain = page("https://x.com/cs", "Ahoj", vec![]);
+        plain.language = Some("CS".into());
+        let bare = page("https://x.com/none", "None", vec![]);
+        let pages = vec![en, de, plain, bare];
+
+        assert_eq!(page_locale(&pages[1]).as_deref(), Some("de"));
+        assert_eq!(page_locale(&pages[2]).as_deref(), Some("cs"));
+        // Alternates in a hand-edited dump come out lower-cased too.
+        let mut shouting = page("https://x.com/gb", "Hello", vec![]);
+        shouting.hreflang_alternates = vec![alt("EN-GB", "https://x.com/gb")];
+        assert_eq!(page_locale(&shouting).as_deref(), Some("en-gb"));
+
+        // en and de declare the same set — one cluster, both scraped.
+        let clusters = build_hreflang_clusters(&pages);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].alternates.len(), 3);
+        assert_eq!(clusters[0].scraped.len(), 2);
+
+        let dumps = build_locale_dumps(&pages);
+        let locales: Vec<&str> = dumps.iter().map(|d| d.locale.as_str()).collect();
+        assert_eq!(locales, vec!["cs", "de", "en", "und"]);
+    }
+
+    #[test]
+    fn collapse_duplicate_pages_keeps_canonical_and_records_aliases() {
+        let blocks = || vec![h(1, "Same"), p("Identical body")];
+        let page = |url: &str, title: &str, blocks: Vec<ContentBlock>| {
+            let mut pg = page(url, title, blocks);
+            pg.content_hash = crate::util::hash_blocks(&pg.content_blocks);
+            pg
+        };
+        let a = page("https://x.com/shop?sort=asc", "Shop", blocks());
+        let mut b = page("https://x.com/shop-all", "Shop", blocks());
+        b.canonical_url = Some("https://x.com/shop-all".into());
+        let c = page("https://x.com/shop", "Shop", blocks());
+        let other = page("https://x.com/about", "About", vec![p("Different")]);
+        let empty1 = page("https://x.com/e1", "E", vec![]);
+        let empty2 = page("https://x.com/e2", "E", vec![]);
+
+        let out = collapse_duplicate_pages(vec![a, b, c, other, empty1, empty2]);
+        let urls: Vec<&str> = out.iter().map(|p| p.url.as_str()).collect();
+        // Self-canonical wins over the shorter URL; empty pages untouched.
+        assert_eq!(
+            urls,
+            vec![
+                "https://x.com/shop-all",
+                "https://x.com/about",
+                "https://x.com/e1",
+                "https://x.com/e2"
+            ]
+        );
+        assert_eq!(
+            out[0].aliases,
+            vec!["https://x.com/shop", "https://x.com/shop?sort=asc"]
+        );
+    }
+
+    #[test]
+    fn page_to_markdown_starts_with_yaml_front_matter() {
+        let mut pg = page("https://x.com/a", "Say \"hi\": now", vec![p("Body")]);
+        pg.total_words = 1;
+        let extra = vec![("section".to_string(), "blog".to_string())];
+        let md = page_to_markdown(&pg, "2024-03-01T12:00:00Z", &extra);
+        assert!(md.starts_with("---\ntitle: \"Say \\\"hi\\\": now\"\nurl: \"https://x.com/a\"\n"));
+        assert!(md.contains("scraped_at: \"2024-03-01T12:00:00Z\"\nword_count: 1\n"));
+        assert!(md.contains("section: \"blog\"\n---\n\n# Say"));
+
+        let extra = vec![
+            ("title".to_string(), "Custom".to_string()),
+            ("layout".to_string(), "page".to_string()),
+            ("layout".to_string(), "post".to_string()),
+        ];
+        let md = page_to_markdown(&pg, "2024-03-01T12:00:00Z", &extra);
+        assert!(md.starts_with("---\ntitle: \"Custom\"\nurl: \"https://x.com/a\"\n"));
+        assert_eq!(md.matches("title:").count(), 1);
+        assert!(md.contains("\nlayout: \"post\"\n---\n") && !md.contains("\"page\""));
+
+        let video = ContentBlock::Video {
+            sources: Vec::new(),
+            poster_url: None,
+            poster_local_path: None,
+            captions: Vec::new(),
+            title: String::new(),
+        };
+        let pg = page("https://x.com/v", "V", vec![video, p("After")]);
+        let md = page_to_markdown(&pg, "2024-03-01T12:00:00Z", &[]);
+        assert!(!md.contains("**video**") && md.ends_with("After\n\n"));
+    }
+
     #[test]
     fn detect_templates_groups_same_shape_pages() {
         let blocks = || {
@@ -2005,6 +2950,8 @@ mod tests {
                     original_url: "x".to_string(),
                     local_path: "p".to_string(),
                     alt_text: "".to_string(),
+                    svg_markup: None,
+                    fallback_urls: Vec::new(),
                 },
                 h(1, "Person Name"),
             ]
@@ -2019,4 +2966,31 @@ mod tests {
         assert_eq!(templates[0].page_count, 3);
         assert_eq!(templates[0].block_pattern, vec!["img", "h1"]);
     }
+
+    #[test]
+    fn scraped_json_streams_whole_or_one_page_per_line() {
+        let result = ScrapedData {
+            schema_version: crate::model::SCHEMA_VERSION.to_string(),
+            generator: crate::model::Generator::current(),
+            total_pages: 2,
+            pages: vec![
+                crate::model::test_page("https://x.com/"),
+                crate::model::test_page("https://x.com/about"),
+            ],
+        };
+        let written = |jsonl| {
+            let mut out = Vec::new();
+            write_scraped_json(&mut out, &result, jsonl).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let whole: JsonValue = serde_json::from_str(&written(false)).unwrap();
+        assert_eq!(whole["total_pages"], 2);
+        assert_eq!(whole["pages"][1]["url"], "https://x.com/about");
+        let lines: Vec<JsonValue> = written(true)
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["url"], "https://x.com/");
+    }
 }
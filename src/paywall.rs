@@ -0,0 +1,155 @@
+use scraper::{ElementRef, Html};
+use serde_json::Value as JsonValue;
+
+use crate::selectors::{SEL_BODY, SEL_INPUT};
+use crate::util::body_text_only;
+
+/// Extracted word count under which subscribe / log-in prompts mean the
+/// content is gated; longer pages just carry a newsletter box.
+const GATED_WORDS_MAX: usize = 300;
+
+/// Lowercased subscription prompts (en, de, cs).
+const PAYWALL_PHRASES: &[&str] = &[
+    "subscribe to continue",
+    "subscribe to read",
+    "subscribe now to read",
+    "subscribers only",
+    "exclusive to subscribers",
+    "already a subscriber",
+    "become a member to read",
+    "this article is for subscribers",
+    "unlock this article",
+    "jetzt abonnieren",
+    "nur für abonnenten",
+    "pouze pro předplatitele",
+    "článek je dostupný pouze",
+];
+
+/// Lowercased log-in prompts (en, de, cs).
+const LOGIN_PHRASES: &[&str] = &[
+    "log in to continue",
+    "login to continue",
+    "sign in to continue",
+    "sign in to read",
+    "log in to read",
+    "log in to view",
+    "sign in to view",
+    "please log in",
+    "please sign in",
+    "you must be logged in",
+    "members only",
+    "bitte melden sie sich an",
+    "pro zobrazení se přihlaste",
+];
+
+/// Class / id fragments of paywall overlays.
+const PAYWALL_MARKERS: &[&str] = &["paywall", "piano-offer", "tp-modal", "meteredcontent"];
+
+/// The gating signals of a page's DOM. The verdict also needs the page's
+/// word count, which for an `--amp prefer` page is only known once the
+/// DOM is gone (its blocks come from the AMP variant).
+pub(crate) struct AccessSignals {
+    declared: bool,
+    prompt: Option<&'static str>,
+}
+
+impl AccessSignals {
+    /// Why the page's content is gated, if it looks gated: `paywall` when
+    /// any structured-data item says `isAccessibleForFree: false`,
+    /// otherwise — only for pages under `GATED_WORDS_MAX` extracted words
+    /// — `paywall` for subscribe prompts or a paywall overlay and
+    /// `login_wall` for log-in prompts or a password field.
+    pub fn classify(&self, total_words: usize) -> Option<&'static str> {
+        if self.declared {
+            Some("paywall")
+        } else if total_words >= GATED_WORDS_MAX {
+            None
+        } else {
+            self.prompt
+        }
+    }
+}
+
+pub(crate) fn access_signals(doc: &Html, structured_data: &[JsonValue]) -> AccessSignals {
+    let declared = structured_data.iter().any(not_free);
+    AccessSignals {
+        declared,
+        prompt: if declared { None } else { gate_prompt(doc) },
+    }
+}
+
+/// Subscribe / log-in prompts, paywall overlays and password fields.
+fn gate_prompt(doc: &Html) -> Option<&'static str> {
+    let body = doc.select(&SEL_BODY).next()?;
+    let text = body_text_only(doc)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let overlay = body.descendants().filter_map(ElementRef::wrap).any(|el| {
+        let v = el.value();
+        [v.attr("class"), v.attr("id")]
+            .into_iter()
+            .flatten()
+            .any(|a| PAYWALL_MARKERS.iter().any(|m| a.to_lowercase().contains(m)))
+    });
+    if overlay || PAYWALL_PHRASES.iter().any(|p| text.contains(p)) {
+        return Some("paywall");
+    }
+    let password = doc.select(&SEL_INPUT).any(|i| {
+        i.value()
+            .attr("type")
+            .is_some_and(|t| t.eq_ignore_ascii_case("password"))
+    });
+    (password || LOGIN_PHRASES.iter().any(|p| text.contains(p))).then_some("login_wall")
+}
+
+/// `isAccessibleForFree` set to `false` (or `"False"`) anywhere in the
+/// item, including `hasPart` sections.
+fn not_free(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(map) => map.iter().any(|(k, v)| {
+            (k == "isAccessibleForFree"
+                && match v {
+                    JsonValue::Bool(b) => !b,
+                    JsonValue::String(s) => s.eq_ignore_ascii_case("false"),
+                    _ => false,
+                })
+                || not_free(v)
+        }),
+        JsonValue::Array(items) => items.iter().any(not_free),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gated_pages_are_recognised() {
+        let teaser = Html::parse_document(
+            "<body><article><p>The first lines of the story.</p></article>\
+             <div class='gate'>Subscribe to continue reading. Already a subscriber?</div></body>",
+        );
+        assert_eq!(access_signals(&teaser, &[]).classify(6), Some("paywall"));
+        assert_eq!(access_signals(&teaser, &[]).classify(1200), None);
+
+        let login = Html::parse_document(
+            "<body><h1>Dashboard</h1><form><input type='password'></form>\
+             <script>var s = 'subscribe to read';</script></body>",
+        );
+        assert_eq!(access_signals(&login, &[]).classify(1), Some("login_wall"));
+
+        let article = Html::parse_document("<body><p>Long read.</p></body>");
+        let ld = serde_json::json!({
+            "@type": "NewsArticle",
+            "hasPart": {"@type": "WebPageElement", "isAccessibleForFree": "False"},
+        });
+        assert_eq!(
+            access_signals(&article, &[ld]).classify(2000),
+            Some("paywall")
+        );
+        assert_eq!(access_signals(&article, &[]).classify(2), None);
+    }
+}
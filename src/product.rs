@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, HashSet};
+
+use scraper::Html;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use url::Url;
+
+use crate::model::{PageData, ProductRecord};
+use crate::selectors::{SEL_H1, SEL_PRODUCT_PRICE, SEL_PRODUCT_SKU};
+
+/// ISO 4217 codes recognised in price text.
+const CURRENCY_CODES: &[&str] = &[
+    "USD", "EUR", "GBP", "CHF", "CZK", "PLN", "HUF", "SEK", "NOK", "DKK", "CAD", "AUD", "JPY",
+    "INR",
+];
+
+/// Currency symbols and their codes, `US$` before `$`.
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("CA$", "CAD"),
+    ("A$", "AUD"),
+    ("Kč", "CZK"),
+    ("zł", "PLN"),
+    ("Ft", "HUF"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("$", "USD"),
+];
+
+/// schema.org `ItemAvailability` names, for normalising `in stock` /
+/// `instock` / `https://schema.org/InStock` alike.
+const AVAILABILITY: &[&str] = &[
+    "InStock",
+    "OutOfStock",
+    "PreOrder",
+    "PreSale",
+    "BackOrder",
+    "Discontinued",
+    "LimitedAvailability",
+    "OnlineOnly",
+    "InStoreOnly",
+    "SoldOut",
+    "MadeToOrder",
+    "Reserved",
+];
+
+/// A `products.json` entry: a product and the page it is on.
+#[derive(Serialize)]
+pub(crate) struct PageProduct<'a> {
+    page_url: &'a str,
+    #[serde(flatten)]
+    product: &'a ProductRecord,
+}
+
+fn currency_of(text: &str) -> Option<&'static str> {
+    let upper = text.to_uppercase();
+    CURRENCY_CODES
+        .iter()
+        .find(|code| {
+            upper
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .any(|word| word == **code)
+        })
+        .copied()
+        .or_else(|| {
+            CURRENCY_SYMBOLS
+                .iter()
+                .find(|(symbol, _)| text.contains(symbol))
+                .map(|(_, code)| *code)
+        })
+}
+
+/// Amount and currency of a price label: `$1,299.00` → (`1299.00`,
+/// `USD`), `1 299,50 Kč` → (`1299.50`, `CZK`), `19,-` → (`19`, none).
+/// A lone separator followed by three digits groups thousands.
+pub(crate) fn parse_price(text: &str) -> Option<(String, Option<&'static str>)> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(*c, '.' | ',' | '\'') || c.is_whitespace())
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .collect();
+    let number = number.trim_end_matches(|c: char| !c.is_ascii_digit());
+    let separators = number.matches(['.', ',']).count();
+    let decimal = match (number.rfind('.'), number.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(dot.max(comma)),
+        (Some(at), None) | (None, Some(at)) => {
+            (separators == 1 && number.len() - at - 1 != 3).then_some(at)
+        }
+        (None, None) => None,
+    };
+    let amount = number
+        .char_indices()
+        .filter_map(|(i, c)| match c {
+            '0'..='9' => Some(c),
+            _ if Some(i) == decimal => Some('.'),
+            _ => None,
+        })
+        .collect();
+    Some((amount, currency_of(text)))
+}
+
+fn availability(value: &str) -> Option<String> {
+    let name = value.trim().rsplit('/').next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let key: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase();
+    let known = AVAILABILITY.iter().find(|a| a.to_lowercase() == key);
+    Some(known.map_or(name, |a| *a).to_string())
+}
+
+/// A string or number as trimmed text; the first of an array, the `name`
+/// of an object (`"brand": {"@type": "Brand", "name": …}`).
+fn text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        JsonValue::Number(n) => Some(n.to_string()),
+        JsonValue::Array(items) => items.iter().find_map(text),
+        JsonValue::Object(obj) => obj.get("name").and_then(text),
+        _ => None,
+    }
+}
+
+fn price_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Number(n) => Some(n.to_string()),
+        _ => parse_price(&text(value)?).map(|(amount, _)| amount),
+    }
+}
+
+fn has_type(obj: &Map<String, JsonValue>, name: &str) -> bool {
+    let is = |t: &JsonValue| {
+        t.as_str()
+            .and_then(|t| t.rsplit(['/', ':']).next())
+            .is_some_and(|t| t.eq_ignore_ascii_case(name))
+    };
+    match obj.get("@type") {
+        Some(JsonValue::Array(types)) => types.iter().any(is),
+        Some(t) => is(t),
+        None => false,
+    }
+}
+
+/// Image URLs of an `image` value: URLs, ImageObjects or a list of them.
+fn images(value: &JsonValue, base: &Url, out: &mut Vec<String>) {
+    match value {
+        JsonValue::String(src) => {
+            if let Ok(url) = base.join(src.trim()) {
+                out.push(url.to_string());
+            }
+        }
+        JsonValue::Array(items) => items.iter().for_each(|v| images(v, base, out)),
+        JsonValue::Object(obj) => {
+            if let Some(src) = obj.get("url").or_else(|| obj.get("contentUrl")) {
+                images(src, base, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Every offer under `offers`: single Offers, lists, and the offers of an
+/// AggregateOffer after the AggregateOffer itself.
+fn offers<'a>(value: &'a JsonValue, out: &mut Vec<&'a Map<String, JsonValue>>) {
+    match value {
+        JsonValue::Array(items) => items.iter().for_each(|v| offers(v, out)),
+        JsonValue::Object(obj) => {
+            out.push(obj);
+            if let Some(inner) = obj.get("offers") {
+                offers(inner, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn from_structured(obj: &Map<String, JsonValue>, base: &Url) -> ProductRecord {
+    let mut all = Vec::new();
+    if let Some(value) = obj.get("offers") {
+        offers(value, &mut all);
+    }
+    let spec = |offer: &Map<String, JsonValue>, key: &str| {
+        let spec = offer.get("priceSpecification")?;
+        let spec = spec.as_array().and_then(|s| s.first()).unwrap_or(spec);
+        spec.get(key).cloned()
+    };
+    let priced = all.iter().find_map(|offer| {
+        let price = offer
+            .get("price")
+            .or_else(|| offer.get("lowPrice"))
+            .cloned()
+            .or_else(|| spec(offer, "price"))
+            .and_then(|p| price_text(&p))?;
+        let currency = offer
+            .get("priceCurrency")
+            .cloned()
+            .or_else(|| spec(offer, "priceCurrency"))
+            .and_then(|c| text(&c));
+        Some((*offer, price, currency))
+    });
+    let (offer, price, currency) = match priced {
+        Some((offer, price, currency)) => (Some(offer), Some(price), currency),
+        None => (None, None, None),
+    };
+    let availability = offer
+        .and_then(|offer| offer.get("availability"))
+        .or_else(|| all.iter().find_map(|offer| offer.get("availability")))
+        .and_then(text)
+        .and_then(|a| availability(&a));
+    let mut image_urls = Vec::new();
+    if let Some(value) = obj.get("image") {
+        images(value, base, &mut image_urls);
+    }
+    let mut seen = HashSet::new();
+    image_urls.retain(|u| seen.insert(u.clone()));
+    ProductRecord {
+        name: obj.get("name").and_then(text),
+        sku: obj
+            .get("sku")
+            .or_else(|| obj.get("productID"))
+            .and_then(text),
+        brand: obj.get("brand").and_then(text),
+        url: obj
+            .get("url")
+            .and_then(text)
+            .and_then(|u| base.join(&u).ok())
+            .map(|u| u.to_string()),
+        price,
+        currency,
+        availability,
+        images: image_urls,
+        source: "structured_data".to_string(),
+    }
+}
+
+fn walk(value: &JsonValue, base: &Url, out: &mut Vec<ProductRecord>) {
+    match value {
+        JsonValue::Object(obj) if has_type(obj, "Product") => out.push(from_structured(obj, base)),
+        JsonValue::Object(obj) => obj.values().for_each(|v| walk(v, base, out)),
+        JsonValue::Array(items) => items.iter().for_each(|v| walk(v, base, out)),
+        _ => {}
+    }
+}
+
+/// A product from `product:*` / `og:*` meta tags and common price / SKU
+/// markup. Needs a price: from the meta tags or a `data-price` attribute,
+/// or a price label that names its currency.
+fn from_markup(
+    doc: &Html,
+    base: &Url,
+    meta: &BTreeMap<String, Vec<String>>,
+) -> Option<ProductRecord> {
+    let meta_value = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|k| meta.get(*k)?.first())
+            .map(|v| v.trim().to_string())
+            .find(|v| !v.is_empty())
+    };
+    let (price, label_currency) = match meta_value(&["product:price:amount", "og:price:amount"]) {
+        Some(amount) => parse_price(&amount)?,
+        None => doc.select(&SEL_PRODUCT_PRICE).find_map(|el| {
+            let v = el.value();
+            let attr = v
+                .attr("data-price")
+                .or_else(|| v.attr("data-product-price"))
+                .or_else(|| v.attr("content"));
+            match attr {
+                Some(attr) => parse_price(attr),
+                None => parse_price(&el.text().collect::<String>()).filter(|(_, c)| c.is_some()),
+            }
+        })?,
+    };
+    let name = meta_value(&["og:title"]).or_else(|| {
+        doc.select(&SEL_H1).next().map(|h1| {
+            h1.text()
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    });
+    let sku = meta_value(&["product:retailer_item_id"]).or_else(|| {
+        doc.select(&SEL_PRODUCT_SKU).find_map(|el| {
+            let v = el.value();
+            let sku = match v.attr("data-sku").or_else(|| v.attr("data-product-sku")) {
+                Some(sku) => sku.to_string(),
+                None => el.text().collect::<String>(),
+            };
+            let sku = sku.trim();
+            let sku = sku
+                .strip_prefix("SKU:")
+                .or_else(|| sku.strip_prefix("SKU"))
+                .unwrap_or(sku)
+                .trim();
+            (!sku.is_empty()).then(|| sku.to_string())
+        })
+    });
+    let images = meta
+        .get("og:image")
+        .into_iter()
+        .flatten()
+        .filter_map(|src| base.join(src.trim()).ok())
+        .map(|u| u.to_string())
+        .collect();
+    Some(ProductRecord {
+        name: name.filter(|n| !n.is_empty()),
+        sku,
+        brand: meta_value(&["product:brand", "og:brand"]),
+        url: None,
+        price: Some(price),
+        currency: meta_value(&["product:price:currency", "og:price:currency"])
+            .or_else(|| label_currency.map(str::to_string)),
+        availability: meta_value(&["product:availability", "og:availability"])
+            .and_then(|a| availability(&a)),
+        images,
+        source: "selectors".to_string(),
+    })
+}
+
+/// `--preset product`: the page's products. schema.org Product data
+/// (JSON-LD, microdata, RDFa) wins; the markup fallback only applies to
+/// pages without any, and only with a price, so ordinary pages stay
+/// product-free.
+pub(crate) fn extract_products(
+    doc: &Html,
+    base: &Url,
+    structured_data: &[JsonValue],
+    meta: &BTreeMap<String, Vec<String>>,
+) -> Vec<ProductRecord> {
+    let mut found = Vec::new();
+    for value in structured_data {
+        walk(value, base, &mut found);
+    }
+    // JSON-LD and microdata often describe the same product twice.
+    let mut seen = HashSet::new();
+    found.retain(|p| seen.insert((p.name.clone(), p.sku.clone())));
+    if found.is_empty() {
+        found.extend(from_markup(doc, base, meta));
+    }
+    found
+}
+
+/// `--preset-only`: drop what the generic extraction made of the page
+/// body, leaving metadata and the preset's records.
+pub(crate) fn strip_generic_blocks(pages: &mut [PageData]) {
+    for page in pages {
+        page.content_blocks.clear();
+        page.amp_content_blocks.clear();
+        page.boilerplate_blocks.clear();
+        page.sections.clear();
+        page.outline.clear();
+        page.plain_text.clear();
+    }
+}
+
+/// Every page's products, for `products.json`.
+pub(crate) fn site_products(pages: &[PageData]) -> Vec<PageProduct<'_>> {
+    pages
+        .iter()
+        .flat_map(|page| {
+            page.products.iter().map(|product| PageProduct {
+                page_url: &page.url,
+                product,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{extract_meta_map, extract_structured_data};
+
+    fn products(html: &str) -> Vec<ProductRecord> {
+        let doc = Html::parse_document(html);
+        let base = Url::parse("https://shop.test/p/mug").unwrap();
+        let structured = extract_structured_data(&doc, &base);
+        extract_products(&doc, &base, &structured, &extract_meta_map(&doc))
+    }
+
+    #[test]
+    fn products_come_from_schema_org_then_markup() {
+        let found = products(
+            r#"<script type="application/ld+json">
+               {"@context": "https://schema.org", "@graph": [
+                 {"@type": "BreadcrumbList"},
+                 {"@type": ["Product"], "name": "Mug", "sku": "MUG-1",
+                  "brand": {"@type": "Brand", "name": "Acme"},
+                  "image": ["/img/mug.jpg", {"@type": "ImageObject", "url": "/img/mug-2.jpg"}],
+                  "offers": {"@type": "AggregateOffer", "lowPrice": "1,299.00",
+                             "priceCurrency": "USD",
+                             "offers": [{"@type": "Offer",
+                                         "availability": "https://schema.org/InStock"}]}}]}
+               </script>
+               <div itemscope itemtype="https://schema.org/Product">
+                 <span itemprop="name">Mug</span><meta itemprop="sku" content="MUG-1">
+                 <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                   <span itemprop="price" content="1299">$1,299</span></div></div>"#,
+        );
+        assert_eq!(
+            found,
+            [ProductRecord {
+                name: Some("Mug".into()),
+                sku: Some("MUG-1".into()),
+                brand: Some("Acme".into()),
+                url: None,
+                price: Some("1299.00".into()),
+                currency: Some("USD".into()),
+                availability: Some("InStock".into()),
+                images: vec![
+                    "https://shop.test/img/mug.jpg".into(),
+                    "https://shop.test/img/mug-2.jpg".into()
+                ],
+                source: "structured_data".into(),
+            }]
+        );
+
+        let found = products(
+            r#"<head><meta property="og:title" content="Cap">
+               <meta property="og:image" content="/img/cap.png">
+               <meta property="product:availability" content="out of stock"></head>
+               <body><h1>Cap</h1><span class="sku">SKU: CAP-7</span>
+               <p class="price">1 299,50 Kč</p></body>"#,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name.as_deref(), Some("Cap"));
+        assert_eq!(found[0].sku.as_deref(), Some("CAP-7"));
+        assert_eq!(found[0].price.as_deref(), Some("1299.50"));
+        assert_eq!(found[0].currency.as_deref(), Some("CZK"));
+        assert_eq!(found[0].availability.as_deref(), Some("OutOfStock"));
+        assert_eq!(found[0].images, ["https://shop.test/img/cap.png"]);
+        assert_eq!(found[0].source, "selectors");
+
+        // A number in a `.price` element without a currency isn't a price.
+        assert!(products(r#"<h1>Plans</h1><div class="price">Save 20</div>"#).is_empty());
+
+        assert_eq!(parse_price("€19,99"), Some(("19.99".into(), Some("EUR"))));
+        assert_eq!(parse_price("1.299 EUR"), Some(("1299".into(), Some("EUR"))));
+        assert_eq!(parse_price("19,-"), Some(("19".into(), None)));
+        assert_eq!(parse_price("free"), None);
+    }
+}
@@ -0,0 +1,174 @@
+#[cfg(feature = "kafka")]
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+#[cfg(feature = "kafka")]
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+#[cfg(feature = "kafka")]
+use rskafka::client::ClientBuilder;
+#[cfg(feature = "kafka")]
+use rskafka::record::Record;
+use serde_json::Value;
+#[cfg(feature = "kafka")]
+use sha2::{Digest, Sha256};
+
+/// `--export kafka`: one record per page on `topic`, keyed by URL.
+#[cfg(feature = "kafka")]
+pub(crate) struct Kafka {
+    brokers: Vec<String>,
+    pub topic: String,
+    /// One client per partition of the topic, in partition order. Filled
+    /// by [`Kafka::connect`].
+    partitions: Vec<PartitionClient>,
+}
+
+/// `--export nats`: one message per page on `subject`.
+#[cfg(feature = "nats")]
+pub(crate) struct Nats {
+    url: String,
+    pub subject: String,
+    client: Option<async_nats::Client>,
+}
+
+/// Index into a topic's partitions for `url`: the same page always lands
+/// on the same partition, across runs too, so consumers see its versions
+/// in order.
+#[cfg(feature = "kafka")]
+fn partition_for(url: &str, partitions: usize) -> usize {
+    let digest = Sha256::digest(url.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % partitions.max(1) as u64) as usize
+}
+
+#[cfg(feature = "kafka")]
+impl Kafka {
+    pub fn from_args(args: &crate::cli::ExportArgs) -> anyhow::Result<Self> {
+        let brokers: Vec<String> = args
+            .kafka_brokers
+            .split(',')
+            .map(|b| b.trim().to_string())
+            .filter(|b| !b.is_empty())
+            .collect();
+        if brokers.is_empty() {
+            anyhow::bail!("--kafka-brokers is empty");
+        }
+        Ok(Self {
+            brokers,
+            topic: args.kafka_topic.clone(),
+            partitions: Vec::new(),
+        })
+    }
+
+    /// Connect and open a client for every partition of the topic, which
+    /// has to exist: clusters that auto-create topics would do it with
+    /// their defaults, not the partitioning the consumers expect.
+    pub async fn connect(&mut self) -> anyhow::Result<()> {
+        let client = ClientBuilder::new(self.brokers.clone())
+            .build()
+            .await
+            .with_context(|| format!("could not reach Kafka at {}", self.brokers.join(",")))?;
+        let topics = client.list_topics().await.context("listing Kafka topics")?;
+        let Some(topic) = topics.into_iter().find(|t| t.name == self.topic) else {
+            anyhow::bail!("Kafka topic {} does not exist", self.topic);
+        };
+        for partition in topic.partitions {
+            let partition_client = client
+                .partition_client(self.topic.clone(), partition, UnknownTopicHandling::Error)
+                .await
+                .with_context(|| format!("opening {} partition {partition}", self.topic))?;
+            self.partitions.push(partition_client);
+        }
+        Ok(())
+    }
+
+    /// Produce one record per document; returns how many were rejected.
+    pub async fn produce(&self, docs: &[Value]) -> anyhow::Result<usize> {
+        let mut by_partition: BTreeMap<usize, Vec<Record>> = BTreeMap::new();
+        for doc in docs {
+            let url = doc["url"].as_str().unwrap_or_default();
+            by_partition
+                .entry(partition_for(url, self.partitions.len()))
+                .or_default()
+                .push(Record {
+                    key: Some(url.as_bytes().to_vec()),
+                    value: Some(doc.to_string().into_bytes()),
+                    headers: BTreeMap::from([(
+                        "content-type".to_string(),
+                        b"application/json".to_vec(),
+                    )]),
+                    timestamp: chrono::Utc::now(),
+                });
+        }
+        let mut rejected = 0;
+        for (index, records) in by_partition {
+            let count = records.len();
+            let Some(partition) = self.partitions.get(index) else {
+                rejected += count;
+                continue;
+            };
+            // Usually a record over the broker's message.max.bytes (1 MB
+            // by default): a long page with every block in it.
+            if let Err(e) = partition.produce(records, Compression::NoCompression).await {
+                tracing::warn!("Kafka rejected {count} records: {e}");
+                rejected += count;
+            }
+        }
+        Ok(rejected)
+    }
+}
+
+#[cfg(feature = "nats")]
+impl Nats {
+    pub fn from_args(args: &crate::cli::ExportArgs) -> Self {
+        Self {
+            url: args.nats_url.clone(),
+            subject: args.nats_subject.clone(),
+            client: None,
+        }
+    }
+
+    pub async fn connect(&mut self) -> anyhow::Result<()> {
+        let client = async_nats::connect(self.url.as_str())
+            .await
+            .with_context(|| format!("could not reach NATS at {}", self.url))?;
+        self.client = Some(client);
+        Ok(())
+    }
+
+    /// Publish one message per document and flush; returns how many could
+    /// not be published (over the server's max_payload, 1 MB by default).
+    pub async fn publish(&self, docs: &[Value]) -> anyhow::Result<usize> {
+        let client = self.client.as_ref().context("NATS is not connected")?;
+        let mut rejected = 0;
+        for doc in docs {
+            let payload = doc.to_string().into_bytes();
+            if let Err(e) = client.publish(self.subject.clone(), payload.into()).await {
+                tracing::warn!(
+                    "NATS publish of {} failed: {e}",
+                    doc["url"].as_str().unwrap_or_default()
+                );
+                rejected += 1;
+            }
+        }
+        client.flush().await.context("flushing NATS messages")?;
+        Ok(rejected)
+    }
+}
+
+#[cfg(all(test, feature = "kafka"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_keep_their_partition() {
+        let a = partition_for("https://x.com/a", 6);
+        assert!(a < 6);
+        assert_eq!(a, partition_for("https://x.com/a", 6));
+        let spread: std::collections::BTreeSet<usize> = (0..60)
+            .map(|i| partition_for(&format!("https://x.com/{i}"), 6))
+            .collect();
+        assert!(spread.len() > 1);
+        assert_eq!(partition_for("https://x.com/a", 0), 0);
+    }
+}
@@ -0,0 +1,184 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use regex::RegexBuilder;
+
+use crate::model::PageData;
+
+/// Characters of page text shown around a hit.
+const SNIPPET_CHARS: usize = 200;
+
+/// One page matching a `dump-it search` query.
+pub(crate) struct SearchHit {
+    pub url: String,
+    pub title: String,
+    pub score: f32,
+    /// Excerpt of the page text around the best match.
+    pub snippet: String,
+    /// Byte ranges of matched terms within `snippet`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// `dump-it search`: query `index_dir` when it holds a `--index` index,
+/// otherwise scan `<input>/scraped.json` (or scraped.jsonl) for pages
+/// containing every query word.
+pub(crate) fn run_search(
+    query: &str,
+    input: &Path,
+    index_dir: Option<&Path>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let limit = limit.max(1);
+    let index_dir = index_dir.map_or_else(|| input.join("index"), Path::to_path_buf);
+    let hits = if crate::index::is_index(&index_dir) {
+        crate::index::search_index(&index_dir, query, limit, SNIPPET_CHARS)?
+    } else {
+        println!(
+            "🔎 No search index at {} — scanning page text instead",
+            index_dir.display()
+        );
+        grep_pages(&crate::dump::load_dump(input)?.pages, query, limit)
+    };
+
+    if hits.is_empty() {
+        println!("No pages match {query:?}");
+        return Ok(());
+    }
+    let color = std::io::stdout().is_terminal();
+    for (i, hit) in hits.iter().enumerate() {
+        let title = if hit.title.is_empty() {
+            "(untitled)"
+        } else {
+            &hit.title
+        };
+        println!("{}. {title} — {} ({:.2})", i + 1, hit.url, hit.score);
+        if !hit.snippet.is_empty() {
+            println!("   {}", highlight(&hit.snippet, &hit.highlights, color));
+        }
+    }
+    Ok(())
+}
+
+/// Case-insensitive scan for pages whose title or text contains every
+/// word of `query`, ranked by match count (title matches count triple,
+/// like the index's title boost).
+fn grep_pages(pages: &[PageData], query: &str, limit: usize) -> Vec<SearchHit> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<SearchHit> = pages
+        .iter()
+        .filter_map(|page| {
+            let body = grep_text(&page.plain_text, &words);
+            let title = grep_text(&page.title, &words);
+            let title_score = title.as_ref().map_or(0, |(n, _, _)| 3 * n);
+            let (body_score, snippet, highlights) = body.or(title.map(|(_, s, h)| (0, s, h)))?;
+            Some(SearchHit {
+                url: page.url.clone(),
+                title: page.title.clone(),
+                score: (body_score + title_score) as f32,
+                snippet,
+                highlights,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.url.cmp(&b.url)));
+    hits.truncate(limit);
+    hits
+}
+
+/// Match count, snippet and in-snippet highlight ranges of [`grep_text`].
+type Grep = (usize, String, Vec<(usize, usize)>);
+
+/// Match count, snippet and in-snippet highlight ranges when `text`
+/// contains every one of `words` (case-insensitively); `None` otherwise.
+fn grep_text(text: &str, words: &[&str]) -> Option<Grep> {
+    // Longest first: the regex takes the first alternative that matches,
+    // so `grind|grinders` would only ever find `grind`.
+    let mut sorted = words.to_vec();
+    sorted.sort_by_key(|w| std::cmp::Reverse(w.len()));
+    let alternation = sorted
+        .iter()
+        .map(|w| regex::escape(w))
+        .collect::<Vec<_>>()
+        .join("|");
+    let re = RegexBuilder::new(&alternation)
+        .case_insensitive(true)
+        .build()
+        .ok()?;
+    let matches: Vec<regex::Match> = re.find_iter(text).collect();
+    let all_found = words.iter().all(|w| {
+        matches
+            .iter()
+            .any(|m| m.as_str().to_lowercase().contains(&w.to_lowercase()))
+    });
+    if !all_found {
+        return None;
+    }
+
+    // Window of ~SNIPPET_CHARS starting a little before the first match.
+    let first = matches[0].start();
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS / 4)
+        .map_or(0, |(i, _)| i);
+    let end = text[start..]
+        .char_indices()
+        .nth(SNIPPET_CHARS)
+        .map_or(text.len(), |(i, _)| start + i);
+    let highlights = matches
+        .iter()
+        .filter(|m| m.start() >= start && m.end() <= end)
+        .map(|m| (m.start() - start, m.end() - start))
+        .collect();
+    Some((matches.len(), text[start..end].to_string(), highlights))
+}
+
+/// Snippet on one line with its matches emphasised: bold yellow on a
+/// terminal, `**…**` when piped.
+fn highlight(snippet: &str, ranges: &[(usize, usize)], color: bool) -> String {
+    let (open, close) = if color {
+        ("\x1b[1;33m", "\x1b[0m")
+    } else {
+        ("**", "**")
+    };
+    let mut out = String::with_capacity(snippet.len() + ranges.len() * 8);
+    let mut pos = 0;
+    for &(s, e) in ranges {
+        if s < pos || e > snippet.len() {
+            continue;
+        }
+        out.push_str(&snippet[pos..s]);
+        out.push_str(open);
+        out.push_str(&snippet[s..e]);
+        out.push_str(close);
+        pos = e;
+    }
+    out.push_str(&snippet[pos..]);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grep_text_requires_every_word_and_highlights_matches() {
+        let text = "Our Coffee grinders ship worldwide. Grinders come with a warranty.";
+        let (count, snippet, ranges) = grep_text(text, &["grinders", "warranty"]).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(snippet, text);
+        assert_eq!(&snippet[ranges[0].0..ranges[0].1], "grinders");
+        assert_eq!(&snippet[ranges[1].0..ranges[1].1], "Grinders");
+        assert!(grep_text(text, &["grinders", "refund"]).is_none());
+        let (count, _, ranges) = grep_text(text, &["grind", "grinders"]).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(&text[ranges[0].0..ranges[0].1], "grinders");
+        assert_eq!(
+            highlight(&snippet[..20], &ranges[..1], false),
+            "Our Coffee **grinders**"
+        );
+    }
+}
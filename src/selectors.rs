@@ -10,7 +10,6 @@ macro_rules! sel {
     };
 }
 
-sel!(SEL_LOC, "loc");
 sel!(SEL_BODY, "body");
 sel!(SEL_TITLE, "title");
 sel!(SEL_META, "meta");
@@ -21,7 +20,14 @@ sel!(
     SEL_SUBMIT,
     "button[type='submit'], input[type='submit'], button:not([type])"
 );
+sel!(
+    SEL_FORM_BUTTON,
+    "button, input[type='button'], input[type='submit']"
+);
+sel!(SEL_FORM_SECTION_TITLE, "legend, h1, h2, h3, h4, h5, h6");
 sel!(SEL_LINK, "a[href]");
+sel!(SEL_IMG, "img[alt]");
+sel!(SEL_IMG_ANY, "img");
 sel!(SEL_HTML, "html");
 sel!(SEL_MAIN, "main, article, [role='main']");
 sel!(SEL_NAV, "nav, header, [role='navigation'], [role='banner']");
@@ -32,6 +38,8 @@ sel!(
     "link[rel~='icon'], link[rel='apple-touch-icon']"
 );
 sel!(SEL_JSONLD, r#"script[type="application/ld+json"]"#);
+sel!(SEL_ITEMSCOPE, "[itemscope]");
+sel!(SEL_RDFA_TYPEOF, "[typeof]");
 sel!(
     SEL_HEADER_IMG,
     "header img, header svg, [class*='logo'] img, [class*='logo'] svg, [id*='logo'] img, [id*='logo'] svg, a[aria-label*='home' i] img, a[href='/'] img"
@@ -39,6 +47,7 @@ sel!(
 sel!(SEL_STYLE_BLOCK, "style");
 sel!(SEL_STYLESHEET, "link[rel='stylesheet']");
 sel!(SEL_HREFLANG, "link[rel='alternate'][hreflang]");
+sel!(SEL_LINK_REL, "link[rel][href]");
 sel!(SEL_TR, "tr");
 sel!(SEL_TH, "th");
 sel!(SEL_TD, "td");
@@ -46,7 +55,17 @@ sel!(SEL_CAPTION, "caption");
 sel!(SEL_FIGCAPTION, "figcaption");
 sel!(SEL_CODE_INSIDE_PRE, "code");
 sel!(SEL_VIDEO_SOURCE, "source");
+sel!(SEL_VIDEO_TRACK, "track");
 sel!(SEL_DT, "dt");
+sel!(SEL_H1, "h1");
+sel!(
+    SEL_PRODUCT_PRICE,
+    "[data-price], [data-product-price], .product-price, .product__price, .price, .woocommerce-Price-amount"
+);
+sel!(
+    SEL_PRODUCT_SKU,
+    "[data-sku], [data-product-sku], .sku, .product-sku, .product__sku"
+);
 sel!(SEL_DD, "dd");
 sel!(
     SEL_SKIP,
@@ -55,6 +74,10 @@ sel!(
      .swiper-slide-duplicate, .swiper-slide-duplicate-active, .slick-cloned"
 );
 
+/// Always prepended to `--skip-selectors`: a custom skip list replaces
+/// `SEL_SKIP`'s chrome selectors, never these.
+pub(crate) const ALWAYS_SKIPPED: &str = "script, style, noscript";
+
 pub(crate) const USER_AGENT: &str = "Mozilla/5.0 (compatible; DumpIt/0.1)";
 
 pub(crate) static RE_EMAIL: LazyLock<Regex> = LazyLock::new(|| {
@@ -126,3 +149,23 @@ pub(crate) const DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[
     "/my-account/",
     "?p=",
 ];
+
+/// Tracker / beacon image URLs never downloaded (case-insensitive
+/// substrings). Host- and path-specific, so a legitimate `analytics.png`
+/// or `pixel-art.jpg` still gets through. `--no-default-image-denylist`
+/// disables, `--image-deny` / `--image-allow` extend.
+pub(crate) const DEFAULT_IMAGE_DENY_PATTERNS: &[&str] = &[
+    "googletagmanager.com/",
+    "google-analytics.com/",
+    "doubleclick.net/",
+    "facebook.com/tr",
+    "bat.bing.com/",
+    "px.ads.linkedin.com/",
+    "ct.pinterest.com/",
+    "analytics.twitter.com/",
+    "t.co/i/adsct",
+    "pixel.wp.com/",
+    "/tracking-pixel",
+    "/pixel.gif",
+    "/beacon.gif",
+];
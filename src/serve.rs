@@ -0,0 +1,196 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Request line + headers we read; anything longer is not a browser GET.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// `dump-it serve`: a minimal read-only static file server over a bundle
+/// directory. GET / HEAD only, one request per connection.
+pub(crate) async fn run_serve(input: &Path, bind: SocketAddr) -> anyhow::Result<()> {
+    let root = input
+        .canonicalize()
+        .with_context(|| format!("{} not found", input.display()))?;
+    let listener = TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("failed to listen on {bind}"))?;
+    println!(
+        "🌐 Serving {} at http://{bind}/ (Ctrl+C to stop)",
+        root.display()
+    );
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let root = root.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &root).await {
+                tracing::debug!("serve: {e:#}");
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, root: &Path) -> anyhow::Result<()> {
+    let mut buf = vec![0u8; MAX_REQUEST_BYTES];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or("/");
+
+    let (status, content_type, body) = if method != "GET" && method != "HEAD" {
+        (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"GET only\n".to_vec(),
+        )
+    } else {
+        match resolve(root, target) {
+            Some(path) if path.is_dir() => (
+                "200 OK",
+                "text/html; charset=utf-8",
+                listing(&path, target).into_bytes(),
+            ),
+            Some(path) => match tokio::fs::read(&path).await {
+                Ok(bytes) => ("200 OK", content_type(&path), bytes),
+                Err(_) => ("404 Not Found", "text/plain", b"not found\n".to_vec()),
+            },
+            None => ("404 Not Found", "text/plain", b"not found\n".to_vec()),
+        }
+    };
+    tracing::debug!("serve: {method} {target} → {status}");
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if method != "HEAD" {
+        stream.write_all(&body).await?;
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Bundle-relative path for a request target: query / fragment dropped,
+/// `/` → `index.md`. `None` for targets that try to climb out with `..`.
+/// Bundle file names are ASCII slugs, so no percent-decoding is needed.
+fn request_path(target: &str) -> Option<&str> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let rel = path.trim_start_matches('/');
+    if rel.split('/').any(|seg| seg == "..") {
+        return None;
+    }
+    Some(if rel.is_empty() { "index.md" } else { rel })
+}
+
+/// Existing file or directory under `root` for a request target. The
+/// canonicalised path must stay inside `root`, which also rules out
+/// symlinks pointing elsewhere.
+fn resolve(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = root.join(request_path(target)?).canonicalize().ok()?;
+    path.starts_with(root).then_some(path)
+}
+
+fn listing(dir: &Path, target: &str) -> String {
+    let base = target.split(['?', '#']).next().unwrap_or("/");
+    let base = base.trim_end_matches('/');
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    if e.path().is_dir() {
+                        format!("{name}/")
+                    } else {
+                        name
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    let base = escape_html(base);
+    let items: String = names
+        .iter()
+        .map(|n| {
+            let n = escape_html(n);
+            format!("<li><a href=\"{base}/{n}\">{n}</a></li>\n")
+        })
+        .collect();
+    format!("<!doctype html>\n<title>{base}/</title>\n<ul>\n{items}</ul>\n")
+}
+
+/// `text` safe inside an element or a double-quoted attribute.
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "json" => "application/json",
+        "jsonl" => "application/x-ndjson",
+        "md" => "text/markdown; charset=utf-8",
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "avif" => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_path_maps_root_and_rejects_traversal() {
+        assert_eq!(request_path("/"), Some("index.md"));
+        assert_eq!(request_path("/site.json?x=1"), Some("site.json"));
+        assert_eq!(request_path("/images/logo.png"), Some("images/logo.png"));
+        assert_eq!(request_path("/../etc/passwd"), None);
+        assert_eq!(request_path("/images/../../secret"), None);
+    }
+
+    #[test]
+    fn listing_escapes_file_names() {
+        let dir = std::env::temp_dir().join(format!("dump-it-serve-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("<img src=x onerror=alert(1)>.md"), "").unwrap();
+        std::fs::write(dir.join("a\"&b.json"), "").unwrap();
+        let html = listing(&dir, "/");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(!html.contains("<img"), "{html}");
+        assert!(
+            html.contains("&lt;img src=x onerror=alert(1)&gt;.md</a>"),
+            "{html}"
+        );
+        assert!(html.contains("href=\"/a&quot;&amp;b.json\""), "{html}");
+    }
+}
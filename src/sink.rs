@@ -0,0 +1,285 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::elasticsearch::Elasticsearch;
+use crate::meilisearch::Meilisearch;
+use crate::model::PageData;
+#[cfg(feature = "kafka")]
+use crate::queue::Kafka;
+#[cfg(feature = "nats")]
+use crate::queue::Nats;
+
+/// Pages per request to a sink.
+const BATCH_SIZE: usize = 100;
+
+/// Pages queued for a sink before the publisher waits on it.
+const QUEUE: usize = 10 * BATCH_SIZE;
+
+/// A part-filled batch for a search index goes out after this long without
+/// a new page.
+const FLUSH_AFTER: Duration = Duration::from_secs(2);
+
+/// `--export`: the sinks pages are streamed to. `None` while there are
+/// none, which makes [`publish`] and [`publish_extracted`] no-ops.
+static STREAMS: Mutex<Option<Vec<Stream>>> = Mutex::new(None);
+
+/// A destination for finished pages, outside the output directory.
+pub(crate) enum Sink {
+    Elasticsearch(Elasticsearch),
+    Meilisearch(Meilisearch),
+    #[cfg(feature = "kafka")]
+    Kafka(Kafka),
+    #[cfg(feature = "nats")]
+    Nats(Nats),
+    /// An in-process consumer: the `dump-it grpc` stream of the call.
+    /// Bounded, so a slow consumer holds the crawl back.
+    #[cfg(feature = "grpc")]
+    Channel(mpsc::Sender<Value>),
+}
+
+impl Sink {
+    /// For progress lines: `Elasticsearch index site`.
+    fn name(&self) -> String {
+        match self {
+            Sink::Elasticsearch(es) => format!("Elasticsearch index {}", es.index),
+            Sink::Meilisearch(meili) => format!("MeiliSearch index {}", meili.index),
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(kafka) => format!("Kafka topic {}", kafka.topic),
+            #[cfg(feature = "nats")]
+            Sink::Nats(nats) => format!("NATS subject {}", nats.subject),
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => "gRPC stream".to_string(),
+        }
+    }
+
+    /// Whether the sink takes each page as soon as it is extracted rather
+    /// than the final pages of the run. Only the gRPC stream does: its
+    /// crawl has no post-crawl passes that could still drop or change a
+    /// page.
+    fn live(&self) -> bool {
+        match self {
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => true,
+            _ => false,
+        }
+    }
+
+    /// How long a part-filled batch waits for more pages: search indexes
+    /// prefer fewer, fuller requests, while a queue's consumers want each
+    /// page as soon as it's done.
+    fn linger(&self) -> Duration {
+        match self {
+            Sink::Elasticsearch(_) | Sink::Meilisearch(_) => FLUSH_AFTER,
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(_) => Duration::ZERO,
+            #[cfg(feature = "nats")]
+            Sink::Nats(_) => Duration::ZERO,
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => Duration::ZERO,
+        }
+    }
+
+    /// Pages per batch and pages queued ahead of it. An in-process
+    /// consumer takes them one at a time, so its own bound is the only
+    /// buffer between it and the crawl.
+    fn sizes(&self) -> (usize, usize) {
+        match self {
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => (1, 1),
+            _ => (BATCH_SIZE, QUEUE),
+        }
+    }
+
+    /// Set up the destination before the first page (index + mapping,
+    /// broker connection).
+    async fn prepare(&mut self) -> anyhow::Result<()> {
+        match self {
+            Sink::Elasticsearch(es) => {
+                if es.create_index().await? {
+                    progress!("🔍 Created Elasticsearch index {}", es.index);
+                }
+                Ok(())
+            }
+            Sink::Meilisearch(meili) => meili.configure().await,
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(kafka) => kafka.connect().await,
+            #[cfg(feature = "nats")]
+            Sink::Nats(nats) => nats.connect().await,
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => Ok(()),
+        }
+    }
+
+    /// What this sink stores for `page`.
+    fn document(&self, page: &PageData) -> Value {
+        match self {
+            Sink::Elasticsearch(_) => crate::elasticsearch::document(page),
+            Sink::Meilisearch(_) => crate::meilisearch::document(page),
+            // Queues carry the whole page.
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(_) => serde_json::to_value(page).unwrap_or_default(),
+            #[cfg(feature = "nats")]
+            Sink::Nats(_) => serde_json::to_value(page).unwrap_or_default(),
+            #[cfg(feature = "grpc")]
+            Sink::Channel(_) => serde_json::to_value(page).unwrap_or_default(),
+        }
+    }
+
+    /// Deliver a batch; `Ok` holds how many documents were rejected.
+    async fn send(&self, docs: &[Value]) -> anyhow::Result<usize> {
+        match self {
+            Sink::Elasticsearch(es) => es.bulk(docs).await,
+            Sink::Meilisearch(meili) => meili.add_documents(docs).await,
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(kafka) => kafka.produce(docs).await,
+            #[cfg(feature = "nats")]
+            Sink::Nats(nats) => nats.publish(docs).await,
+            #[cfg(feature = "grpc")]
+            Sink::Channel(tx) => {
+                let mut rejected = 0;
+                for doc in docs {
+                    if tx.send(doc.clone()).await.is_err() {
+                        rejected += 1;
+                    }
+                }
+                Ok(rejected)
+            }
+        }
+    }
+}
+
+struct Stream {
+    sink: Arc<Sink>,
+    tx: mpsc::Sender<Value>,
+    task: JoinHandle<Delivered>,
+}
+
+/// Per-sink totals for the end-of-run line.
+struct Delivered {
+    sent: usize,
+    failed: usize,
+}
+
+async fn flush(sink: &Sink, batch: &mut Vec<Value>, delivered: &mut Delivered) {
+    if batch.is_empty() {
+        return;
+    }
+    match sink.send(batch).await {
+        Ok(rejected) => {
+            delivered.sent += batch.len() - rejected;
+            delivered.failed += rejected;
+        }
+        // The pages still end up in the bundle; a sink outage shouldn't
+        // stop the crawl.
+        Err(e) => {
+            tracing::warn!(
+                "{}: {} pages not delivered: {e:#}",
+                sink.name(),
+                batch.len()
+            );
+            delivered.failed += batch.len();
+        }
+    }
+    batch.clear();
+}
+
+/// Batch up pages for one sink until [`finish`] closes the channel.
+async fn drain(sink: Arc<Sink>, mut rx: mpsc::Receiver<Value>) -> Delivered {
+    let mut delivered = Delivered { sent: 0, failed: 0 };
+    let (batch_size, _) = sink.sizes();
+    let mut batch = Vec::with_capacity(batch_size);
+    loop {
+        let next = if batch.is_empty() {
+            Some(rx.recv().await)
+        } else {
+            tokio::time::timeout(sink.linger(), rx.recv()).await.ok()
+        };
+        match next {
+            Some(Some(doc)) => {
+                batch.push(doc);
+                if batch.len() < batch_size {
+                    continue;
+                }
+            }
+            Some(None) => {
+                flush(&sink, &mut batch, &mut delivered).await;
+                return delivered;
+            }
+            // Nothing more within the linger: send what's there.
+            None => {}
+        }
+        flush(&sink, &mut batch, &mut delivered).await;
+    }
+}
+
+/// Prepare every sink and start streaming to them. A sink that can't be
+/// set up (unreachable, bad credentials) fails the run before the crawl.
+pub(crate) async fn start(sinks: Vec<Sink>) -> anyhow::Result<()> {
+    if sinks.is_empty() {
+        return Ok(());
+    }
+    let mut streams = Vec::with_capacity(sinks.len());
+    for mut sink in sinks {
+        sink.prepare().await?;
+        progress!("📡 Streaming pages to {}", sink.name());
+        let sink = Arc::new(sink);
+        let (tx, rx) = mpsc::channel(sink.sizes().1);
+        let task = tokio::spawn(drain(Arc::clone(&sink), rx));
+        streams.push(Stream { sink, tx, task });
+    }
+    *STREAMS.lock().unwrap() = Some(streams);
+    Ok(())
+}
+
+/// Hand one of the run's final pages (soft 404s, duplicates and the like
+/// already dropped) to every `--export` sink, waiting while a sink's
+/// queue is full.
+pub(crate) async fn publish(page: &PageData) {
+    send_to(page, false).await;
+}
+
+/// Hand a page to the live sinks as soon as it is extracted.
+pub(crate) async fn publish_extracted(page: &PageData) {
+    send_to(page, true).await;
+}
+
+async fn send_to(page: &PageData, live: bool) {
+    let queues: Vec<_> = STREAMS
+        .lock()
+        .unwrap()
+        .iter()
+        .flatten()
+        .filter(|stream| stream.sink.live() == live)
+        .map(|stream| (Arc::clone(&stream.sink), stream.tx.clone()))
+        .collect();
+    for (sink, tx) in queues {
+        let _ = tx.send(sink.document(page)).await;
+    }
+}
+
+/// Send what's still queued, stop streaming and report per sink.
+pub(crate) async fn finish() {
+    let Some(streams) = STREAMS.lock().unwrap().take() else {
+        return;
+    };
+    for Stream { sink, tx, task } in streams {
+        drop(tx);
+        let Ok(delivered) = task.await else {
+            continue;
+        };
+        if delivered.failed > 0 {
+            progress!(
+                "⚠️  {}: {} pages sent, {} failed",
+                sink.name(),
+                delivered.sent,
+                delivered.failed
+            );
+        } else {
+            progress!("📡 {}: {} pages sent", sink.name(), delivered.sent);
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+
+use crate::model::{SitemapImage, SitemapMeta, SitemapNews, SitemapVideo};
+
+/// Namespace of `<urlset>` / `<sitemapindex>` (sitemaps.org).
+const SITEMAP_NS: &[u8] = b"http://www.sitemaps.org/schemas/sitemap/0.9";
+/// Google's sitemap extensions.
+const IMAGE_NS: &[u8] = b"http://www.google.com/schemas/sitemap-image/1.1";
+const VIDEO_NS: &[u8] = b"http://www.google.com/schemas/sitemap-video/1.1";
+const NEWS_NS: &[u8] = b"http://www.google.com/schemas/sitemap-news/0.9";
+
+/// How many `<sitemapindex>` levels are followed below the first one.
+/// The protocol allows none; real sites nest two, loops nest forever.
+pub(crate) const MAX_INDEX_DEPTH: usize = 3;
+
+/// One page URL from a sitemap, with its `<url>` entry's metadata.
+pub(crate) struct SitemapEntry {
+    pub loc: String,
+    pub meta: SitemapMeta,
+}
+
+/// A parsed sitemap document.
+pub(crate) enum SitemapDoc {
+    /// `<urlset>`: page URLs.
+    Urls(Vec<SitemapEntry>),
+    /// `<sitemapindex>`: `<loc>`s of further sitemaps.
+    Index(Vec<String>),
+}
+
+/// Vocabulary an element belongs to.
+#[derive(Clone, Copy, PartialEq)]
+enum Ns {
+    Sitemap,
+    Image,
+    Video,
+    News,
+    Other,
+}
+
+/// Namespace and local name of an element. Sitemaps without an `xmlns`
+/// count as sitemaps.org; `image:loc` is `(Image, "loc")`, not `loc`.
+fn element_name(ns: &ResolveResult, e: &BytesStart) -> (Ns, String) {
+    let ns = match ns {
+        ResolveResult::Unbound => Ns::Sitemap,
+        ResolveResult::Bound(n) => match n.as_ref() {
+            SITEMAP_NS => Ns::Sitemap,
+            IMAGE_NS => Ns::Image,
+            VIDEO_NS => Ns::Video,
+            NEWS_NS => Ns::News,
+            _ => Ns::Other,
+        },
+        ResolveResult::Unknown(_) => Ns::Other,
+    };
+    (
+        ns,
+        String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+    )
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// The `<url>` being read.
+#[derive(Default)]
+struct Entry {
+    loc: String,
+    meta: SitemapMeta,
+    image: Option<SitemapImage>,
+    video: Option<SitemapVideo>,
+    news: Option<SitemapNews>,
+}
+
+impl Entry {
+    /// Store the text of the element that just closed: `name` as a child
+    /// of `parent`, `depth` levels below the root.
+    fn set(&mut self, depth: usize, parent: (Ns, &str), name: (Ns, &str), value: &str) {
+        match (depth, parent, name) {
+            (2, _, (Ns::Sitemap, "loc")) => self.loc = value.to_string(),
+            (2, _, (Ns::Sitemap, "lastmod")) => self.meta.lastmod = non_empty(value),
+            (2, _, (Ns::Sitemap, "changefreq")) => {
+                self.meta.changefreq = non_empty(value).map(|v| v.to_lowercase())
+            }
+            (2, _, (Ns::Sitemap, "priority")) => self.meta.priority = value.parse().ok(),
+            (3, (Ns::Image, "image"), (Ns::Image, field)) => {
+                let Some(image) = self.image.as_mut() else {
+                    return;
+                };
+                match field {
+                    "loc" => image.loc = value.to_string(),
+                    "title" => image.title = non_empty(value),
+                    "caption" => image.caption = non_empty(value),
+                    "license" => image.license = non_empty(value),
+                    _ => {}
+                }
+            }
+            (3, (Ns::Video, "video"), (Ns::Video, field)) => {
+                let Some(video) = self.video.as_mut() else {
+                    return;
+                };
+                match field {
+                    "title" => video.title = value.to_string(),
+                    "description" => video.description = non_empty(value),
+                    "thumbnail_loc" => video.thumbnail_loc = non_empty(value),
+                    "content_loc" => video.content_loc = non_empty(value),
+                    "player_loc" => video.player_loc = non_empty(value),
+                    "duration" => video.duration = value.parse().ok(),
+                    "publication_date" => video.publication_date = non_empty(value),
+                    _ => {}
+                }
+            }
+            (3, (Ns::News, "news"), (Ns::News, field)) => {
+                let Some(news) = self.news.as_mut() else {
+                    return;
+                };
+                match field {
+                    "title" => news.title = value.to_string(),
+                    "publication_date" => news.publication_date = non_empty(value),
+                    _ => {}
+                }
+            }
+            (4, (Ns::News, "publication"), (Ns::News, field)) => {
+                let Some(news) = self.news.as_mut() else {
+                    return;
+                };
+                match field {
+                    "name" => news.publication = value.to_string(),
+                    "language" => news.language = non_empty(value),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// An extension block (`<image:image>`, …) directly under `<url>` opened.
+    fn open(&mut self, name: (Ns, &str)) {
+        match name {
+            (Ns::Image, "image") => self.image = Some(SitemapImage::default()),
+            (Ns::Video, "video") => self.video = Some(SitemapVideo::default()),
+            (Ns::News, "news") => self.news = Some(SitemapNews::default()),
+            _ => {}
+        }
+    }
+
+    /// An extension block directly under `<url>` closed: keep it if it
+    /// says anything.
+    fn close(&mut self, name: (Ns, &str)) {
+        match name {
+            (Ns::Image, "image") => {
+                if let Some(image) = self.image.take().filter(|i| !i.loc.is_empty()) {
+                    self.meta.images.push(image);
+                }
+            }
+            (Ns::Video, "video") => {
+                let video = self.video.take().filter(|v| {
+                    v.content_loc.is_some() || v.player_loc.is_some() || v.thumbnail_loc.is_some()
+                });
+                self.meta.videos.extend(video);
+            }
+            (Ns::News, "news") => {
+                self.meta.news = self
+                    .news
+                    .take()
+                    .filter(|n| !n.publication.is_empty() || !n.title.is_empty());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parse a sitemap with a namespace-aware XML reader: `<loc>` and its
+/// siblings are read only as direct children of `<url>` / `<sitemap>`,
+/// CDATA and entities are decoded. Google's image, video and news
+/// extensions land in `SitemapMeta.images` / `videos` / `news`.
+/// `Ok(None)` when the root element is neither `<urlset>` nor
+/// `<sitemapindex>` (an HTML page, a feed). A document that breaks off
+/// mid-way keeps the entries read so far.
+pub(crate) fn parse_sitemap(xml: &str) -> anyhow::Result<Option<SitemapDoc>> {
+    let mut reader = NsReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    reader.config_mut().check_end_names = false;
+
+    let mut index = None;
+    // Element names from the root down.
+    let mut path: Vec<(Ns, String)> = Vec::new();
+    let mut text = String::new();
+    let mut entry = Entry::default();
+    let mut urls = Vec::new();
+    let mut sitemaps = Vec::new();
+    loop {
+        let event = match reader.read_resolved_event() {
+            Ok(event) => event,
+            Err(e) if urls.is_empty() && sitemaps.is_empty() => {
+                return Err(anyhow::anyhow!("malformed sitemap XML: {e}"));
+            }
+            Err(e) => {
+                tracing::warn!("Sitemap XML broke off ({e}); keeping the entries read so far");
+                break;
+            }
+        };
+        match event {
+            (ns, Event::Start(e)) => {
+                let (ns, local) = element_name(&ns, &e);
+                match path.len() {
+                    0 => {
+                        index = match (ns, local.as_str()) {
+                            (Ns::Sitemap, "urlset") => Some(false),
+                            (Ns::Sitemap, "sitemapindex") => Some(true),
+                            _ => return Ok(None),
+                        }
+                    }
+                    2 => entry.open((ns, &local)),
+                    _ => {}
+                }
+                path.push((ns, local));
+                text.clear();
+            }
+            (ns, Event::Empty(e)) if path.is_empty() => {
+                return Ok(match element_name(&ns, &e) {
+                    (Ns::Sitemap, local) if local == "urlset" => Some(SitemapDoc::Urls(Vec::new())),
+                    (Ns::Sitemap, local) if local == "sitemapindex" => {
+                        Some(SitemapDoc::Index(Vec::new()))
+                    }
+                    _ => None,
+                });
+            }
+            (_, Event::Text(t)) if path.len() >= 3 => {
+                text.push_str(&t.unescape().unwrap_or_default());
+            }
+            (_, Event::CData(t)) if path.len() >= 3 => {
+                text.push_str(&String::from_utf8_lossy(&t));
+            }
+            (_, Event::End(_)) => {
+                let Some((ns, local)) = path.pop() else {
+                    break;
+                };
+                let name = (ns, local.as_str());
+                match path.len() {
+                    0 => break,
+                    1 => {
+                        let done = std::mem::take(&mut entry);
+                        match name {
+                            (Ns::Sitemap, "url") if !done.loc.is_empty() => {
+                                urls.push(SitemapEntry {
+                                    loc: done.loc,
+                                    meta: done.meta,
+                                })
+                            }
+                            (Ns::Sitemap, "sitemap") if !done.loc.is_empty() => {
+                                sitemaps.push(done.loc)
+                            }
+                            _ => {}
+                        }
+                    }
+                    2 => {
+                        entry.set(2, (Ns::Sitemap, ""), name, text.trim());
+                        entry.close(name);
+                    }
+                    depth => {
+                        let (parent_ns, parent) = &path[depth - 1];
+                        entry.set(depth, (*parent_ns, parent), name, text.trim());
+                    }
+                }
+                text.clear();
+            }
+            (_, Event::Eof) => break,
+            _ => {}
+        }
+    }
+    Ok(match index {
+        None => None,
+        Some(true) => Some(SitemapDoc::Index(sitemaps)),
+        Some(false) => Some(SitemapDoc::Urls(urls)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urlset_reads_loc_siblings_and_ignores_extensions() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+              <url><loc>https://x.com/a</loc><lastmod> 2024-03-01 </lastmod>
+                <changefreq>Weekly</changefreq><priority>0.8</priority>
+                <image:image><image:loc>https://x.com/a.jpg</image:loc></image:image></url>
+              <url><loc><![CDATA[https://x.com/b?x=1&y=2]]></loc><priority>high</priority></url>
+              <url><loc>https://x.com/feed.xml</loc></url>
+            </urlset>"#;
+        let Some(SitemapDoc::Urls(entries)) = parse_sitemap(xml).unwrap() else {
+            panic!("expected a urlset");
+        };
+        let locs: Vec<&str> = entries.iter().map(|e| e.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            vec![
+                "https://x.com/a",
+                "https://x.com/b?x=1&y=2",
+                "https://x.com/feed.xml"
+            ]
+        );
+        assert_eq!(entries[0].meta.lastmod.as_deref(), Some("2024-03-01"));
+        assert_eq!(entries[0].meta.changefreq.as_deref(), Some("weekly"));
+        assert_eq!(entries[0].meta.priority, Some(0.8));
+        assert_eq!(entries[1].meta, SitemapMeta::default());
+
+        let index = "<sitemapindex><sitemap><loc>https://x.com/posts.xml?p=1&amp;q=2</loc>\
+                     </sitemap></sitemapindex>";
+        let Some(SitemapDoc::Index(sitemaps)) = parse_sitemap(index).unwrap() else {
+            panic!("expected a sitemap index");
+        };
+        assert_eq!(sitemaps, vec!["https://x.com/posts.xml?p=1&q=2"]);
+        assert!(parse_sitemap("<!DOCTYPE html><html><body>hi</body></html>")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn image_video_and_news_extensions_are_read() {
+        let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+                    xmlns:video="http://www.google.com/schemas/sitemap-video/1.1"
+                    xmlns:news="http://www.google.com/schemas/sitemap-news/0.9">
+              <url><loc>https://x.com/story</loc>
+                <image:image><image:loc>https://x.com/a.jpg</image:loc>
+                  <image:caption>Harbour at dawn</image:caption></image:image>
+                <image:image><image:title>no loc</image:title></image:image>
+                <video:video><video:thumbnail_loc>https://x.com/t.jpg</video:thumbnail_loc>
+                  <video:title>Tour</video:title><video:duration>95</video:duration>
+                  <video:player_loc>https://x.com/embed/1</video:player_loc></video:video>
+                <news:news><news:publication><news:name>X Daily</news:name>
+                  <news:language>en</news:language></news:publication>
+                  <news:publication_date>2024-05-01</news:publication_date>
+                  <news:title>Harbour reopens</news:title></news:news></url>
+            </urlset>"#;
+        let Some(SitemapDoc::Urls(entries)) = parse_sitemap(xml).unwrap() else {
+            panic!("expected a urlset");
+        };
+        let meta = &entries[0].meta;
+        assert_eq!(meta.images.len(), 1);
+        assert_eq!(meta.images[0].loc, "https://x.com/a.jpg");
+        assert_eq!(meta.images[0].caption.as_deref(), Some("Harbour at dawn"));
+        assert_eq!(meta.videos[0].title, "Tour");
+        assert_eq!(meta.videos[0].duration, Some(95));
+        assert_eq!(
+            meta.videos[0].player_loc.as_deref(),
+            Some("https://x.com/embed/1")
+        );
+        let news = meta.news.as_ref().unwrap();
+        assert_eq!(
+            (
+                news.publication.as_str(),
+                news.language.as_deref(),
+                news.title.as_str()
+            ),
+            ("X Daily", Some("en"), "Harbour reopens")
+        );
+        assert_eq!(news.publication_date.as_deref(), Some("2024-05-01"));
+    }
+}
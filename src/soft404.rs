@@ -0,0 +1,275 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{ContentBlock, PageData, SkippedPage};
+use crate::util::hash_blocks;
+
+/// Share of the blocks of a page and of the probed 404 page together
+/// (Jaccard) that both must have for it to count as the error template:
+/// a page that only shares the site's chrome with it stays well below.
+const TEMPLATE_SIMILARITY_MIN: f64 = 0.9;
+
+/// Hosts of a multi-host crawl probed for their 404 template.
+pub(crate) const MAX_PROBED_HOSTS: usize = 20;
+
+/// Word count under which a "not found" title or `<h1>` marks an error
+/// page; longer pages are articles that merely talk about 404s.
+const THIN_WORDS_MAX: usize = 150;
+
+/// Lowercased error-page titles and headings (en, de, cs, fr, es), matched
+/// as whole words. A bare `404` or `not found` is not one: product codes,
+/// "Results not found" filters and posts about 404 pages have those.
+const NOT_FOUND_PHRASES: &[&str] = &[
+    "page not found",
+    "404 not found",
+    "error 404",
+    "404 error",
+    "page doesn't exist",
+    "page does not exist",
+    "seite nicht gefunden",
+    "stránka nenalezena",
+    "stránka nebyla nalezena",
+    "stránka neexistuje",
+    "page introuvable",
+    "page non trouvée",
+    "página no encontrada",
+];
+
+fn has_not_found_phrase(text: &str) -> bool {
+    // Words joined by single spaces, so phrases match on word boundaries.
+    let words: Vec<String> = text
+        .to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect();
+    let text = format!(" {} ", words.join(" "));
+    NOT_FOUND_PHRASES
+        .iter()
+        .any(|p| text.contains(&format!(" {p} ")))
+}
+
+fn title_or_h1_says_not_found(title: &str, blocks: &[ContentBlock]) -> bool {
+    let h1 = blocks.iter().find_map(|b| match b {
+        ContentBlock::Heading { level: 1, text, .. } => Some(text.as_str()),
+        _ => None,
+    });
+    has_not_found_phrase(title) || h1.is_some_and(has_not_found_phrase)
+}
+
+/// What a host serves for a URL that can't exist, when it answers the
+/// probe with a page: a 2xx, not an error status or a redirect.
+pub(crate) struct ErrorTemplate {
+    pub title: String,
+    pub content_blocks: Vec<ContentBlock>,
+}
+
+/// Host whose error template a page is compared against.
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|h| h.to_ascii_lowercase())
+}
+
+fn fingerprints(blocks: &[ContentBlock]) -> HashSet<String> {
+    blocks
+        .iter()
+        .map(|b| hash_blocks(std::slice::from_ref(b)))
+        .collect()
+}
+
+/// Why a page that came back 200 is really an error page, if it is.
+/// Only a host with an `ErrorTemplate` serves soft 404s at all; `None`
+/// means no page of the host is one. Then `error_template` is a page with
+/// (nearly) the template's blocks, and `not_found_phrase` one with next to
+/// no content whose title or first `<h1>` says "page not found", as the
+/// template's does.
+pub(crate) fn soft_404_signal(
+    page: &PageData,
+    template: Option<&ErrorTemplate>,
+) -> Option<&'static str> {
+    let template = template.filter(|t| !t.content_blocks.is_empty())?;
+    let ours = fingerprints(&page.content_blocks);
+    let theirs = fingerprints(&template.content_blocks);
+    let shared = ours.intersection(&theirs).count();
+    let all = ours.union(&theirs).count();
+    if all > 0 && shared as f64 / all as f64 >= TEMPLATE_SIMILARITY_MIN {
+        return Some("error_template");
+    }
+    let says_not_found = title_or_h1_says_not_found(&page.title, &page.content_blocks)
+        && title_or_h1_says_not_found(&template.title, &template.content_blocks);
+    (says_not_found && page.total_words < THIN_WORDS_MAX).then_some("not_found_phrase")
+}
+
+/// Hosts worth probing for their error template, as `(host, scheme)` in
+/// page order: those with a thin page whose title or `<h1>` says "page
+/// not found", or with two pages of the very same blocks (one template
+/// answering several URLs). Other hosts are not probed and keep every page.
+pub(crate) fn suspected_hosts(pages: &[PageData]) -> Vec<(String, String)> {
+    let mut seen_hashes: HashSet<(String, &str)> = HashSet::new();
+    let mut hosts: Vec<(String, String)> = Vec::new();
+    for page in pages {
+        let Some(url) = url::Url::parse(&page.url).ok().filter(url::Url::has_host) else {
+            continue;
+        };
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let thin_not_found = page.total_words < THIN_WORDS_MAX
+            && title_or_h1_says_not_found(&page.title, &page.content_blocks);
+        let repeated = !page.content_hash.is_empty()
+            && !seen_hashes.insert((host.clone(), page.content_hash.as_str()));
+        if (thin_not_found || repeated)
+            && hosts.len() < MAX_PROBED_HOSTS
+            && !hosts.iter().any(|(h, _)| *h == host)
+        {
+            hosts.push((host, url.scheme().to_string()));
+        }
+    }
+    hosts
+}
+
+/// Mark soft 404s in `PageData.soft_404`, each against the error template
+/// of its own host in `templates`. With `skip` they are taken out of `pages`
+/// instead and returned as `soft_404` skips.
+pub(crate) fn detect_soft_404s(
+    pages: &mut Vec<PageData>,
+    templates: &HashMap<String, ErrorTemplate>,
+    skip: bool,
+) -> Vec<SkippedPage> {
+    let mut skipped = Vec::new();
+    pages.retain_mut(|page| {
+        let template = host_of(&page.url).and_then(|h| templates.get(&h));
+        let Some(signal) = soft_404_signal(page, template) else {
+            return true;
+        };
+        tracing::debug!("soft 404 ({signal}): {}", page.url);
+        if !skip {
+            page.soft_404 = Some(signal.to_string());
+            return true;
+        }
+        skipped.push(SkippedPage {
+            url: page.url.clone(),
+            reason: "soft_404".to_string(),
+            challenge: None,
+        });
+        false
+    });
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, title: &str, blocks: serde_json::Value, words: usize) -> PageData {
+        let mut page = crate::model::test_page(url);
+        page.title = title.to_string();
+        page.content_blocks = serde_json::from_value(blocks).unwrap();
+        page.total_words = words;
+        page.content_hash = hash_blocks(&page.content_blocks);
+        page
+    }
+
+    #[test]
+    fn error_pages_served_with_200_are_flagged_or_skipped() {
+        let oops = serde_json::json!([
+            {"type": "heading", "level": 1, "text": "Page not found"},
+            {"type": "paragraph", "text": "We looked everywhere."},
+        ]);
+        let template = ErrorTemplate {
+            title: "Page not found".to_string(),
+            content_blocks: serde_json::from_value(oops.clone()).unwrap(),
+        };
+        let templates: HashMap<String, ErrorTemplate> =
+            [("x.com".to_string(), template)].into_iter().collect();
+        let pages = || {
+            vec![
+                page(
+                    "https://x.com/",
+                    "Home",
+                    serde_json::json!([{"type": "paragraph", "text": "Welcome"}]),
+                    1,
+                ),
+                page("https://x.com/old", "Page not found", oops.clone(), 5),
+                page(
+                    "https://x.com/gone",
+                    "Shop",
+                    serde_json::json!([{"type": "heading", "level": 1, "text": "Error 404"}]),
+                    2,
+                ),
+                page(
+                    "https://x.com/blog/404-pages",
+                    "Designing a 404 page",
+                    serde_json::json!([]),
+                    900,
+                ),
+                page(
+                    "https://x.com/search?q=zzz",
+                    "Results not found",
+                    serde_json::json!([]),
+                    3,
+                ),
+                page("https://x.com/p/404", "Part 404", serde_json::json!([]), 3),
+                // Another host, never probed: its own 404s are real ones.
+                page("https://y.com/old", "Page not found", oops.clone(), 5),
+            ]
+        };
+
+        let mut flagged = pages();
+        assert!(detect_soft_404s(&mut flagged, &templates, false).is_empty());
+        assert_eq!(flagged.len(), 7);
+        let signals: Vec<Option<&str>> = flagged.iter().map(|p| p.soft_404.as_deref()).collect();
+        assert_eq!(
+            signals,
+            [
+                None,
+                Some("error_template"),
+                Some("not_found_phrase"),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+
+        let mut kept = pages();
+        let skipped = detect_soft_404s(&mut kept, &templates, true);
+        let urls: Vec<&str> = skipped.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://x.com/old", "https://x.com/gone"]);
+        assert!(skipped.iter().all(|s| s.reason == "soft_404"));
+        assert_eq!(kept.len(), 5);
+    }
+
+    #[test]
+    fn only_hosts_with_suspect_pages_are_probed() {
+        let text = |t: &str| serde_json::json!([{"type": "paragraph", "text": t}]);
+        let pages = [
+            page("https://x.com/", "Home", text("Welcome"), 1),
+            page("https://x.com/about", "About", text("About us"), 2),
+            page(
+                "https://y.com/old",
+                "Page not found",
+                serde_json::json!([]),
+                3,
+            ),
+            page("https://z.com/a", "Shop", text("Nothing here"), 2),
+            page("http://z.com/b", "Shop", text("Nothing here"), 2),
+            page("https://w.com/old", "Page not found", text("long"), 900),
+        ];
+        assert_eq!(
+            suspected_hosts(&pages),
+            [
+                ("y.com".to_string(), "https".to_string()),
+                ("z.com".to_string(), "http".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_found_phrases_match_whole_words() {
+        assert!(has_not_found_phrase("Error 404 – Example Shop"));
+        assert!(has_not_found_phrase("Seite nicht gefunden"));
+        assert!(!has_not_found_phrase("Model 404 spare parts"));
+        assert!(!has_not_found_phrase("Filters: nothing not found"));
+        assert!(!has_not_found_phrase("Homepage notfound"));
+    }
+}
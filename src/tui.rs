@@ -0,0 +1,503 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Gauge, List, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use url::Url;
+
+use crate::eta::{format_duration, Throughput};
+
+/// Log lines kept for the scrollable pane.
+const LOG_LINES: usize = 500;
+/// Failures listed in the "Recent errors" pane.
+const RECENT_ERRORS: usize = 5;
+/// Redraw / key-poll interval.
+const TICK: Duration = Duration::from_millis(250);
+/// How often a paused fetch re-checks the pause flag.
+const PAUSE_POLL: Duration = Duration::from_millis(200);
+
+/// Live counters for the page fetch, shared between `scrape_all` and the
+/// dashboard thread. Also the pause / skip-host switches the keys flip.
+pub(crate) struct CrawlMonitor {
+    paused: AtomicBool,
+    skipped_hosts: Mutex<HashSet<String>>,
+    state: Mutex<MonitorState>,
+}
+
+struct MonitorState {
+    started: Instant,
+    total: usize,
+    queued: usize,
+    in_flight: usize,
+    done: usize,
+    failed: usize,
+    skipped: usize,
+    hosts: BTreeMap<String, HostStats>,
+    errors: VecDeque<String>,
+    log: VecDeque<String>,
+    /// Fetch completions, for the rate and ETA in the gauge.
+    throughput: Throughput,
+}
+
+#[derive(Default)]
+struct HostStats {
+    queued: usize,
+    done: usize,
+    failed: usize,
+    skipped: usize,
+    /// First fetch start; throughput is measured from here.
+    first_started: Option<Instant>,
+}
+
+impl HostStats {
+    fn pages_per_min(&self) -> f64 {
+        match self.first_started {
+            Some(t) => self.done as f64 * 60.0 / t.elapsed().as_secs_f64().max(1.0),
+            None => 0.0,
+        }
+    }
+}
+
+fn host_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Poisoning only means a dashboard draw panicked; the counters are
+/// still usable.
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl CrawlMonitor {
+    pub fn new(urls: &[String]) -> Arc<Self> {
+        let mut hosts: BTreeMap<String, HostStats> = BTreeMap::new();
+        for url in urls {
+            hosts.entry(host_of(url)).or_default().queued += 1;
+        }
+        Arc::new(Self {
+            paused: AtomicBool::new(false),
+            skipped_hosts: Mutex::new(HashSet::new()),
+            state: Mutex::new(MonitorState {
+                started: Instant::now(),
+                total: urls.len(),
+                queued: urls.len(),
+                in_flight: 0,
+                done: 0,
+                failed: 0,
+                skipped: 0,
+                hosts,
+                errors: VecDeque::new(),
+                log: VecDeque::new(),
+                throughput: Throughput::new(urls.len(), Instant::now()),
+            }),
+        })
+    }
+
+    /// Called before each page fetch: waits while paused, then `false` if
+    /// the URL's host was skipped from the dashboard.
+    pub async fn admit(&self, url: &str) -> bool {
+        while self.paused.load(Ordering::Relaxed) {
+            tokio::time::sleep(PAUSE_POLL).await;
+        }
+        let host = host_of(url);
+        let skipped = lock(&self.skipped_hosts).contains(&host);
+        let mut guard = lock(&self.state);
+        let state = &mut *guard;
+        state.queued = state.queued.saturating_sub(1);
+        let stats = state.hosts.entry(host).or_default();
+        stats.queued = stats.queued.saturating_sub(1);
+        if skipped {
+            stats.skipped += 1;
+            state.skipped += 1;
+        } else {
+            stats.first_started.get_or_insert_with(Instant::now);
+            state.in_flight += 1;
+        }
+        !skipped
+    }
+
+    /// Record the outcome of an admitted fetch; `Err` is the skip reason.
+    pub fn finish(&self, url: &str, result: Result<(), &str>) {
+        let mut guard = lock(&self.state);
+        let state = &mut *guard;
+        state.in_flight = state.in_flight.saturating_sub(1);
+        state.throughput.record(Instant::now());
+        let stats = state.hosts.entry(host_of(url)).or_default();
+        match result {
+            Ok(()) => {
+                stats.done += 1;
+                state.done += 1;
+            }
+            Err(reason) => {
+                stats.failed += 1;
+                state.failed += 1;
+                state.errors.push_back(format!("{reason}: {url}"));
+                if state.errors.len() > RECENT_ERRORS {
+                    state.errors.pop_front();
+                }
+            }
+        }
+    }
+
+    fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    /// Let paused fetches go: with the dashboard gone there is no key
+    /// left to resume them.
+    fn release(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn skip_host(&self, host: &str) {
+        lock(&self.skipped_hosts).insert(host.to_string());
+    }
+
+    fn push_log(&self, text: &str) {
+        let mut state = lock(&self.state);
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            state.log.push_back(strip_ansi(line));
+            if state.log.len() > LOG_LINES {
+                state.log.pop_front();
+            }
+        }
+    }
+}
+
+/// The tracing formatter colours level names; the log pane is plain text.
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI: ESC '[' params… final byte in @..~
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) && c != '[' {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Where tracing output goes while the dashboard owns the terminal.
+static LOG_SINK: Mutex<Option<Arc<CrawlMonitor>>> = Mutex::new(None);
+
+/// tracing writer: stderr normally, the dashboard's log pane while it is
+/// open (stderr lines would scribble over the alternate screen).
+pub(crate) struct LogWriter;
+
+pub(crate) fn log_writer() -> LogWriter {
+    LogWriter
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let sink = lock(&LOG_SINK).clone();
+        match sink {
+            Some(monitor) => {
+                monitor.push_log(&String::from_utf8_lossy(buf));
+                Ok(buf.len())
+            }
+            None => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+/// Put a `progress!` line in the dashboard's log pane while it is open,
+/// where printing it would scribble over the screen. `false` when it
+/// isn't open and the line is the caller's to print.
+pub(crate) fn log_progress(line: &str) -> bool {
+    let Some(monitor) = lock(&LOG_SINK).clone() else {
+        return false;
+    };
+    monitor.push_log(line);
+    true
+}
+
+/// The running dashboard thread. `close` restores the terminal.
+pub(crate) struct Dashboard {
+    stop: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl Dashboard {
+    /// Take over the terminal. `None` (with a warning) when stdout isn't a
+    /// terminal; the fetch then runs with plain logging.
+    pub fn start(monitor: Arc<CrawlMonitor>) -> Option<Self> {
+        use std::io::IsTerminal;
+        if !std::io::stdout().is_terminal() {
+            tracing::warn!("--tui needs an interactive terminal; continuing without it");
+            return None;
+        }
+        let terminal = match ratatui::try_init() {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!("--tui could not take over the terminal: {e}");
+                return None;
+            }
+        };
+        *lock(&LOG_SINK) = Some(Arc::clone(&monitor));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            if let Err(e) = run(terminal, &monitor, &thread_stop) {
+                monitor.push_log(&format!("dashboard error: {e}"));
+            }
+            ratatui::restore();
+            *lock(&LOG_SINK) = None;
+            monitor.release();
+        });
+        Some(Self { stop, handle })
+    }
+
+    pub fn close(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// Dashboard-local view state.
+#[derive(Default)]
+struct View {
+    hosts: TableState,
+    /// Lines scrolled up from the tail of the log; 0 follows new output.
+    log_scroll: usize,
+}
+
+fn run(
+    mut terminal: DefaultTerminal,
+    monitor: &CrawlMonitor,
+    stop: &AtomicBool,
+) -> std::io::Result<()> {
+    let mut view = View::default();
+    view.hosts.select(Some(0));
+    while !stop.load(Ordering::Relaxed) {
+        terminal.draw(|frame| draw(frame, monitor, &mut view))?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            // Raw mode swallows SIGINT; keep Ctrl+C meaning "stop the run".
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                ratatui::restore();
+                std::process::exit(130);
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => monitor.toggle_pause(),
+            KeyCode::Char('s') => {
+                let state = lock(&monitor.state);
+                let host = view
+                    .hosts
+                    .selected()
+                    .and_then(|i| state.hosts.keys().nth(i).cloned());
+                drop(state);
+                if let Some(host) = host {
+                    monitor.skip_host(&host);
+                }
+            }
+            KeyCode::Up => view.hosts.select_previous(),
+            KeyCode::Down => view.hosts.select_next(),
+            KeyCode::PageUp => view.log_scroll += 10,
+            KeyCode::PageDown => view.log_scroll = view.log_scroll.saturating_sub(10),
+            KeyCode::End => view.log_scroll = 0,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, monitor: &CrawlMonitor, view: &mut View) {
+    let state = lock(&monitor.state);
+    let skipped_hosts = lock(&monitor.skipped_hosts).clone();
+    let paused = monitor.paused.load(Ordering::Relaxed);
+
+    let [progress_area, hosts_area, errors_area, log_area, keys_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(RECENT_ERRORS as u16 + 2),
+        Constraint::Min(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let finished = state.done + state.failed + state.skipped;
+    let status = if paused { "PAUSED" } else { "running" };
+    let now = Instant::now();
+    let rate = match state.throughput.per_minute(now) {
+        Some(rate) if !paused => {
+            let eta = state
+                .throughput
+                .eta_for(state.total.saturating_sub(finished), now)
+                .map(format_duration)
+                .unwrap_or_default();
+            format!(" · {rate:.1}/min · ETA {eta}")
+        }
+        _ => String::new(),
+    };
+    let label = format!(
+        "{finished}/{} — {} done, {} failed, {} skipped, {} in flight, {} queued · {}s{rate} · {status}",
+        state.total,
+        state.done,
+        state.failed,
+        state.skipped,
+        state.in_flight,
+        state.queued,
+        state.started.elapsed().as_secs()
+    );
+    let ratio = if state.total == 0 {
+        1.0
+    } else {
+        finished as f64 / state.total as f64
+    };
+    let gauge_color = if paused { Color::Yellow } else { Color::Green };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" dump-it "))
+            .gauge_style(Style::default().fg(gauge_color))
+            .ratio(ratio.min(1.0))
+            .label(label),
+        progress_area,
+    );
+
+    let rows = state.hosts.iter().map(|(host, s)| {
+        let name = if skipped_hosts.contains(host) {
+            format!("{host} (skipped)")
+        } else {
+            host.clone()
+        };
+        Row::new(vec![
+            name,
+            s.queued.to_string(),
+            s.done.to_string(),
+            s.failed.to_string(),
+            s.skipped.to_string(),
+            format!("{:.1}", s.pages_per_min()),
+        ])
+    });
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(["Host", "Queued", "Done", "Failed", "Skipped", "Pages/min"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(" Hosts "))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, hosts_area, &mut view.hosts);
+
+    let errors = List::new(state.errors.iter().map(String::as_str))
+        .style(Style::default().fg(Color::Red))
+        .block(Block::bordered().title(" Recent errors "));
+    frame.render_widget(errors, errors_area);
+
+    let visible = log_area.height.saturating_sub(2) as usize;
+    view.log_scroll = view.log_scroll.min(state.log.len().saturating_sub(visible));
+    let end = state.log.len() - view.log_scroll;
+    let start = end.saturating_sub(visible);
+    let lines: Vec<&str> = state.log.range(start..end).map(String::as_str).collect();
+    let title = if view.log_scroll > 0 {
+        format!(" Log (↑{}) ", view.log_scroll)
+    } else {
+        " Log ".to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(lines.join("\n")).block(Block::bordered().title(title)),
+        log_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new(
+            "p pause/resume · ↑/↓ select host · s skip host · PgUp/PgDn scroll log · q close",
+        )
+        .style(Style::default().fg(Color::DarkGray)),
+        keys_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn skipped_host_is_not_admitted() {
+        let monitor =
+            CrawlMonitor::new(&["https://a.com/1".to_string(), "https://b.com/1".to_string()]);
+        monitor.skip_host("b.com");
+        assert!(monitor.admit("https://a.com/1").await);
+        assert!(!monitor.admit("https://b.com/1").await);
+        monitor.finish("https://a.com/1", Err("http_error"));
+        let state = lock(&monitor.state);
+        assert_eq!((state.queued, state.in_flight), (0, 0));
+        assert_eq!((state.failed, state.skipped), (1, 1));
+        assert_eq!(state.errors[0], "http_error: https://a.com/1");
+    }
+
+    #[tokio::test]
+    async fn released_monitor_admits_again() {
+        let monitor = CrawlMonitor::new(&["https://a.com/1".to_string()]);
+        monitor.toggle_pause();
+        let waiting = tokio::spawn({
+            let monitor = Arc::clone(&monitor);
+            async move { monitor.admit("https://a.com/1").await }
+        });
+        tokio::time::sleep(2 * PAUSE_POLL).await;
+        assert!(!waiting.is_finished());
+        monitor.release();
+        let admitted = tokio::time::timeout(Duration::from_secs(2), waiting).await;
+        assert!(admitted.unwrap().unwrap());
+    }
+
+    #[test]
+    fn progress_lines_go_to_the_open_dashboard() {
+        let monitor = CrawlMonitor::new(&["https://a.com/1".to_string()]);
+        *lock(&LOG_SINK) = Some(Arc::clone(&monitor));
+        progress!("✓ Scraped: https://a.com/1 (3 blocks)");
+        // A rate line that is due at once.
+        let started = Instant::now() - Duration::from_secs(20);
+        let throughput = Mutex::new(Throughput::new(10, started));
+        crate::eta::record_and_report(&throughput, "pages");
+        *lock(&LOG_SINK) = None;
+        assert!(!log_progress("not captured"));
+        let state = lock(&monitor.state);
+        assert!(state
+            .log
+            .iter()
+            .any(|l| l == "✓ Scraped: https://a.com/1 (3 blocks)"));
+        assert!(state.log.iter().any(|l| l.starts_with("⏱️  1/10 pages")));
+        assert!(!state.log.iter().any(|l| l == "not captured"));
+    }
+
+    #[test]
+    fn strip_ansi_removes_colour_codes() {
+        assert_eq!(strip_ansi("\u{1b}[33m WARN\u{1b}[0m slow"), " WARN slow");
+    }
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use anyhow::Context;
+use url::Url;
+
+/// Header names (normalised, see [`normalize_header`]) that hold page URLs
+/// in the usual exports, most specific first: Search Console's "Top pages",
+/// Analytics' "Page path" / "Landing page", crawler "Address" columns.
+const URL_COLUMNS: &[&str] = &[
+    "url",
+    "page",
+    "toppages",
+    "pages",
+    "address",
+    "landingpage",
+    "pagelocation",
+    "fullpageurl",
+    "pagepath",
+    "pagepathandscreenclass",
+    "pagepathandquerystring",
+    "loc",
+    "link",
+];
+
+/// Lowercase alphanumerics only, so `Top pages`, `top_pages` and
+/// `TOP-PAGES` all match `toppages`.
+fn normalize_header(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn looks_like_url(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with("http://") || value.starts_with("https://") || value.starts_with('/')
+}
+
+/// Field separator of the export: whichever of tab, semicolon and comma the
+/// first non-comment line has most of (Analytics writes TSV, spreadsheet
+/// tools in comma-decimal locales use `;`).
+fn sniff_delimiter(text: &str) -> u8 {
+    let Some(line) = text
+        .lines()
+        .find(|l| !l.trim().is_empty() && !l.starts_with('#'))
+    else {
+        return b',';
+    };
+    [b'\t', b';', b',']
+        .into_iter()
+        .max_by_key(|d| line.bytes().filter(|b| b == d).count())
+        .filter(|d| line.as_bytes().contains(d))
+        .unwrap_or(b',')
+}
+
+/// Index of the URL column in `header`: `column` by name or 1-based
+/// number, else the first known URL column name. `None` when nothing
+/// matches by name.
+fn find_column(header: &[String], column: Option<&str>) -> anyhow::Result<Option<usize>> {
+    let names: Vec<String> = header.iter().map(|h| normalize_header(h)).collect();
+    if let Some(column) = column {
+        if let Some(i) = names.iter().position(|n| *n == normalize_header(column)) {
+            return Ok(Some(i));
+        }
+        if let Ok(n) = column.trim().parse::<usize>() {
+            if (1..=header.len()).contains(&n) {
+                return Ok(Some(n - 1));
+            }
+        }
+        anyhow::bail!(
+            "no column {column:?} in the URL list (columns: {})",
+            header.join(", ")
+        );
+    }
+    Ok(URL_COLUMNS
+        .iter()
+        .find_map(|wanted| names.iter().position(|n| n == wanted)))
+}
+
+/// Page URLs from a CSV / TSV export or a plain one-per-line list.
+/// `#` comment lines (Analytics' report preamble) are skipped, the first
+/// row is the header unless its URL cell already is a URL, and cells that
+/// are neither absolute http(s) URLs nor paths (totals rows, `(not set)`)
+/// are dropped. Paths are resolved against `base`.
+pub(crate) fn parse_url_list(
+    text: &str,
+    column: Option<&str>,
+    base: Option<&Url>,
+) -> anyhow::Result<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(text))
+        .has_headers(false)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+    let mut rows = Vec::new();
+    for row in reader.records() {
+        let row = row.context("malformed URL list")?;
+        if row.iter().any(|cell| !cell.trim().is_empty()) {
+            rows.push(row.iter().map(|c| c.trim().to_string()).collect::<Vec<_>>());
+        }
+    }
+    let Some(first) = rows.first() else {
+        return Ok(Vec::new());
+    };
+
+    let (index, skip_header) = match find_column(first, column)? {
+        Some(i) => (i, !first.get(i).is_some_and(|c| looks_like_url(c))),
+        None => {
+            // No header we know: the first column whose values are URLs,
+            // checking the first data rows (a header row won't be one).
+            let probe = |i: usize| {
+                rows.iter()
+                    .take(5)
+                    .any(|r| r.get(i).is_some_and(|c| looks_like_url(c)))
+            };
+            let i = (0..first.len())
+                .find(|&i| probe(i))
+                .context("no URL column found in the URL list; pick one with --url-column")?;
+            (i, !looks_like_url(&first[i]))
+        }
+    };
+
+    let mut urls = Vec::new();
+    for row in rows.iter().skip(usize::from(skip_header)) {
+        let Some(cell) = row.get(index).filter(|c| looks_like_url(c)) else {
+            continue;
+        };
+        if cell.starts_with('/') {
+            let base = base.context("the URL list has relative paths; pass the site's --url")?;
+            match base.join(cell) {
+                Ok(u) => urls.push(u.to_string()),
+                Err(e) => tracing::debug!("skipping {cell} from the URL list: {e}"),
+            }
+        } else {
+            urls.push(cell.clone());
+        }
+    }
+    Ok(urls)
+}
+
+/// Read `--url-list`, see [`parse_url_list`]. An empty result is an error:
+/// an export with the wrong column or no rows shouldn't fall back to a
+/// crawl of the whole site.
+pub(crate) fn read_url_list(
+    path: &Path,
+    column: Option<&str>,
+    base: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let base = base
+        .map(Url::parse)
+        .transpose()
+        .context("invalid target URL")?;
+    let urls = parse_url_list(&text, column, base.as_ref())
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    if urls.is_empty() {
+        anyhow::bail!("no URLs in {}", path.display());
+    }
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_and_plain_lists_yield_their_url_column() {
+        let gsc = "\u{feff}Top pages,Clicks,Impressions,CTR,Position\n\
+                   https://x.com/pricing,120,4000,3%,4.2\n\
+                   \"https://x.com/blog/a,b\",30,900,3.3%,7.1\n";
+        assert_eq!(
+            parse_url_list(gsc, None, None).unwrap(),
+            vec!["https://x.com/pricing", "https://x.com/blog/a,b"]
+        );
+
+        let ga = "# ----------------------------------------\n\
+                  # Pages and screens\n\
+                  Page path and screen class\tViews\tUsers\n\
+                  /\t900\t400\n\
+                  /docs/start\t300\t120\n\
+                  (not set)\t2\t1\n\
+                  \t1202\t521\n";
+        let base = Url::parse("https://x.com/").unwrap();
+        assert_eq!(
+            parse_url_list(ga, Some("page path and screen class"), Some(&base)).unwrap(),
+            vec!["https://x.com/", "https://x.com/docs/start"]
+        );
+        assert!(parse_url_list(ga, None, None).is_err());
+
+        let plain = "https://x.com/a\nhttps://x.com/b\n";
+        assert_eq!(parse_url_list(plain, None, None).unwrap().len(), 2);
+        let by_number = "views;address\n5;https://x.com/c\n";
+        assert_eq!(
+            parse_url_list(by_number, Some("2"), None).unwrap(),
+            vec!["https://x.com/c"]
+        );
+        assert!(parse_url_list(by_number, Some("page"), None).is_err());
+    }
+}
@@ -1,10 +1,10 @@
-use scraper::{ElementRef, Html};
+use scraper::{ElementRef, Html, Selector};
 use std::time::Duration;
 use url::Url;
 
-use crate::cli::Args;
-use crate::model::ContentBlock;
-use crate::selectors::{DEFAULT_EXCLUDE_PATTERNS, SEL_BODY, SEL_SKIP};
+use crate::cli::RunArgs;
+use crate::model::{ContentBlock, ContentMetrics, OutlineEntry};
+use crate::selectors::{DEFAULT_EXCLUDE_PATTERNS, SEL_BODY};
 
 /// Pull the human-readable text out of an element, inserting whitespace
 /// between text nodes from different child elements so that
@@ -16,6 +16,113 @@ pub(crate) fn element_text(el: &ElementRef) -> String {
         .join(" ")
 }
 
+/// `element_text` without the subtrees `skip` matches.
+pub(crate) fn element_text_skipping(el: &ElementRef, skip: &dyn Fn(&ElementRef) -> bool) -> String {
+    fn collect<'a>(el: ElementRef<'a>, skip: &dyn Fn(&ElementRef) -> bool, out: &mut Vec<&'a str>) {
+        for child in el.children() {
+            if let Some(text) = child.value().as_text() {
+                out.extend(text.split_whitespace());
+            } else if let Some(child) = ElementRef::wrap(child).filter(|c| !skip(c)) {
+                collect(child, skip, out);
+            }
+        }
+    }
+    let mut words = Vec::new();
+    collect(*el, skip, &mut words);
+    words.join(" ")
+}
+
+/// `--rich-text` counterpart of `element_text`: links become
+/// `[text](absolute-url)`, `<strong>` / `<b>` `**bold**`, `<em>` / `<i>`
+/// `*italic*` and `<code>` / `<kbd>` / `<samp>` `` `code` ``. Literal
+/// `\`, `*`, `[`, `]` and `` ` `` in the text are backslash-escaped, and
+/// parentheses in link URLs percent-encoded so `)` can't end the link
+/// early. Whitespace is collapsed like `element_text`. Subtrees `skip`
+/// matches are left out.
+pub(crate) fn inline_markdown(
+    el: &ElementRef,
+    base: &Url,
+    skip: &dyn Fn(&ElementRef) -> bool,
+) -> String {
+    fn escape(text: &str, out: &mut String) {
+        for c in text.chars() {
+            if matches!(c, '\\' | '*' | '[' | ']' | '`') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    /// `open` / `close` around the trimmed inner text; surrounding whitespace
+    /// stays outside so `** bold**` can't happen.
+    fn wrap(inner: &str, open: &str, close: &str, out: &mut String) {
+        let trimmed = inner.trim();
+        if trimmed.is_empty() {
+            out.push_str(inner);
+            return;
+        }
+        let lead = &inner[..inner.len() - inner.trim_start().len()];
+        let trail = &inner[inner.trim_end().len()..];
+        out.push_str(lead);
+        out.push_str(open);
+        out.push_str(trimmed);
+        out.push_str(close);
+        out.push_str(trail);
+    }
+    fn render(el: ElementRef, base: &Url, skip: &dyn Fn(&ElementRef) -> bool, out: &mut String) {
+        for child in el.children() {
+            if let Some(text) = child.value().as_text() {
+                escape(text, out);
+                continue;
+            }
+            let Some(el) = ElementRef::wrap(child).filter(|c| !skip(c)) else {
+                continue;
+            };
+            let mut inner = String::new();
+            match el.value().name() {
+                "script" | "style" | "noscript" | "template" => {}
+                "br" => out.push(' '),
+                "code" | "kbd" | "samp" => {
+                    let raw = el.text().collect::<String>();
+                    if raw.contains('`') {
+                        escape(&raw, out);
+                    } else {
+                        wrap(&raw, "`", "`", out);
+                    }
+                }
+                "strong" | "b" => {
+                    render(el, base, skip, &mut inner);
+                    wrap(&inner, "**", "**", out);
+                }
+                "em" | "i" => {
+                    render(el, base, skip, &mut inner);
+                    wrap(&inner, "*", "*", out);
+                }
+                "a" => {
+                    render(el, base, skip, &mut inner);
+                    let href = el
+                        .value()
+                        .attr("href")
+                        .map(str::trim)
+                        .filter(|h| !h.is_empty() && !h.starts_with("javascript:"))
+                        .and_then(|h| base.join(h).ok());
+                    match href {
+                        Some(url) => {
+                            let url = url.as_str().replace('(', "%28").replace(')', "%29");
+                            let close = format!("]({url})");
+                            wrap(&inner, "[", &close, out);
+                        }
+                        None => out.push_str(&inner),
+                    }
+                }
+                _ => render(el, base, skip, out),
+            }
+        }
+    }
+    let mut out = String::new();
+    render(*el, base, skip, &mut out);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 pub(crate) fn normalize_path(p: &str) -> String {
     p.replace('\\', "/")
 }
@@ -116,7 +223,7 @@ pub(crate) fn unmsys_pattern(s: &str) -> String {
     format!("/{trail}")
 }
 
-pub(crate) fn build_exclude_patterns(args: &Args) -> Vec<String> {
+pub(crate) fn build_exclude_patterns(args: &RunArgs) -> Vec<String> {
     let mut patterns: Vec<String> = if args.no_default_excludes {
         Vec::new()
     } else {
@@ -129,7 +236,7 @@ pub(crate) fn build_exclude_patterns(args: &Args) -> Vec<String> {
     patterns
 }
 
-pub(crate) fn build_include_patterns(args: &Args) -> Vec<String> {
+pub(crate) fn build_include_patterns(args: &RunArgs) -> Vec<String> {
     args.includes.iter().map(|s| unmsys_pattern(s)).collect()
 }
 
@@ -159,23 +266,157 @@ pub(crate) fn extension_from_content_type(ct: &str) -> Option<&'static str> {
     }
 }
 
+/// `srcset` candidates as `(url, descriptor)`, descriptor `""` when
+/// absent. Candidates are split on a comma followed by whitespace when
+/// there is one, so CDN URLs with commas in the path
+/// (`/w_400,h_300/a.jpg`) survive.
+pub(crate) fn parse_srcset(srcset: &str) -> Vec<(&str, &str)> {
+    let spaced = srcset
+        .split(',')
+        .skip(1)
+        .any(|p| p.starts_with(char::is_whitespace));
+    let bytes = srcset.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        let next_is_space = bytes.get(i + 1).is_some_and(u8::is_ascii_whitespace);
+        if *b == b',' && (!spaced || next_is_space) {
+            parts.push(&srcset[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&srcset[start..]);
+    parts
+        .into_iter()
+        .filter_map(|part| {
+            let mut bits = part.split_whitespace();
+            let url = bits.next()?;
+            Some((url, bits.next().unwrap_or("")))
+        })
+        .collect()
+}
+
+/// `srcset` URLs with the best fit for a `viewport`-px window first, the
+/// rest in document order. Best fit is the smallest candidate at least as
+/// wide as the viewport, else the widest. `x` descriptors count as
+/// multiples of the viewport; no descriptor is `1x`.
+pub(crate) fn order_srcset_for_viewport(entries: &[(&str, &str)], viewport: u32) -> Vec<String> {
+    let viewport = f64::from(viewport.max(1));
+    let width = |descriptor: &str| -> f64 {
+        if let Some(w) = descriptor.strip_suffix('w') {
+            w.parse().unwrap_or(0.0)
+        } else if let Some(x) = descriptor.strip_suffix('x') {
+            x.parse::<f64>().unwrap_or(1.0) * viewport
+        } else {
+            viewport
+        }
+    };
+    let widths: Vec<f64> = entries.iter().map(|(_, d)| width(d)).collect();
+    let best = widths
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| **w >= viewport)
+        .min_by(|a, b| a.1.total_cmp(b.1))
+        .or_else(|| widths.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)))
+        .map(|(i, _)| i);
+    let mut out = Vec::with_capacity(entries.len());
+    if let Some(i) = best {
+        out.push(entries[i].0.to_string());
+    }
+    for (i, (url, _)) in entries.iter().enumerate() {
+        if Some(i) != best && !out.iter().any(|u| u == url) {
+            out.push(url.to_string());
+        }
+    }
+    out
+}
+
+/// Whether a `<source media>` query matches a desktop screen `viewport` px
+/// wide: `min-width` / `max-width` (px, em, rem), `orientation`
+/// (landscape), `prefers-color-scheme` (light), `prefers-reduced-motion`
+/// (no-preference), media types `all` / `screen`, `and`, `not` and
+/// comma-separated alternatives. Anything else doesn't match.
+pub(crate) fn media_matches(media: &str, viewport: u32) -> bool {
+    media.split(',').any(|query| {
+        let query = query.trim().to_lowercase();
+        let (negated, query) = match query.strip_prefix("not ") {
+            Some(rest) => (true, rest.trim().to_string()),
+            None => (false, query),
+        };
+        let matched = query
+            .split(" and ")
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .all(|part| media_feature_matches(part, viewport));
+        matched != negated
+    })
+}
+
+fn media_feature_matches(part: &str, viewport: u32) -> bool {
+    if matches!(part, "all" | "screen" | "only screen") {
+        return true;
+    }
+    let Some(feature) = part.strip_prefix('(').and_then(|p| p.strip_suffix(')')) else {
+        return false;
+    };
+    let Some((name, value)) = feature.split_once(':') else {
+        return false;
+    };
+    let value = value.trim();
+    let px = || {
+        if let Some(v) = value
+            .strip_suffix("rem")
+            .or_else(|| value.strip_suffix("em"))
+        {
+            v.trim().parse::<f64>().ok().map(|v| v * 16.0)
+        } else {
+            value.trim_end_matches("px").trim().parse::<f64>().ok()
+        }
+    };
+    let viewport = f64::from(viewport);
+    match name.trim() {
+        "min-width" => px().is_some_and(|v| viewport >= v),
+        "max-width" => px().is_some_and(|v| viewport <= v),
+        "orientation" => value == "landscape",
+        "prefers-color-scheme" => value == "light",
+        "prefers-reduced-motion" => value == "no-preference",
+        _ => false,
+    }
+}
+
+fn known_image_extension(ext: &str) -> Option<&'static str> {
+    Some(match ext {
+        "svg" => "svg",
+        "png" => "png",
+        "jpeg" | "jpg" => "jpg",
+        "webp" => "webp",
+        "gif" => "gif",
+        "avif" => "avif",
+        "bmp" => "bmp",
+        "ico" => "ico",
+        _ => return None,
+    })
+}
+
 pub(crate) fn image_extension_from_url(url: &str) -> &'static str {
     let url_lc = url.to_lowercase();
-    for ext_with_dot in [
-        ".svg", ".png", ".jpeg", ".jpg", ".webp", ".gif", ".avif", ".bmp", ".ico",
+    // The last path segment's own extension wins, so `a.png?alt=b.svg` or
+    // `/images.gifts/a.png` aren't misread. Proxy URLs
+    // (`_next/image?url=%2Flogo.png`) only name the format in the query.
+    let (path, query) = url_lc.split_once('?').unwrap_or((&url_lc, ""));
+    let path = path.split('#').next().unwrap_or_default();
+    let segment = path.rsplit('/').next().unwrap_or_default();
+    if let Some(ext) = segment
+        .rsplit_once('.')
+        .and_then(|(_, ext)| known_image_extension(ext))
+    {
+        return ext;
+    }
+    for ext in [
+        "svg", "png", "jpeg", "jpg", "webp", "gif", "avif", "bmp", "ico",
     ] {
-        if url_lc.contains(ext_with_dot) {
-            return match ext_with_dot {
-                ".svg" => "svg",
-                ".png" => "png",
-                ".jpeg" | ".jpg" => "jpg",
-                ".webp" => "webp",
-                ".gif" => "gif",
-                ".avif" => "avif",
-                ".bmp" => "bmp",
-                ".ico" => "ico",
-                _ => "jpg",
-            };
+        if query.contains(&format!(".{ext}")) {
+            return known_image_extension(ext).unwrap_or("jpg");
         }
     }
     "jpg"
@@ -226,8 +467,8 @@ pub(crate) fn dedup_adjacent_long_text(blocks: Vec<ContentBlock>) -> Vec<Content
     let mut result: Vec<ContentBlock> = Vec::with_capacity(blocks.len());
     for block in blocks {
         let cur: Option<(u8, &str)> = match &block {
-            ContentBlock::Paragraph { text } if text.len() > 30 => Some((0, text.as_str())),
-            ContentBlock::Heading { level, text } if text.len() > 30 => {
+            ContentBlock::Paragraph { text, .. } if text.len() > 30 => Some((0, text.as_str())),
+            ContentBlock::Heading { level, text, .. } if text.len() > 30 => {
                 Some((*level, text.as_str()))
             }
             _ => None,
@@ -235,8 +476,8 @@ pub(crate) fn dedup_adjacent_long_text(blocks: Vec<ContentBlock>) -> Vec<Content
         if let Some((cur_level, cur_text)) = cur {
             if let Some(prev) = result.last() {
                 let prev_sig: Option<(u8, &str)> = match prev {
-                    ContentBlock::Paragraph { text } => Some((0, text.as_str())),
-                    ContentBlock::Heading { level, text } => Some((*level, text.as_str())),
+                    ContentBlock::Paragraph { text, .. } => Some((0, text.as_str())),
+                    ContentBlock::Heading { level, text, .. } => Some((*level, text.as_str())),
                     _ => None,
                 };
                 if prev_sig == Some((cur_level, cur_text)) {
@@ -255,13 +496,13 @@ pub(crate) fn blocks_to_plain_text(blocks: &[ContentBlock]) -> String {
     let mut out = String::new();
     for b in blocks {
         match b {
-            ContentBlock::Heading { text, .. } | ContentBlock::Paragraph { text } => {
-                if !text.is_empty() {
-                    out.push_str(text);
-                    out.push('\n');
-                }
+            ContentBlock::Heading { text, .. } | ContentBlock::Paragraph { text, .. }
+                if !text.is_empty() =>
+            {
+                out.push_str(text);
+                out.push('\n');
             }
-            ContentBlock::List { items } => {
+            ContentBlock::List { items, .. } => {
                 for item in items {
                     if !item.is_empty() {
                         out.push_str(item);
@@ -275,13 +516,280 @@ pub(crate) fn blocks_to_plain_text(blocks: &[ContentBlock]) -> String {
     out.trim_end().to_string()
 }
 
+/// Nest the Heading blocks into a table of contents. Keeps a stack of open
+/// entries; a heading closes every open entry at its level or deeper and
+/// becomes a child of whatever is left on top.
+pub(crate) fn build_outline(blocks: &[ContentBlock]) -> Vec<OutlineEntry> {
+    fn close(stack: &mut Vec<OutlineEntry>, roots: &mut Vec<OutlineEntry>) {
+        if let Some(done) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(done),
+                None => roots.push(done),
+            }
+        }
+    }
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineEntry> = Vec::new();
+    for (i, b) in blocks.iter().enumerate() {
+        let ContentBlock::Heading { level, text, .. } = b else {
+            continue;
+        };
+        if text.is_empty() {
+            continue;
+        }
+        while stack.last().is_some_and(|top| top.level >= *level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(OutlineEntry {
+            level: *level,
+            text: text.clone(),
+            block_index: i,
+            children: Vec::new(),
+        });
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+/// SHA-256 hex over the content blocks, normalized so the hash only moves
+/// when the content does: every string has its whitespace collapsed, and
+/// `local_path` fields (which depend on the output directory) are dropped.
+/// serde_json's maps are sorted, so key order is stable too. Empty string
+/// for a page without blocks.
+pub(crate) fn hash_blocks(blocks: &[ContentBlock]) -> String {
+    use sha2::{Digest, Sha256};
+    fn normalize(v: &mut serde_json::Value) {
+        match v {
+            serde_json::Value::String(s) => {
+                *s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(normalize),
+            serde_json::Value::Object(map) => {
+                map.retain(|k, _| !k.ends_with("local_path") && k != "svg_markup");
+                map.values_mut().for_each(normalize);
+            }
+            _ => {}
+        }
+    }
+    if blocks.is_empty() {
+        return String::new();
+    }
+    let mut value = serde_json::to_value(blocks).unwrap_or_default();
+    normalize(&mut value);
+    let mut hasher = Sha256::new();
+    hasher.update(value.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Format a Unix timestamp (seconds) as an RFC 3339 UTC string, e.g.
+/// `2024-03-01T12:00:00Z`. Civil-from-days conversion (proleptic
+/// Gregorian) so we don't pull in a date crate for one timestamp.
+pub(crate) fn unix_to_rfc3339(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+pub(crate) fn now_rfc3339() -> String {
+    unix_to_rfc3339(now_unix())
+}
+
+pub(crate) fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date — the inverse of
+/// the conversion in [`unix_to_rfc3339`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn unix_from_parts(year: i64, month: u32, day: u32, secs_of_day: i64) -> Option<u64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    u64::try_from(days_from_civil(year, month, day) * 86_400 + secs_of_day).ok()
+}
+
+/// Unix seconds of a W3C datetime as used by sitemap `<lastmod>`:
+/// `YYYY`, `YYYY-MM`, `YYYY-MM-DD`, or a full date-time with optional
+/// seconds, fraction and `Z` / `±hh:mm` offset.
+pub(crate) fn parse_w3c_datetime(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (date, time) = match s.split_once(['T', ' ']) {
+        Some((d, t)) => (d, Some(t)),
+        None => (s, None),
+    };
+    let mut parts = date.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next().map_or(Some(1), |m| m.parse().ok())?;
+    let day: u32 = parts.next().map_or(Some(1), |d| d.parse().ok())?;
+    let mut secs_of_day = 0i64;
+    if let Some(time) = time {
+        let (clock, offset) = if let Some(t) = time.strip_suffix(['Z', 'z']) {
+            (t, 0)
+        } else if let Some(i) = time.rfind(['+', '-']) {
+            let (clock, tz) = time.split_at(i);
+            let sign = if tz.starts_with('-') { -1 } else { 1 };
+            let (h, m) = tz[1..].split_once(':').unwrap_or((&tz[1..], "0"));
+            let minutes = h.parse::<i64>().ok()? * 60 + m.parse::<i64>().ok()?;
+            (clock, sign * minutes * 60)
+        } else {
+            (time, 0)
+        };
+        let mut hms = clock.split(':');
+        let h: i64 = hms.next()?.parse().ok()?;
+        let m: i64 = hms.next().map_or(Some(0), |m| m.parse().ok())?;
+        let sec: f64 = hms.next().map_or(Some(0.0), |s| s.parse().ok())?;
+        secs_of_day = h * 3_600 + m * 60 + sec as i64 - offset;
+    }
+    unix_from_parts(year, month, day, secs_of_day)
+}
+
+/// Unix seconds of an HTTP date (`Last-Modified`), in the IMF-fixdate
+/// form every current server sends: `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub(crate) fn parse_http_date(s: &str) -> Option<u64> {
+    let (_, rest) = s.trim().split_once(", ")?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let [day, month, year, time, ..] = fields.as_slice() else {
+        return None;
+    };
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let month = MONTHS.iter().position(|m| month.eq_ignore_ascii_case(m))? as u32 + 1;
+    let mut hms = time.split(':').map(|v| v.parse::<i64>().ok());
+    let secs_of_day = hms.next()?? * 3_600
+        + hms.next().flatten().unwrap_or(0) * 60
+        + hms.next().flatten().unwrap_or(0);
+    unix_from_parts(year.parse().ok()?, month, day.parse().ok()?, secs_of_day)
+}
+
+/// `--modified-since` parser: a W3C date / date-time (`2024-03-01`,
+/// `2024-03-01T12:00:00Z`) or an age relative to now (`36h`, `7d`, `2w`).
+pub(crate) fn parse_modified_since(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let unit = match s.chars().last() {
+        Some('h') => Some(3_600),
+        Some('d') => Some(86_400),
+        Some('w') => Some(7 * 86_400),
+        _ => None,
+    };
+    if let Some(unit) = unit {
+        if let Ok(n) = s[..s.len() - 1].parse::<u64>() {
+            let age = n
+                .checked_mul(unit)
+                .ok_or_else(|| format!("age {s:?} is too large"))?;
+            return Ok(now_unix().saturating_sub(age));
+        }
+    }
+    parse_w3c_datetime(s)
+        .ok_or_else(|| format!("expected a date (2024-03-01), date-time or age (7d), got {s:?}"))
+}
+
+/// Parse a `--front-matter KEY=VALUE` pair. Keys are restricted to
+/// `[A-Za-z0-9_-]` so they can be written into YAML unquoted.
+pub(crate) fn parse_front_matter_field(spec: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("--front-matter expects `key=value`, got `{spec}`"))?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        anyhow::bail!("--front-matter: `{key}` is not a valid key (letters, digits, `_`, `-`)");
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Average adult silent-reading speed used for `reading_time_minutes`.
+const READING_WPM: usize = 230;
+
+fn round3(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+/// `part / whole` rounded to 3 decimals; 1.0 for an empty `whole`.
+pub(crate) fn byte_ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        return 1.0;
+    }
+    (part as f64 / whole as f64 * 1000.0).round() / 1000.0
+}
+
+/// Reading time, text-to-HTML ratio and paragraph/heading ratios.
+/// `text_bytes` is the whitespace-collapsed visible body text,
+/// `html_bytes` the decoded document size.
+pub(crate) fn content_metrics(
+    blocks: &[ContentBlock],
+    total_words: usize,
+    text_bytes: usize,
+    html_bytes: usize,
+) -> ContentMetrics {
+    let mut paragraph_count = 0;
+    let mut paragraph_words = 0;
+    let mut heading_count = 0;
+    for b in blocks {
+        match b {
+            ContentBlock::Paragraph { text, .. } => {
+                paragraph_count += 1;
+                paragraph_words += text.split_whitespace().count();
+            }
+            ContentBlock::Heading { .. } => heading_count += 1,
+            _ => {}
+        }
+    }
+    let ratio = |a: usize, b: usize| {
+        if b == 0 {
+            0.0
+        } else {
+            round3(a as f64 / b as f64)
+        }
+    };
+    ContentMetrics {
+        reading_time_minutes: total_words.div_ceil(READING_WPM) as u32,
+        text_to_html_ratio: ratio(text_bytes, html_bytes),
+        paragraph_count,
+        heading_count,
+        words_per_paragraph: ratio(paragraph_words, paragraph_count),
+        paragraphs_per_heading: ratio(paragraph_count, heading_count),
+    }
+}
+
 pub(crate) fn count_words(blocks: &[ContentBlock]) -> usize {
     blocks.iter().fold(0, |acc, b| {
         acc + match b {
-            ContentBlock::Heading { text, .. } | ContentBlock::Paragraph { text } => {
+            ContentBlock::Heading { text, .. } | ContentBlock::Paragraph { text, .. } => {
                 text.split_whitespace().count()
             }
-            ContentBlock::List { items } => {
+            ContentBlock::List { items, .. } => {
                 items.iter().map(|s| s.split_whitespace().count()).sum()
             }
             ContentBlock::Table {
@@ -316,7 +824,9 @@ pub(crate) fn count_words(blocks: &[ContentBlock]) -> usize {
             ContentBlock::Image { .. }
             | ContentBlock::Form { .. }
             | ContentBlock::Embed { .. }
-            | ContentBlock::Media { .. } => 0,
+            | ContentBlock::Media { .. }
+            | ContentBlock::Video { .. }
+            | ContentBlock::Cta { .. } => 0,
         }
     })
 }
@@ -341,7 +851,7 @@ pub(crate) fn classify_form_purpose(
     let mut field_count_non_hidden = 0;
     let mut joined_names = String::new();
 
-    for f in fields {
+    for f in fields.iter().filter(|f| f.field_type != "hidden") {
         field_count_non_hidden += 1;
         let n = f.name.to_lowercase();
         let l = f.label.to_lowercase();
@@ -460,13 +970,87 @@ pub(crate) fn embed_provider_from_src(src: &str) -> &'static str {
     }
 }
 
-pub(crate) fn element_in_skip_zone(el: &ElementRef) -> bool {
-    if SEL_SKIP.matches(el) {
+/// Content id and public page URL of an embed from a provider with
+/// stable ids: YouTube (`/embed/ID`, `watch?v=`, `youtu.be/ID`,
+/// `/shorts/ID`), Vimeo (`player.vimeo.com/video/ID`), Spotify
+/// (`/embed/{kind}/ID`) and SoundCloud (`player/?url=`). `None` for other
+/// providers and unrecognised paths.
+pub(crate) fn embed_id_and_url(provider: &str, src: &str) -> Option<(String, String)> {
+    let url = Url::parse(src).ok()?;
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let query = |key: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.into_owned())
+    };
+    let valid = |id: &str| {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    match provider {
+        "youtube" => {
+            let id = match segments.as_slice() {
+                ["embed" | "shorts" | "v" | "live", id, ..] => id.to_string(),
+                ["watch"] => query("v")?,
+                [id] if url.host_str().is_some_and(|h| h.ends_with("youtu.be")) => id.to_string(),
+                _ => return None,
+            };
+            valid(&id).then(|| {
+                let page = format!("https://www.youtube.com/watch?v={id}");
+                (id, page)
+            })
+        }
+        "vimeo" => {
+            let id = segments
+                .iter()
+                .rev()
+                .find(|s| s.chars().all(|c| c.is_ascii_digit()))?
+                .to_string();
+            let page = format!("https://vimeo.com/{id}");
+            Some((id, page))
+        }
+        "spotify" => {
+            let rest = match segments.as_slice() {
+                [embed, rest @ ..] if embed.starts_with("embed") => rest,
+                rest => rest,
+            };
+            let [kind, id, ..] = rest else {
+                return None;
+            };
+            let known = ["track", "album", "playlist", "artist", "episode", "show"];
+            (known.contains(kind) && valid(id)).then(|| {
+                let page = format!("https://open.spotify.com/{kind}/{id}");
+                (id.to_string(), page)
+            })
+        }
+        "soundcloud" => {
+            let target = query("url")?;
+            let id = target
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .filter(|id| valid(id))?
+                .to_string();
+            Some((id, target))
+        }
+        _ => None,
+    }
+}
+
+/// `el` or one of its ancestors matches `skip` — `SEL_SKIP` unless
+/// `--skip-selectors` replaced it.
+pub(crate) fn element_in_skip_zone(el: &ElementRef, skip: &Selector) -> bool {
+    if skip.matches(el) {
         return true;
     }
     for anc in el.ancestors() {
         if let Some(anc_el) = ElementRef::wrap(anc) {
-            if SEL_SKIP.matches(&anc_el) {
+            if skip.matches(&anc_el) {
                 return true;
             }
         }
@@ -482,10 +1066,20 @@ pub(crate) async fn fetch_with_retry(
     client: &reqwest::Client,
     url: &str,
     max_retries: u32,
+) -> Option<reqwest::Response> {
+    fetch_with_retry_from(url, max_retries, || client.get(url)).await
+}
+
+/// `fetch_with_retry` for a request other than the client's plain GET:
+/// `request` builds each attempt (`--url-rule` timeout, method, body).
+pub(crate) async fn fetch_with_retry_from(
+    url: &str,
+    max_retries: u32,
+    request: impl Fn() -> reqwest::RequestBuilder,
 ) -> Option<reqwest::Response> {
     let mut delay = Duration::from_millis(200);
     for attempt in 0..=max_retries {
-        match client.get(url).send().await {
+        match crate::har::send(request()).await {
             Ok(resp) => {
                 let status = resp.status();
                 if status.is_success() || !status.is_server_error() {
@@ -520,17 +1114,159 @@ pub(crate) async fn fetch_with_retry(
     None
 }
 
+/// Undo the `Content-Encoding` layers of a raw response body. Page fetches
+/// go through a client with reqwest's transparent decoding switched off
+/// (reqwest strips `Content-Length` when it decodes, so the wire size
+/// would be lost); this restores the document bytes. Stacked encodings
+/// (`gzip, br`) are undone in reverse order of application. Output is cut
+/// one byte past `limit` so callers can detect oversize without inflating
+/// a decompression bomb in full.
+pub(crate) fn decode_content_encoding(
+    encoding: Option<&str>,
+    raw: Vec<u8>,
+    limit: u64,
+) -> anyhow::Result<Vec<u8>> {
+    use std::io::Read;
+    let Some(encoding) = encoding else {
+        return Ok(raw);
+    };
+    let mut body = raw;
+    for layer in encoding.rsplit(',').map(|e| e.trim().to_ascii_lowercase()) {
+        let mut out = Vec::with_capacity(body.len() * 4);
+        let cap = limit.saturating_add(1);
+        match layer.as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => {
+                flate2::read::MultiGzDecoder::new(body.as_slice())
+                    .take(cap)
+                    .read_to_end(&mut out)?;
+            }
+            "deflate" => {
+                // RFC 9110 says zlib-wrapped, but enough servers send raw
+                // deflate streams that we retry without the header.
+                if flate2::read::ZlibDecoder::new(body.as_slice())
+                    .take(cap)
+                    .read_to_end(&mut out)
+                    .is_err()
+                {
+                    out.clear();
+                    flate2::read::DeflateDecoder::new(body.as_slice())
+                        .take(cap)
+                        .read_to_end(&mut out)?;
+                }
+            }
+            "br" => {
+                brotli::Decompressor::new(body.as_slice(), 4096)
+                    .take(cap)
+                    .read_to_end(&mut out)?;
+            }
+            other => anyhow::bail!("unsupported Content-Encoding `{other}`"),
+        }
+        body = out;
+    }
+    Ok(body)
+}
+
+/// Decode document bytes to text using the `charset=` parameter of the
+/// `Content-Type` header (the name in any case), falling back to UTF-8.
+/// Mirrors what `reqwest::Response::text()` does, which we can't use on
+/// the raw path.
+pub(crate) fn decode_charset(content_type: Option<&str>, bytes: &[u8]) -> String {
+    let encoding = content_type
+        .and_then(|ct| {
+            ct.split(';')
+                .filter_map(|p| p.trim().split_once('='))
+                .find_map(|(name, value)| name.eq_ignore_ascii_case("charset").then_some(value))
+                .map(|c| c.trim_matches('"'))
+        })
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// Parse a human byte size for CLI flags: `5MB`, `512KB`, `1G`, `1048576`.
+/// Units are binary (1 KB = 1024 bytes) and case-insensitive.
+pub(crate) fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let t = s.trim().to_ascii_uppercase();
+    let t = t.strip_suffix('B').unwrap_or(&t);
+    let (digits, mult) = match t.chars().last() {
+        Some('K') => (&t[..t.len() - 1], 1024),
+        Some('M') => (&t[..t.len() - 1], 1024 * 1024),
+        Some('G') => (&t[..t.len() - 1], 1024 * 1024 * 1024),
+        _ => (t, 1),
+    };
+    let n = digits
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("`{s}` is not a size like 5MB, 512KB or 1048576"))?;
+    n.checked_mul(mult)
+        .ok_or_else(|| format!("`{s}` is too large"))
+}
+
+/// Effective `(noindex, nofollow)` for our crawler, combining the
+/// `<meta name="robots">` content with every `X-Robots-Tag` header line.
+/// Header lines scoped to another crawler (`googlebot: noindex`) are
+/// ignored; `none` implies both directives.
+pub(crate) fn robots_directives(meta: Option<&str>, x_robots_tag: &[String]) -> (bool, bool) {
+    // Directives that legitimately carry a `name: value` argument, so their
+    // colon isn't a user-agent scope.
+    const VALUED: [&str; 4] = [
+        "unavailable_after",
+        "max-snippet",
+        "max-image-preview",
+        "max-video-preview",
+    ];
+    let mut noindex = false;
+    let mut nofollow = false;
+    let mut apply = |token: &str| match token.trim() {
+        "noindex" => noindex = true,
+        "nofollow" => nofollow = true,
+        "none" => {
+            noindex = true;
+            nofollow = true;
+        }
+        _ => {}
+    };
+    if let Some(meta) = meta {
+        meta.to_ascii_lowercase().split(',').for_each(&mut apply);
+    }
+    for line in x_robots_tag {
+        let line = line.to_ascii_lowercase();
+        let mut ours = true;
+        for token in line.split(',') {
+            let token = match token.split_once(':') {
+                Some((scope, rest)) if !VALUED.contains(&scope.trim()) => {
+                    let scope = scope.trim();
+                    ours = scope == "*" || scope == "dumpit";
+                    rest
+                }
+                _ => token,
+            };
+            if ours {
+                apply(token);
+            }
+        }
+    }
+    (noindex, nofollow)
+}
+
 /// Parsed robots.txt rules that apply to our user-agent (`*` or `DumpIt`).
+#[derive(Default)]
 pub(crate) struct RobotsRules {
     pub disallow: Vec<String>,
     pub crawl_delay_ms: Option<u64>,
+    /// `Sitemap:` URLs, in file order. They belong to no user-agent group.
+    pub sitemaps: Vec<String>,
 }
 
 /// Parse a robots.txt body and return Disallow paths + Crawl-delay for our
-/// user-agent. The largest Crawl-delay across matching groups wins.
+/// user-agent, and every `Sitemap:` line. The largest Crawl-delay across
+/// matching groups wins.
 pub(crate) fn parse_robots(body: &str) -> RobotsRules {
     let mut disallow: Vec<String> = Vec::new();
     let mut crawl_delay_ms: Option<u64> = None;
+    let mut sitemaps: Vec<String> = Vec::new();
     let mut applies = false;
     for raw in body.lines() {
         let line = raw.split('#').next().unwrap_or(raw).trim();
@@ -539,7 +1275,12 @@ pub(crate) fn parse_robots(body: &str) -> RobotsRules {
             continue;
         }
         let lower = line.to_lowercase();
-        if let Some(rest) = lower.strip_prefix("user-agent:") {
+        if lower.starts_with("sitemap:") {
+            let loc = line["sitemap:".len()..].trim();
+            if !loc.is_empty() && !sitemaps.iter().any(|s| s == loc) {
+                sitemaps.push(loc.to_string());
+            }
+        } else if let Some(rest) = lower.strip_prefix("user-agent:") {
             let ua = rest.trim();
             applies = ua == "*" || ua.contains("dumpit");
         } else if applies {
@@ -562,6 +1303,7 @@ pub(crate) fn parse_robots(body: &str) -> RobotsRules {
     RobotsRules {
         disallow,
         crawl_delay_ms,
+        sitemaps,
     }
 }
 
@@ -708,6 +1450,55 @@ pub(crate) fn url_priority(url: &str) -> u32 {
     100
 }
 
+/// Whether `url`, referenced from `page_url`, may be fetched: a `file://`
+/// reference only counts on a `file://` page, never on a web page.
+pub(crate) fn fetchable_from(page_url: &str, url: &str) -> bool {
+    !url.starts_with("file:") || page_url.starts_with("file:")
+}
+
+/// Whether `url` is on the site of `base`. A leading `www.` and the scheme
+/// don't matter, so a crawl started at `http://example.com` keeps
+/// `https://www.example.com` links; `strict` (`--strict-host`) wants the
+/// exact scheme and host.
+pub(crate) fn same_site(url: &Url, base: &Url, strict: bool) -> bool {
+    if strict {
+        return url.scheme() == base.scheme() && url.host_str() == base.host_str();
+    }
+    let host = |u: &Url| {
+        u.host_str()
+            .map(|h| h.strip_prefix("www.").unwrap_or(h).to_string())
+    };
+    host(url) == host(base)
+}
+
+/// The robots.txt `Sitemap:` URLs on `base`'s site. The rest are dropped:
+/// discovery doesn't look for `/sitemap.xml` on other hosts either.
+pub(crate) fn sitemaps_on_site(sitemaps: Vec<String>, base: &Url, strict: bool) -> Vec<String> {
+    sitemaps
+        .into_iter()
+        .filter(|sitemap| {
+            let on_site = Url::parse(sitemap).is_ok_and(|u| same_site(&u, base, strict));
+            if !on_site {
+                tracing::warn!("Skipping robots.txt sitemap {sitemap}: not on the target site");
+            }
+            on_site
+        })
+        .collect()
+}
+
+/// What the crawler tells URLs apart by: without `strict`, the URL minus
+/// its scheme and a leading `www.`, so `http://example.com/a` and
+/// `https://www.example.com/a` are crawled once.
+pub(crate) fn site_key(url: &str, strict: bool) -> String {
+    match Url::parse(url) {
+        Ok(parsed) if !strict => {
+            let rest = &parsed[url::Position::BeforeHost..];
+            rest.strip_prefix("www.").unwrap_or(rest).to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
 /// Filesystem-safe host slug for a URL. Strips `www.`, replaces dots with
 /// underscores, drops non-alphanumeric characters. `https://www.foo-bar.cz/x`
 /// → `foo-bar_cz`. Used by `--test-run` to derive `test_runs/<slug>/`.
@@ -739,6 +1530,117 @@ pub(crate) fn url_to_host_slug(url: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn inline_markdown_keeps_links_and_emphasis() {
+        let doc = Html::parse_fragment(
+            "<p>Read <a href='/docs'>the <strong>docs</strong> </a>, run <code>a*b</code> \
+             and *stars* [x] <a href='javascript:void(0)'>here</a> \
+             <a href='/wiki/Rust_(language)'>wiki</a></p>",
+        );
+        let p = doc.select(&Selector::parse("p").unwrap()).next().unwrap();
+        let base = Url::parse("https://x.com/page").unwrap();
+        assert_eq!(
+            inline_markdown(&p, &base, &|_| false),
+            "Read [the **docs**](https://x.com/docs) , run `a*b` and \\*stars\\* \\[x\\] here \
+             [wiki](https://x.com/wiki/Rust_%28language%29)"
+        );
+    }
+
+    #[test]
+    fn hash_blocks_ignores_whitespace_and_local_paths() {
+        let blocks = |text: &str, local: &str| {
+            vec![
+                ContentBlock::Paragraph {
+                    text: text.into(),
+                    links: Vec::new(),
+                    markdown: None,
+                },
+                ContentBlock::Image {
+                    original_url: "https://x.com/a.png".into(),
+                    local_path: local.into(),
+                    alt_text: String::new(),
+                    svg_markup: None,
+                    fallback_urls: Vec::new(),
+                },
+            ]
+        };
+        let a = hash_blocks(&blocks("Hello  world", "out1/images/a.png"));
+        let b = hash_blocks(&blocks(" Hello world\n", "out2/images/a.png"));
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, hash_blocks(&blocks("Hello there", "out1/images/a.png")));
+        assert!(hash_blocks(&[]).is_empty());
+    }
+
+    #[test]
+    fn unix_to_rfc3339_formats_utc() {
+        assert_eq!(unix_to_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(unix_to_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(unix_to_rfc3339(1_709_294_400), "2024-03-01T12:00:00Z");
+    }
+
+    #[test]
+    fn content_metrics_ratios() {
+        let p = |t: &str| ContentBlock::Paragraph {
+            text: t.into(),
+            links: Vec::new(),
+            markdown: None,
+        };
+        let blocks = vec![
+            ContentBlock::Heading {
+                level: 1,
+                text: "Hi".into(),
+                markdown: None,
+            },
+            p("one two three four"),
+            p("five six"),
+        ];
+        let m = content_metrics(&blocks, 461, 250, 1000);
+        assert_eq!(m.reading_time_minutes, 3);
+        assert_eq!(m.text_to_html_ratio, 0.25);
+        assert_eq!(m.paragraph_count, 2);
+        assert_eq!(m.heading_count, 1);
+        assert_eq!(m.words_per_paragraph, 3.0);
+        assert_eq!(m.paragraphs_per_heading, 2.0);
+        let empty = content_metrics(&[], 0, 0, 0);
+        assert_eq!(empty.reading_time_minutes, 0);
+        assert_eq!(empty.text_to_html_ratio, 0.0);
+    }
+
+    #[test]
+    fn build_outline_nests_by_level() {
+        let h = |level: u8, text: &str| ContentBlock::Heading {
+            level,
+            text: text.to_string(),
+            markdown: None,
+        };
+        let blocks = vec![
+            h(1, "Title"),
+            ContentBlock::Paragraph {
+                text: "intro".into(),
+                links: Vec::new(),
+                markdown: None,
+            },
+            h(2, "A"),
+            h(4, "A.deep"),
+            h(3, "A.1"),
+            h(2, "B"),
+            h(1, "Second"),
+        ];
+        let outline = build_outline(&blocks);
+        assert_eq!(outline.len(), 2);
+        let title = &outline[0];
+        assert_eq!(title.block_index, 0);
+        assert_eq!(title.children.len(), 2);
+        let a = &title.children[0];
+        assert_eq!(a.text, "A");
+        // h4 then h3: both nest under h2, as siblings.
+        let kids: Vec<&str> = a.children.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(kids, vec!["A.deep", "A.1"]);
+        assert_eq!(title.children[1].block_index, 5);
+        assert_eq!(outline[1].text, "Second");
+    }
+
     #[test]
     fn url_priority_pushes_chrome_pages_to_front() {
         assert_eq!(url_priority("https://x.com/"), 0);
@@ -759,7 +1661,7 @@ mod tests {
         assert_eq!(url_priority("https://x.com/search"), 200);
         assert_eq!(url_priority("https://x.com/account/password/recover"), 200);
         // Regression: sort key keeps home above everything and utility at end.
-        let mut urls = vec![
+        let mut urls = [
             "https://x.com/products/widget".to_string(),
             "https://x.com/account/login".to_string(),
             "https://x.com/blog/post".to_string(),
@@ -787,6 +1689,162 @@ mod tests {
         assert_eq!(url_to_host_slug("https://x.com"), "x_com");
     }
 
+    #[test]
+    fn web_pages_cant_reference_local_files() {
+        assert!(!fetchable_from("https://x.com/", "file:///etc/passwd"));
+        assert!(fetchable_from(
+            "file:///site/index.html",
+            "file:///site/a.png"
+        ));
+        assert!(fetchable_from("https://x.com/", "https://cdn.x.com/a.png"));
+    }
+
+    #[test]
+    fn robots_sitemaps_off_the_site_are_dropped() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let sitemaps = vec![
+            "https://www.example.com/sitemap.xml".to_string(),
+            "https://evil.test/sitemap.xml".to_string(),
+            "http://example.com/news.xml".to_string(),
+            "not a url".to_string(),
+        ];
+        assert_eq!(
+            sitemaps_on_site(sitemaps.clone(), &base, false),
+            vec![
+                "https://www.example.com/sitemap.xml",
+                "http://example.com/news.xml"
+            ]
+        );
+        assert!(sitemaps_on_site(sitemaps, &base, true).is_empty());
+    }
+
+    #[test]
+    fn scheme_and_www_dont_split_a_site() {
+        let base = Url::parse("http://example.com/").unwrap();
+        let www = Url::parse("https://www.example.com/about").unwrap();
+        assert!(same_site(&www, &base, false));
+        assert!(!same_site(&www, &base, true));
+        let blog = Url::parse("https://blog.example.com/").unwrap();
+        assert!(!same_site(&blog, &base, false));
+        assert!(same_site(
+            &Url::parse("http://example.com/x").unwrap(),
+            &base,
+            true
+        ));
+
+        assert_eq!(
+            site_key("http://example.com/a?b=1", false),
+            site_key("https://www.example.com/a?b=1", false)
+        );
+        assert_ne!(
+            site_key("http://example.com/a", true),
+            site_key("https://example.com/a", true)
+        );
+    }
+
+    #[test]
+    fn parse_srcset_keeps_commas_inside_cdn_urls() {
+        assert_eq!(
+            parse_srcset("/w_400,h_300/a.jpg 400w, /w_800,h_600/a.jpg 800w"),
+            vec![
+                ("/w_400,h_300/a.jpg", "400w"),
+                ("/w_800,h_600/a.jpg", "800w")
+            ]
+        );
+        assert_eq!(
+            parse_srcset("a.jpg 1x,b.jpg 2x"),
+            vec![("a.jpg", "1x"), ("b.jpg", "2x")]
+        );
+        assert_eq!(parse_srcset("solo.png"), vec![("solo.png", "")]);
+    }
+
+    #[test]
+    fn media_queries_and_srcset_resolve_for_desktop_viewport() {
+        assert!(media_matches("(min-width: 1024px)", 1280));
+        assert!(!media_matches("(max-width: 767px)", 1280));
+        assert!(media_matches("screen and (min-width: 64em)", 1280));
+        assert!(media_matches(
+            "(max-width: 600px), (min-width: 1200px)",
+            1280
+        ));
+        assert!(!media_matches("print", 1280));
+        assert!(!media_matches("(prefers-color-scheme: dark)", 1280));
+        assert!(media_matches("not (max-width: 767px)", 1280));
+
+        let entries = parse_srcset("s.jpg 640w, m.jpg 1400w, l.jpg 2800w");
+        assert_eq!(
+            order_srcset_for_viewport(&entries, 1280),
+            vec!["m.jpg", "s.jpg", "l.jpg"]
+        );
+        assert_eq!(order_srcset_for_viewport(&entries, 4000)[0], "l.jpg");
+        let dpr = parse_srcset("a.jpg, a@2x.jpg 2x");
+        assert_eq!(order_srcset_for_viewport(&dpr, 1280)[0], "a.jpg");
+    }
+
+    #[test]
+    fn embed_ids_from_player_urls() {
+        assert_eq!(
+            embed_id_and_url(
+                "youtube",
+                "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?rel=0"
+            ),
+            Some((
+                "dQw4w9WgXcQ".to_string(),
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string()
+            ))
+        );
+        assert_eq!(
+            embed_id_and_url(
+                "vimeo",
+                "https://player.vimeo.com/video/76979871?h=8272103f6e"
+            ),
+            Some((
+                "76979871".to_string(),
+                "https://vimeo.com/76979871".to_string()
+            ))
+        );
+        assert_eq!(
+            embed_id_and_url(
+                "spotify",
+                "https://open.spotify.com/embed/track/4uLU6hMCjMI75M1A2tKUQC"
+            ),
+            Some((
+                "4uLU6hMCjMI75M1A2tKUQC".to_string(),
+                "https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC".to_string()
+            ))
+        );
+        assert_eq!(
+            embed_id_and_url("youtube", "https://www.youtube.com/embed/"),
+            None
+        );
+        assert_eq!(
+            embed_id_and_url("maps", "https://www.google.com/maps/embed?pb=x"),
+            None
+        );
+    }
+
+    #[test]
+    fn dates_parse_to_unix_seconds() {
+        assert_eq!(parse_w3c_datetime("1970-01-02"), Some(86_400));
+        assert_eq!(parse_w3c_datetime("2024-03-01"), Some(1_709_251_200));
+        assert_eq!(
+            parse_w3c_datetime("2024-03-01T12:30:00+02:00"),
+            Some(1_709_251_200 + 10 * 3_600 + 30 * 60)
+        );
+        assert_eq!(
+            parse_w3c_datetime("2024-03"),
+            parse_w3c_datetime("2024-03-01")
+        );
+        assert_eq!(parse_w3c_datetime("yesterday"), None);
+        assert_eq!(
+            parse_http_date("Fri, 01 Mar 2024 00:00:00 GMT"),
+            Some(1_709_251_200)
+        );
+        assert!(parse_modified_since("7d").unwrap() < now_unix());
+        assert!(parse_modified_since("soon").is_err());
+        assert!(parse_modified_since("9999999999999999999w").is_err());
+    }
+
     #[test]
     fn image_ext_from_url_handles_proxy() {
         assert_eq!(
@@ -798,6 +1856,14 @@ mod tests {
         assert_eq!(image_extension_from_url("https://x.com/a.jpeg"), "jpg");
         assert_eq!(image_extension_from_url("https://x.com/photo"), "jpg"); // fallback
         assert_eq!(image_extension_from_url("https://x.com/x.webp"), "webp");
+        assert_eq!(
+            image_extension_from_url("https://x.com/a.png?alt=b.svg"),
+            "png"
+        );
+        assert_eq!(
+            image_extension_from_url("https://x.com/images.gifts/a"),
+            "jpg"
+        );
     }
 
     #[test]
@@ -824,12 +1890,22 @@ Disallow: /private/
 
 User-agent: BadBot
 Disallow: /
+
+Sitemap: https://x.com/sitemap_index.xml
+sitemap: https://cdn.x.com/products.xml
 "#;
         let rules = parse_robots(body);
         assert!(rules.disallow.contains(&"/admin/".to_string()));
         assert!(rules.disallow.contains(&"/private/".to_string()));
         assert!(!rules.disallow.iter().any(|r| r == "/"));
         assert_eq!(rules.crawl_delay_ms, Some(2000));
+        assert_eq!(
+            rules.sitemaps,
+            vec![
+                "https://x.com/sitemap_index.xml",
+                "https://cdn.x.com/products.xml"
+            ]
+        );
     }
 
     #[test]
@@ -876,8 +1952,7 @@ Disallow: /
             name: name.to_string(),
             label: String::new(),
             placeholder: String::new(),
-            required: false,
-            options: vec![],
+            ..Default::default()
         };
         let contact = vec![
             f("name", "text"),
@@ -912,19 +1987,108 @@ Disallow: /
             ContentBlock::Heading {
                 level: 1,
                 text: "Hello".to_string(),
+                markdown: None,
             },
             ContentBlock::Paragraph {
                 text: "World".to_string(),
+                links: Vec::new(),
+                markdown: None,
             },
             ContentBlock::Image {
                 original_url: "x".to_string(),
                 local_path: "".to_string(),
                 alt_text: "".to_string(),
+                svg_markup: None,
+                fallback_urls: Vec::new(),
             },
             ContentBlock::List {
                 items: vec!["a".to_string(), "b".to_string()],
+                markdown_items: Vec::new(),
             },
         ];
         assert_eq!(blocks_to_plain_text(&blocks), "Hello\nWorld\na\nb");
     }
+
+    #[test]
+    fn decode_content_encoding_handles_gzip_and_identity() {
+        use std::io::Write;
+        let html = b"<html><body>Hello</body></html>".to_vec();
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(&html).unwrap();
+        let gz = enc.finish().unwrap();
+        assert_eq!(
+            decode_content_encoding(Some("gzip"), gz.clone(), u64::MAX).unwrap(),
+            html
+        );
+        // Output stops one byte past the limit.
+        assert_eq!(
+            decode_content_encoding(Some("gzip"), gz, 10).unwrap().len(),
+            11
+        );
+        assert_eq!(
+            decode_content_encoding(None, html.clone(), u64::MAX).unwrap(),
+            html
+        );
+        assert_eq!(
+            decode_content_encoding(Some("identity"), html.clone(), u64::MAX).unwrap(),
+            html
+        );
+        assert!(decode_content_encoding(Some("zstd"), html, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn robots_directives_merges_meta_and_scoped_headers() {
+        assert_eq!(
+            robots_directives(Some("noindex, follow"), &[]),
+            (true, false)
+        );
+        assert_eq!(robots_directives(Some("NONE"), &[]), (true, true));
+        assert_eq!(
+            robots_directives(None, &["nofollow".to_string()]),
+            (false, true)
+        );
+        // Scoped to another crawler — not ours.
+        assert_eq!(
+            robots_directives(None, &["googlebot: noindex, nofollow".to_string()]),
+            (false, false)
+        );
+        // The colon in `unavailable_after` is not a scope.
+        assert_eq!(
+            robots_directives(
+                None,
+                &["unavailable_after: 25 Jun 2010 15:00:00 PST, noindex".to_string()]
+            ),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn parse_byte_size_accepts_units() {
+        assert_eq!(parse_byte_size("1048576"), Ok(1_048_576));
+        assert_eq!(parse_byte_size("5MB"), Ok(5 * 1024 * 1024));
+        assert_eq!(parse_byte_size("512kb"), Ok(512 * 1024));
+        assert_eq!(parse_byte_size("1G"), Ok(1024 * 1024 * 1024));
+        assert_eq!(parse_byte_size("0"), Ok(0));
+        assert!(parse_byte_size("lots").is_err());
+        assert!(parse_byte_size("18446744073709551615G").is_err());
+    }
+
+    #[test]
+    fn decode_charset_honours_content_type() {
+        // "Příliš" in windows-1250.
+        let bytes = [0x50, 0xF8, 0xED, 0x6C, 0x69, 0x9A];
+        assert_eq!(
+            decode_charset(Some("text/html; charset=windows-1250"), &bytes),
+            "Příliš"
+        );
+        assert_eq!(
+            decode_charset(Some("text/html; Charset=\"windows-1250\""), &bytes),
+            "Příliš"
+        );
+        assert_eq!(
+            decode_charset(Some("text/html;CHARSET=WINDOWS-1250"), &bytes),
+            "Příliš"
+        );
+        assert_eq!(decode_charset(None, b"plain"), "plain");
+    }
 }
@@ -0,0 +1,204 @@
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::model::{ContentBlock, PageData};
+use crate::util::{fetch_with_retry, fetchable_from, normalize_path};
+
+/// Extensions a downloaded video can be stored under.
+const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "webm", "ogv", "mov", "m4v"];
+
+/// `--video-max-bytes`: download each `<video>`'s first source that fits
+/// under `max_bytes` into `videos_dir`, and set that source's
+/// `local_path`. Sources are tried in document order, like a browser
+/// would; a video shared by several pages is fetched once. Returns the
+/// number of videos stored.
+pub(crate) async fn download_videos(
+    client: &Client,
+    pages: &mut [PageData],
+    videos_dir: &str,
+    max_bytes: u64,
+    concurrency: usize,
+    retries: u32,
+) -> usize {
+    // Keyed by the first source; the value is every source in order.
+    let mut jobs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for page in pages.iter() {
+        for block in &page.content_blocks {
+            if let ContentBlock::Video { sources, .. } = block {
+                let urls: Vec<String> = sources
+                    .iter()
+                    .filter(|s| fetchable_from(&page.url, &s.url))
+                    .map(|s| s.url.clone())
+                    .collect();
+                if let Some(first) = urls.first() {
+                    jobs.entry(first.clone()).or_insert(urls);
+                }
+            }
+        }
+    }
+
+    let stored: HashMap<String, (String, String)> = stream::iter(jobs)
+        .map(|(key, urls)| async move {
+            for url in &urls {
+                match download_video(client, url, videos_dir, max_bytes, retries).await {
+                    Ok(local_path) => return Some((key, (url.clone(), local_path))),
+                    Err(reason) => tracing::debug!("Video not stored ({reason}): {url}"),
+                }
+            }
+            None
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|r| async move { r })
+        .collect()
+        .await;
+
+    for page in pages.iter_mut() {
+        for block in &mut page.content_blocks {
+            if let ContentBlock::Video { sources, .. } = block {
+                let Some((url, local_path)) = sources.first().and_then(|s| stored.get(&s.url))
+                else {
+                    continue;
+                };
+                if let Some(source) = sources.iter_mut().find(|s| &s.url == url) {
+                    source.local_path = Some(local_path.clone());
+                }
+            }
+        }
+    }
+    stored.len()
+}
+
+/// Download one video, streaming to disk and giving up as soon as it
+/// passes `max_bytes`. The error is the reason it was not stored:
+/// `too_large`, `http_error`, `fetch_failed` or `write_failed`.
+async fn download_video(
+    client: &Client,
+    url: &str,
+    videos_dir: &str,
+    max_bytes: u64,
+    retries: u32,
+) -> Result<String, &'static str> {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    let stem = &hash[..16];
+
+    let cached = VIDEO_EXTENSIONS
+        .iter()
+        .map(|ext| Path::new(videos_dir).join(format!("{stem}.{ext}")))
+        .find(|p| p.metadata().is_ok_and(|m| m.is_file() && m.len() > 0));
+    if let Some(cached) = cached {
+        return Ok(normalize_path(&cached.to_string_lossy()));
+    }
+
+    let mut response = match fetch_with_retry(client, url, retries).await {
+        Some(r) if r.status().is_success() => r,
+        Some(r) => {
+            tracing::debug!("Video HTTP {}: {url}", r.status());
+            return Err("http_error");
+        }
+        None => return Err("fetch_failed"),
+    };
+    if response.content_length().is_some_and(|n| n > max_bytes) {
+        return Err("too_large");
+    }
+    let extension = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(video_extension_from_content_type)
+        .unwrap_or_else(|| video_extension_from_url(url));
+
+    let filepath = format!("{videos_dir}/{stem}.{extension}");
+    let part = Path::new(&filepath).with_extension("part");
+    let mut file = fs::File::create(&part).await.map_err(|_| "write_failed")?;
+    let mut written: u64 = 0;
+    let body_read = crate::har::BodyRead::start(&response);
+    let outcome = loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                written += chunk.len() as u64;
+                if written > max_bytes {
+                    break Err("too_large");
+                }
+                if file.write_all(&chunk).await.is_err() {
+                    break Err("write_failed");
+                }
+            }
+            Ok(None) if written == 0 => break Err("fetch_failed"),
+            Ok(None) => break Ok(()),
+            Err(_) => break Err("fetch_failed"),
+        }
+    };
+    body_read.finish(written, written);
+    let outcome = match outcome {
+        Ok(()) => file.flush().await.map_err(|_| "write_failed"),
+        Err(reason) => Err(reason),
+    };
+    drop(file);
+    if let Err(reason) = outcome {
+        let _ = fs::remove_file(&part).await;
+        return Err(reason);
+    }
+    fs::rename(&part, &filepath)
+        .await
+        .map_err(|_| "write_failed")?;
+    Ok(normalize_path(&filepath))
+}
+
+fn video_extension_from_content_type(ct: &str) -> Option<&'static str> {
+    let ct = ct.to_lowercase();
+    if ct.contains("video/mp4") {
+        Some("mp4")
+    } else if ct.contains("video/webm") {
+        Some("webm")
+    } else if ct.contains("video/ogg") {
+        Some("ogv")
+    } else if ct.contains("video/quicktime") {
+        Some("mov")
+    } else if ct.contains("video/x-m4v") {
+        Some("m4v")
+    } else {
+        None
+    }
+}
+
+/// Extension from the URL path, `mp4` when it names none we store.
+fn video_extension_from_url(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    let ext = path.rsplit('/').next().and_then(|f| f.rsplit_once('.'));
+    match ext {
+        Some((_, "webm")) => "webm",
+        Some((_, "ogv" | "ogg")) => "ogv",
+        Some((_, "mov")) => "mov",
+        Some((_, "m4v")) => "m4v",
+        _ => "mp4",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_extension_prefers_content_type() {
+        assert_eq!(
+            video_extension_from_content_type("video/webm; codecs=vp9"),
+            Some("webm")
+        );
+        assert_eq!(video_extension_from_content_type("text/html"), None);
+        assert_eq!(
+            video_extension_from_url("https://cdn.example.com/clip.MOV?v=2"),
+            "mov"
+        );
+        assert_eq!(
+            video_extension_from_url("https://example.com/stream"),
+            "mp4"
+        );
+    }
+}